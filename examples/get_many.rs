@@ -0,0 +1,45 @@
+//! Compares getting many paths from a wide document with `get_many` and a `get_path` loop.
+//!
+//! Run with `cargo run --release --example get_many`.
+
+use std::time::{Duration, Instant};
+use jsonc_parser::parse_to_value;
+use jsonc_parser::path::JsonPath;
+
+fn main() {
+    let value = parse_to_value(&create_fixture(2_000, 50)).unwrap().unwrap();
+    // 40 paths that share the prefixes of a few sections near the end of the root object
+    let paths = (0..40).map(|i| format!("/section{}/field{}", 1_990 + i % 4, 49 - i).parse::<JsonPath>().unwrap()).collect::<Vec<_>>();
+    let naive_values = paths.iter().map(|path| value.get_path(path)).collect::<Vec<_>>();
+    assert_eq!(value.get_many(&paths), naive_values);
+
+    let naive_duration = measure(|| {
+        for path in paths.iter() {
+            std::hint::black_box(value.get_path(path));
+        }
+    });
+    let get_many_duration = measure(|| {
+        std::hint::black_box(value.get_many(&paths));
+    });
+
+    println!("get_path loop: {:?}", naive_duration);
+    println!("get_many: {:?}", get_many_duration);
+    println!("speedup: {:.1}x", naive_duration.as_secs_f64() / get_many_duration.as_secs_f64());
+}
+
+/// Gets the fastest of several runs.
+fn measure(mut action: impl FnMut()) -> Duration {
+    (0..1_000).map(|_| {
+        let start = Instant::now();
+        action();
+        start.elapsed()
+    }).min().unwrap()
+}
+
+fn create_fixture(section_count: usize, field_count: usize) -> String {
+    let sections = (0..section_count).map(|i| {
+        let fields = (0..field_count).map(|j| format!("\"field{}\": {}", j, i * j)).collect::<Vec<_>>();
+        format!("\"section{}\": {{ {} }}", i, fields.join(", "))
+    }).collect::<Vec<_>>();
+    format!("{{ {} }}", sections.join(",\n"))
+}
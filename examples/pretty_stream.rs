@@ -0,0 +1,92 @@
+//! Compares the peak memory allocated while pretty printing a large value to a writer by
+//! building the text with `serialize_json_value` and by streaming it with `write_json_value_to`.
+//!
+//! Run with `cargo run --release --example pretty_stream`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use jsonc_parser::serializer::{serialize_json_value, write_json_value_to, SerializeOptions, SerializeStyle};
+use jsonc_parser::value::{JsonArray, JsonObject, JsonValue};
+
+/// Allocator that tracks the current and peak number of allocated bytes.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn main() {
+    let value = create_fixture(1_000_000);
+    let options = SerializeOptions::default().with_style(SerializeStyle::Pretty);
+
+    let (string_peak, string_len) = measure_peak("serialize_json_value", || {
+        let text = serialize_json_value(&value, &options);
+        io::sink().write_all(text.as_bytes()).unwrap();
+        text.len()
+    });
+    let (stream_peak, stream_len) = measure_peak("write_json_value_to", || {
+        let mut writer = CountingWriter(0);
+        write_json_value_to(&mut writer, &value, &options).unwrap();
+        writer.0
+    });
+    assert_eq!(string_len, stream_len);
+    // streaming only allocates the depth stack, so its peak doesn't grow with the output
+    assert!(stream_peak < 1024, "streaming peaked at {} bytes", stream_peak);
+
+    println!("output: {} bytes", string_len);
+    println!("peak reduction: {:.0}x", string_peak as f64 / stream_peak.max(1) as f64);
+}
+
+/// Runs the action and prints the peak bytes allocated beyond those allocated before it started.
+fn measure_peak(name: &str, action: impl FnOnce() -> usize) -> (usize, usize) {
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let start = Instant::now();
+    let result = action();
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+    println!("{}: {:?}, peak {} bytes", name, start.elapsed(), peak);
+    (peak, result)
+}
+
+/// Writer that only counts the bytes written to it.
+struct CountingWriter(usize);
+
+impl Write for CountingWriter {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0 += bytes.len();
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Creates an array of objects with about the provided number of nodes.
+fn create_fixture(node_count: usize) -> JsonValue {
+    let items = (0..node_count / 5).map(|i| {
+        let mut obj = JsonObject::new();
+        obj.insert(String::from("id"), JsonValue::Number(i.to_string()));
+        obj.insert(String::from("name"), JsonValue::String(format!("item {}", i)));
+        obj.insert(String::from("tags"), JsonValue::Array(JsonArray::from(vec![JsonValue::String(String::from("a")), JsonValue::Null])));
+        JsonValue::Object(obj)
+    }).collect();
+    JsonValue::Array(items)
+}
@@ -0,0 +1,45 @@
+//! Compares the time to validate a large document with `validate_fast` and `parse_to_value`.
+//!
+//! Run with `cargo run --release --example validate_fast`.
+
+use std::time::{Duration, Instant};
+use jsonc_parser::{parse_to_value, validate_fast, ParseOptions};
+
+fn main() {
+    let text = create_fixture(20_000);
+    let options = ParseOptions::default();
+    assert!(validate_fast(&text, &options).is_ok());
+
+    let parse_duration = measure(|| {
+        parse_to_value(&text).unwrap();
+    });
+    let validate_duration = measure(|| {
+        validate_fast(&text, &options).unwrap();
+    });
+
+    println!("fixture: {} bytes", text.len());
+    println!("parse_to_value: {:?}", parse_duration);
+    println!("validate_fast: {:?}", validate_duration);
+    println!("speedup: {:.1}x", parse_duration.as_secs_f64() / validate_duration.as_secs_f64());
+}
+
+/// Gets the fastest of several runs.
+fn measure(mut action: impl FnMut()) -> Duration {
+    (0..10).map(|_| {
+        let start = Instant::now();
+        action();
+        start.elapsed()
+    }).min().unwrap()
+}
+
+fn create_fixture(item_count: usize) -> String {
+    let mut text = String::from("// generated fixture\n[\n");
+    for i in 0..item_count {
+        text.push_str(&format!(
+            "  {{ \"id\": {}, \"name\": \"item {}\", \"price\": {}.{:02}, \"tags\": [\"a\", \"b\\n\"], \"active\": {}, \"parent\": null }}, // item\n",
+            i, i, i % 1000, i % 100, i % 2 == 0,
+        ));
+    }
+    text.push_str("]\n");
+    text
+}
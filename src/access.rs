@@ -0,0 +1,441 @@
+use std::borrow::Cow;
+use std::iter::FromIterator;
+use super::ast::Value;
+use super::common::Range;
+use super::errors::AccessError;
+use super::strings::decode_string;
+use super::value::JsonValue;
+
+/// A value that can be read with an `Accessor`, which is either an AST value or a `JsonValue`.
+pub trait AccessValue {
+    /// Gets the value of the property with the provided name when this is an object.
+    ///
+    /// For duplicate property names, the last value is used.
+    fn property(&self, name: &str) -> Option<&Self>;
+    /// Gets the decoded names and values of the properties in order when this is an object.
+    fn properties(&self) -> Option<Vec<(Cow<'_, str>, &Self)>>;
+    /// Gets the elements when this is an array.
+    fn elements(&self) -> Option<Vec<&Self>>;
+    /// Gets the decoded value when this is a string.
+    fn string_value(&self) -> Option<Cow<'_, str>>;
+    /// Gets the text when this is a number.
+    fn number_text(&self) -> Option<&str>;
+    /// Gets the value when this is a boolean.
+    fn boolean_value(&self) -> Option<bool>;
+    /// Gets the name of the kind of value (ex. `string`).
+    fn kind_name(&self) -> &'static str;
+    /// Gets the range of the value in the text, if known.
+    fn text_range(&self) -> Option<&Range>;
+}
+
+impl AccessValue for Value {
+    fn property(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Object(obj) => obj.get_all(name).last().map(|prop| &prop.value),
+            _ => None,
+        }
+    }
+
+    fn properties(&self) -> Option<Vec<(Cow<'_, str>, &Value)>> {
+        match self {
+            Value::Object(obj) => Some(obj.properties.iter().map(|prop| (decode_string(prop.name.value.as_ref(), prop.name.quote), &prop.value)).collect()),
+            _ => None,
+        }
+    }
+
+    fn elements(&self) -> Option<Vec<&Value>> {
+        match self {
+            Value::Array(arr) => Some(arr.elements.iter().collect()),
+            _ => None,
+        }
+    }
+
+    fn string_value(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Value::StringLit(lit) => Some(decode_string(lit.value.as_ref(), lit.quote)),
+            _ => None,
+        }
+    }
+
+    fn number_text(&self) -> Option<&str> {
+        match self {
+            Value::NumberLit(lit) => Some(lit.value.as_ref()),
+            _ => None,
+        }
+    }
+
+    fn boolean_value(&self) -> Option<bool> {
+        match self {
+            Value::BooleanLit(lit) => Some(lit.value),
+            _ => None,
+        }
+    }
+
+    fn kind_name(&self) -> &'static str {
+        match self {
+            Value::StringLit(_) => "string",
+            Value::NumberLit(_) => "number",
+            Value::BooleanLit(_) => "boolean",
+            Value::Object(_) => "object",
+            Value::Array(_) => "array",
+            Value::NullKeyword(_) => "null",
+        }
+    }
+
+    fn text_range(&self) -> Option<&Range> {
+        Some(self.range())
+    }
+}
+
+impl AccessValue for JsonValue {
+    fn property(&self, name: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(obj) => obj.get(name),
+            _ => None,
+        }
+    }
+
+    fn properties(&self) -> Option<Vec<(Cow<'_, str>, &JsonValue)>> {
+        match self {
+            JsonValue::Object(obj) => Some(obj.iter().map(|(name, value)| (Cow::Borrowed(name.as_str()), value)).collect()),
+            _ => None,
+        }
+    }
+
+    fn elements(&self) -> Option<Vec<&JsonValue>> {
+        match self {
+            JsonValue::Array(elements) => Some(elements.iter().collect()),
+            _ => None,
+        }
+    }
+
+    fn string_value(&self) -> Option<Cow<'_, str>> {
+        match self {
+            JsonValue::String(value) => Some(Cow::Borrowed(value)),
+            JsonValue::Wtf8String(value) => Some(Cow::Owned(value.to_string_lossy())),
+            _ => None,
+        }
+    }
+
+    fn number_text(&self) -> Option<&str> {
+        match self {
+            JsonValue::Number(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn boolean_value(&self) -> Option<bool> {
+        match self {
+            JsonValue::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn kind_name(&self) -> &'static str {
+        match self {
+            JsonValue::String(_) | JsonValue::Wtf8String(_) => "string",
+            JsonValue::Number(_) => "number",
+            JsonValue::Boolean(_) => "boolean",
+            JsonValue::Object(_) => "object",
+            JsonValue::Array(_) => "array",
+            JsonValue::Null => "null",
+        }
+    }
+
+    fn text_range(&self) -> Option<&Range> {
+        None
+    }
+}
+
+/// Reads a value with errors that include the JSON pointer path of the value and, for AST values, its range.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::access::Accessor;
+///
+/// let value = parse_text(r#"{ "server": { "port": "80" } }"#).unwrap().value.unwrap();
+/// let err = Accessor::new(&value).required("server").and_then(|server| server.required("port")?.as_u16()).unwrap_err();
+/// assert_eq!(err.to_string(), r#""/server/port": expected an integer between 0 and 65535, found string"#);
+/// ```
+#[derive(Debug)]
+pub struct Accessor<'a, V: AccessValue> {
+    value: &'a V,
+    path: String,
+}
+
+impl<'a, V: AccessValue> Accessor<'a, V> {
+    /// Creates an accessor for the root value.
+    pub fn new(value: &'a V) -> Accessor<'a, V> {
+        Accessor {
+            value,
+            path: String::new(),
+        }
+    }
+
+    /// Creates an accessor for the value of the property with the provided name in a root object
+    /// that isn't available as a value (ex. a `JsonObject`).
+    #[cfg(feature = "serde")]
+    pub(crate) fn new_property(value: &'a V, name: &str) -> Accessor<'a, V> {
+        let path = Accessor::new(value).child_path(name);
+        Accessor { value, path }
+    }
+
+    /// Gets the value.
+    pub fn value(&self) -> &'a V {
+        self.value
+    }
+
+    /// Gets the JSON pointer path of the value (ex. `/server/port`), which is empty for the root value.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Gets the property with the provided name, erroring when it's missing or this isn't an object.
+    pub fn required(&self, name: &str) -> Result<Accessor<'a, V>, AccessError> {
+        match self.optional(name)? {
+            Some(accessor) => Ok(accessor),
+            None => Err(AccessError::new(self.child_path(name), self.value.text_range().cloned(), "missing required property")),
+        }
+    }
+
+    /// Gets the property with the provided name, erroring when this isn't an object.
+    pub fn optional(&self, name: &str) -> Result<Option<Accessor<'a, V>>, AccessError> {
+        if self.value.kind_name() != "object" {
+            return Err(self.create_error("expected an object"));
+        }
+        Ok(self.value.property(name).map(|value| Accessor {
+            value,
+            path: self.child_path(name),
+        }))
+    }
+
+    /// Gets the names and values of the properties in order, erroring when this isn't an object.
+    ///
+    /// For duplicate property names in an AST, each property is returned.
+    pub fn properties(&self) -> Result<Vec<(String, Accessor<'a, V>)>, AccessError> {
+        match self.value.properties() {
+            Some(properties) => Ok(properties.into_iter().map(|(name, value)| {
+                let path = self.child_path(&name);
+                (name.into_owned(), Accessor { value, path })
+            }).collect()),
+            None => Err(self.create_error("expected an object")),
+        }
+    }
+
+    /// Converts the value of each property and collects them with their names into a collection
+    /// chosen by the caller, erroring when this isn't an object or a conversion fails.
+    ///
+    /// The properties are collected in order, so an order-preserving collection (ex. a `Vec<(String, T)>`)
+    /// keeps the order of the text. Errors from the conversion have the path of the property's value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use jsonc_parser::parse_to_value;
+    /// use jsonc_parser::access::Accessor;
+    ///
+    /// let value = parse_to_value(r#"{ "b": { "port": 80 }, "a": { "port": "443" } }"#).unwrap().unwrap();
+    /// let err = Accessor::new(&value)
+    ///     .collect_map::<u16, BTreeMap<_, _>, _>(|server| server.required("port")?.as_u16())
+    ///     .unwrap_err();
+    /// assert_eq!(err.to_string(), r#""/a/port": expected an integer between 0 and 65535, found string"#);
+    /// ```
+    pub fn collect_map<T, M, F>(&self, mut convert: F) -> Result<M, AccessError>
+    where
+        M: FromIterator<(String, T)>,
+        F: FnMut(&Accessor<'a, V>) -> Result<T, AccessError>,
+    {
+        self.properties()?.into_iter().map(|(name, value)| Ok((name, convert(&value)?))).collect()
+    }
+
+    /// Gets the elements, erroring when this isn't an array.
+    pub fn elements(&self) -> Result<Vec<Accessor<'a, V>>, AccessError> {
+        match self.value.elements() {
+            Some(elements) => Ok(elements.into_iter().enumerate().map(|(index, value)| Accessor {
+                value,
+                path: self.child_path(&index.to_string()),
+            }).collect()),
+            None => Err(self.create_error("expected an array")),
+        }
+    }
+
+    /// Gets the decoded string, erroring when this isn't a string.
+    pub fn as_str(&self) -> Result<Cow<'a, str>, AccessError> {
+        self.value.string_value().ok_or_else(|| self.create_error("expected a string"))
+    }
+
+    /// Gets the boolean, erroring when this isn't a boolean.
+    pub fn as_bool(&self) -> Result<bool, AccessError> {
+        self.value.boolean_value().ok_or_else(|| self.create_error("expected a boolean"))
+    }
+
+    /// Gets the number, erroring when this isn't a number.
+    pub fn as_f64(&self) -> Result<f64, AccessError> {
+        match self.value.number_text().and_then(|text| text.parse::<f64>().ok()) {
+            Some(value) => Ok(value),
+            None => Err(self.create_error("expected a number")),
+        }
+    }
+
+    /// Gets the number as an integer, erroring when this isn't an integer.
+    ///
+    /// Numbers with a fraction or exponent are accepted when their value is a whole number (ex. `1e+3`).
+    pub fn as_i64(&self) -> Result<i64, AccessError> {
+        self.as_integer_between(i64::MIN, i64::MAX, "expected an integer")
+    }
+
+    /// Gets the number as an integer between 0 and 65535 (ex. a port), erroring otherwise.
+    pub fn as_u16(&self) -> Result<u16, AccessError> {
+        let message = format!("expected an integer between 0 and {}", u16::MAX);
+        self.as_integer_between(0, i64::from(u16::MAX), &message).map(|value| value as u16)
+    }
+
+    /// Gets the number as an integer between 0 and 4294967295, erroring otherwise.
+    pub fn as_u32(&self) -> Result<u32, AccessError> {
+        let message = format!("expected an integer between 0 and {}", u32::MAX);
+        self.as_integer_between(0, i64::from(u32::MAX), &message).map(|value| value as u32)
+    }
+
+    fn as_integer_between(&self, min: i64, max: i64, message: &str) -> Result<i64, AccessError> {
+        let text = match self.value.number_text() {
+            Some(text) => text,
+            None => return Err(self.create_error(message)),
+        };
+        let value = text.parse::<i64>().ok().or_else(|| match text.parse::<f64>() {
+            Ok(value) if value.fract() == 0.0 && value >= i64::MIN as f64 && value < i64::MAX as f64 => Some(value as i64),
+            _ => None,
+        });
+        match value {
+            Some(value) if value >= min && value <= max => Ok(value),
+            _ => Err(AccessError::new(self.path.clone(), self.value.text_range().cloned(), &format!("{}, found {}", message, text))),
+        }
+    }
+
+    /// Creates an error at this value that says what kind of value was found instead.
+    fn create_error(&self, expected: &str) -> AccessError {
+        let message = format!("{}, found {}", expected, self.value.kind_name());
+        AccessError::new(self.path.clone(), self.value.text_range().cloned(), &message)
+    }
+
+    fn child_path(&self, name: &str) -> String {
+        format!("{}/{}", self.path, name.replace('~', "~0").replace('/', "~1"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse_text, parse_to_value};
+
+    const TEXT: &str = r#"{
+  "server": { "port": 8080, "host": "localhost", "tls": "yes" },
+  "limits": [10, 1.5, -3, 1e+3],
+  "a/b": { "~c": null }
+}"#;
+
+    #[test]
+    fn it_reads_values() {
+        let value = parse_text(TEXT).unwrap().value.unwrap();
+        let root = Accessor::new(&value);
+        let server = root.required("server").unwrap();
+        assert_eq!(server.required("port").unwrap().as_u16().unwrap(), 8080);
+        assert_eq!(server.required("host").unwrap().as_str().unwrap(), "localhost");
+        assert!(server.optional("timeout").unwrap().is_none());
+
+        let limits = root.required("limits").unwrap().elements().unwrap();
+        assert_eq!(limits[1].as_f64().unwrap(), 1.5);
+        assert_eq!(limits[2].as_i64().unwrap(), -3);
+        assert_eq!(limits[3].as_u32().unwrap(), 1000);
+        assert_eq!(limits[3].path(), "/limits/3");
+    }
+
+    #[test]
+    fn it_creates_errors_with_paths_and_ranges() {
+        let value = parse_text(TEXT).unwrap().value.unwrap();
+        let root = Accessor::new(&value);
+        let server = root.required("server").unwrap();
+
+        let err = server.required("tls").unwrap().as_bool().unwrap_err();
+        assert_eq!(err.to_string(), r#""/server/tls": expected a boolean, found string"#);
+        assert_eq!(err.range.as_ref().map(|range| (range.start, range.start_line)), Some((58, 1)));
+
+        let err = server.required("timeout").unwrap_err();
+        assert_eq!(err.to_string(), r#""/server/timeout": missing required property"#);
+        assert_eq!(err.range.as_ref().map(|range| range.start), Some(14));
+
+        let limits = root.required("limits").unwrap().elements().unwrap();
+        let err = limits[1].as_i64().unwrap_err();
+        assert_eq!(err.to_string(), r#""/limits/1": expected an integer, found 1.5"#);
+        let err = limits[2].as_u16().unwrap_err();
+        assert_eq!(err.to_string(), r#""/limits/2": expected an integer between 0 and 65535, found -3"#);
+        assert_eq!(err.path, "/limits/2");
+
+        let err = root.required("a/b").unwrap().required("~c").unwrap().required("d").unwrap_err();
+        assert_eq!(err.to_string(), r#""/a~1b/~0c": expected an object, found null"#);
+        let err = root.required("limits").unwrap().required("a").unwrap_err();
+        assert_eq!(err.to_string(), r#""/limits": expected an object, found array"#);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Server {
+        port: u16,
+        host: String,
+    }
+
+    fn read_server<V: AccessValue>(accessor: &Accessor<'_, V>) -> Result<Server, AccessError> {
+        Ok(Server {
+            port: accessor.required("port")?.as_u16()?,
+            host: accessor.required("host")?.as_str()?.into_owned(),
+        })
+    }
+
+    #[test]
+    fn it_collects_properties_into_maps() {
+        use std::collections::BTreeMap;
+
+        let text = r#"{
+  "web": { "port": 80, "host": "a" },
+  "api": { "port": 8080, "host": "b" },
+  "a\u002Fb": { "port": 1, "host": "c" }
+}"#;
+        let expected = vec![
+            (String::from("web"), Server { port: 80, host: String::from("a") }),
+            (String::from("api"), Server { port: 8080, host: String::from("b") }),
+            (String::from("a/b"), Server { port: 1, host: String::from("c") }),
+        ];
+
+        let value = parse_text(text).unwrap().value.unwrap();
+        let servers: Vec<(String, Server)> = Accessor::new(&value).collect_map(read_server).unwrap();
+        assert_eq!(servers, expected);
+        let value = parse_to_value(text).unwrap().unwrap();
+        let servers: Vec<(String, Server)> = Accessor::new(&value).collect_map(read_server).unwrap();
+        assert_eq!(servers, expected);
+        let servers: BTreeMap<String, Server> = Accessor::new(&value).collect_map(read_server).unwrap();
+        assert_eq!(servers.keys().collect::<Vec<_>>(), vec!["a/b", "api", "web"]);
+        assert_eq!(servers["api"], expected[1].1);
+    }
+
+    #[test]
+    fn it_creates_errors_naming_the_property_that_failed_to_convert() {
+        let value = parse_text(TEXT).unwrap().value.unwrap();
+        let root = Accessor::new(&value);
+
+        let err = root.required("server").unwrap().collect_map::<bool, Vec<_>, _>(|value| value.as_bool()).unwrap_err();
+        assert_eq!(err.to_string(), r#""/server/port": expected a boolean, found number"#);
+        let err = root.required("a/b").unwrap().collect_map::<Server, Vec<_>, _>(read_server).unwrap_err();
+        assert_eq!(err.to_string(), r#""/a~1b/~0c": expected an object, found null"#);
+        let err = root.required("limits").unwrap().properties().unwrap_err();
+        assert_eq!(err.to_string(), r#""/limits": expected an object, found array"#);
+    }
+
+    #[test]
+    fn it_creates_errors_without_ranges_for_json_values() {
+        let value = parse_to_value(TEXT).unwrap().unwrap();
+        let err = Accessor::new(&value).required("server").unwrap().required("port").unwrap().elements().unwrap_err();
+        assert_eq!(err.to_string(), r#""/server/port": expected an array, found number"#);
+        assert!(err.range.is_none());
+    }
+}
@@ -1,63 +1,605 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
 use super::common::{ImmutableString, Range};
+use super::path::{self, JsonPath, PathSegment};
+use super::errors::{ErrorKind, ParseError};
+use super::parser::{parse_text, ParseResult};
+use super::strings::{decode_string, decode_string_with_offsets, get_escapes_used, EscapesUsed};
 
 /// Different kinds of JSON values.
+///
+/// Each node is annotated with its range in the text by default. Use `map_annotations`
+/// to create a tree annotated with other data, which is stored in each node's `range` field.
 #[derive(Debug, PartialEq, Clone)]
-pub enum Value {
-    StringLit(StringLit),
-    NumberLit(NumberLit),
-    BooleanLit(BooleanLit),
-    Object(Object),
-    Array(Array),
-    NullKeyword(NullKeyword),
+pub enum Value<T = Range> {
+    StringLit(StringLit<T>),
+    NumberLit(NumberLit<T>),
+    BooleanLit(BooleanLit<T>),
+    Object(Object<T>),
+    Array(Array<T>),
+    NullKeyword(NullKeyword<T>),
 }
 
-/// Node surrounded in double quotes (ex. `"my string"`).
+impl Value {
+    /// Gets the range of the value.
+    pub fn range(&self) -> &Range {
+        self.annotation()
+    }
+
+    /// Creates a copy of the tree where each node is also annotated with an identifier.
+    ///
+    /// Identifiers are assigned in the order the nodes appear in the text, starting at zero,
+    /// with object properties and their names included.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_text;
+    /// use jsonc_parser::ast::{NodeId, Value};
+    ///
+    /// let value = parse_text("[1, [2]]").unwrap().value.unwrap().with_node_ids();
+    /// match &value {
+    ///     Value::Array(arr) => assert_eq!(arr.elements[1].annotation().1, NodeId(2)),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn with_node_ids(&self) -> Value<(Range, NodeId)> {
+        let mut next_id = 0;
+        self.map_annotations(&mut |range| {
+            let id = NodeId(next_id);
+            next_id += 1;
+            (range.clone(), id)
+        })
+    }
+}
+
+/// Identifier of a node in a tree created by `Value::with_node_ids`.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+pub struct NodeId(pub u32);
+
+/// A container whose children are being fingerprinted.
+enum FingerprintFrame<'a, T> {
+    Array(std::slice::Iter<'a, Value<T>>, DefaultHasher),
+    Object(std::slice::Iter<'a, ObjectProp<T>>, DefaultHasher),
+}
+
+/// Gets a hash of the structure and content of the value that ignores the annotations (ex. ranges),
+/// which can be used to key cached results that should survive edits that only change whitespace
+/// or comments.
+///
+/// The hash includes the kind of each node, the decoded values of strings and property names, the
+/// text of numbers, and the fingerprints of the children. It is stable for the same version of the
+/// crate and compiler, but shouldn't be persisted.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::ast::structural_fingerprint;
+///
+/// let a = parse_text(r#"{"a": [1, "\u0062"]}"#).unwrap().value.unwrap();
+/// let b = parse_text("{\n  // comment\n  \"a\": [1, \"b\"]\n}").unwrap().value.unwrap();
+/// assert_eq!(structural_fingerprint(&a), structural_fingerprint(&b));
+/// ```
+pub fn structural_fingerprint<T>(value: &Value<T>) -> u64 {
+    let mut stack = Vec::new();
+    let mut next = Some(value);
+
+    loop {
+        let mut completed = match next.take() {
+            Some(Value::Object(obj)) => {
+                stack.push(FingerprintFrame::Object(obj.properties.iter(), create_hasher(0)));
+                None
+            },
+            Some(Value::Array(arr)) => {
+                stack.push(FingerprintFrame::Array(arr.elements.iter(), create_hasher(1)));
+                None
+            },
+            Some(Value::StringLit(lit)) => {
+                let mut hasher = create_hasher(2);
+                hasher.write(decode_string(lit.value.as_ref(), lit.quote).as_bytes());
+                Some(hasher.finish())
+            },
+            Some(Value::NumberLit(lit)) => {
+                let mut hasher = create_hasher(3);
+                hasher.write(lit.value.as_ref().as_bytes());
+                Some(hasher.finish())
+            },
+            Some(Value::BooleanLit(lit)) => Some(create_hasher(if lit.value { 4 } else { 5 }).finish()),
+            Some(Value::NullKeyword(_)) => Some(create_hasher(6).finish()),
+            None => None,
+        };
+
+        // add the completed fingerprints to their containers until there is another child to fingerprint
+        while next.is_none() {
+            let frame = match stack.last_mut() {
+                None => return completed.expect("Expected a completed fingerprint."),
+                Some(frame) => frame,
+            };
+            match frame {
+                FingerprintFrame::Array(elements, hasher) => {
+                    if let Some(fingerprint) = completed.take() {
+                        hasher.write_u64(fingerprint);
+                    }
+                    next = elements.next();
+                },
+                FingerprintFrame::Object(properties, hasher) => {
+                    if let Some(fingerprint) = completed.take() {
+                        hasher.write_u64(fingerprint);
+                    }
+                    next = properties.next().map(|prop| {
+                        let name = decode_string(prop.name.value.as_ref(), prop.name.quote);
+                        hasher.write_usize(name.len());
+                        hasher.write(name.as_bytes());
+                        &prop.value
+                    });
+                },
+            }
+
+            if next.is_none() {
+                completed = match stack.pop() {
+                    Some(FingerprintFrame::Array(_, hasher)) | Some(FingerprintFrame::Object(_, hasher)) => Some(hasher.finish()),
+                    None => unreachable!(),
+                };
+            }
+        }
+    }
+}
+
+fn create_hasher(kind: u8) -> DefaultHasher {
+    let mut hasher = DefaultHasher::new();
+    hasher.write_u8(kind);
+    hasher
+}
+
+impl<T> Value<T> {
+    /// Gets the node at the path or `None` when there is no node at the path.
+    ///
+    /// Like `Object::get`, the last property with a name is used when there are duplicates.
+    pub fn get_path(&self, path: &JsonPath) -> Option<&Value<T>> {
+        path.segments().iter().try_fold(self, |value, segment| value.get_child(segment))
+    }
+
+    /// Gets the nodes at the paths in a single traversal, in the same order as the paths.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_text;
+    /// use jsonc_parser::path::JsonPath;
+    ///
+    /// let value = parse_text(r#"{ "a": [1, { "b": 2 }] }"#).unwrap().value.unwrap();
+    /// let nodes = value.get_many(&["/a/1/b".parse::<JsonPath>().unwrap(), "/a/2".parse::<JsonPath>().unwrap(), "/a/0".parse::<JsonPath>().unwrap()]);
+    /// assert_eq!(nodes[0].unwrap().range().start, 18);
+    /// assert!(nodes[1].is_none());
+    /// assert_eq!(nodes[2].unwrap().range().start, 8);
+    /// ```
+    pub fn get_many<'a>(&'a self, paths: &[JsonPath]) -> Vec<Option<&'a Value<T>>> {
+        path::get_many(self, paths, Value::get_child)
+    }
+
+    fn get_child(&self, segment: &PathSegment) -> Option<&Value<T>> {
+        match self {
+            Value::Object(obj) => obj.get(&segment.as_key()),
+            Value::Array(arr) => segment.as_index().and_then(|index| arr.get(index)),
+            _ => None,
+        }
+    }
+
+    /// Gets the annotation of the value, which is its range by default.
+    pub fn annotation(&self) -> &T {
+        match self {
+            Value::StringLit(lit) => &lit.range,
+            Value::NumberLit(lit) => &lit.range,
+            Value::BooleanLit(lit) => &lit.range,
+            Value::Object(obj) => &obj.range,
+            Value::Array(arr) => &arr.range,
+            Value::NullKeyword(keyword) => &keyword.range,
+        }
+    }
+
+    /// Creates a copy of the tree where each node is annotated with the result of the provided function.
+    ///
+    /// The function is called with the annotation of each node (object properties included) in
+    /// the order the nodes appear in the text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_text;
+    /// use jsonc_parser::ast::Value;
+    ///
+    /// let value = parse_text("[1, [2]]").unwrap().value.unwrap();
+    /// let lines: Value<usize> = value.map_annotations(&mut |range| range.start_line + 1);
+    /// assert_eq!(*lines.annotation(), 1);
+    /// ```
+    pub fn map_annotations<U>(&self, f: &mut impl FnMut(&T) -> U) -> Value<U> {
+        let mut stack = Vec::new();
+        let mut next = Some(self);
+
+        loop {
+            let mut completed = match next.take() {
+                Some(Value::StringLit(lit)) => Some(Value::StringLit(StringLit {
+                    range: f(&lit.range),
+                    value: lit.value.clone(),
+                    quote: lit.quote,
+                })),
+                Some(Value::NumberLit(lit)) => Some(Value::NumberLit(NumberLit {
+                    range: f(&lit.range),
+                    value: lit.value.clone(),
+                })),
+                Some(Value::BooleanLit(lit)) => Some(Value::BooleanLit(BooleanLit {
+                    range: f(&lit.range),
+                    value: lit.value,
+                })),
+                Some(Value::NullKeyword(keyword)) => Some(Value::NullKeyword(NullKeyword {
+                    range: f(&keyword.range),
+                })),
+                Some(Value::Object(obj)) => {
+                    let range = f(&obj.range);
+                    stack.push(MapFrame::Object(obj.properties.iter(), Object {
+                        range,
+                        properties: Vec::with_capacity(obj.properties.len()),
+                    }, None));
+                    None
+                },
+                Some(Value::Array(arr)) => {
+                    let range = f(&arr.range);
+                    stack.push(MapFrame::Array(arr.elements.iter(), Array {
+                        range,
+                        elements: Vec::with_capacity(arr.elements.len()),
+                    }));
+                    None
+                },
+                None => None,
+            };
+
+            // add the completed values to their containers until there is another child to map
+            while next.is_none() {
+                match stack.last_mut() {
+                    None => return completed.expect("Expected a completed value."),
+                    Some(MapFrame::Array(elements, arr)) => {
+                        arr.elements.extend(completed.take());
+                        next = elements.next();
+                    },
+                    Some(MapFrame::Object(properties, obj, prop)) => {
+                        if let Some(value) = completed.take() {
+                            let (range, name) = prop.take().expect("Expected a property.");
+                            obj.properties.push(ObjectProp { range, name, value });
+                        }
+                        next = properties.next().map(|next_prop| {
+                            let range = f(&next_prop.range);
+                            let name = StringLit {
+                                range: f(&next_prop.name.range),
+                                value: next_prop.name.value.clone(),
+                                quote: next_prop.name.quote,
+                            };
+                            *prop = Some((range, name));
+                            &next_prop.value
+                        });
+                    },
+                }
+
+                if next.is_none() {
+                    completed = match stack.pop() {
+                        Some(MapFrame::Array(_, arr)) => Some(Value::Array(arr)),
+                        Some(MapFrame::Object(_, obj, _)) => Some(Value::Object(obj)),
+                        None => unreachable!(),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Drops the value using an explicit stack so that a deeply nested value doesn't overflow the
+    /// call stack, which dropping it normally does because that recurses once per level of nesting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_text;
+    ///
+    /// let text = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+    /// parse_text(&text).unwrap().value.unwrap().drop_nested();
+    /// ```
+    pub fn drop_nested(self) {
+        drop_values(vec![self]);
+    }
+
+    /// Moves the values of the container's children to the provided vector.
+    fn take_children(&mut self, values: &mut Vec<Value<T>>) {
+        match self {
+            Value::Object(obj) => obj.take_children(values),
+            Value::Array(arr) => values.append(&mut arr.elements),
+            _ => {},
+        }
+    }
+}
+
+impl<T: Clone> Value<T> {
+    /// Replaces the value with null and returns the previous value.
+    ///
+    /// The null keyword keeps the range of the previous value.
+    pub fn take(&mut self) -> Value<T> {
+        let null_keyword = Value::NullKeyword(NullKeyword {
+            range: self.annotation().clone(),
+        });
+        std::mem::replace(self, null_keyword)
+    }
+}
+
+/// A container whose children are being mapped.
+enum MapFrame<'a, T, U> {
+    Array(std::slice::Iter<'a, Value<T>>, Array<U>),
+    /// The properties left, the mapped object, and the range and name of the property being mapped.
+    Object(std::slice::Iter<'a, ObjectProp<T>>, Object<U>, Option<(U, StringLit<U>)>),
+}
+
+/// Drops the values, moving the children of each container to the stack before it's dropped.
+fn drop_values<T>(mut values: Vec<Value<T>>) {
+    while let Some(mut value) = values.pop() {
+        value.take_children(&mut values);
+    }
+}
+
+/// Node surrounded in quotes (ex. `"my string"`).
 #[derive(Debug, PartialEq, Clone)]
-pub struct StringLit {
-    pub range: Range,
+pub struct StringLit<T = Range> {
+    pub range: T,
+    /// Text between the quotes with its escapes unprocessed.
     pub value: ImmutableString,
+    /// Quote the string was surrounded in, which is `'` for strings scanned with single quotes.
+    pub quote: char,
+}
+
+impl<T> StringLit<T> {
+    /// Gets the text of the string as it was in the source, including its quotes.
+    pub fn raw_text(&self) -> String {
+        format!("{}{}{}", self.quote, self.value.as_ref(), self.quote)
+    }
+
+    /// Gets the kinds of escapes used in the string (ex. to flag `\u0041` where `A` could be used).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_text;
+    /// use jsonc_parser::ast::Value;
+    /// use jsonc_parser::strings::EscapesUsed;
+    ///
+    /// let parse_result = parse_text(r#""\u0041\n""#).unwrap();
+    /// match parse_result.value.unwrap() {
+    ///     Value::StringLit(lit) => assert_eq!(lit.escapes_used(), EscapesUsed::UNICODE | EscapesUsed::CONTROL),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn escapes_used(&self) -> EscapesUsed {
+        get_escapes_used(self.value.as_ref())
+    }
+
+    /// Gets if the decoded value of the string equals the provided text.
+    fn is_value(&self, text: &str) -> bool {
+        let value = self.value.as_ref();
+        if value.contains('\\') {
+            decode_string(value, self.quote) == text
+        } else {
+            value == text
+        }
+    }
+}
+
+impl StringLit<Range> {
+    /// Parses the decoded value of the string as a JSON document (ex. the value of
+    /// `"config": "{\"nested\": true}"`).
+    ///
+    /// Along with the result, this returns the positions of the embedded document's characters in
+    /// the outer text, which can be used to report errors in the embedded document.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_text;
+    /// use jsonc_parser::ast::Value;
+    ///
+    /// let text = r#"{ "config": "{\"a\": tru}" }"#;
+    /// let value = parse_text(text).unwrap().value.unwrap();
+    /// let config = match value.get_path(&"config".parse().unwrap()) {
+    ///     Some(Value::StringLit(lit)) => lit,
+    ///     _ => unreachable!(),
+    /// };
+    /// let (positions, result) = config.parse_embedded();
+    /// let err = positions.map_error(result.err().unwrap(), text);
+    /// assert_eq!(err.to_string(), "1:22: Unexpected token.");
+    /// ```
+    pub fn parse_embedded(&self) -> (EmbeddedPositions, Result<ParseResult, ParseError>) {
+        let raw_text = self.value.as_ref();
+        let decoded = decode_string_with_offsets(raw_text, self.quote);
+        let text = decoded.iter().map(|(c, _, _)| *c).collect::<String>();
+
+        // the string starts after its quote and strings may contain newlines in loose dialects
+        let value_start = self.range.start + 1;
+        let mut line_starts = Vec::with_capacity(raw_text.len() + 1);
+        let mut line = self.range.start_line;
+        for c in raw_text.chars() {
+            line_starts.push(line);
+            if c == '\n' {
+                line += 1;
+            }
+        }
+        line_starts.push(line);
+
+        let create_range = |start: usize, end: usize| Range {
+            start: value_start + start,
+            end: value_start + end,
+            start_line: line_starts[start],
+            end_line: line_starts[end],
+        };
+        let mut ranges = decoded.iter().map(|(_, start, end)| create_range(*start, *end)).collect::<Vec<_>>();
+        let end = line_starts.len() - 1;
+        ranges.push(create_range(end, end));
+
+        (EmbeddedPositions { ranges }, parse_text(&text))
+    }
+}
+
+/// Positions in the outer text of the characters of a document embedded in a string.
+///
+/// Created by `StringLit::parse_embedded`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EmbeddedPositions {
+    /// The range in the outer text of each character of the embedded document, followed by an
+    /// empty range at the closing quote for the end of the document.
+    ranges: Vec<Range>,
+}
+
+impl EmbeddedPositions {
+    /// Gets the range in the outer text of the characters at the position in the embedded
+    /// document, which is longer than a character for escapes (ex. `\u0074`).
+    ///
+    /// The end of the document maps to an empty range at the string's closing quote.
+    pub fn map_position(&self, pos: usize) -> Range {
+        self.ranges[pos.min(self.ranges.len() - 1)].clone()
+    }
+
+    /// Gets the range in the outer text of a range in the embedded document.
+    pub fn map_range(&self, range: &Range) -> Range {
+        let start = self.map_position(range.start);
+        if range.end <= range.start {
+            return Range { end: start.start, end_line: start.start_line, ..start };
+        }
+        let end = self.map_position(range.end - 1);
+        Range {
+            start: start.start,
+            end: end.end,
+            start_line: start.start_line,
+            end_line: end.end_line,
+        }
+    }
+
+    /// Moves an error in the embedded document to the corresponding position in the outer text,
+    /// including the positions and ranges of its kind.
+    pub fn map_error(&self, mut err: ParseError, outer_text: &str) -> ParseError {
+        err.pos = self.map_position(err.pos).start;
+        match &mut err.kind {
+            ErrorKind::UnexpectedEof { opened_at, insertion_point, .. } => {
+                if let Some(range) = opened_at {
+                    **range = self.map_range(range);
+                }
+                if let Some(pos) = insertion_point {
+                    *pos = self.map_position(*pos).start;
+                }
+            },
+            ErrorKind::MissingComma { insertion_point } | ErrorKind::MissingColon { insertion_point } => {
+                *insertion_point = self.map_position(*insertion_point).start;
+            },
+            ErrorKind::InvalidNumber { range, .. } => *range = self.map_range(range),
+            ErrorKind::Io(_) | ErrorKind::Other => {},
+        }
+        let source_name = err.source_name.take();
+        err.locate(outer_text.chars(), source_name.as_ref())
+    }
 }
 
 /// Represents a number (ex. `123`, `99.99`, `-1.2e+2`).
 #[derive(Debug, PartialEq, Clone)]
-pub struct NumberLit {
-    pub range: Range,
+pub struct NumberLit<T = Range> {
+    pub range: T,
+    /// Text of the number as it was in the source.
     pub value: ImmutableString,
 }
 
+impl<T> NumberLit<T> {
+    /// Gets the text of the number as it was in the source.
+    pub fn raw_text(&self) -> &str {
+        self.value.as_ref()
+    }
+}
+
 /// Represents a boolean (ex. `true` or `false`).
 #[derive(Debug, PartialEq, Clone)]
-pub struct BooleanLit {
-    pub range: Range,
+pub struct BooleanLit<T = Range> {
+    pub range: T,
     pub value: bool,
 }
 
+impl<T> BooleanLit<T> {
+    /// Gets the text of the boolean as it was in the source.
+    pub fn raw_text(&self) -> &'static str {
+        if self.value { "true" } else { "false" }
+    }
+}
+
 /// Represents the null keyword (ex. `null`).
 #[derive(Debug, PartialEq, Clone)]
-pub struct NullKeyword {
-    pub range: Range,
+pub struct NullKeyword<T = Range> {
+    pub range: T,
+}
+
+impl<T> NullKeyword<T> {
+    /// Gets the text of the keyword as it was in the source.
+    pub fn raw_text(&self) -> &'static str {
+        "null"
+    }
 }
 
 /// Represents an object that may contain properties (ex. `{}`, `{ "prop": 4 }`).
 #[derive(Debug, PartialEq, Clone)]
-pub struct Object {
-    pub range: Range,
-    pub properties: Vec<ObjectProp>,
+pub struct Object<T = Range> {
+    pub range: T,
+    pub properties: Vec<ObjectProp<T>>,
+}
+
+impl<T> Object<T> {
+    /// Gets the value of the property with the provided name.
+    ///
+    /// When the name is duplicated, this is the value of the last property, which is the value
+    /// a `JsonObject` keeps.
+    pub fn get(&self, name: &str) -> Option<&Value<T>> {
+        self.properties.iter().rev().find(|prop| prop.name.is_value(name)).map(|prop| &prop.value)
+    }
+
+    /// Gets a mutable reference to the value of the property with the provided name.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Value<T>> {
+        self.properties.iter_mut().rev().find(|prop| prop.name.is_value(name)).map(|prop| &mut prop.value)
+    }
+
+    /// Gets every property with the provided name in the order they appear in the text.
+    ///
+    /// Unlike `get`, which finds the last property, this includes properties with duplicate names.
+    pub fn get_all(&self, name: &str) -> Vec<&ObjectProp<T>> {
+        self.properties.iter().filter(|prop| prop.name.is_value(name)).collect()
+    }
+
+    fn take_children(&mut self, values: &mut Vec<Value<T>>) {
+        values.extend(std::mem::take(&mut self.properties).into_iter().map(|prop| prop.value));
+    }
 }
 
 /// Represents an object property (ex. `"prop": []`).
 #[derive(Debug, PartialEq, Clone)]
-pub struct ObjectProp {
-    pub range: Range,
-    pub name: StringLit,
-    pub value: Value,
+pub struct ObjectProp<T = Range> {
+    pub range: T,
+    pub name: StringLit<T>,
+    pub value: Value<T>,
 }
 
 /// Represents an array that may contain elements (ex. `[]` or `[5, 6]`).
 #[derive(Debug, PartialEq, Clone)]
-pub struct Array {
-    pub range: Range,
-    pub elements: Vec<Value>,
+pub struct Array<T = Range> {
+    pub range: T,
+    pub elements: Vec<Value<T>>,
+}
+
+impl<T> Array<T> {
+    /// Gets the element at the provided index.
+    pub fn get(&self, index: usize) -> Option<&Value<T>> {
+        self.elements.get(index)
+    }
+
+    /// Gets a mutable reference to the element at the provided index.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Value<T>> {
+        self.elements.get_mut(index)
+    }
 }
 
 /// Different kinds of JSONC comments.
@@ -67,16 +609,490 @@ pub enum Comment {
     Block(CommentBlock)
 }
 
+impl Comment {
+    /// Gets the kind of comment.
+    pub fn kind(&self) -> CommentKind {
+        match self {
+            Comment::Line(_) => CommentKind::Line,
+            Comment::Block(_) => CommentKind::Block,
+        }
+    }
+
+    /// Gets the text of the comment exactly as in the source, including the delimiters (ex. `// a`).
+    pub fn raw_text(&self) -> String {
+        match self {
+            Comment::Line(line) => format!("//{}", line.text.as_ref()),
+            Comment::Block(block) => format!("/*{}*/", block.text.as_ref()),
+        }
+    }
+
+    /// Gets the text between the delimiters without any trimming (ex. ` a` for `// a`).
+    pub fn inner_text(&self) -> &str {
+        match self {
+            Comment::Line(line) => line.text.as_ref(),
+            Comment::Block(block) => block.text.as_ref(),
+        }
+    }
+
+    /// Gets the content of the comment, which is the inner text without the single space that
+    /// commonly pads it from the delimiters.
+    ///
+    /// For comment blocks whose lines after the first all start with a `*` gutter (ex. JSDoc),
+    /// the gutter and the space after it are removed from each line, and a first line with only
+    /// `*` characters and a last line with only whitespace are left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_text;
+    ///
+    /// let parse_result = parse_text("/**\n * Description.\n * @since 1.0\n */\n5").unwrap();
+    /// let comment = &parse_result.comments.get(&0).unwrap()[0];
+    /// assert_eq!(comment.content(), "Description.\n@since 1.0");
+    /// ```
+    pub fn content(&self) -> String {
+        match self {
+            Comment::Line(line) => get_line_content(line.text.as_ref()),
+            Comment::Block(block) => get_block_content(block.text.as_ref()),
+        }
+    }
+
+    /// Gets the style of the comment (ex. `CommentStyle::Doc` for `/** a */`).
+    pub fn style(&self) -> CommentStyle {
+        match self {
+            Comment::Line(line) => line.style(),
+            Comment::Block(block) => block.style(),
+        }
+    }
+}
+
+/// Gets the content of a comment line, which may span several lines when comment lines were folded.
+fn get_line_content(text: &str) -> String {
+    let lines = text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).enumerate().map(|(index, line)| {
+        if index == 0 {
+            strip_space(line)
+        } else {
+            let line = line.trim_start();
+            strip_space(line.strip_prefix("//").unwrap_or(line))
+        }
+    });
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+fn get_block_content(text: &str) -> String {
+    let lines = text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).collect::<Vec<_>>();
+    if lines.len() == 1 {
+        let text = strip_space(text);
+        return String::from(text.strip_suffix(' ').unwrap_or(text));
+    }
+
+    let has_gutter = lines[1..].iter().all(|line| {
+        let line = line.trim_start();
+        line.is_empty() || line.starts_with('*')
+    });
+    let last_index = lines.len() - 1;
+    let mut result = Vec::with_capacity(lines.len());
+    for (index, line) in lines.iter().enumerate() {
+        let line = if index == 0 {
+            let line = if has_gutter { line.trim_start_matches('*') } else { line };
+            if line.trim().is_empty() {
+                continue;
+            }
+            strip_space(line)
+        } else if has_gutter {
+            let line = line.trim_start();
+            strip_space(line.strip_prefix('*').unwrap_or(line))
+        } else {
+            line
+        };
+        if index == last_index && line.trim().is_empty() {
+            continue;
+        }
+        result.push(line);
+    }
+    result.join("\n")
+}
+
+fn strip_space(text: &str) -> &str {
+    text.strip_prefix(' ').unwrap_or(text)
+}
+
+/// The kind of a comment without its data.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CommentKind {
+    /// A comment line (ex. `// my comment`).
+    Line,
+    /// A comment block (ex. `/* my comment */`).
+    Block,
+}
+
+/// The style of a comment, which is determined by the character after its opening delimiter.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CommentStyle {
+    /// A regular comment (ex. `// a` or `/* a */`).
+    Normal,
+    /// A doc comment (ex. `/// a` or `/** a */`).
+    Doc,
+    /// An inner doc comment (ex. `//! a` or `/*! a */`).
+    InnerDoc,
+}
+
+/// Gets the style of a comment from the text between its delimiters.
+///
+/// Like Rust, a delimiter followed by more of the same character (ex. `////` or `/***`) and the
+/// empty block `/**/` are normal comments.
+pub(crate) fn get_comment_style(kind: CommentKind, text: &str) -> CommentStyle {
+    let doc_char = match kind {
+        CommentKind::Line => '/',
+        CommentKind::Block => '*',
+    };
+    let mut chars = text.chars();
+    match chars.next() {
+        Some('!') => CommentStyle::InnerDoc,
+        Some(c) if c == doc_char => {
+            let is_doc = match chars.next() {
+                Some(next) => next != doc_char,
+                // `/**/` is an empty block and `///` is an empty doc comment
+                None => kind == CommentKind::Line,
+            };
+            if is_doc { CommentStyle::Doc } else { CommentStyle::Normal }
+        }
+        _ => CommentStyle::Normal,
+    }
+}
+
 /// Represents a comment line (ex. `// my comment`).
+///
+/// When parsing with `ParseOptions::fold_comment_lines`, this may span several lines, in which
+/// case the text includes the newlines, indentation, and `//` of the following lines.
 #[derive(Debug, PartialEq, Clone)]
 pub struct CommentLine {
     pub range: Range,
     pub text: ImmutableString,
 }
 
+impl CommentLine {
+    /// Gets the style of the comment line (ex. `CommentStyle::InnerDoc` for `//! a`).
+    pub fn style(&self) -> CommentStyle {
+        get_comment_style(CommentKind::Line, self.text.as_ref())
+    }
+}
+
 /// Represents a comment block (ex. `/* my comment */`).
 #[derive(Debug, PartialEq, Clone)]
 pub struct CommentBlock {
     pub range: Range,
     pub text: ImmutableString,
 }
+
+impl CommentBlock {
+    /// Gets the style of the comment block (ex. `CommentStyle::Doc` for `/** a */`).
+    pub fn style(&self) -> CommentStyle {
+        get_comment_style(CommentKind::Block, self.text.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse_text;
+
+    #[test]
+    fn it_gets_many_nodes_with_their_ranges() {
+        let text = r#"{ "a": [1, { "b": 2 }], "c": 3, "c": 4 }"#;
+        let value = parse_text(text).unwrap().value.unwrap();
+        let paths = ["/c", "/a/1/b", "/a/1/x", "/a/0", "/a/1/b"].iter().map(|path| path.parse::<JsonPath>().unwrap()).collect::<Vec<_>>();
+        let ranges = value.get_many(&paths).into_iter().map(|node| node.map(|node| node.range().start..node.range().end)).collect::<Vec<_>>();
+        assert_eq!(ranges, vec![Some(37..38), Some(18..19), None, Some(8..9), Some(18..19)]);
+        assert_eq!(value.get_path(&"/a/1".parse::<JsonPath>().unwrap()).unwrap().range().start, 11);
+    }
+
+    #[test]
+    fn it_gets_the_text_of_comments() {
+        let text = "/**\n   * Description.\n   *   indented\n   *\n   * @since 1.0\n   */\n//no-space\n// space\n/* a */ /* b\n   c */\n5";
+        let parse_result = parse_text(text).unwrap();
+        let comments = parse_result.comments.values().flat_map(|comments| comments.iter()).collect::<Vec<_>>();
+        let comment = |start: usize| *comments.iter().find(|comment| match comment {
+            Comment::Line(line) => line.range.start == start,
+            Comment::Block(block) => block.range.start == start,
+        }).unwrap();
+
+        let jsdoc = comment(0);
+        assert_eq!(jsdoc.raw_text(), &text[..64]);
+        assert_eq!(jsdoc.inner_text(), &text[2..62]);
+        assert_eq!(jsdoc.content(), "Description.\n  indented\n\n@since 1.0");
+
+        let no_space = comment(65);
+        assert_eq!((no_space.raw_text().as_str(), no_space.inner_text(), no_space.content().as_str()), ("//no-space", "no-space", "no-space"));
+        let space = comment(76);
+        assert_eq!((space.raw_text().as_str(), space.inner_text(), space.content().as_str()), ("// space", " space", "space"));
+        assert_eq!(comment(85).content(), "a");
+        // lines without a gutter are kept as-is
+        assert_eq!(comment(93).content(), "b\n   c ");
+    }
+
+    #[test]
+    fn it_gets_the_style_of_comments() {
+        let text = "/** a */ /*! b */ /* c */ /**/ /*** d */\n/// e\n//! f\n// g\n//// h\n///\n5";
+        let parse_result = parse_text(text).unwrap();
+        let mut comments = parse_result.comments.values().flat_map(|comments| comments.iter()).map(|comment| match comment {
+            Comment::Line(line) => (line.range.start, comment.style()),
+            Comment::Block(block) => (block.range.start, comment.style()),
+        }).collect::<Vec<_>>();
+        comments.sort_by_key(|(start, _)| *start);
+        comments.dedup();
+        let styles = comments.into_iter().map(|(_, style)| style).collect::<Vec<_>>();
+        assert_eq!(styles, vec![
+            CommentStyle::Doc,
+            CommentStyle::InnerDoc,
+            CommentStyle::Normal,
+            CommentStyle::Normal,
+            CommentStyle::Normal,
+            CommentStyle::Doc,
+            CommentStyle::InnerDoc,
+            CommentStyle::Normal,
+            CommentStyle::Normal,
+            CommentStyle::Doc,
+        ]);
+    }
+
+    #[test]
+    fn it_maps_errors_in_embedded_documents_to_the_outer_text() {
+        let text = "{\n  \"a\": 1,\n  \"config\": \"{\\\"nested\\\": \\u0074ru\\u00e9, \\\"\\ud83d\\ude00\\\": 1}\"\n}";
+        let value = parse_text(text).unwrap().value.unwrap();
+        let lit = match value.get_path(&"config".parse::<JsonPath>().unwrap()) {
+            Some(Value::StringLit(lit)) => lit,
+            _ => unreachable!(),
+        };
+        let (positions, result) = lit.parse_embedded();
+        let err = result.err().unwrap();
+        assert_eq!(err.pos, 11);
+        let err = positions.map_error(err, text);
+        assert_eq!(err.to_string(), "3:27: Unexpected token.");
+        let outer_text = |range: Range| text.chars().skip(range.start).take(range.end - range.start).collect::<String>();
+        assert_eq!(outer_text(positions.map_position(11)), "\\u0074");
+        assert_eq!(outer_text(positions.map_range(&Range { start: 11, end: 15, start_line: 0, end_line: 0 })), "\\u0074ru\\u00e9");
+        assert_eq!(outer_text(positions.map_position(18)), "\\ud83d\\ude00");
+        // the end of the document is at the closing quote
+        let end = positions.map_position(100);
+        assert_eq!((text.chars().nth(end.start), end.end - end.start, end.start_line), (Some('"'), 0, 2));
+    }
+
+    #[test]
+    fn it_maps_the_positions_of_each_error_kind_in_embedded_documents() {
+        // parses the JSON string literal in an object and maps the error of its embedded document
+        let get_error = |literal: &str| {
+            let text = format!("{{ \"c\": {} }}", literal);
+            let value = parse_text(&text).unwrap().value.unwrap();
+            let lit = match value.get_path(&"c".parse::<JsonPath>().unwrap()) {
+                Some(Value::StringLit(lit)) => lit,
+                _ => unreachable!(),
+            };
+            let (positions, result) = lit.parse_embedded();
+            (text, positions, result.err())
+        };
+        let outer_text = |text: &str, range: &Range| text.chars().skip(range.start).take(range.end - range.start).collect::<String>();
+
+        // "[\u0031" decodes to "[1", which ends where the closing quote is
+        let (text, positions, err) = get_error(r#""[\u0031""#);
+        let err = positions.map_error(err.unwrap(), &text);
+        match &err.kind {
+            ErrorKind::UnexpectedEof { opened_at: Some(opened_at), insertion_point: Some(insertion_point), .. } => {
+                assert_eq!(outer_text(&text, opened_at), "[");
+                assert_eq!(*insertion_point, 15);
+                assert_eq!(text.chars().nth(*insertion_point), Some('"'));
+            },
+            kind => panic!("Unexpected kind: {:?}", kind),
+        }
+
+        let (text, positions, err) = get_error(r#""{\"a\" 1}""#);
+        let err = positions.map_error(err.unwrap(), &text);
+        assert_eq!(err.kind, ErrorKind::MissingColon { insertion_point: 14 });
+        assert_eq!(&text[..14], r#"{ "c": "{\"a\""#);
+
+        let (text, positions, err) = get_error(r#""[1.\u0065]""#);
+        let err = positions.map_error(err.unwrap(), &text);
+        match &err.kind {
+            ErrorKind::InvalidNumber { range, .. } => assert_eq!(outer_text(&text, range), "1.\\u0065"),
+            kind => panic!("Unexpected kind: {:?}", kind),
+        }
+
+        // commas are optional when parsing embedded documents, so the error is created directly
+        let (text, positions, _) = get_error(r#""[\u0031 2]""#);
+        let err = positions.map_error(ParseError::new_missing_comma(3, "Expected a comma.", 2), &text);
+        assert_eq!((err.pos, err.kind), (16, ErrorKind::MissingComma { insertion_point: 15 }));
+        assert_eq!(&text[15..17], " 2");
+    }
+
+    #[test]
+    fn it_assigns_node_ids_in_text_order() {
+        let value = parse_text(r#"{ "a": [true], "b": null }"#).unwrap().value.unwrap().with_node_ids();
+        let obj = match &value {
+            Value::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        assert_eq!(obj.range.1, NodeId(0));
+        assert_eq!((obj.properties[0].range.1, obj.properties[0].name.range.1), (NodeId(1), NodeId(2)));
+        assert_eq!(obj.properties[1].value.annotation().1, NodeId(7));
+        assert_eq!(obj.properties[1].value.annotation().0.start, 20);
+    }
+
+    #[test]
+    fn it_keeps_fingerprints_for_whitespace_only_edits() {
+        let a = parse_text(r#"{"a": [1, {"b": "c"}], "d": null}"#).unwrap().value.unwrap();
+        let b = parse_text("{\n  \"a\": [\n    1,\n    { \"b\": \"c\" },\n  ],\n  \"d\": null\n}").unwrap().value.unwrap();
+        assert_eq!(get_fingerprints(&a), get_fingerprints(&b));
+    }
+
+    #[test]
+    fn it_changes_only_the_fingerprints_of_the_changed_value_and_its_ancestors() {
+        let a = get_fingerprints(&parse_text(r#"{"a": [1, {"b": "c"}, 2], "d": null}"#).unwrap().value.unwrap());
+        let b = get_fingerprints(&parse_text(r#"{"a": [1, {"b": "e"}, 2], "d": null}"#).unwrap().value.unwrap());
+        // root, "a", {"b": "c"}, and "c"
+        let changed = (0..a.len()).filter(|i| a[*i] != b[*i]).collect::<Vec<_>>();
+        assert_eq!(changed, vec![0, 1, 3, 4]);
+
+        // the kind and property names are included
+        let fingerprint = |text: &str| structural_fingerprint(&parse_text(text).unwrap().value.unwrap());
+        assert_ne!(fingerprint(r#"["1"]"#), fingerprint("[1]"));
+        assert_ne!(fingerprint(r#"{"a": 1}"#), fingerprint(r#"{"b": 1}"#));
+        assert_ne!(fingerprint("[[], []]"), fingerprint("[[[]]]"));
+    }
+
+    /// Gets the fingerprints of the values in the order they appear in the text.
+    fn get_fingerprints(value: &Value) -> Vec<u64> {
+        let mut fingerprints = vec![structural_fingerprint(value)];
+        match value {
+            Value::Object(obj) => obj.properties.iter().for_each(|prop| fingerprints.extend(get_fingerprints(&prop.value))),
+            Value::Array(arr) => arr.elements.iter().for_each(|element| fingerprints.extend(get_fingerprints(element))),
+            _ => {},
+        }
+        fingerprints
+    }
+
+    #[test]
+    fn it_mutates_nested_values() {
+        let mut value = parse_text(r#"{ "a": [1, { "b": true }] }"#).unwrap().value.unwrap();
+        let obj = match &mut value {
+            Value::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        let inner = match obj.get_mut("a") {
+            Some(Value::Array(arr)) => arr.get_mut(1).unwrap(),
+            _ => unreachable!(),
+        };
+        match inner {
+            Value::Object(obj) => match obj.get_mut("b") {
+                Some(Value::BooleanLit(lit)) => lit.value = false,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+
+        match obj.get("a") {
+            Some(Value::Array(arr)) => match arr.get(1) {
+                Some(Value::Object(obj)) => match obj.get("b") {
+                    Some(Value::BooleanLit(lit)) => assert!(!lit.value),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+        assert!(obj.get("c").is_none());
+    }
+
+    #[test]
+    fn it_gets_properties_with_escaped_names() {
+        let value = parse_text(r#"{ "\u0061": 1 }"#).unwrap().value.unwrap();
+        match value {
+            Value::Object(obj) => assert!(obj.get("a").is_some()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn it_takes_values() {
+        let mut value = parse_text(r#"{ "a": [1, 2] }"#).unwrap().value.unwrap();
+        let taken = match &mut value {
+            Value::Object(obj) => obj.get_mut("a").unwrap().take(),
+            _ => unreachable!(),
+        };
+
+        match taken {
+            Value::Array(arr) => assert_eq!(arr.elements.len(), 2),
+            _ => unreachable!(),
+        }
+        match value {
+            Value::Object(obj) => match obj.get("a") {
+                Some(Value::NullKeyword(keyword)) => assert_eq!(keyword.range.start, 7),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[derive(Debug, PartialEq, Clone)]
+    struct MyAnnotation {
+        line: usize,
+        is_container: bool,
+    }
+
+    #[test]
+    fn it_maps_annotations() {
+        let text = "{\n  \"a\": [1,\n    null],\n  \"b\": true\n}";
+        let value = parse_text(text).unwrap().value.unwrap();
+        let mut count = 0;
+        let annotated: Value<MyAnnotation> = value.map_annotations(&mut |range| {
+            count += 1;
+            MyAnnotation {
+                line: range.start_line,
+                is_container: text[range.start..].starts_with(&['{', '['][..]),
+            }
+        });
+        // object, 2 properties and their names, array, 2 elements, and a boolean
+        assert_eq!(count, 9);
+        assert_eq!(*annotated.annotation(), MyAnnotation { line: 0, is_container: true });
+
+        let obj = match &annotated {
+            Value::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        assert_eq!(obj.properties[1].range, MyAnnotation { line: 3, is_container: false });
+        match obj.get("a") {
+            Some(Value::Array(arr)) => {
+                assert_eq!(arr.range, MyAnnotation { line: 1, is_container: true });
+                assert_eq!(arr.get(1).unwrap().annotation().line, 2);
+            },
+            _ => unreachable!(),
+        }
+
+        // mapping back to ranges gives the original tree
+        let ranges: Value = value.map_annotations(&mut |range| range.clone());
+        assert_eq!(ranges, value);
+    }
+
+    #[test]
+    fn it_gets_all_properties_with_a_name() {
+        let value = parse_text(r#"{ "env": 1, "a": 2, "env": 3, "\u0065nv": 4 }"#).unwrap().value.unwrap();
+        let obj = match &value {
+            Value::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        let props = obj.get_all("env");
+        assert_eq!(props.iter().map(|prop| prop.range.start).collect::<Vec<_>>(), vec![2, 20, 30]);
+        assert!(obj.get_all("b").is_empty());
+    }
+
+    #[test]
+    fn it_gets_the_last_property_with_a_duplicate_name() {
+        let mut value = parse_text(r#"{ "a": 1, "b": 2, "a": 3 }"#).unwrap().value.unwrap();
+        assert_eq!(value.get_path(&"a".parse().unwrap()).unwrap().range().start, 23);
+        let obj = match &mut value {
+            Value::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        assert_eq!(obj.get("a").unwrap().range().start, 23);
+        assert_eq!(obj.get_mut("a").unwrap().range().start, 23);
+    }
+}
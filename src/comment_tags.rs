@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use super::ast::Comment;
+use super::common::Range;
+use super::parser::ParseResult;
+
+/// A tag found in a comment (ex. `@deprecated use "b"` in `// @deprecated use "b"`).
+#[derive(Debug, PartialEq, Clone)]
+pub struct CommentTag {
+    /// Range of the comment that contains the tag.
+    pub range: Range,
+    /// Name of the tag without the `@` (ex. `deprecated`).
+    pub name: String,
+    /// Rest of the line after the name with surrounding whitespace removed.
+    pub value: String,
+}
+
+/// Gets the tags in the comments that precede each token.
+///
+/// The key is the start position of the token following the comments, which is also
+/// the start position of the node that begins with that token. Each line of a comment
+/// that starts with `@name` produces a tag. Leading `*` characters are ignored on the
+/// lines of comment blocks.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::ast::Value;
+/// use jsonc_parser::comment_tags::parse_comment_tags;
+///
+/// let parse_result = parse_text("{\n  // @deprecated use b\n  \"a\": 1\n}").unwrap();
+/// let tags = parse_comment_tags(&parse_result);
+/// if let Some(Value::Object(obj)) = &parse_result.value {
+///     let tag = &tags[&obj.properties[0].range.start][0];
+///     assert_eq!((tag.name.as_str(), tag.value.as_str()), ("deprecated", "use b"));
+/// }
+/// ```
+pub fn parse_comment_tags(parse_result: &ParseResult) -> HashMap<usize, Vec<CommentTag>> {
+    let mut result = HashMap::new();
+
+    for token in parse_result.tokens.iter() {
+        if let Some(comments) = parse_result.comments.get(&token.range.start) {
+            let tags = comments.iter().flat_map(get_tags).collect::<Vec<_>>();
+            if !tags.is_empty() {
+                result.insert(token.range.start, tags);
+            }
+        }
+    }
+
+    result
+}
+
+/// A value along with the text of the comments that precede it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Commented<T> {
+    /// The value.
+    pub value: T,
+    /// Text of the leading comments, or `None` when there are none.
+    pub comment: Option<String>,
+}
+
+/// Deserializes the value, and the comment when deserializing a parse result with `de::from_parse_result`.
+///
+/// Other deserializers provide a struct with `value` and `comment` fields.
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Commented<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Commented<T>, D::Error> {
+        use super::de::{COMMENTED_FIELDS, COMMENTED_NAME};
+        deserializer.deserialize_struct(COMMENTED_NAME, COMMENTED_FIELDS, CommentedVisitor(std::marker::PhantomData))
+    }
+}
+
+#[cfg(feature = "serde")]
+struct CommentedVisitor<T>(std::marker::PhantomData<T>);
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for CommentedVisitor<T> {
+    type Value = Commented<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a value with a comment")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Commented<T>, A::Error> {
+        let mut value = None;
+        let mut comment = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "value" => value = Some(map.next_value()?),
+                "comment" => comment = map.next_value()?,
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                },
+            }
+        }
+        match value {
+            Some(value) => Ok(Commented { value, comment }),
+            None => Err(serde::de::Error::missing_field("value")),
+        }
+    }
+}
+
+/// Gets the text of the comments that lead the node starting at the provided position
+/// (ex. the start of an object property's range).
+///
+/// Comments on the same line as the previous token are excluded since they trail that
+/// token. Comment markers and the leading `*` characters of comment block lines are
+/// removed, and the text of multiple comments is joined with newlines.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::access::Accessor;
+/// use jsonc_parser::ast::Value;
+/// use jsonc_parser::comment_tags::{get_leading_comment, Commented};
+///
+/// let parse_result = parse_text("{\n  // Port to listen on.\n  \"port\": 8080\n}").unwrap();
+/// let value = parse_result.value.as_ref().unwrap();
+/// let port = match value {
+///     Value::Object(obj) => Commented {
+///         value: Accessor::new(&obj.properties[0].value).as_i64().unwrap(),
+///         comment: get_leading_comment(&parse_result, obj.properties[0].range.start),
+///     },
+///     _ => unreachable!(),
+/// };
+/// assert_eq!(port, Commented { value: 8080, comment: Some(String::from("Port to listen on.")) });
+/// ```
+pub fn get_leading_comment(parse_result: &ParseResult, pos: usize) -> Option<String> {
+    let comments = parse_result.comments.get(&pos)?;
+    let previous_end_line = parse_result.tokens.iter()
+        .take_while(|token| token.range.start < pos)
+        .last()
+        .map(|token| token.range.end_line);
+    let texts = comments.iter().filter_map(|comment| {
+        let (text, range, is_block) = match comment {
+            Comment::Line(line) => (line.text.as_ref(), &line.range, false),
+            Comment::Block(block) => (block.text.as_ref(), &block.range, true),
+        };
+        if previous_end_line.map(|line| range.start_line <= line).unwrap_or(false) {
+            return None;
+        }
+        let lines = text.lines().map(|line| {
+            let line = line.trim();
+            if is_block { line.trim_start_matches('*').trim() } else { line }
+        }).filter(|line| !line.is_empty()).collect::<Vec<_>>();
+        if lines.is_empty() { None } else { Some(lines.join("\n")) }
+    }).collect::<Vec<_>>();
+
+    if texts.is_empty() { None } else { Some(texts.join("\n")) }
+}
+
+fn get_tags(comment: &Comment) -> Vec<CommentTag> {
+    let (text, range, is_block) = match comment {
+        Comment::Line(line) => (line.text.as_ref(), &line.range, false),
+        Comment::Block(block) => (block.text.as_ref(), &block.range, true),
+    };
+
+    text.lines().filter_map(|line| {
+        let mut line = line.trim_start();
+        if is_block {
+            line = line.trim_start_matches('*').trim_start();
+        }
+        let line = match line.strip_prefix('@') {
+            Some(line) => line,
+            None => return None,
+        };
+        let name_end = line.find(|c: char| c.is_whitespace()).unwrap_or(line.len());
+        if name_end == 0 {
+            return None;
+        }
+
+        Some(CommentTag {
+            range: range.clone(),
+            name: String::from(&line[..name_end]),
+            value: String::from(line[name_end..].trim()),
+        })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::access::Accessor;
+    use super::super::parse_text;
+
+    #[test]
+    fn it_gets_tags_for_the_following_node() {
+        let parse_result = parse_text("{\n  \"a\": 1, // other\n  // @deprecated use b\n  \"b\": 2\n}").unwrap();
+        let tags = parse_comment_tags(&parse_result);
+        assert_eq!(tags.len(), 1);
+        let tags = &tags[&46];
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].name, "deprecated");
+        assert_eq!(tags[0].value, "use b");
+        assert_eq!(tags[0].range.start, 23);
+    }
+
+    #[test]
+    fn it_gets_multiple_tags_in_comment_blocks() {
+        let parse_result = parse_text("/**\n * Description.\n * @since 1.0\n * @internal\n */\n5").unwrap();
+        let tags = parse_comment_tags(&parse_result);
+        let tags = &tags[&51];
+        assert_eq!(tags.iter().map(|t| (t.name.as_str(), t.value.as_str())).collect::<Vec<_>>(), vec![
+            ("since", "1.0"),
+            ("internal", ""),
+        ]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Config {
+        port: Commented<i64>,
+        host: Commented<String>,
+    }
+
+    #[test]
+    fn it_reads_fields_with_their_leading_comments() {
+        let text = "{\n  // Port to listen on.\n  \"port\": 8080, // trailing\n  /**\n   * Host name.\n   */\n  \"host\": \"localhost\"\n}";
+        let parse_result = parse_text(text).unwrap();
+        let obj = match parse_result.value.as_ref().unwrap() {
+            super::super::ast::Value::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        let read_field = |name: &str| {
+            let prop = *obj.get_all(name).last().unwrap();
+            (Accessor::new(&prop.value), get_leading_comment(&parse_result, prop.range.start))
+        };
+        let (port, port_comment) = read_field("port");
+        let (host, host_comment) = read_field("host");
+        let config = Config {
+            port: Commented { value: port.as_i64().unwrap(), comment: port_comment },
+            host: Commented { value: host.as_str().unwrap().into_owned(), comment: host_comment },
+        };
+        assert_eq!(config, Config {
+            port: Commented { value: 8080, comment: Some(String::from("Port to listen on.")) },
+            host: Commented { value: String::from("localhost"), comment: Some(String::from("Host name.")) },
+        });
+    }
+
+    #[test]
+    fn it_gets_no_leading_comment_for_trailing_comments() {
+        let parse_result = parse_text("[1, // one\n2]").unwrap();
+        assert_eq!(get_leading_comment(&parse_result, 11), None);
+        assert_eq!(get_leading_comment(&parse_result, 0), None);
+    }
+
+    #[test]
+    fn it_ignores_comments_without_tags() {
+        let parse_result = parse_text("// email@example.com\n// @ nothing\n5").unwrap();
+        assert!(parse_comment_tags(&parse_result).is_empty());
+    }
+}
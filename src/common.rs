@@ -7,6 +7,7 @@ pub struct ImmutableString {
 }
 
 impl ImmutableString {
+    #[allow(clippy::should_implement_trait)]
     pub fn as_ref(&self) -> &str {
         &self.inner
     }
@@ -37,3 +38,324 @@ pub struct Range {
     /// Line of the end position of the node in the text.
     pub end_line: usize,
 }
+
+impl Range {
+    /// Gets if the ranges share at least one character.
+    ///
+    /// Ranges that only touch at an endpoint (ex. `0..2` and `2..4`) don't overlap, and neither
+    /// does an empty range with any other range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::common::Range;
+    ///
+    /// let range = |start, end| Range { start, end, start_line: 0, end_line: 0 };
+    /// assert!(range(0, 3).overlaps(&range(2, 4)));
+    /// assert!(!range(0, 2).overlaps(&range(2, 4)));
+    /// ```
+    pub fn overlaps(&self, other: &Range) -> bool {
+        self.start.max(other.start) < self.end.min(other.end)
+    }
+}
+
+/// Options describing how the parsed text was taken from the raw input.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PositionMapperOptions {
+    /// Whether a byte order mark at the start of the raw input was removed before parsing. Defaults to `false`.
+    pub bom_skipped: bool,
+    /// Number of characters of the raw input (after any byte order mark) before the parsed text. Defaults to `0`.
+    pub char_offset: usize,
+    /// Line of the raw input (zero-indexed) that the parsed text starts on. Defaults to `0`.
+    pub start_line: usize,
+}
+
+impl PositionMapperOptions {
+    /// Sets whether a byte order mark was removed before parsing.
+    pub fn with_bom_skipped(mut self, value: bool) -> Self {
+        self.bom_skipped = value;
+        self
+    }
+
+    /// Sets the number of characters of the raw input before the parsed text.
+    pub fn with_char_offset(mut self, value: usize) -> Self {
+        self.char_offset = value;
+        self
+    }
+
+    /// Sets the line of the raw input that the parsed text starts on.
+    pub fn with_start_line(mut self, value: usize) -> Self {
+        self.start_line = value;
+        self
+    }
+}
+
+/// Converts the character positions of ranges to byte offsets in the raw input (ex. the bytes of a file on disk).
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::common::{PositionMapper, PositionMapperOptions};
+///
+/// let raw_input = "\u{feff}[\"\u{e9}\"]";
+/// let value = parse_text(raw_input.trim_start_matches('\u{feff}')).unwrap().value.unwrap();
+/// let mapper = PositionMapper::new(raw_input, &PositionMapperOptions::default().with_bom_skipped(true));
+/// assert_eq!(mapper.byte_range(value.range()), Some(3..9));
+/// ```
+pub struct PositionMapper {
+    /// Byte offset in the raw input of each character of the parsed text, followed by the end of the raw input.
+    byte_offsets: Vec<usize>,
+    start_line: usize,
+}
+
+impl PositionMapper {
+    /// Creates a mapper for text that was parsed from the raw input as described by the options.
+    pub fn new(raw_input: &str, options: &PositionMapperOptions) -> PositionMapper {
+        let mut start = 0;
+        if options.bom_skipped && raw_input.starts_with('\u{feff}') {
+            start = '\u{feff}'.len_utf8();
+        }
+        let chars = raw_input[start..].char_indices().skip(options.char_offset);
+        let mut byte_offsets = chars.map(|(index, _)| start + index).collect::<Vec<_>>();
+        byte_offsets.push(raw_input.len());
+        PositionMapper {
+            byte_offsets,
+            start_line: options.start_line,
+        }
+    }
+
+    /// Gets the byte offset in the raw input of a position in the parsed text.
+    ///
+    /// Returns `None` when the position is after the end of the text.
+    pub fn byte_offset(&self, pos: usize) -> Option<usize> {
+        self.byte_offsets.get(pos).copied()
+    }
+
+    /// Gets the byte range in the raw input of a range in the parsed text.
+    pub fn byte_range(&self, range: &Range) -> Option<std::ops::Range<usize>> {
+        Some(self.byte_offset(range.start)?..self.byte_offset(range.end)?)
+    }
+
+    /// Gets the lines in the raw input (zero-indexed) that a range in the parsed text starts
+    /// and ends on.
+    pub fn line_range(&self, range: &Range) -> (usize, usize) {
+        (self.start_line + range.start_line, self.start_line + range.end_line)
+    }
+}
+
+/// Gets the UTF-8 bytes of the source text in the range.
+///
+/// The range's character positions are converted to byte offsets by scanning the text up to the
+/// end of the range. Use a `PositionMapper` when getting the bytes of many ranges.
+///
+/// # Panics
+///
+/// Panics when the range ends after the end of the text, like slicing out of bounds.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::common::token_bytes;
+///
+/// let text = "[\"\u{e9}\"]";
+/// let token = &parse_text(text).unwrap().tokens[1];
+/// assert_eq!(token_bytes(text, &token.range), "\"\u{e9}\"".as_bytes());
+/// ```
+pub fn token_bytes<'a>(source: &'a str, range: &Range) -> &'a [u8] {
+    let mut byte_offsets = source.char_indices().map(|(index, _)| index).chain(std::iter::once(source.len()));
+    let start = byte_offsets.nth(range.start).expect("The range should start within the text.");
+    let end = match range.end.checked_sub(range.start + 1) {
+        Some(count) => byte_offsets.nth(count).expect("The range should end within the text."),
+        None => start,
+    };
+    &source.as_bytes()[start..end]
+}
+
+/// Converts character positions to lines and columns, which is faster than scanning the text
+/// for each position when converting many positions in the same text.
+///
+/// Lines and columns are zero-indexed and columns are in characters, like `ParseError`.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::common::LineIndex;
+///
+/// let index = LineIndex::new("{\n  \"a\": 1\n}");
+/// assert_eq!(index.line_col(9), (1, 7));
+/// assert_eq!(index.line_start(2), Some(11));
+/// ```
+pub struct LineIndex {
+    /// Character position of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    /// Creates an index of the lines in the text.
+    pub fn new(text: &str) -> LineIndex {
+        let mut line_starts = vec![0];
+        for (pos, c) in text.chars().enumerate() {
+            if c == '\n' {
+                line_starts.push(pos + 1);
+            }
+        }
+        LineIndex { line_starts }
+    }
+
+    /// Gets the line and column of a position.
+    ///
+    /// Positions after the end of the text are on the last line.
+    pub fn line_col(&self, pos: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&pos) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        (line, pos - self.line_starts[line])
+    }
+
+    /// Gets the position of the start of a line.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line).copied()
+    }
+
+    /// Gets the number of lines.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ast::Value;
+    use super::super::parse_text;
+
+    #[test]
+    fn it_maps_positions_after_a_bom_and_multibyte_chars() {
+        let raw_input = "\u{feff}{ \"\u{e9}\u{1F600}\": [\"\u{20ac}\", 1] }";
+        let text = raw_input.trim_start_matches('\u{feff}');
+        let value = parse_text(text).unwrap().value.unwrap();
+        let mapper = PositionMapper::new(raw_input, &PositionMapperOptions::default().with_bom_skipped(true));
+
+        let prop = match &value {
+            Value::Object(obj) => &obj.properties[0],
+            _ => unreachable!(),
+        };
+        assert_eq!(&raw_input[mapper.byte_range(&prop.name.range).unwrap()], "\"\u{e9}\u{1F600}\"");
+        let elements = match &prop.value {
+            Value::Array(arr) => &arr.elements,
+            _ => unreachable!(),
+        };
+        assert_eq!(&raw_input[mapper.byte_range(elements[0].range()).unwrap()], "\"\u{20ac}\"");
+        assert_eq!(&raw_input[mapper.byte_range(elements[1].range()).unwrap()], "1");
+        assert_eq!(mapper.byte_range(value.range()), Some(3..raw_input.len()));
+        assert_eq!(mapper.byte_offset(value.range().end + 1), None);
+    }
+
+    #[test]
+    fn it_maps_positions_without_a_bom() {
+        // a skipped byte order mark that wasn't in the raw input doesn't change the offsets
+        let raw_input = "[\"\u{e9}\", 2]";
+        let value = parse_text(raw_input).unwrap().value.unwrap();
+        for options in [PositionMapperOptions::default(), PositionMapperOptions::default().with_bom_skipped(true)].iter() {
+            assert_eq!(PositionMapper::new(raw_input, options).byte_range(value.range()), Some(0..raw_input.len()));
+        }
+
+        // the byte order mark is kept in the parsed text when it wasn't skipped
+        let mapper = PositionMapper::new("\u{feff}\u{e9}", &PositionMapperOptions::default());
+        assert_eq!(mapper.byte_offset(1), Some(3));
+    }
+
+    #[test]
+    fn it_maps_positions_of_text_at_a_char_offset() {
+        let raw_input = "\u{feff}config = {\"\u{e9}\": 1}";
+        let text = &raw_input["\u{feff}config = ".len()..];
+        let value = parse_text(text).unwrap().value.unwrap();
+        let options = PositionMapperOptions::default().with_bom_skipped(true).with_char_offset("config = ".len());
+        let mapper = PositionMapper::new(raw_input, &options);
+        assert_eq!(&raw_input[mapper.byte_range(value.range()).unwrap()], "{\"\u{e9}\": 1}");
+    }
+
+    #[test]
+    fn it_maps_lines_of_text_at_a_start_line() {
+        let raw_input = "\u{feff}# \u{1F600}\nconfig = {\n  \"\u{e9}\": [\n    1\n  ]\n}";
+        let text = &raw_input["\u{feff}# \u{1F600}\nconfig = ".len()..];
+        let value = parse_text(text).unwrap().value.unwrap();
+        let options = PositionMapperOptions::default()
+            .with_bom_skipped(true)
+            .with_char_offset("# \u{1F600}\nconfig = ".chars().count())
+            .with_start_line(1);
+        let mapper = PositionMapper::new(raw_input, &options);
+        let prop_value = match &value {
+            Value::Object(obj) => &obj.properties[0].value,
+            _ => unreachable!(),
+        };
+        assert_eq!(mapper.line_range(value.range()), (1, 5));
+        assert_eq!(mapper.line_range(prop_value.range()), (2, 4));
+        assert_eq!(&raw_input[mapper.byte_range(prop_value.range()).unwrap()], "[\n    1\n  ]");
+        assert_eq!(PositionMapper::new(text, &PositionMapperOptions::default()).line_range(prop_value.range()), (1, 3));
+    }
+
+    #[test]
+    fn it_gets_the_same_lines_and_columns_as_scanning() {
+        let text = "{\n  \"\u{e9}\": [1,\r\n\n    2], // \u{1F600}\n}\n";
+        let index = LineIndex::new(text);
+        assert_eq!(index.line_count(), 6);
+        for pos in 0..=text.chars().count() {
+            let mut line = 0;
+            let mut column = 0;
+            for c in text.chars().take(pos) {
+                if c == '\n' {
+                    line += 1;
+                    column = 0;
+                } else {
+                    column += 1;
+                }
+            }
+            assert_eq!(index.line_col(pos), (line, column), "pos {}", pos);
+        }
+    }
+
+    #[test]
+    fn it_indexes_text_without_newlines() {
+        let index = LineIndex::new("");
+        assert_eq!(index.line_col(0), (0, 0));
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(index.line_start(1), None);
+    }
+
+    #[test]
+    fn it_gets_the_bytes_of_ranges() {
+        let text = "{ \"\u{e9}\u{1F600}\": [\"a\u{20ac}\"] }";
+        let tokens = parse_text(text).unwrap().tokens;
+        let bytes = tokens.iter().map(|token| token_bytes(text, &token.range)).collect::<Vec<_>>();
+        assert_eq!(bytes[1], "\"\u{e9}\u{1F600}\"".as_bytes());
+        assert_eq!(bytes[1].len(), 8);
+        assert_eq!(bytes[4], "\"a\u{20ac}\"".as_bytes());
+        assert_eq!(bytes[4].len(), 6);
+        assert_eq!(bytes[6], b"}");
+        let range = |start: usize, end: usize| Range { start, end, start_line: 0, end_line: 0 };
+        assert_eq!(token_bytes(text, &range(3, 3)), b"");
+        assert_eq!(token_bytes(text, &range(16, 16)), b"");
+    }
+
+    #[test]
+    fn it_checks_if_ranges_overlap() {
+        let range = |start, end| Range { start, end, start_line: 0, end_line: 0 };
+        // overlapping
+        assert!(range(0, 3).overlaps(&range(2, 5)));
+        assert!(range(2, 5).overlaps(&range(0, 3)));
+        assert!(range(0, 5).overlaps(&range(1, 2)));
+        assert!(range(1, 2).overlaps(&range(1, 2)));
+        // adjacent
+        assert!(!range(0, 2).overlaps(&range(2, 4)));
+        assert!(!range(2, 4).overlaps(&range(0, 2)));
+        // disjoint
+        assert!(!range(0, 1).overlaps(&range(3, 4)));
+        // empty
+        assert!(!range(2, 2).overlaps(&range(0, 4)));
+        assert!(!range(2, 2).overlaps(&range(2, 2)));
+    }
+}
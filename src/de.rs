@@ -0,0 +1,440 @@
+//! Deserializing values with serde, which requires the `serde` feature.
+//!
+//! Errors have the JSON pointer path of the value that failed and, for AST values, its range.
+//! When deserializing a parse result, `Commented` values also get the comments that lead them.
+
+use serde::de::{self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::Deserialize;
+use std::borrow::Cow;
+use super::access::{AccessValue, Accessor};
+use super::comment_tags::get_leading_comment;
+use super::errors::AccessError;
+use super::numbers::to_json_number_text;
+use super::parser::ParseResult;
+
+/// Name of the struct that `Commented` deserializes as, which lets this deserializer provide the comment.
+pub(crate) const COMMENTED_NAME: &str = "$jsonc_parser::Commented";
+/// Fields of the struct that `Commented` deserializes as.
+pub(crate) const COMMENTED_FIELDS: &[&str] = &["value", "comment"];
+
+/// Deserializes a value of the parse result, where each `Commented` value gets the text of the
+/// comments that lead its property (see `comment_tags::get_leading_comment`).
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::comment_tags::Commented;
+/// use jsonc_parser::de::from_parse_result;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     port: Commented<i64>,
+/// }
+///
+/// let parse_result = parse_text("{\n  // Port to listen on.\n  \"port\": 8080\n}").unwrap();
+/// let config: Config = from_parse_result(&parse_result).unwrap();
+/// assert_eq!(config.port, Commented { value: 8080, comment: Some(String::from("Port to listen on.")) });
+/// ```
+pub fn from_parse_result<'a, T: Deserialize<'a>>(parse_result: &'a ParseResult) -> Result<T, AccessError> {
+    match &parse_result.value {
+        Some(value) => {
+            let mut deserializer = Deserializer::new(value);
+            deserializer.parse_result = Some(parse_result);
+            T::deserialize(deserializer)
+        },
+        None => Err(AccessError::new(String::new(), None, "expected a value, found nothing")),
+    }
+}
+
+/// Deserializes an AST value or a `JsonValue`.
+///
+/// `Commented` values don't get comments since the value isn't part of a parse result.
+pub fn from_value<'a, T: Deserialize<'a>, V: AccessValue>(value: &'a V) -> Result<T, AccessError> {
+    T::deserialize(Deserializer::new(value))
+}
+
+/// Serde deserializer for an AST value or a `JsonValue`.
+pub struct Deserializer<'a, V: AccessValue> {
+    accessor: Accessor<'a, V>,
+    /// Parse result the value is from, which has the comments.
+    parse_result: Option<&'a ParseResult>,
+    /// Whether the value is the value of an object property, which is where the leading comments are found.
+    is_property_value: bool,
+}
+
+impl<'a, V: AccessValue> Deserializer<'a, V> {
+    /// Creates a deserializer for the root value.
+    pub fn new(value: &'a V) -> Deserializer<'a, V> {
+        Deserializer::from_accessor(Accessor::new(value))
+    }
+
+    /// Creates a deserializer for the value of the accessor, whose path is used in errors.
+    pub fn from_accessor(accessor: Accessor<'a, V>) -> Deserializer<'a, V> {
+        Deserializer {
+            accessor,
+            parse_result: None,
+            is_property_value: false,
+        }
+    }
+
+    fn child(&self, accessor: Accessor<'a, V>, is_property_value: bool) -> Deserializer<'a, V> {
+        Deserializer {
+            accessor,
+            parse_result: self.parse_result,
+            is_property_value,
+        }
+    }
+
+    /// Gets the text of the comments that lead the property this is the value of.
+    fn leading_comment(&self) -> Option<String> {
+        let parse_result = self.parse_result?;
+        if !self.is_property_value {
+            return None;
+        }
+        // the property starts at its name, which is the token before the colon that precedes the value
+        let value_start = self.accessor.value().text_range()?.start;
+        let tokens = &parse_result.tokens;
+        let index = tokens.binary_search_by_key(&value_start, |token| token.range.start).ok()?;
+        let name = tokens.get(index.checked_sub(2)?)?;
+        get_leading_comment(parse_result, name.range.start)
+    }
+
+    /// Sets the path and range of this value on errors that don't have them (ex. those created by a visitor).
+    fn locate<T>(&self, result: Result<T, AccessError>) -> Result<T, AccessError> {
+        result.map_err(|mut err| {
+            if err.path.is_empty() && err.range.is_none() {
+                err.path = String::from(self.accessor.path());
+                err.range = self.accessor.value().text_range().cloned();
+            }
+            err
+        })
+    }
+
+    fn create_error(&self, message: &str) -> AccessError {
+        AccessError::new(String::from(self.accessor.path()), self.accessor.value().text_range().cloned(), message)
+    }
+}
+
+impl<'de, A: AccessValue> de::Deserializer<'de> for Deserializer<'de, A> {
+    type Error = AccessError;
+
+    fn deserialize_any<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, AccessError> {
+        let value = self.accessor.value();
+        let result = match value.kind_name() {
+            "null" => visitor.visit_unit(),
+            "boolean" => visitor.visit_bool(self.accessor.as_bool()?),
+            "string" => match self.accessor.as_str()? {
+                Cow::Borrowed(text) => visitor.visit_borrowed_str(text),
+                Cow::Owned(text) => visitor.visit_string(text),
+            },
+            "number" => {
+                let text = match to_json_number_text(value.number_text().unwrap_or_default()) {
+                    Ok(text) => text,
+                    Err(err) => return Err(self.create_error(&err.message)),
+                };
+                if let Ok(value) = text.parse::<u64>() {
+                    visitor.visit_u64(value)
+                } else if let Ok(value) = text.parse::<i64>() {
+                    visitor.visit_i64(value)
+                } else {
+                    match text.parse::<f64>() {
+                        Ok(value) => visitor.visit_f64(value),
+                        Err(_) => return Err(self.create_error(&format!("expected a number, found {}", text))),
+                    }
+                }
+            },
+            "array" => {
+                let elements = self.accessor.elements()?.into_iter().map(|element| self.child(element, false)).collect::<Vec<_>>();
+                visitor.visit_seq(SeqDeserializer { elements: elements.into_iter() })
+            },
+            _ => {
+                let properties = self.accessor.properties()?.into_iter().map(|(name, value)| (name, self.child(value, true))).collect::<Vec<_>>();
+                visitor.visit_map(MapDeserializer {
+                    properties: properties.into_iter(),
+                    value: None,
+                })
+            },
+        };
+        self.locate(result)
+    }
+
+    fn deserialize_option<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, AccessError> {
+        if self.accessor.value().kind_name() == "null" {
+            self.locate(visitor.visit_none())
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<W: Visitor<'de>>(self, _name: &'static str, visitor: W) -> Result<W::Value, AccessError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<W: Visitor<'de>>(self, name: &'static str, fields: &'static [&'static str], visitor: W) -> Result<W::Value, AccessError> {
+        if name != COMMENTED_NAME {
+            return self.deserialize_any(visitor);
+        }
+        // errors from the value have its path, so the value's deserializer is used as it is
+        let comment = self.leading_comment();
+        visitor.visit_map(CommentedDeserializer {
+            fields: fields.iter(),
+            value: Some(Deserializer { is_property_value: false, ..self }),
+            comment,
+        })
+    }
+
+    fn deserialize_enum<W: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: W) -> Result<W::Value, AccessError> {
+        let result = match self.accessor.value().kind_name() {
+            "string" => visitor.visit_enum(self.accessor.as_str()?.into_owned().into_deserializer()),
+            "object" => {
+                let mut properties = self.accessor.properties()?;
+                if properties.len() != 1 {
+                    return Err(self.create_error("expected an object with one property for an enum variant"));
+                }
+                let (name, value) = properties.remove(0);
+                visitor.visit_enum(EnumDeserializer {
+                    name,
+                    value: self.child(value, true),
+                })
+            },
+            kind => return Err(self.create_error(&format!("expected a string or an object for an enum variant, found {}", kind))),
+        };
+        self.locate(result)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map identifier ignored_any
+    }
+}
+
+impl de::Error for AccessError {
+    fn custom<T: std::fmt::Display>(message: T) -> AccessError {
+        AccessError::new(String::new(), None, &message.to_string())
+    }
+}
+
+struct SeqDeserializer<'a, A: AccessValue> {
+    elements: std::vec::IntoIter<Deserializer<'a, A>>,
+}
+
+impl<'de, A: AccessValue> SeqAccess<'de> for SeqDeserializer<'de, A> {
+    type Error = AccessError;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>, AccessError> {
+        self.elements.next().map(|element| seed.deserialize(element)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+struct MapDeserializer<'a, A: AccessValue> {
+    properties: std::vec::IntoIter<(String, Deserializer<'a, A>)>,
+    /// Value of the property whose name was provided last.
+    value: Option<Deserializer<'a, A>>,
+}
+
+impl<'de, A: AccessValue> MapAccess<'de> for MapDeserializer<'de, A> {
+    type Error = AccessError;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>, AccessError> {
+        match self.properties.next() {
+            Some((name, value)) => {
+                let key = value.locate(seed.deserialize(name.into_deserializer()));
+                self.value = Some(value);
+                key.map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, AccessError> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.properties.len())
+    }
+}
+
+/// Provides the fields of a `Commented` value, which are the value itself and its leading comment.
+struct CommentedDeserializer<'a, A: AccessValue> {
+    fields: std::slice::Iter<'static, &'static str>,
+    value: Option<Deserializer<'a, A>>,
+    comment: Option<String>,
+}
+
+impl<'de, A: AccessValue> MapAccess<'de> for CommentedDeserializer<'de, A> {
+    type Error = AccessError;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>, AccessError> {
+        match self.fields.next() {
+            Some(field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, AccessError> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => seed.deserialize(CommentDeserializer(self.comment.take())),
+        }
+    }
+}
+
+/// Deserializes the optional text of a comment.
+struct CommentDeserializer(Option<String>);
+
+impl<'de> de::Deserializer<'de> for CommentDeserializer {
+    type Error = AccessError;
+
+    fn deserialize_any<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, AccessError> {
+        match self.0 {
+            Some(text) => visitor.visit_some(text.into_deserializer()),
+            None => visitor.visit_none(),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Provides an enum variant written as an object with one property (ex. `{ "Rgb": [0, 0, 0] }`).
+struct EnumDeserializer<'a, A: AccessValue> {
+    name: String,
+    value: Deserializer<'a, A>,
+}
+
+impl<'de, A: AccessValue> EnumAccess<'de> for EnumDeserializer<'de, A> {
+    type Error = AccessError;
+    type Variant = Deserializer<'de, A>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, Deserializer<'de, A>), AccessError> {
+        let variant = self.value.locate(seed.deserialize(self.name.into_deserializer()))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de, A: AccessValue> VariantAccess<'de> for Deserializer<'de, A> {
+    type Error = AccessError;
+
+    fn unit_variant(self) -> Result<(), AccessError> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, AccessError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<W: Visitor<'de>>(self, _len: usize, visitor: W) -> Result<W::Value, AccessError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<W: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: W) -> Result<W::Value, AccessError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use serde::Deserialize;
+    use super::{from_parse_result, from_value};
+    use super::super::comment_tags::Commented;
+    use super::super::{parse_text, parse_to_value};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+        tls: Option<bool>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Mode {
+        Fast,
+        Limit(u32),
+    }
+
+    #[test]
+    fn it_deserializes_values() {
+        let text = r#"{
+  // a comment
+  "servers": { "a": { "host": "local\u0068ost", "port": 80, }, "b": { "host": "example.com", "port": 443, "tls": true } },
+  "modes": ["Fast", { "Limit": 10 }],
+  "ratio": 1.5,
+}"#;
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Config {
+            servers: HashMap<String, Server>,
+            modes: Vec<Mode>,
+            ratio: f64,
+        }
+
+        let parse_result = parse_text(text).unwrap();
+        let config: Config = from_parse_result(&parse_result).unwrap();
+        assert_eq!(config.servers["a"], Server { host: String::from("localhost"), port: 80, tls: None });
+        assert_eq!(config.servers["b"].tls, Some(true));
+        assert_eq!(config.modes, vec![Mode::Fast, Mode::Limit(10)]);
+        assert_eq!(config.ratio, 1.5);
+
+        let value = parse_to_value(text).unwrap().unwrap();
+        assert_eq!(from_value::<Config, _>(&value).unwrap(), config);
+    }
+
+    #[test]
+    fn it_errors_with_the_path_and_range_of_the_value() {
+        let parse_result = parse_text(r#"{ "servers": [{ "host": "a", "port": 70000 }, { "port": 1 }] }"#).unwrap();
+        #[derive(Debug, Deserialize)]
+        struct Config {
+            #[allow(dead_code)]
+            servers: Vec<Server>,
+        }
+
+        let err = from_parse_result::<Config>(&parse_result).unwrap_err();
+        assert_eq!(err.to_string(), r#""/servers/0/port": invalid value: integer `70000`, expected u16"#);
+        assert_eq!(err.range.map(|range| range.start), Some(37));
+
+        let parse_result = parse_text(r#"{ "servers": [{ "port": 1 }] }"#).unwrap();
+        let err = from_parse_result::<Config>(&parse_result).unwrap_err();
+        assert_eq!(err.to_string(), r#""/servers/0": missing field `host`"#);
+    }
+
+    #[test]
+    fn it_deserializes_leading_comments() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Config {
+            port: Commented<i64>,
+            host: Commented<String>,
+            limits: Vec<Commented<u32>>,
+        }
+
+        let text = r#"{
+  /**
+   * Port to listen on.
+   */
+  "port": 8080, // trailing
+  "host": "localhost",
+  // not on elements
+  "limits": [1]
+}"#;
+        let config: Config = from_parse_result(&parse_text(text).unwrap()).unwrap();
+        assert_eq!(config.port, Commented { value: 8080, comment: Some(String::from("Port to listen on.")) });
+        assert_eq!(config.host, Commented { value: String::from("localhost"), comment: None });
+        assert_eq!(config.limits, vec![Commented { value: 1, comment: None }]);
+
+        // values without a parse result don't have comments
+        let value = parse_to_value(text).unwrap().unwrap();
+        let config: Config = from_value(&value).unwrap();
+        assert_eq!(config.port, Commented { value: 8080, comment: None });
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::HashSet;
+use super::ast::*;
+use super::common::Range;
+use super::errors::ErrorKind;
+use super::lint::trailing_whitespace;
+use super::parser::{parse_text_tolerant, TolerantParseResult};
+use super::scanner::Scanner;
+use super::strings::decode_string;
+use super::tokens::TokenAndRange;
+
+/// A problem found in the text.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub range: Range,
+    /// Kind of the error when the diagnostic is a parse error, otherwise `None`.
+    pub kind: Option<ErrorKind>,
+    pub message: String,
+}
+
+/// Options for the warnings reported by `parse_with_diagnostics`.
+///
+/// All warnings are enabled by default.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DiagnosticOptions {
+    /// Warn about object properties with the same name.
+    pub duplicate_keys: bool,
+    /// Warn about numbers that can't be represented exactly as a 64-bit float.
+    pub lossy_numbers: bool,
+    /// Warn about whitespace at the end of lines.
+    pub trailing_whitespace: bool,
+    /// Maximum number of errors to report. Defaults to `None`, which reports all errors.
+    pub max_errors: Option<usize>,
+    /// What to do when an error is found after reporting the maximum number of errors.
+    /// Defaults to `ErrorLimitBehavior::Recover`.
+    pub error_limit_behavior: ErrorLimitBehavior,
+}
+
+/// What to do when an error is found after reporting `DiagnosticOptions::max_errors` errors.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorLimitBehavior {
+    /// Keep parsing without reporting more errors to get the best-effort value.
+    Recover,
+    /// Stop parsing, which leaves out the value.
+    Stop,
+}
+
+impl Default for DiagnosticOptions {
+    fn default() -> DiagnosticOptions {
+        DiagnosticOptions {
+            duplicate_keys: true,
+            lossy_numbers: true,
+            trailing_whitespace: true,
+            max_errors: None,
+            error_limit_behavior: ErrorLimitBehavior::Recover,
+        }
+    }
+}
+
+impl DiagnosticOptions {
+    /// Sets whether to warn about duplicate property names.
+    pub fn with_duplicate_keys(mut self, value: bool) -> Self {
+        self.duplicate_keys = value;
+        self
+    }
+
+    /// Sets whether to warn about numbers that can't be represented exactly.
+    pub fn with_lossy_numbers(mut self, value: bool) -> Self {
+        self.lossy_numbers = value;
+        self
+    }
+
+    /// Sets whether to warn about whitespace at the end of lines.
+    pub fn with_trailing_whitespace(mut self, value: bool) -> Self {
+        self.trailing_whitespace = value;
+        self
+    }
+
+    /// Sets the maximum number of errors to report.
+    pub fn with_max_errors(mut self, value: usize) -> Self {
+        self.max_errors = Some(value);
+        self
+    }
+
+    /// Sets what to do when an error is found after reporting the maximum number of errors.
+    pub fn with_error_limit_behavior(mut self, value: ErrorLimitBehavior) -> Self {
+        self.error_limit_behavior = value;
+        self
+    }
+}
+
+/// Result of parsing the text with diagnostics.
+pub struct DiagnosticsParseResult {
+    /// The best-effort JSON value the text contained.
+    pub value: Option<Value>,
+    /// Errors that make the text invalid.
+    pub errors: Vec<Diagnostic>,
+    /// Whether errors were left out because of `DiagnosticOptions::max_errors`.
+    pub errors_truncated: bool,
+    /// Problems that don't make the text invalid.
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Parses a string containing JSONC, recovering from errors, and reports all the
+/// errors and warnings found.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::diagnostics::{parse_with_diagnostics, DiagnosticOptions};
+///
+/// let result = parse_with_diagnostics(r#"{ "a": 1, "a": 2, "b" 3 }"#, &DiagnosticOptions::default());
+/// assert!(result.value.is_some());
+/// assert_eq!(result.errors[0].message, "Expected a colon after the string in an object property.");
+/// assert_eq!(result.warnings[0].message, "Duplicate property name \"a\".");
+/// ```
+pub fn parse_with_diagnostics(text: &str, options: &DiagnosticOptions) -> DiagnosticsParseResult {
+    let stop_at_max_errors = options.error_limit_behavior == ErrorLimitBehavior::Stop;
+    let TolerantParseResult { parse_result, errors, errors_truncated } = parse_text_tolerant(text, options.max_errors, stop_at_max_errors);
+    let mut warnings = Vec::new();
+
+    if let Some(value) = &parse_result.value {
+        let mut stack = vec![value];
+        while let Some(value) = stack.pop() {
+            match value {
+                Value::Object(obj) => {
+                    if options.duplicate_keys {
+                        add_duplicate_key_warnings(&mut warnings, obj);
+                    }
+                    stack.extend(obj.properties.iter().rev().map(|prop| &prop.value));
+                },
+                Value::Array(arr) => stack.extend(arr.elements.iter().rev()),
+                Value::NumberLit(lit) if options.lossy_numbers && is_lossy_number(lit.value.as_ref()) => {
+                    warnings.push(Diagnostic {
+                        range: lit.range.clone(),
+                        kind: None,
+                        message: String::from("Number cannot be represented exactly as a 64-bit float."),
+                    });
+                },
+                _ => {},
+            }
+        }
+    }
+
+    if options.trailing_whitespace {
+        warnings.extend(trailing_whitespace(&scan_lossless_tolerant(text)).into_iter().map(|range| Diagnostic {
+            range,
+            kind: None,
+            message: String::from("Trailing whitespace."),
+        }));
+    }
+
+    warnings.sort_by_key(|warning| warning.range.start);
+
+    DiagnosticsParseResult {
+        value: parse_result.value,
+        errors,
+        errors_truncated,
+        warnings,
+    }
+}
+
+fn add_duplicate_key_warnings(warnings: &mut Vec<Diagnostic>, obj: &Object) {
+    let mut names = HashSet::new();
+    for prop in obj.properties.iter() {
+        let name = decode_string(prop.name.value.as_ref(), prop.name.quote);
+        if names.contains(&name) {
+            warnings.push(Diagnostic {
+                range: prop.name.range.clone(),
+                kind: None,
+                message: format!("Duplicate property name \"{}\".", name),
+            });
+        } else {
+            names.insert(name);
+        }
+    }
+}
+
+fn is_lossy_number(text: &str) -> bool {
+    let value = match text.parse::<f64>() {
+        Ok(value) => value,
+        Err(_) => return false,
+    };
+
+    if value.is_infinite() {
+        return true;
+    }
+    if value == 0.0 {
+        // underflowed when the significant digits aren't all zero
+        let significand = text.split(['e', 'E']).next().unwrap_or(text);
+        return significand.chars().any(|c| ('1'..='9').contains(&c));
+    }
+    if !text.contains(['.', 'e', 'E']) {
+        // large integers might not be represented exactly
+        return format!("{:.0}", value) != text;
+    }
+    false
+}
+
+/// Scans the text losslessly, skipping any text that can't be scanned.
+fn scan_lossless_tolerant(text: &str) -> Vec<TokenAndRange> {
+    let mut scanner = Scanner::new_lossless(text);
+    let mut tokens = Vec::new();
+
+    loop {
+        match scanner.scan() {
+            Ok(Some(token)) => tokens.push(TokenAndRange {
+                token,
+                range: Range {
+                    start: scanner.token_start(),
+                    end: scanner.token_end(),
+                    start_line: scanner.token_start_line(),
+                    end_line: scanner.token_end_line(),
+                },
+            }),
+            Ok(None) => break,
+            Err(_) => scanner.recover_from_error(),
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reports_errors_and_warnings_with_a_partial_value() {
+        let result = parse_with_diagnostics("{\n  \"a\": [1, 2], \n  \"b\": @,\n  \"c\": true\n}", &DiagnosticOptions::default());
+        assert_eq!(get_messages(&result.errors), vec![(25, "Unexpected token.")]);
+        assert_eq!(get_messages(&result.warnings), vec![(16, "Trailing whitespace.")]);
+
+        match result.value {
+            Some(Value::Object(obj)) => {
+                assert_eq!(obj.properties.len(), 2);
+                assert_eq!(obj.properties[0].name.value.as_ref(), "a");
+                assert_eq!(obj.properties[1].name.value.as_ref(), "c");
+            },
+            _ => panic!("Expected an object."),
+        }
+    }
+
+    #[test]
+    fn it_reports_one_error_and_one_warning() {
+        let result = parse_with_diagnostics(r#"{ "a": 1, "a": 2, "b" 3 }"#, &DiagnosticOptions::default());
+        assert_eq!(get_messages(&result.errors), vec![(22, "Expected a colon after the string in an object property.")]);
+        assert_eq!(get_messages(&result.warnings), vec![(10, "Duplicate property name \"a\".")]);
+        match result.value {
+            Some(value) => assert_eq!(value.into_value().to_string(), r#"{"a":2,"b":3}"#),
+            None => panic!("Expected a value."),
+        }
+    }
+
+    #[test]
+    fn it_recovers_from_unterminated_containers() {
+        let result = parse_with_diagnostics(r#"{ "a": [1, { "b": "#, &DiagnosticOptions::default());
+        assert_eq!(get_messages(&result.errors), vec![
+            (18, "Expected value after colon in object property."),
+            (18, "Unexpected end of file; '{' opened on line 1 was never closed"),
+            (18, "Unexpected end of file; '[' opened on line 1 was never closed"),
+            (18, "Unexpected end of file; '{' opened on line 1 was never closed"),
+        ]);
+        match result.value {
+            Some(value) => assert_eq!(value.into_value().to_string(), r#"{"a":[1,{}]}"#),
+            None => panic!("Expected a value."),
+        }
+    }
+
+    #[test]
+    fn it_recovers_from_invalid_tokens() {
+        let result = parse_with_diagnostics("[\"a\\x\", 1.e, ], 2", &DiagnosticOptions::default());
+        assert_eq!(get_messages(&result.errors), vec![
+            (3, "Invalid escape."),
+            (8, "Expected a digit after the decimal point."),
+            (14, "Text cannot contain more than one JSON value."),
+        ]);
+        match result.value {
+            // the string is kept with its invalid escape
+            Some(value) => assert_eq!(value.into_value().to_string(), r#"["a\\x"]"#),
+            None => panic!("Expected a value."),
+        }
+    }
+
+    #[test]
+    fn it_recovers_from_unexpected_tokens() {
+        let result = parse_with_diagnostics("[1,, 2 }, { \"a\" } , { : \"b\": 3 }]", &DiagnosticOptions::default());
+        assert_eq!(get_messages(&result.errors), vec![
+            (3, "Unexpected comma."),
+            (7, "Unexpected close brace."),
+            (16, "Expected a colon after the string in an object property."),
+            (22, "Unexpected token in object literal."),
+        ]);
+        match result.value {
+            Some(value) => assert_eq!(value.into_value().to_string(), r#"[1,2,{},{"b":3}]"#),
+            None => panic!("Expected a value."),
+        }
+    }
+
+    #[test]
+    fn it_limits_the_number_of_errors() {
+        let text = format!("[{}1]", "@, 'a', ".repeat(50));
+        let result = parse_with_diagnostics(&text, &DiagnosticOptions::default());
+        assert_eq!((result.errors.len(), result.errors_truncated), (100, false));
+
+        let options = DiagnosticOptions::default().with_max_errors(10);
+        let result = parse_with_diagnostics(&text, &options);
+        assert_eq!((result.errors.len(), result.errors_truncated), (10, true));
+        assert_eq!(result.errors[9].range.start, 36);
+        assert!(result.value.is_some());
+
+        let result = parse_with_diagnostics(&text, &options.with_error_limit_behavior(ErrorLimitBehavior::Stop));
+        assert_eq!((result.errors.len(), result.errors_truncated, result.value.is_none()), (10, true, true));
+
+        let result = parse_with_diagnostics("[1, @]", &DiagnosticOptions::default().with_max_errors(1));
+        assert_eq!((result.errors.len(), result.errors_truncated), (1, false));
+    }
+
+    #[test]
+    fn it_warns_about_lossy_numbers() {
+        let result = parse_with_diagnostics("[9007199254740993, 1e+400, 1e-400, 0e-400, 1.5, 9007199254740992]", &DiagnosticOptions::default());
+        assert_eq!(get_messages(&result.warnings).iter().map(|w| w.0).collect::<Vec<_>>(), vec![1, 19, 27]);
+    }
+
+    #[test]
+    fn it_skips_disabled_warnings() {
+        let options = DiagnosticOptions::default().with_duplicate_keys(false).with_lossy_numbers(false).with_trailing_whitespace(false);
+        let result = parse_with_diagnostics("{ \"a\": 1e+400, \"a\": 2 } \n", &options);
+        assert!(result.warnings.is_empty());
+        assert!(result.errors.is_empty());
+    }
+
+    fn get_messages(diagnostics: &[Diagnostic]) -> Vec<(usize, &str)> {
+        diagnostics.iter().map(|d| (d.range.start, d.message.as_str())).collect()
+    }
+}
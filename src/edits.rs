@@ -0,0 +1,1432 @@
+use super::ast::*;
+use super::common::{ImmutableString, Range};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::diagnostics::Diagnostic;
+use super::errors::{EditError, EditVerifyError, ParseError};
+use super::parser::{parse_text, parse_text_with_options, ParseOptions};
+use super::path::JsonPath;
+use super::scanner::{scan_tokens, Scanner, ScannerOptions};
+use super::serializer::KeyQuoteStyle;
+use super::strings::{decode_string, escape_string, EscapeOptions};
+use super::tokens::{Token, TokenAndRange};
+use super::tooling::{analyze, LineEnding};
+use super::value::JsonValue;
+
+/// A change to the text.
+///
+/// Positions are character offsets in the text, the same as the positions of a `Range`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TextEdit {
+    /// Start position of the text to replace.
+    pub start: usize,
+    /// End position of the text to replace.
+    pub end: usize,
+    /// Text to insert in place of the range.
+    pub new_text: String,
+}
+
+impl TextEdit {
+    /// Creates an edit that inserts text at the provided position.
+    pub fn insert(pos: usize, new_text: &str) -> TextEdit {
+        TextEdit {
+            start: pos,
+            end: pos,
+            new_text: String::from(new_text),
+        }
+    }
+}
+
+/// Options for creating edits.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EditOptions {
+    /// How the names of inserted properties are quoted. Defaults to `KeyQuoteStyle::Detect`.
+    pub key_quote_style: KeyQuoteStyle,
+}
+
+impl Default for EditOptions {
+    fn default() -> EditOptions {
+        EditOptions {
+            key_quote_style: KeyQuoteStyle::Detect,
+        }
+    }
+}
+
+impl EditOptions {
+    /// Sets how the names of inserted properties are quoted.
+    pub fn with_key_quote_style(mut self, value: KeyQuoteStyle) -> Self {
+        self.key_quote_style = value;
+        self
+    }
+}
+
+/// Applies the edits to the text.
+///
+/// The edits may be provided in any order. Errors naming the offending edit when an edit is out
+/// of the bounds of the text or overlaps another edit. See `EditSet`.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::edits::{apply_text_edits, TextEdit};
+///
+/// let edits = [TextEdit { start: 7, end: 8, new_text: String::from("3") }, TextEdit::insert(6, " 2,")];
+/// assert_eq!(apply_text_edits("[1, 2, 4]", &edits).unwrap(), "[1, 2, 2, 3]");
+/// let err = apply_text_edits("[1]", &[TextEdit::insert(4, "2")]).unwrap_err();
+/// assert_eq!(err.to_string(), "Edit 0 (4..4): The edit ends after the end of the text.");
+/// ```
+pub fn apply_text_edits(text: &str, edits: &[TextEdit]) -> Result<String, EditError> {
+    EditSet::new(edits.to_vec())?.apply(text)
+}
+
+/// Edits that were validated to not overlap, sorted by position.
+///
+/// Two inserts at the same position, or an insert at the start of a replaced range, overlap
+/// because the order of their text would be ambiguous. When edits overlap, the error names the
+/// one that was provided last.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EditSet {
+    /// The edits sorted by position along with their index in the provided edits.
+    edits: Vec<(usize, TextEdit)>,
+}
+
+impl EditSet {
+    /// Creates a set of the edits, which may be provided in any order.
+    pub fn new(edits: Vec<TextEdit>) -> Result<EditSet, EditError> {
+        let mut edits = edits.into_iter().enumerate().collect::<Vec<_>>();
+        if let Some((index, edit)) = edits.iter().find(|(_, edit)| edit.start > edit.end) {
+            return Err(EditError::new(*index, edit.start, edit.end, "The edit starts after its end."));
+        }
+        edits.sort_by_key(|(_, edit)| (edit.start, edit.end));
+
+        for pair in edits.windows(2) {
+            let (previous, edit) = (&pair[0].1, &pair[1].1);
+            let is_insert_conflict = edit.start == previous.start && (edit.start == edit.end || previous.start == previous.end);
+            if edit.start < previous.end || is_insert_conflict {
+                // name the edit that was provided last
+                let (index, edit) = if pair[0].0 > pair[1].0 { &pair[0] } else { &pair[1] };
+                return Err(EditError::new(*index, edit.start, edit.end, "The edit overlaps another edit."));
+            }
+        }
+        Ok(EditSet { edits })
+    }
+
+    /// Gets the edits sorted by position.
+    pub fn edits(&self) -> impl Iterator<Item = &TextEdit> {
+        self.edits.iter().map(|(_, edit)| edit)
+    }
+
+    /// Applies the edits to the text.
+    ///
+    /// Errors when an edit ends after the end of the text.
+    pub fn apply(&self, text: &str) -> Result<String, EditError> {
+        let mut chars = text.chars();
+        let mut result = String::with_capacity(text.len());
+        let mut pos = 0;
+        for (index, edit) in self.edits.iter() {
+            while pos < edit.end {
+                match chars.next() {
+                    Some(c) => {
+                        if pos < edit.start {
+                            result.push(c);
+                        }
+                        pos += 1;
+                    },
+                    None => return Err(EditError::new(*index, edit.start, edit.end, "The edit ends after the end of the text.")),
+                }
+            }
+            result.push_str(&edit.new_text);
+        }
+        result.extend(chars);
+        Ok(result)
+    }
+
+    /// Maps a position in the text before the edits to the corresponding position in the text
+    /// after the edits.
+    ///
+    /// Positions inside a replaced range move to the start of its new text and the position
+    /// of an insert moves after the inserted text, so a cursor stays after text typed at it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::edits::{EditSet, TextEdit};
+    ///
+    /// // "[1, 2, 3]" -> "[1, 3, 4]"
+    /// let edits = EditSet::new(vec![TextEdit { start: 3, end: 6, new_text: String::new() }, TextEdit::insert(8, ", 4")]).unwrap();
+    /// assert_eq!(edits.map_position(1), 1);
+    /// assert_eq!(edits.map_position(5), 3);
+    /// assert_eq!(edits.map_position(9), 9);
+    /// ```
+    pub fn map_position(&self, pos: usize) -> usize {
+        let mut offset: isize = 0;
+        for (_, edit) in self.edits.iter() {
+            let is_after = if edit.start == edit.end { pos >= edit.end } else { pos >= edit.end && pos > edit.start };
+            if is_after {
+                offset += edit.new_text.chars().count() as isize - (edit.end - edit.start) as isize;
+            } else if pos > edit.start {
+                return (edit.start as isize + offset) as usize;
+            } else {
+                break;
+            }
+        }
+        (pos as isize + offset) as usize
+    }
+}
+
+/// Verifies that the text is still valid after applying the edits and that the values outside
+/// the edits are unchanged.
+///
+/// Each value and property name whose range doesn't overlap an edit must still be in the edited
+/// text with the same decoded value. Objects and arrays must keep their kind and number of
+/// children. This catches edits that change how the surrounding text is tokenized, such as a
+/// string ending in a backslash inserted before an existing quote. The edit functions of this
+/// module run this in debug builds.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::ParseOptions;
+/// use jsonc_parser::edits::{verify_edits, TextEdit};
+///
+/// let text = r#"["a", "b"]"#;
+/// let options = ParseOptions::default();
+/// assert!(verify_edits(text, &[TextEdit::insert(1, r#""\\", "#)], &options).is_ok());
+/// assert!(verify_edits(text, &[TextEdit::insert(1, r#""\"#)], &options).is_err());
+/// ```
+pub fn verify_edits(text: &str, edits: &[TextEdit], options: &ParseOptions) -> Result<(), EditVerifyError> {
+    let parse_result = parse_text_with_options(text, options).map_err(EditVerifyError::Parse)?;
+    let new_text = apply_text_edits(text, edits).map_err(EditVerifyError::Edit)?;
+    let new_parse_result = parse_text_with_options(&new_text, options).map_err(EditVerifyError::Parse)?;
+
+    let mut new_nodes = HashMap::new();
+    for_each_node(new_parse_result.value.as_ref(), |node| {
+        new_nodes.insert(node.range().start, node);
+    });
+
+    let mut result = Ok(());
+    for_each_node(parse_result.value.as_ref(), |node| {
+        let range = node.range();
+        if result.is_err() || edits.iter().any(|edit| edit.start < range.end && edit.end > range.start) {
+            return;
+        }
+
+        // move the position by the change in length of the edits before it
+        let mut new_start = range.start as isize;
+        for edit in edits.iter().filter(|edit| edit.end <= range.start) {
+            new_start += edit.new_text.chars().count() as isize - (edit.end - edit.start) as isize;
+        }
+        let is_unchanged = match new_nodes.get(&(new_start as usize)) {
+            Some(new_node) => node.is_same(new_node),
+            None => false,
+        };
+        if !is_unchanged {
+            result = Err(EditVerifyError::ChangedValue {
+                range: range.clone(),
+                message: String::from("Value outside the edits changed."),
+            });
+        }
+    });
+    drop(new_nodes);
+    parse_result.drop_nested();
+    new_parse_result.drop_nested();
+    result
+}
+
+/// A value or property name that is compared when verifying edits.
+enum EditNode<'a> {
+    Value(&'a Value),
+    PropertyName(&'a StringLit),
+}
+
+impl<'a> EditNode<'a> {
+    fn range(&self) -> &'a Range {
+        match self {
+            EditNode::Value(value) => value.range(),
+            EditNode::PropertyName(name) => &name.range,
+        }
+    }
+
+    /// Gets if the nodes have the same decoded value, or the same kind and number of children for containers.
+    fn is_same(&self, other: &EditNode) -> bool {
+        match (self, other) {
+            (EditNode::PropertyName(a), EditNode::PropertyName(b)) => decode_string(a.value.as_ref(), a.quote) == decode_string(b.value.as_ref(), b.quote),
+            (EditNode::Value(a), EditNode::Value(b)) => match (a, b) {
+                (Value::StringLit(a), Value::StringLit(b)) => decode_string(a.value.as_ref(), a.quote) == decode_string(b.value.as_ref(), b.quote),
+                (Value::NumberLit(a), Value::NumberLit(b)) => a.value == b.value,
+                (Value::BooleanLit(a), Value::BooleanLit(b)) => a.value == b.value,
+                (Value::NullKeyword(_), Value::NullKeyword(_)) => true,
+                (Value::Object(a), Value::Object(b)) => a.properties.len() == b.properties.len(),
+                (Value::Array(a), Value::Array(b)) => a.elements.len() == b.elements.len(),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+fn for_each_node<'a>(value: Option<&'a Value>, mut action: impl FnMut(EditNode<'a>)) {
+    let mut stack = Vec::new();
+    stack.extend(value);
+
+    while let Some(value) = stack.pop() {
+        action(EditNode::Value(value));
+        match value {
+            Value::Object(obj) => {
+                for prop in obj.properties.iter() {
+                    action(EditNode::PropertyName(&prop.name));
+                    stack.push(&prop.value);
+                }
+            },
+            Value::Array(arr) => stack.extend(arr.elements.iter()),
+            _ => {},
+        }
+    }
+}
+
+/// Gets the edits that add a trailing comma to every multi-line object and array.
+///
+/// A comma is added after the last element of a container when the closing brace or bracket
+/// is on a different line than that element. Containers that already have a trailing comma
+/// are skipped. The comma is inserted directly after the element, so any comments between the
+/// element and the closing brace or bracket stay after the comma. The text may use single
+/// quoted strings.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::edits::{add_trailing_commas, apply_text_edits};
+///
+/// let text = "[\n  1,\n  2\n]";
+/// let edits = add_trailing_commas(text).unwrap();
+/// assert_eq!(apply_text_edits(text, &edits).unwrap(), "[\n  1,\n  2,\n]");
+/// ```
+pub fn add_trailing_commas(text: &str) -> Result<Vec<TextEdit>, ParseError> {
+    let parse_options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_single_quotes(true));
+    let parse_result = parse_text_with_options(text, &parse_options)?;
+    let mut edits = Vec::new();
+    let mut stack = Vec::new();
+    stack.extend(parse_result.value.as_ref());
+
+    while let Some(value) = stack.pop() {
+        let (container_range, last_element_range) = match value {
+            Value::Object(obj) => {
+                stack.extend(obj.properties.iter().map(|prop| &prop.value));
+                (&obj.range, obj.properties.last().map(|prop| &prop.range))
+            },
+            Value::Array(arr) => {
+                stack.extend(arr.elements.iter());
+                (&arr.range, arr.elements.last().map(|element| element.range()))
+            },
+            _ => continue,
+        };
+
+        if let Some(last_element_range) = last_element_range {
+            if last_element_range.end_line != container_range.end_line
+                && !is_comma_at(&parse_result.tokens, last_element_range.end)
+            {
+                edits.push(TextEdit::insert(last_element_range.end, ","));
+            }
+        }
+    }
+
+    edits.sort_by_key(|edit| edit.start);
+    debug_assert_eq!(verify_edits(text, &edits, &parse_options), Ok(()));
+    parse_result.drop_nested();
+    Ok(edits)
+}
+
+/// Gets the edits that add a property to the end of the object starting at the provided position.
+///
+/// A property on its own line is added to objects spanning multiple lines, using the indentation
+/// of the last property, and a trailing comma is kept when the object had one. The text may use
+/// single quoted strings. The value is serialized compactly.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::edits::{apply_text_edits, insert_property, EditOptions};
+/// use jsonc_parser::value::JsonValue;
+///
+/// let text = "{\n  'a': 1\n}";
+/// let edits = insert_property(text, 0, "b", &JsonValue::Boolean(true), &EditOptions::default()).unwrap();
+/// assert_eq!(apply_text_edits(text, &edits).unwrap(), "{\n  'a': 1,\n  'b': true\n}");
+/// ```
+pub fn insert_property(text: &str, object_pos: usize, name: &str, value: &JsonValue, options: &EditOptions) -> Result<Vec<TextEdit>, ParseError> {
+    let parse_options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_single_quotes(true));
+    let parse_result = parse_text_with_options(text, &parse_options)?;
+    let obj = match find_object(parse_result.value.as_ref(), object_pos) {
+        Some(obj) => obj,
+        None => return Err(ParseError::new(object_pos, "Expected an object at the provided position.").locate(text.chars(), None)),
+    };
+
+    let chars = text.chars().collect::<Vec<_>>();
+    let last_prop = obj.properties.last();
+    let key_quote_style = match options.key_quote_style {
+        KeyQuoteStyle::Detect => {
+            // names are always quoted since unquoted names can't be parsed
+            let single_count = obj.properties.iter().filter(|prop| prop.name.quote == '\'').count();
+            if single_count * 2 > obj.properties.len() {
+                KeyQuoteStyle::Single
+            } else {
+                KeyQuoteStyle::Double
+            }
+        },
+        style => style,
+    };
+    let create_edit = |prop_text: String| match last_prop {
+        Some(last_prop) => {
+            let is_multi_line = last_prop.range.start_line > obj.range.start_line;
+            let separator = if is_multi_line {
+                format!("\n{}", get_indent_text(&chars, last_prop.range.start))
+            } else {
+                String::from(" ")
+            };
+            match find_comma_at(&parse_result.tokens, last_prop.range.end) {
+                Some(comma_range) => TextEdit::insert(comma_range.end, &format!("{}{},", separator, prop_text)),
+                None => TextEdit::insert(last_prop.range.end, &format!(",{}{}", separator, prop_text)),
+            }
+        },
+        None => TextEdit::insert(obj.range.start + 1, &prop_text),
+    };
+    let edits = vec![create_edit(format!("{}: {}", format_property_name(name, key_quote_style), value))];
+    debug_assert_eq!(verify_edits(text, &edits, &parse_options), Ok(()));
+    parse_result.drop_nested();
+    Ok(edits)
+}
+
+/// Gets the edits that set the value at the path, replacing the existing value or adding a
+/// property to its object when the property doesn't exist.
+///
+/// When a name is duplicated, the last property is changed since it's the one that takes effect.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::edits::{apply_text_edits, set_value, EditOptions};
+/// use jsonc_parser::path::JsonPath;
+/// use jsonc_parser::value::JsonValue;
+///
+/// let text = "{\n  // enabled\n  \"a\": 1\n}";
+/// let edits = set_value(text, &"a".parse::<JsonPath>().unwrap(), &JsonValue::Boolean(true), &EditOptions::default()).unwrap();
+/// assert_eq!(apply_text_edits(text, &edits).unwrap(), "{\n  // enabled\n  \"a\": true\n}");
+/// ```
+pub fn set_value(text: &str, path: &JsonPath, value: &JsonValue, options: &EditOptions) -> Result<Vec<TextEdit>, ParseError> {
+    let parse_options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_single_quotes(true));
+    let parse_result = parse_text_with_options(text, &parse_options)?;
+    let root = match parse_result.value.as_ref() {
+        Some(root) => root,
+        None => return Err(ParseError::new(0, "Expected a value in the text.").locate(text.chars(), None)),
+    };
+
+    if let Some(existing) = root.get_path(path) {
+        let range = existing.range();
+        let edits = vec![TextEdit {
+            start: range.start,
+            end: range.end,
+            new_text: value.to_string(),
+        }];
+        debug_assert_eq!(verify_edits(text, &edits, &parse_options), Ok(()));
+        parse_result.drop_nested();
+        return Ok(edits);
+    }
+
+    let (parent, name) = match (path.parent().and_then(|parent| root.get_path(&parent)), path.segments().last()) {
+        (Some(Value::Object(obj)), Some(segment)) => (obj, segment.as_key()),
+        _ => return Err(path_error(text, path, "Expected an object to add the property to at the path")),
+    };
+    let object_pos = parent.range.start;
+    parse_result.drop_nested();
+    insert_property(text, object_pos, &name, value, options)
+}
+
+/// Gets the edits that remove the property or array element at the path along with its comma.
+///
+/// Comments in the container are kept, including when the removed child is its only one.
+/// When a name is duplicated, the last property is removed.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::edits::{apply_text_edits, remove_value};
+/// use jsonc_parser::path::JsonPath;
+///
+/// let text = "{\n  \"a\": [1, 2, 3],\n  \"b\": 4\n}";
+/// let edits = remove_value(text, &"a[1]".parse::<JsonPath>().unwrap()).unwrap();
+/// assert_eq!(apply_text_edits(text, &edits).unwrap(), "{\n  \"a\": [1, 3],\n  \"b\": 4\n}");
+/// let edits = remove_value(text, &"b".parse::<JsonPath>().unwrap()).unwrap();
+/// assert_eq!(apply_text_edits(text, &edits).unwrap(), "{\n  \"a\": [1, 2, 3]\n}");
+/// ```
+pub fn remove_value(text: &str, path: &JsonPath) -> Result<Vec<TextEdit>, ParseError> {
+    let parse_options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_single_quotes(true));
+    let parse_result = parse_text_with_options(text, &parse_options)?;
+    let parent = path.parent().and_then(|parent| parse_result.value.as_ref().and_then(|root| root.get_path(&parent)));
+    let segment = path.segments().last();
+    // the ranges of the container and its children along with the index of the removed child
+    let (container_range, child_ranges, index) = match (parent, segment) {
+        (Some(Value::Object(obj)), Some(segment)) => {
+            let name = segment.as_key();
+            // the last property with a duplicate name is the one that takes effect
+            let last_prop = obj.get_all(&name).last().copied();
+            let index = last_prop.and_then(|last_prop| obj.properties.iter().position(|prop| std::ptr::eq(prop, last_prop)));
+            (&obj.range, obj.properties.iter().map(|prop| &prop.range).collect::<Vec<_>>(), index)
+        },
+        (Some(Value::Array(arr)), Some(segment)) => {
+            let index = segment.as_index().filter(|index| *index < arr.elements.len());
+            (&arr.range, arr.elements.iter().map(|element| element.range()).collect::<Vec<_>>(), index)
+        },
+        _ => return Err(path_error(text, path, "Expected a property or array element at the path")),
+    };
+    let index = match index {
+        Some(index) => index,
+        None => return Err(path_error(text, path, "Expected a property or array element at the path")),
+    };
+
+    let tokens = &parse_result.tokens;
+    let chars = text.chars().collect::<Vec<_>>();
+    let is_whitespace = |start: usize, end: usize| chars[start..end].iter().all(|c| c.is_whitespace());
+    let range = child_ranges[index];
+    let comma = find_comma_at(tokens, range.end);
+    let end = comma.map(|comma| comma.end).unwrap_or(range.end);
+    if child_ranges.len() == 1 && is_whitespace(container_range.start + 1, range.start) && is_whitespace(end, container_range.end - 1) {
+        // clear the container when there's nothing else in it
+        let edits = vec![TextEdit { start: container_range.start + 1, end: container_range.end - 1, new_text: String::new() }];
+        debug_assert_eq!(verify_edits(text, &edits, &parse_options), Ok(()));
+        parse_result.drop_nested();
+        return Ok(edits);
+    }
+
+    // remove only the child, its comma, and the whitespace next to them so the comments in the
+    // container are kept
+    let mut removed = vec![(range.start, range.end)];
+    match comma {
+        Some(comma) => removed.push((comma.start, comma.end)),
+        // the last child doesn't have a comma, so remove the previous child's comma instead
+        None if index > 0 && index == child_ranges.len() - 1 => {
+            if let Some(comma) = find_comma_at(tokens, child_ranges[index - 1].end) {
+                removed.insert(0, (comma.start, comma.end));
+            }
+        },
+        None => {},
+    }
+    let last_end = removed.last().unwrap().1;
+    let next_pos = last_end + chars[last_end..].iter().take_while(|c| c.is_whitespace()).count();
+    if next_pos == container_range.end - 1 {
+        // remove the whitespace before the child when it's followed by the closing brace or
+        // bracket, but keep a newline when there's no newline before the closing one so it
+        // doesn't end up in a comment line
+        let has_newline = chars[last_end..next_pos].iter().any(|c| is_newline(*c));
+        let child = removed.iter_mut().find(|(start, _)| *start == range.start).unwrap();
+        while child.0 > 0 && chars[child.0 - 1].is_whitespace() && (has_newline || !is_newline(chars[child.0 - 1])) {
+            child.0 -= 1;
+        }
+    } else if removed[0].0 == container_range.start + 1 || chars[removed[0].0 - 1].is_whitespace() {
+        // remove the whitespace after it when that doesn't join the text on either side
+        removed.last_mut().unwrap().1 = next_pos;
+    }
+
+    let mut edits: Vec<TextEdit> = Vec::new();
+    for (start, end) in removed {
+        match edits.last_mut() {
+            Some(edit) if is_whitespace(edit.end, start) => edit.end = end,
+            _ => edits.push(TextEdit { start, end, new_text: String::new() }),
+        }
+    }
+    debug_assert_eq!(verify_edits(text, &edits, &parse_options), Ok(()));
+    parse_result.drop_nested();
+    Ok(edits)
+}
+
+fn path_error(text: &str, path: &JsonPath, message: &str) -> ParseError {
+    ParseError::new(0, &format!("{} '{}'.", message, path)).locate(text.chars(), None)
+}
+
+/// Collects edits to a document so they can be applied to the original text at once.
+///
+/// The paths of each change refer to the original text, so removing an element doesn't shift
+/// the indexes of the following changes. Properties added to the same object are added in the
+/// order they were set, while changes whose edits overlap a previous change are rejected.
+/// Newlines in the inserted text use the line ending of the document.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::edits::DocumentEditor;
+/// use jsonc_parser::value::JsonValue;
+///
+/// let mut doc = DocumentEditor::new("{\r\n  \"a\": [1, 2],\r\n  \"b\": 3\r\n}\r\n");
+/// doc.set(&"b".parse().unwrap(), &JsonValue::Boolean(false)).unwrap();
+/// doc.set(&"c".parse().unwrap(), &JsonValue::Null).unwrap();
+/// doc.remove(&"a[0]".parse().unwrap()).unwrap();
+/// assert!(doc.remove(&"a".parse().unwrap()).is_err());
+/// assert_eq!(doc.apply().unwrap(), "{\r\n  \"a\": [2],\r\n  \"b\": false,\r\n  \"c\": null\r\n}\r\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DocumentEditor {
+    text: String,
+    edits: Vec<TextEdit>,
+    options: EditOptions,
+}
+
+impl DocumentEditor {
+    /// Creates an editor of the text.
+    pub fn new(text: &str) -> DocumentEditor {
+        DocumentEditor::with_options(text, EditOptions::default())
+    }
+
+    /// Creates an editor of the text that creates edits with the provided options.
+    pub fn with_options(text: &str, options: EditOptions) -> DocumentEditor {
+        DocumentEditor {
+            text: String::from(text),
+            edits: Vec::new(),
+            options,
+        }
+    }
+
+    /// Gets the original text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Gets the edits collected so far.
+    pub fn edits(&self) -> &[TextEdit] {
+        &self.edits
+    }
+
+    /// Sets the value at the path. See `set_value`.
+    pub fn set(&mut self, path: &JsonPath, value: &JsonValue) -> Result<(), ParseError> {
+        let edits = set_value(&self.text, path, value, &self.options)?;
+        self.add_edits(edits)
+    }
+
+    /// Removes the property or array element at the path. See `remove_value`.
+    pub fn remove(&mut self, path: &JsonPath) -> Result<(), ParseError> {
+        let edits = remove_value(&self.text, path)?;
+        self.add_edits(edits)
+    }
+
+    fn add_edits(&mut self, edits: Vec<TextEdit>) -> Result<(), ParseError> {
+        let is_insert = |edit: &TextEdit| edit.start == edit.end;
+        // an insert at the start of a replaced range conflicts because their order would be ambiguous
+        let conflicts = |a: &TextEdit, b: &TextEdit| (a.start < b.end && b.start < a.end) || (a.start == b.start && is_insert(a) != is_insert(b));
+        if let Some(edit) = edits.iter().find(|edit| self.edits.iter().any(|other| conflicts(edit, other))) {
+            return Err(ParseError::new(edit.start, "The change overlaps a previous change.").locate(self.text.chars(), None));
+        }
+
+        for edit in edits {
+            match self.edits.iter_mut().find(|other| is_insert(other) && is_insert(&edit) && other.start == edit.start) {
+                // a property was already added at the end of the same object, so add this one after it
+                Some(other) => {
+                    if !other.new_text.ends_with(',') && !edit.new_text.starts_with(',') {
+                        other.new_text.push_str(", ");
+                    }
+                    other.new_text.push_str(&edit.new_text);
+                },
+                None => self.edits.push(edit),
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the edits to the original text.
+    ///
+    /// The edits are validated when each change is made, so this only errors when they are out
+    /// of the bounds of the text.
+    pub fn apply(&self) -> Result<String, EditError> {
+        match analyze(&self.text).line_ending {
+            Some(line_ending @ LineEnding::CrLf) | Some(line_ending @ LineEnding::Cr) => {
+                let newline = if line_ending == LineEnding::CrLf { "\r\n" } else { "\r" };
+                let edits = self.edits.iter().map(|edit| TextEdit {
+                    start: edit.start,
+                    end: edit.end,
+                    new_text: edit.new_text.replace('\n', newline),
+                }).collect::<Vec<_>>();
+                apply_text_edits(&self.text, &edits)
+            },
+            _ => apply_text_edits(&self.text, &self.edits),
+        }
+    }
+}
+
+/// Edits the file at the path and writes it back when the editor has changes.
+///
+/// The text outside of the changed values, including comments, a byte order mark, and whether
+/// the file ends with a newline, is kept as it is. The file is replaced by renaming a temporary
+/// file written next to it with the same permissions, so it's never left partially written. Nothing is written when the
+/// callback returns an error.
+///
+/// # Example
+///
+/// ```no_run
+/// use jsonc_parser::edits::update_file;
+/// use jsonc_parser::value::JsonValue;
+///
+/// update_file("tsconfig.json", |doc| {
+///     doc.set(&"compilerOptions.strict".parse()?, &JsonValue::Boolean(true))?;
+///     doc.remove(&"exclude[2]".parse()?)
+/// }).unwrap();
+/// ```
+pub fn update_file(path: impl AsRef<Path>, update: impl FnOnce(&mut DocumentEditor) -> Result<(), ParseError>) -> Result<(), ParseError> {
+    let path = path.as_ref();
+    let source_name = path.to_string_lossy();
+    let io_error = |err: io::Error| ParseError::new_io(&err, Some(&source_name));
+    let text = fs::read_to_string(path).map_err(io_error)?;
+    let (bom, text) = match text.strip_prefix('\u{feff}') {
+        Some(text) => ("\u{feff}", text),
+        None => ("", text.as_str()),
+    };
+
+    let mut editor = DocumentEditor::new(text);
+    update(&mut editor).map_err(|mut err| {
+        err.source_name.get_or_insert_with(|| ImmutableString::new(source_name.to_string()));
+        err
+    })?;
+    if editor.edits.is_empty() {
+        return Ok(());
+    }
+
+    let new_text = editor.apply().map_err(|err| {
+        let mut err = ParseError::new(err.start, &err.message).locate(text.chars(), None);
+        err.source_name = Some(ImmutableString::new(source_name.to_string()));
+        err
+    })?;
+    replace_file(path, format!("{}{}", bom, new_text).as_bytes()).map_err(io_error)
+}
+
+/// Replaces the file at the path with the contents by renaming a temporary file written next to it.
+///
+/// The temporary file gets the permissions of the file and is synced to disk before the rename. It's
+/// removed when any step fails.
+fn replace_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let permissions = fs::metadata(path)?.permissions();
+    let (temp_path, mut file) = create_temp_file(path)?;
+    let result = file
+        .write_all(contents)
+        .and_then(|_| file.set_permissions(permissions))
+        .and_then(|_| file.sync_all())
+        .and_then(|_| {
+            drop(file);
+            fs::rename(&temp_path, path)
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Creates a temporary file next to the file at the path with a name that isn't used yet, so
+/// concurrent updates of the same file don't write to the same temporary file.
+fn create_temp_file(path: &Path) -> io::Result<(PathBuf, fs::File)> {
+    static TEMP_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    loop {
+        let count = TEMP_FILE_COUNT.fetch_add(1, Ordering::Relaxed);
+        let temp_path = path.with_file_name(format!(".{}.{}.{}.tmp", file_name, std::process::id(), count));
+        match fs::OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Selects the properties renamed by `rename_all_keys`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct KeyMatcher {
+    /// The decoded name of the properties.
+    pub name: String,
+    /// Pattern the path of the object containing the property must match, where `*` matches any
+    /// one segment (see `JsonPath::matches`). Defaults to `None`, which matches every object.
+    pub parent_pattern: Option<JsonPath>,
+}
+
+impl KeyMatcher {
+    /// Creates a matcher for the properties with the provided name in every object.
+    pub fn new(name: &str) -> KeyMatcher {
+        KeyMatcher {
+            name: String::from(name),
+            parent_pattern: None,
+        }
+    }
+
+    /// Sets the pattern the path of the object containing the property must match.
+    pub fn with_parent_pattern(mut self, value: &JsonPath) -> Self {
+        self.parent_pattern = Some(value.clone());
+        self
+    }
+}
+
+/// Gets the edits that rename the properties selected by the matcher to the new name.
+///
+/// Each name keeps its quotes. Objects that already have a property with the new name are
+/// skipped, and a warning with the range of the property that would have been renamed is
+/// returned for each of them.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::edits::{apply_text_edits, rename_all_keys, KeyMatcher};
+///
+/// let text = r#"{ "a": { "foo": 1 }, "b": [{ 'foo': "foo" }], "c": { "foo": 2, "bar": 3 } }"#;
+/// let (edits, warnings) = rename_all_keys(text, &KeyMatcher::new("foo"), "bar").unwrap();
+/// assert_eq!(apply_text_edits(text, &edits).unwrap(), r#"{ "a": { "bar": 1 }, "b": [{ 'bar': "foo" }], "c": { "foo": 2, "bar": 3 } }"#);
+/// assert_eq!(warnings[0].message, "The object already has a property named \"bar\".");
+/// ```
+pub fn rename_all_keys(text: &str, matcher: &KeyMatcher, new_name: &str) -> Result<(Vec<TextEdit>, Vec<Diagnostic>), ParseError> {
+    let parse_options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_single_quotes(true));
+    let parse_result = parse_text_with_options(text, &parse_options)?;
+    let mut edits = Vec::new();
+    let mut warnings = Vec::new();
+
+    let mut stack = Vec::new();
+    stack.extend(parse_result.value.as_ref().map(|value| (value, JsonPath::new())));
+    while let Some((value, path)) = stack.pop() {
+        match value {
+            Value::Object(obj) => {
+                let is_match = |prop: &&ObjectProp| decode_string(prop.name.value.as_ref(), prop.name.quote) == matcher.name;
+                let is_parent_match = matcher.parent_pattern.as_ref().map(|pattern| path.matches(pattern)).unwrap_or(true);
+                if is_parent_match && matcher.name != new_name {
+                    let has_new_name = obj.properties.iter().any(|prop| decode_string(prop.name.value.as_ref(), prop.name.quote) == new_name);
+                    for prop in obj.properties.iter().filter(is_match) {
+                        if has_new_name {
+                            warnings.push(Diagnostic {
+                                range: prop.name.range.clone(),
+                                kind: None,
+                                message: format!("The object already has a property named {}.", escape_string(new_name, &EscapeOptions::default())),
+                            });
+                        } else {
+                            let key_quote_style = if prop.name.quote == '\'' { KeyQuoteStyle::Single } else { KeyQuoteStyle::Double };
+                            edits.push(TextEdit {
+                                start: prop.name.range.start,
+                                end: prop.name.range.end,
+                                new_text: format_property_name(new_name, key_quote_style),
+                            });
+                        }
+                    }
+                }
+                for prop in obj.properties.iter() {
+                    let mut prop_path = path.clone();
+                    prop_path.push(decode_string(prop.name.value.as_ref(), prop.name.quote).as_ref());
+                    stack.push((&prop.value, prop_path));
+                }
+            },
+            Value::Array(arr) => {
+                for (index, element) in arr.elements.iter().enumerate() {
+                    let mut element_path = path.clone();
+                    element_path.push(index);
+                    stack.push((element, element_path));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    edits.sort_by_key(|edit| edit.start);
+    warnings.sort_by_key(|warning| warning.range.start);
+    debug_assert_eq!(verify_edits(text, &edits, &parse_options), Ok(()));
+    parse_result.drop_nested();
+    Ok((edits, warnings))
+}
+
+fn find_object(value: Option<&Value>, pos: usize) -> Option<&Object> {
+    let mut stack = Vec::new();
+    stack.extend(value);
+
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::Object(obj) if obj.range.start == pos => return Some(obj),
+            Value::Object(obj) => stack.extend(obj.properties.iter().map(|prop| &prop.value)),
+            Value::Array(arr) => stack.extend(arr.elements.iter()),
+            _ => {},
+        }
+    }
+
+    None
+}
+
+fn format_property_name(name: &str, key_quote_style: KeyQuoteStyle) -> String {
+    match key_quote_style {
+        KeyQuoteStyle::Single => escape_string(name, &EscapeOptions::default().with_single_quotes(true)),
+        _ => escape_string(name, &EscapeOptions::default()),
+    }
+}
+
+/// Gets the edits that convert a comment to the provided kind of comment.
+///
+/// The comment is the one containing the start of the provided range. When converting
+/// to a comment block, the run of comment lines on consecutive lines containing that
+/// comment is converted to a single comment block, replacing each `//` after the
+/// first with spaces so the text stays at the same indentation. When converting to
+/// comment lines, each line of the comment block becomes a comment line.
+///
+/// No edits are returned when the comment is already the provided kind.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::ast::CommentKind;
+/// use jsonc_parser::common::Range;
+/// use jsonc_parser::edits::{apply_text_edits, convert_comment};
+///
+/// let text = "// a\n// b\n5";
+/// let range = Range { start: 0, end: 4, start_line: 0, end_line: 0 };
+/// let edits = convert_comment(text, &range, CommentKind::Block).unwrap();
+/// assert_eq!(apply_text_edits(text, &edits).unwrap(), "/* a\n   b */\n5");
+/// ```
+pub fn convert_comment(text: &str, comment_range: &Range, target_kind: CommentKind) -> Result<Vec<TextEdit>, ParseError> {
+    let tokens = scan_tokens(&mut Scanner::new(text))?;
+    let index = tokens.iter().position(|token| match token.token {
+        Token::CommentLine(_) | Token::CommentBlock(_) => token.range.start <= comment_range.start && comment_range.start < token.range.end,
+        _ => false,
+    });
+    let index = match index {
+        Some(index) => index,
+        None => return Err(ParseError::new(comment_range.start, "Expected a comment at the provided range.").locate(text.chars(), None)),
+    };
+
+    let result = match (&tokens[index].token, target_kind) {
+        (Token::CommentLine(_), CommentKind::Block) => comment_lines_to_block(&tokens, index).map_err(|err| err.locate(text.chars(), None)),
+        (Token::CommentBlock(comment_text), CommentKind::Line) => {
+            let range = &tokens[index].range;
+            if let Some(next_token) = tokens.get(index + 1) {
+                if next_token.range.start_line == range.end_line {
+                    return Err(ParseError::new(next_token.range.start, "Cannot convert a comment block to comment lines when text follows it on the same line.").locate(text.chars(), None));
+                }
+            }
+
+            let chars = text.chars().collect::<Vec<_>>();
+            let indent_text = get_indent_text(&chars, range.start);
+            let strip_width = indent_text.chars().count() + 2;
+            let lines = comment_text.as_ref().split('\n').map(|line| line.trim_end_matches(|c: char| c.is_whitespace())).collect::<Vec<_>>();
+            let mut new_text = String::new();
+            for (i, line) in lines.iter().enumerate() {
+                let is_first = i == 0;
+                let is_last = i + 1 == lines.len();
+                if line.is_empty() && lines.len() > 1 && (is_first || is_last) {
+                    continue;
+                }
+                if !new_text.is_empty() {
+                    new_text.push('\n');
+                    new_text.push_str(&indent_text);
+                }
+                new_text.push_str("//");
+                if is_first {
+                    new_text.push_str(line);
+                } else {
+                    new_text.push_str(strip_leading_whitespace(line, strip_width));
+                }
+            }
+
+            Ok(vec![TextEdit {
+                start: range.start,
+                end: range.end,
+                new_text,
+            }])
+        },
+        _ => Ok(Vec::new()),
+    };
+    if let Ok(edits) = &result {
+        // the text only needs to tokenize, so only verify text that parses
+        debug_assert!(parse_text(text).is_err() || verify_edits(text, edits, &ParseOptions::default()).is_ok());
+    }
+    result
+}
+
+fn comment_lines_to_block(tokens: &[TokenAndRange], index: usize) -> Result<Vec<TextEdit>, ParseError> {
+    let is_comment_line = |i: usize| match tokens.get(i) {
+        Some(token) => matches!(token.token, Token::CommentLine(_)),
+        None => false,
+    };
+    let mut first_index = index;
+    while first_index > 0 && is_comment_line(first_index - 1) && tokens[first_index - 1].range.end_line + 1 == tokens[first_index].range.start_line {
+        first_index -= 1;
+    }
+    let mut last_index = index;
+    while is_comment_line(last_index + 1) && tokens[last_index].range.end_line + 1 == tokens[last_index + 1].range.start_line {
+        last_index += 1;
+    }
+
+    let mut edits = Vec::new();
+    for (i, token) in tokens[first_index..=last_index].iter().enumerate() {
+        if let Token::CommentLine(comment_text) = &token.token {
+            if let Some(pos) = comment_text.as_ref().find("*/") {
+                let pos = token.range.start + 2 + comment_text.as_ref()[..pos].chars().count();
+                return Err(ParseError::new(pos, "Cannot convert comment lines containing */ to a comment block."));
+            }
+        }
+        edits.push(TextEdit {
+            start: token.range.start,
+            end: token.range.start + 2,
+            new_text: String::from(if i == 0 { "/*" } else { "  " }),
+        });
+    }
+    edits.push(TextEdit::insert(tokens[last_index].range.end, " */"));
+
+    Ok(edits)
+}
+
+/// Gets the edits that re-indent a comment block to the provided indentation, such as after the
+/// node it comments was moved to a different depth.
+///
+/// The comment is the comment block containing the start of the provided range. The lines after
+/// the first keep their indentation relative to the first line, which keeps the `*` gutter of
+/// comments like JSDoc aligned. No edits are returned when the comment doesn't start its line or
+/// when a line after the first isn't indented with the indentation of the first line.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::common::Range;
+/// use jsonc_parser::edits::{apply_text_edits, reindent_comment_block};
+///
+/// let text = "{\n  /**\n   * a\n   */\n  \"a\": 1\n}";
+/// let range = Range { start: 4, end: 4, start_line: 1, end_line: 1 };
+/// let edits = reindent_comment_block(text, &range, "\t").unwrap();
+/// assert_eq!(apply_text_edits(text, &edits).unwrap(), "{\n\t/**\n\t * a\n\t */\n  \"a\": 1\n}");
+/// ```
+pub fn reindent_comment_block(text: &str, comment_range: &Range, new_indent: &str) -> Result<Vec<TextEdit>, ParseError> {
+    let tokens = scan_tokens(&mut Scanner::new(text))?;
+    let token = tokens.iter().find(|token| {
+        matches!(token.token, Token::CommentBlock(_)) && token.range.start <= comment_range.start && comment_range.start < token.range.end
+    });
+    let range = match token {
+        Some(token) => &token.range,
+        None => return Err(ParseError::new(comment_range.start, "Expected a comment block at the provided range.").locate(text.chars(), None)),
+    };
+
+    let chars = text.chars().collect::<Vec<_>>();
+    let line_start = chars[..range.start].iter().rposition(|c| *c == '\n').map(|i| i + 1).unwrap_or(0);
+    let old_indent = &chars[line_start..range.start];
+    if !old_indent.iter().all(|c| c.is_whitespace()) || old_indent.iter().copied().eq(new_indent.chars()) {
+        return Ok(Vec::new());
+    }
+
+    let mut edits = vec![TextEdit {
+        start: line_start,
+        end: range.start,
+        new_text: String::from(new_indent),
+    }];
+    let mut pos = range.start;
+    while let Some(index) = chars[pos..range.end].iter().position(|c| *c == '\n') {
+        let start = pos + index + 1;
+        pos = start;
+        let line_end = chars[start..range.end].iter().position(|c| *c == '\n').map(|i| start + i).unwrap_or(range.end);
+        let line = &chars[start..line_end];
+        if line.iter().all(|c| c.is_whitespace()) {
+            continue;
+        }
+        if !line.starts_with(old_indent) {
+            return Ok(Vec::new());
+        }
+        edits.push(TextEdit {
+            start,
+            end: start + old_indent.len(),
+            new_text: String::from(new_indent),
+        });
+    }
+
+    debug_assert!(parse_text(text).is_err() || verify_edits(text, &edits, &ParseOptions::default()).is_ok());
+    Ok(edits)
+}
+
+/// Gets the whitespace that precedes the position on its line, or spaces up
+/// to the position's column when there is other text before it on the line.
+fn get_indent_text(chars: &[char], pos: usize) -> String {
+    let line_start = chars[..pos].iter().rposition(|c| *c == '\n').map(|i| i + 1).unwrap_or(0);
+    let line_prefix = &chars[line_start..pos];
+    if line_prefix.iter().all(|c| c.is_whitespace()) {
+        line_prefix.iter().collect()
+    } else {
+        " ".repeat(line_prefix.len())
+    }
+}
+
+fn is_newline(c: char) -> bool {
+    c == '\n' || c == '\r'
+}
+
+fn strip_leading_whitespace(text: &str, max_width: usize) -> &str {
+    let mut byte_index = 0;
+    for (i, c) in text.char_indices().take(max_width) {
+        if !c.is_whitespace() {
+            break;
+        }
+        byte_index = i + c.len_utf8();
+    }
+    &text[byte_index..]
+}
+
+/// Gets if the first token at or after the position is a comma.
+fn is_comma_at(tokens: &[TokenAndRange], pos: usize) -> bool {
+    find_comma_at(tokens, pos).is_some()
+}
+
+/// Gets the range of the first token at or after the position when it's a comma.
+fn find_comma_at(tokens: &[TokenAndRange], pos: usize) -> Option<&Range> {
+    let index = tokens.partition_point(|token| token.range.start < pos);
+    match tokens.get(index) {
+        Some(token) if token.token == Token::Comma => Some(&token.range),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse_to_value;
+
+    #[test]
+    fn it_rejects_invalid_edits() {
+        let replace = |start: usize, end: usize, new_text: &str| TextEdit { start, end, new_text: String::from(new_text) };
+        let err = EditSet::new(vec![replace(0, 1, "a"), replace(5, 8, "b"), replace(7, 9, "c")]).unwrap_err();
+        assert_eq!(err, EditError::new(2, 7, 9, "The edit overlaps another edit."));
+        assert_eq!(EditSet::new(vec![TextEdit::insert(2, "a"), TextEdit::insert(2, "b")]).unwrap_err().index, 1);
+        assert_eq!(EditSet::new(vec![replace(2, 4, "a"), TextEdit::insert(2, "b")]).unwrap_err().index, 1);
+        assert_eq!(EditSet::new(vec![replace(3, 2, "a")]).unwrap_err().message, "The edit starts after its end.");
+        // adjacent edits don't overlap
+        assert!(EditSet::new(vec![replace(2, 4, "a"), TextEdit::insert(4, "b"), replace(5, 6, "c")]).is_ok());
+        assert!(EditSet::new(vec![replace(4, 5, "a"), replace(2, 4, "b")]).is_ok());
+        assert_eq!(apply_text_edits("[1]", &[TextEdit::insert(1, "0"), replace(2, 4, "")]).unwrap_err().index, 1);
+    }
+
+    #[test]
+    fn it_applies_edits_at_the_end_of_the_text() {
+        let text = "[\u{e9}]";
+        assert_eq!(apply_text_edits(text, &[TextEdit::insert(3, "\n")]).unwrap(), "[\u{e9}]\n");
+        assert_eq!(apply_text_edits(text, &[replace_all(text, "[]"), TextEdit::insert(3, "\n")]).unwrap(), "[]\n");
+        assert!(apply_text_edits(text, &[TextEdit::insert(4, "\n")]).is_err());
+    }
+
+    fn replace_all(text: &str, new_text: &str) -> TextEdit {
+        TextEdit {
+            start: 0,
+            end: text.chars().count(),
+            new_text: String::from(new_text),
+        }
+    }
+
+    #[test]
+    fn it_maps_positions_across_edits() {
+        // "[1, 22, 3]" -> "[1, 3, 4, 5]"
+        let text = "[1, 22, 3]";
+        let edits = EditSet::new(vec![TextEdit::insert(9, ", 4, 5"), TextEdit { start: 3, end: 7, new_text: String::new() }]).unwrap();
+        let new_text = edits.apply(text).unwrap();
+        assert_eq!(new_text, "[1, 3, 4, 5]");
+        let mapped = (0..=text.len()).map(|pos| edits.map_position(pos)).collect::<Vec<_>>();
+        assert_eq!(mapped, vec![0, 1, 2, 3, 3, 3, 3, 3, 4, 11, 12]);
+        // the character at each position outside the edits is the same in the new text
+        for pos in [0, 1, 2, 7, 8, 9].iter() {
+            assert_eq!(text.chars().nth(*pos), new_text.chars().nth(edits.map_position(*pos)));
+        }
+    }
+
+    #[test]
+    fn it_adds_trailing_commas_to_multi_line_containers() {
+        assert_adds_trailing_commas(
+            "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {\n    \"c\": true\n  }\n}",
+            "{\n  \"a\": [\n    1,\n    2,\n  ],\n  \"b\": {\n    \"c\": true,\n  },\n}",
+        );
+    }
+
+    #[test]
+    fn it_skips_containers_with_trailing_commas() {
+        assert_adds_trailing_commas(
+            "[\n  [\n    1,\n  ],\n  2,\n]",
+            "[\n  [\n    1,\n  ],\n  2,\n]",
+        );
+    }
+
+    #[test]
+    fn it_adds_trailing_commas_with_single_quoted_strings() {
+        assert_adds_trailing_commas("{\n  'a': 1\n}", "{\n  'a': 1,\n}");
+    }
+
+    #[test]
+    fn it_skips_single_line_containers() {
+        assert_adds_trailing_commas(
+            "[\n  [1, 2],\n  { \"a\": 1 }, {}, []\n]",
+            "[\n  [1, 2],\n  { \"a\": 1 }, {}, [],\n]",
+        );
+    }
+
+    #[test]
+    fn it_skips_containers_whose_last_element_ends_on_the_closing_line() {
+        assert_adds_trailing_commas(
+            "[1, {\n  \"a\": 1\n}]",
+            "[1, {\n  \"a\": 1,\n}]",
+        );
+    }
+
+    #[test]
+    fn it_inserts_before_comments() {
+        assert_adds_trailing_commas(
+            "{\n  \"a\": 1 // comment\n  /* other */\n}",
+            "{\n  \"a\": 1, // comment\n  /* other */\n}",
+        );
+        assert_adds_trailing_commas(
+            "[\n  1 /* , */\n]",
+            "[\n  1, /* , */\n]",
+        );
+    }
+
+    #[test]
+    fn it_converts_comment_lines_to_block() {
+        assert_converts_comment(
+            "{\n  // first\n  //   second\n  \"a\": 1 // other\n}",
+            9,
+            CommentKind::Block,
+            "{\n  /* first\n       second */\n  \"a\": 1 // other\n}",
+        );
+        assert_converts_comment("[1] // test", 6, CommentKind::Block, "[1] /* test */");
+    }
+
+    #[test]
+    fn it_does_not_join_comment_lines_separated_by_a_blank_line() {
+        assert_converts_comment("// a\n\n// b\n1", 7, CommentKind::Block, "// a\n\n/* b */\n1");
+    }
+
+    #[test]
+    fn it_errors_converting_comment_lines_containing_block_end() {
+        let err = convert_comment("// a */\n1", &range_at(0), CommentKind::Block).err().unwrap();
+        assert_eq!(err.pos, 5);
+    }
+
+    #[test]
+    fn it_converts_single_line_block_to_comment_line() {
+        assert_converts_comment("[\n  1 /* test */\n]", 8, CommentKind::Line, "[\n  1 // test\n]");
+    }
+
+    #[test]
+    fn it_converts_multi_line_block_to_comment_lines() {
+        assert_converts_comment(
+            "{\n  /* first\n       second */\n  \"a\": 1\n}",
+            4,
+            CommentKind::Line,
+            "{\n  // first\n  //   second\n  \"a\": 1\n}",
+        );
+        assert_converts_comment("/*\n  a\n  b\n*/\n1", 0, CommentKind::Line, "//a\n//b\n1");
+    }
+
+    #[test]
+    fn it_errors_converting_block_followed_by_text_on_same_line() {
+        let err = convert_comment("[/* a */ 1]", &range_at(1), CommentKind::Line).err().unwrap();
+        assert_eq!(err.pos, 9);
+    }
+
+    #[test]
+    fn it_does_nothing_when_comment_is_already_target_kind() {
+        assert_eq!(convert_comment("// a\n1", &range_at(0), CommentKind::Line).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn it_errors_when_no_comment_at_range() {
+        let err = convert_comment("// a\n1", &range_at(5), CommentKind::Line).err().unwrap();
+        assert_eq!(err.message, "Expected a comment at the provided range.");
+    }
+
+    #[test]
+    fn it_reindents_comment_blocks_keeping_their_alignment() {
+        let depth_1 = r#"{
+  /**
+   * Description.
+   *   - aligned
+   *
+   */
+  "a": 1
+}"#;
+        let depth_3 = r#"{
+      /**
+       * Description.
+       *   - aligned
+       *
+       */
+  "a": 1
+}"#;
+        let edits = reindent_comment_block(depth_1, &range_at(4), "      ").unwrap();
+        assert_eq!(apply_text_edits(depth_1, &edits).unwrap(), depth_3);
+        let edits = reindent_comment_block(depth_3, &range_at(8), "  ").unwrap();
+        assert_eq!(apply_text_edits(depth_3, &edits).unwrap(), depth_1);
+        assert_eq!(reindent_comment_block(depth_1, &range_at(4), "  ").unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn it_does_not_reindent_comment_blocks_without_a_common_indent() {
+        // the second line is indented less than the first
+        assert_eq!(reindent_comment_block("[\n    /* a\n  b */\n    1\n]", &range_at(6), "  ").unwrap(), Vec::new());
+        // text precedes the comment on its line
+        assert_eq!(reindent_comment_block("[1, /* a\n     b */ 2]", &range_at(4), "  ").unwrap(), Vec::new());
+        let err = reindent_comment_block("// a\n1", &range_at(0), "  ").err().unwrap();
+        assert_eq!(err.message, "Expected a comment block at the provided range.");
+    }
+
+    fn assert_converts_comment(text: &str, pos: usize, target_kind: CommentKind, expected: &str) {
+        let edits = convert_comment(text, &range_at(pos), target_kind).unwrap();
+        assert_eq!(apply_text_edits(text, &edits).unwrap(), expected);
+    }
+
+    fn range_at(pos: usize) -> Range {
+        Range {
+            start: pos,
+            end: pos,
+            start_line: 0,
+            end_line: 0,
+        }
+    }
+
+    #[test]
+    fn it_inserts_properties_detecting_double_quotes() {
+        assert_inserts_property("{\n  \"a\": 1\n}", 0, "b", KeyQuoteStyle::Detect, "{\n  \"a\": 1,\n  \"b\": true\n}");
+        assert_inserts_property("{ \"a\": 1 }", 0, "b", KeyQuoteStyle::Detect, "{ \"a\": 1, \"b\": true }");
+        assert_inserts_property("{}", 0, "b", KeyQuoteStyle::Detect, "{\"b\": true}");
+    }
+
+    #[test]
+    fn it_inserts_properties_detecting_single_quotes() {
+        assert_inserts_property("{\n  'a': 1,\n  'b': 2,\n}", 0, "c", KeyQuoteStyle::Detect, "{\n  'a': 1,\n  'b': 2,\n  'c': true,\n}");
+        assert_inserts_property("{ \"a\": 1, 'b': 2, 'c': 3 }", 0, "it's", KeyQuoteStyle::Detect, "{ \"a\": 1, 'b': 2, 'c': 3, 'it\\'s': true }");
+    }
+
+    #[test]
+    fn it_inserts_properties_detecting_the_quotes_most_siblings_use() {
+        assert_inserts_property("{ 'a': 1, 'b': 2, \"c\": 3 }", 0, "d", KeyQuoteStyle::Detect, "{ 'a': 1, 'b': 2, \"c\": 3, 'd': true }");
+        assert_inserts_property("{ \"a\": 1, \"b\": 2, 'c': 3 }", 0, "d", KeyQuoteStyle::Detect, "{ \"a\": 1, \"b\": 2, 'c': 3, \"d\": true }");
+        assert_inserts_property("{ \"a\": 1, 'b': 2 }", 0, "c", KeyQuoteStyle::Detect, "{ \"a\": 1, 'b': 2, \"c\": true }");
+    }
+
+    #[test]
+    fn it_inserts_properties_with_explicit_quote_styles() {
+        assert_inserts_property("{ 'a': 1 }", 0, "b", KeyQuoteStyle::Double, "{ 'a': 1, \"b\": true }");
+        assert_inserts_property("{ \"a\": 1 }", 0, "b", KeyQuoteStyle::Single, "{ \"a\": 1, 'b': true }");
+        // unquoted names can't be parsed yet, so they're quoted
+        assert_inserts_property("{ \"a\": 1 }", 0, "$b_1", KeyQuoteStyle::PreferUnquoted, "{ \"a\": 1, \"$b_1\": true }");
+        assert_inserts_property("{ \"a\": 1 }", 0, "b-c", KeyQuoteStyle::PreferUnquoted, "{ \"a\": 1, \"b-c\": true }");
+    }
+
+    #[test]
+    fn it_inserts_properties_in_nested_objects() {
+        assert_inserts_property("[1, {\n    'a': {}\n  }]", 4, "b", KeyQuoteStyle::Detect, "[1, {\n    'a': {},\n    'b': true\n  }]");
+    }
+
+    #[test]
+    fn it_errors_when_no_object_at_position() {
+        let err = insert_property("[1, {}]", 1, "b", &JsonValue::Null, &EditOptions::default()).err().unwrap();
+        assert_eq!(err.message, "Expected an object at the provided position.");
+    }
+
+    #[test]
+    fn it_verifies_inserted_adversarial_strings() {
+        let text = "{\n  // comment\n  \"a\": \"x\\\\\", /* b */ \"b\": [1]\n}";
+        let value = JsonValue::String(String::from("\"\\\" */ // /* \\"));
+        let edits = insert_property(text, 0, "c\"\\*/", &value, &EditOptions::default()).unwrap();
+        assert_eq!(verify_edits(text, &edits, &ParseOptions::default()), Ok(()));
+
+        let new_value = parse_to_value(&apply_text_edits(text, &edits).unwrap()).unwrap().unwrap();
+        match &new_value {
+            JsonValue::Object(obj) => assert_eq!(obj.get("c\"\\*/"), Some(&value)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn it_errors_verifying_edits_that_change_other_values() {
+        let options = ParseOptions::default();
+        // a string ending in a backslash that escapes the quote of the following string
+        let err = verify_edits(r#"["a", "b"]"#, &[TextEdit::insert(1, r#""\"#)], &options).unwrap_err();
+        assert_eq!(err, EditVerifyError::ChangedValue { range: Range { start: 1, end: 4, start_line: 0, end_line: 0 }, message: String::from("Value outside the edits changed.") });
+
+        // an unclosed comment block that comments out the following values
+        let err = verify_edits("[1, /* a */ 2]", &[TextEdit::insert(1, "0 /* ")], &options).unwrap_err();
+        assert_eq!(err, EditVerifyError::ChangedValue { range: Range { start: 1, end: 2, start_line: 0, end_line: 0 }, message: String::from("Value outside the edits changed.") });
+
+        let err = verify_edits("[1, 2]", &[TextEdit { start: 4, end: 5, new_text: String::from("\"") }], &options).unwrap_err();
+        assert!(matches!(err, EditVerifyError::Parse(_)));
+
+        assert_eq!(verify_edits("[1, 2]", &[TextEdit { start: 4, end: 5, new_text: String::from("\"*/\"") }], &options), Ok(()));
+    }
+
+    #[test]
+    fn it_sets_values() {
+        assert_sets_value("{ 'a': [1, 2] }", "a[1]", "{ 'a': [1, true] }");
+        assert_sets_value("{ 'a': { } }", "a.b", "{ 'a': {\"b\": true } }");
+        assert_sets_value("5", "", "true");
+        // the last property with a duplicate name is the one that takes effect
+        assert_sets_value("{\"a\":1,\"a\":2}", "a", "{\"a\":1,\"a\":true}");
+        let err = set_value("{ 'a': [] }", &"a[0]".parse::<JsonPath>().unwrap(), &JsonValue::Null, &EditOptions::default()).err().unwrap();
+        assert_eq!(err.to_string(), "1:1: Expected an object to add the property to at the path '/a/0'.");
+    }
+
+    #[test]
+    fn it_adds_several_properties_to_the_same_object() {
+        let cases = [
+            ("{\n  \"a\": 1\n}", "{\n  \"a\": 1,\n  \"b\": true,\n  \"c\": null\n}"),
+            ("{\n  \"a\": 1,\n}", "{\n  \"a\": 1,\n  \"b\": true,\n  \"c\": null,\n}"),
+            ("{ \"a\": 1 }", "{ \"a\": 1, \"b\": true, \"c\": null }"),
+            ("{}", "{\"b\": true, \"c\": null}"),
+        ];
+        for (text, expected) in cases.iter() {
+            let mut doc = DocumentEditor::new(text);
+            doc.set(&"/b".parse().unwrap(), &JsonValue::Boolean(true)).unwrap();
+            doc.set(&"/c".parse().unwrap(), &JsonValue::Null).unwrap();
+            let new_text = doc.apply().unwrap();
+            assert_eq!(new_text, *expected);
+            assert!(parse_to_value(&new_text).is_ok());
+        }
+
+        // replacing the last value and adding a property after it don't conflict
+        let mut doc = DocumentEditor::new("{ \"a\": 1 }");
+        doc.set(&"/a".parse().unwrap(), &JsonValue::Null).unwrap();
+        doc.set(&"/b".parse().unwrap(), &JsonValue::Null).unwrap();
+        assert_eq!(doc.apply().unwrap(), "{ \"a\": null, \"b\": null }");
+        let err = doc.set(&"/a".parse().unwrap(), &JsonValue::Boolean(true)).unwrap_err();
+        assert_eq!(err.message, "The change overlaps a previous change.");
+    }
+
+    #[test]
+    fn it_removes_values() {
+        assert_removes_value("[1, 2, 3]", "/0", "[2, 3]");
+        assert_removes_value("[1, 2, 3]", "/2", "[1, 2]");
+        assert_removes_value("[1]", "/0", "[]");
+        assert_removes_value("[\n  1,\n  2,\n]", "/1", "[\n  1,\n]");
+        assert_removes_value("{\n  \"a\": 1,\n  \"b\": 2\n}", "a", "{\n  \"b\": 2\n}");
+        assert_removes_value("{ \"a\": { \"b\": 1 } }", "a.b", "{ \"a\": {} }");
+        assert_removes_value("[\n  // first\n  1, /* one */\n]", "/0", "[\n  // first\n  /* one */\n]");
+        assert_removes_value("{ /* a */ \"a\": 1 }", "a", "{ /* a */ }");
+        assert_removes_value("[\n  // first\n  1\n]", "/0", "[\n  // first\n]");
+        assert_removes_value("{\"a\":1,\"a\":2}", "a", "{\"a\":1}");
+        let err = remove_value("{ \"a\": 1 }", &"b".parse::<JsonPath>().unwrap()).err().unwrap();
+        assert_eq!(err.to_string(), "1:1: Expected a property or array element at the path '/b'.");
+    }
+
+    #[test]
+    fn it_keeps_the_comments_of_siblings_when_removing_values() {
+        assert_removes_value("[\n  1,\n  // about two\n  2\n]", "/0", "[\n  // about two\n  2\n]");
+        assert_removes_value("[\n  1, // about one\n  2\n]", "/1", "[\n  1 // about one\n]");
+        assert_removes_value("{\n  \"a\": 1,\n  // about b\n  \"b\": 2\n}", "a", "{\n  // about b\n  \"b\": 2\n}");
+        assert_removes_value("[\n  1, // about one\n  2 ]", "/1", "[\n  1 // about one\n ]");
+        assert_removes_value("[1 /* a */, 2 /* b */]", "/1", "[1 /* a */ /* b */]");
+    }
+
+    fn assert_sets_value(text: &str, path: &str, expected: &str) {
+        let edits = set_value(text, &path.parse::<JsonPath>().unwrap(), &JsonValue::Boolean(true), &EditOptions::default()).unwrap();
+        assert_eq!(apply_text_edits(text, &edits).unwrap(), expected);
+    }
+
+    fn assert_removes_value(text: &str, path: &str, expected: &str) {
+        let edits = remove_value(text, &path.parse::<JsonPath>().unwrap()).unwrap();
+        assert_eq!(apply_text_edits(text, &edits).unwrap(), expected);
+    }
+
+    fn assert_inserts_property(text: &str, object_pos: usize, name: &str, key_quote_style: KeyQuoteStyle, expected: &str) {
+        let options = EditOptions::default().with_key_quote_style(key_quote_style);
+        let edits = insert_property(text, object_pos, name, &JsonValue::Boolean(true), &options).unwrap();
+        assert_eq!(apply_text_edits(text, &edits).unwrap(), expected);
+    }
+
+    fn assert_adds_trailing_commas(text: &str, expected: &str) {
+        let edits = add_trailing_commas(text).unwrap();
+        assert_eq!(apply_text_edits(text, &edits).unwrap(), expected);
+    }
+}
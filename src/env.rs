@@ -0,0 +1,90 @@
+use super::ast::Value;
+use super::errors::ValueError;
+use super::serializer::serialize_value;
+use super::strings::decode_string;
+
+/// How objects and arrays are handled when rendering a value for an environment variable.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EnvContainerHandling {
+    /// Objects and arrays cause an error.
+    Error,
+    /// Objects and arrays are rendered as compact JSON text.
+    JsonEncode,
+}
+
+/// Renders a value as plain text suitable for the value of an environment variable.
+///
+/// Numbers are rendered as their text, booleans as `true` or `false`, strings without
+/// their quotes and with any escapes decoded, and null as an empty string.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::env::{value_to_env_string, EnvContainerHandling};
+///
+/// let value = parse_text(r#""line\nbreak""#).unwrap().value.unwrap();
+/// assert_eq!(value_to_env_string(&value, EnvContainerHandling::Error).unwrap(), "line\nbreak");
+/// ```
+pub fn value_to_env_string(value: &Value, container_handling: EnvContainerHandling) -> Result<String, ValueError> {
+    match value {
+        Value::StringLit(lit) => Ok(decode_string(lit.value.as_ref(), lit.quote).into_owned()),
+        Value::NumberLit(lit) => Ok(String::from(lit.value.as_ref())),
+        Value::BooleanLit(lit) => Ok(lit.value.to_string()),
+        Value::NullKeyword(_) => Ok(String::new()),
+        Value::Object(obj) => match container_handling {
+            EnvContainerHandling::Error => Err(ValueError::new(obj.range.clone(), "Objects cannot be rendered as an environment variable value.")),
+            EnvContainerHandling::JsonEncode => Ok(serialize_value(value)),
+        },
+        Value::Array(arr) => match container_handling {
+            EnvContainerHandling::Error => Err(ValueError::new(arr.range.clone(), "Arrays cannot be rendered as an environment variable value.")),
+            EnvContainerHandling::JsonEncode => Ok(serialize_value(value)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse_text;
+
+    #[test]
+    fn it_renders_strings_decoded() {
+        assert_renders(r#""te\"st \\ é 😀 \t""#, "te\"st \\ \u{e9} \u{1F600} \t");
+    }
+
+    #[test]
+    fn it_renders_numbers() {
+        assert_renders("-1.5e+10", "-1.5e+10");
+    }
+
+    #[test]
+    fn it_renders_booleans() {
+        assert_renders("true", "true");
+        assert_renders("false", "false");
+    }
+
+    #[test]
+    fn it_renders_null_as_empty() {
+        assert_renders("null", "");
+    }
+
+    #[test]
+    fn it_errors_for_containers() {
+        let value = parse_text(r#"{ "a": [1] }"#).unwrap().value.unwrap();
+        let err = value_to_env_string(&value, EnvContainerHandling::Error).expect_err("Expected an error.");
+        assert_eq!(err.range.start, 0);
+        assert_eq!(err.message, "Objects cannot be rendered as an environment variable value.");
+    }
+
+    #[test]
+    fn it_json_encodes_containers() {
+        let value = parse_text(r#"{ "a": [1, "b\n"], "c": null }"#).unwrap().value.unwrap();
+        assert_eq!(value_to_env_string(&value, EnvContainerHandling::JsonEncode).unwrap(), r#"{"a":[1,"b\n"],"c":null}"#);
+    }
+
+    fn assert_renders(text: &str, expected: &str) {
+        let value = parse_text(text).unwrap().value.unwrap();
+        assert_eq!(value_to_env_string(&value, EnvContainerHandling::Error).unwrap(), expected);
+    }
+}
@@ -1,7 +1,87 @@
+use std::fmt;
+use std::io;
+use super::common::{ImmutableString, Range};
+
+/// The kind of a `ParseError`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ErrorKind {
+    /// The text ended before what was expected (ex. the user isn't done typing).
+    UnexpectedEof {
+        /// Description of what was expected (ex. `'}'` or `a value`).
+        expected: String,
+        /// Range of the `{`, `[`, quote, or `/*` that was never closed, if any, which is boxed to
+        /// keep `ParseError` small since it's returned by value throughout the API.
+        opened_at: Option<Box<Range>>,
+        /// Position where the expected text could be inserted (ex. after the last value of an
+        /// unclosed `[`), when known.
+        insertion_point: Option<usize>,
+    },
+    /// A comma is missing between the items of an object or array (ex. `[1 2]`).
+    MissingComma {
+        /// Position where the comma could be inserted, which is the end of the previous item.
+        insertion_point: usize,
+    },
+    /// A colon is missing after the name of an object property (ex. `{ "a" 1 }`).
+    MissingColon {
+        /// Position where the colon could be inserted, which is the end of the property name.
+        insertion_point: usize,
+    },
+    /// The text could not be read (ex. the file was not found).
+    Io(io::ErrorKind),
+    /// A number literal is malformed (ex. `1.` or `01`).
+    InvalidNumber {
+        kind: NumberErrorKind,
+        /// Range of the attempted number literal.
+        range: Range,
+    },
+    /// Any other error.
+    Other,
+}
+
+/// The way a number literal is malformed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NumberErrorKind {
+    /// A negative sign without a digit after it (ex. `-a`).
+    BareMinus,
+    /// A number that doesn't start with a digit (ex. `.5` when leading decimal points aren't allowed).
+    MissingIntegerDigits,
+    /// An integer with a zero followed by more digits (ex. `0123`), which is only an error with
+    /// `ScannerOptions::strict_numbers`.
+    LeadingZeros,
+    /// A decimal point without a digit after it (ex. `1.e5`).
+    MissingFractionDigits,
+    /// An exponent without a digit (ex. `1e+`).
+    MissingExponentDigits,
+    /// A hexadecimal prefix without a digit after it (ex. `0x`).
+    MissingHexDigits,
+    /// A hexadecimal number that doesn't fit in 64 bits (ex. `0x10000000000000000`).
+    HexOverflow,
+    /// The text ended where the number needs a digit (ex. `[1.`).
+    UnexpectedEnd,
+}
+
+/// A position in a document along with its line and column.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub(crate) struct TextLocation {
+    pub pos: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
 /// Error that could occur while parsing or tokenizing.
+///
+/// When displayed, the error is formatted as `<source name>:<line>:<column>: <message>`
+/// with a one-based line and column. The source name is omitted when not provided.
 #[derive(Debug, PartialEq)]
 pub struct ParseError {
     pub pos: usize,
+    /// Line of the error (zero-indexed).
+    pub line: usize,
+    /// Column of the error in characters (zero-indexed).
+    pub column: usize,
+    /// Name of the source the text came from (ex. a file path).
+    pub source_name: Option<ImmutableString>,
+    pub kind: ErrorKind,
     pub message: String,
 }
 
@@ -9,6 +89,312 @@ impl ParseError {
     pub(super) fn new(pos: usize, message: &str) -> ParseError {
         ParseError {
             pos,
+            line: 0,
+            column: 0,
+            source_name: None,
+            kind: ErrorKind::Other,
+            message: String::from(message),
+        }
+    }
+
+    /// Creates an error for when the text ended while expecting something.
+    pub(super) fn new_unexpected_eof(pos: usize, message: &str, expected: &str) -> ParseError {
+        let mut err = ParseError::new(pos, message);
+        err.kind = ErrorKind::UnexpectedEof {
+            expected: String::from(expected),
+            opened_at: None,
+            insertion_point: None,
+        };
+        err
+    }
+
+    /// Creates an error for when the text ended before the construct at the range was closed.
+    pub(super) fn new_unclosed(pos: usize, opened_at: Range, open_text: &str, expected: &str) -> ParseError {
+        let message = format!("Unexpected end of file; '{}' opened on line {} was never closed", open_text, opened_at.start_line + 1);
+        let mut err = ParseError::new(pos, &message);
+        err.kind = ErrorKind::UnexpectedEof {
+            expected: String::from(expected),
+            opened_at: Some(Box::new(opened_at)),
+            insertion_point: None,
+        };
+        err
+    }
+
+    /// Creates an error for when a comma is missing between the items of an object or array.
+    pub(super) fn new_missing_comma(pos: usize, message: &str, insertion_point: usize) -> ParseError {
+        let mut err = ParseError::new(pos, message);
+        err.kind = ErrorKind::MissingComma { insertion_point };
+        err
+    }
+
+    /// Creates an error for when a colon is missing after the name of an object property.
+    pub(super) fn new_missing_colon(pos: usize, message: &str, insertion_point: usize) -> ParseError {
+        let mut err = ParseError::new(pos, message);
+        err.kind = ErrorKind::MissingColon { insertion_point };
+        err
+    }
+
+    /// Sets where the expected text could be inserted for an error about the text ending early.
+    pub(super) fn with_insertion_point(mut self, pos: usize) -> ParseError {
+        if let ErrorKind::UnexpectedEof { insertion_point, .. } = &mut self.kind {
+            *insertion_point = Some(pos);
+        }
+        self
+    }
+
+    /// Gets the position where the missing comma, colon, or closing text could be inserted
+    /// to fix the error, when known.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::{parse_text_with_options, ParseOptions};
+    ///
+    /// let options = ParseOptions::default().with_require_commas(true);
+    /// let err = parse_text_with_options("[1 2]", &options).err().unwrap();
+    /// assert_eq!(err.insertion_point(), Some(2));
+    /// ```
+    pub fn insertion_point(&self) -> Option<usize> {
+        match &self.kind {
+            ErrorKind::UnexpectedEof { insertion_point, .. } => *insertion_point,
+            ErrorKind::MissingComma { insertion_point } | ErrorKind::MissingColon { insertion_point } => Some(*insertion_point),
+            _ => None,
+        }
+    }
+
+    /// Creates an error for a malformed number literal at the range.
+    pub(super) fn new_invalid_number(pos: usize, range: Range, kind: NumberErrorKind, message: &str) -> ParseError {
+        let mut parse_error = ParseError::new(pos, message);
+        parse_error.kind = ErrorKind::InvalidNumber { kind, range };
+        parse_error
+    }
+
+    /// Creates an error for when the text could not be read from the named source.
+    pub(super) fn new_io(err: &io::Error, source_name: Option<&str>) -> ParseError {
+        let mut parse_error = ParseError::new(0, &err.to_string());
+        parse_error.kind = ErrorKind::Io(err.kind());
+        parse_error.source_name = source_name.map(|name| ImmutableString::new(String::from(name)));
+        parse_error
+    }
+
+    /// Sets the line and column of the error from the characters of the text along with the source name.
+    pub(super) fn locate(self, chars: impl Iterator<Item = char>, source_name: Option<&ImmutableString>) -> ParseError {
+        self.locate_in(chars, TextLocation::default(), source_name)
+    }
+
+    /// Sets the line and column of the error like `locate` for text that starts at the provided
+    /// location of the document, which is used once the text before it was dropped.
+    pub(super) fn locate_in(mut self, chars: impl Iterator<Item = char>, start: TextLocation, source_name: Option<&ImmutableString>) -> ParseError {
+        let mut line = start.line;
+        let mut column = start.column;
+        for c in chars.take(self.pos.saturating_sub(start.pos)) {
+            if c == '\n' {
+                line += 1;
+                column = 0;
+            } else {
+                column += 1;
+            }
+        }
+        self.line = line;
+        self.column = column;
+        self.source_name = source_name.cloned();
+        self
+    }
+
+    /// Formats the error followed by the line of the text it's on and a caret pointing at its column,
+    /// which makes errors in test failures easier to find.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_text;
+    ///
+    /// let text = "{\n\t\"a\": tru\n}";
+    /// let err = parse_text(text).err().unwrap();
+    /// assert_eq!(err.to_test_string(text), "2:7: Unexpected token.\n\t\"a\": tru\n\t     ^");
+    /// ```
+    pub fn to_test_string(&self, text: &str) -> String {
+        let line_text = text.split('\n').nth(self.line).unwrap_or("").trim_end_matches('\r');
+        // keep the tabs before the column so the caret lines up
+        let indent = line_text.chars().take(self.column).map(|c| if c == '\t' { '\t' } else { ' ' }).collect::<String>();
+        format!("{}\n{}\n{}^", self, line_text, indent)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(source_name) = &self.source_name {
+            write!(f, "{}:", source_name.as_ref())?;
+        }
+        write!(f, "{}:{}: {}", self.line + 1, self.column + 1, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Error that could occur while converting a parsed value.
+#[derive(Debug, PartialEq)]
+pub struct ValueError {
+    /// Range of the value that could not be converted.
+    pub range: Range,
+    pub message: String,
+}
+
+impl ValueError {
+    pub(super) fn new(range: Range, message: &str) -> ValueError {
+        ValueError {
+            range,
+            message: String::from(message),
+        }
+    }
+}
+
+/// Error found when verifying the ranges of a parse result with `verify_ranges`.
+///
+/// When displayed, the error is formatted as `Line <line>: <message>`.
+#[derive(Debug, PartialEq)]
+pub struct RangeError {
+    /// The range that breaks a guarantee.
+    pub range: Range,
+    pub message: String,
+}
+
+impl RangeError {
+    pub(super) fn new(range: &Range, message: &str) -> RangeError {
+        RangeError {
+            range: range.clone(),
+            message: String::from(message),
+        }
+    }
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}: {}", self.range.start_line + 1, self.message)
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+/// Error that could occur while verifying that edits keep the text valid.
+#[derive(Debug, PartialEq)]
+pub enum EditVerifyError {
+    /// The text could not be parsed before or after applying the edits.
+    Parse(ParseError),
+    /// The edits overlap or are out of the bounds of the text.
+    Edit(EditError),
+    /// A value that was not edited changed after applying the edits.
+    ChangedValue {
+        /// Range of the value in the text before applying the edits.
+        range: Range,
+        message: String,
+    },
+}
+
+impl fmt::Display for EditVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditVerifyError::Parse(err) => write!(f, "Edited text is invalid: {}", err),
+            EditVerifyError::Edit(err) => write!(f, "{}", err),
+            EditVerifyError::ChangedValue { range, message } => write!(f, "Line {}: {}", range.start_line + 1, message),
+        }
+    }
+}
+
+impl std::error::Error for EditVerifyError {}
+
+/// Error that could occur while validating edits with `EditSet::new` or applying them.
+///
+/// When displayed, the error is formatted as `Edit <index> (<start>..<end>): <message>`.
+#[derive(Debug, PartialEq)]
+pub struct EditError {
+    /// Index of the offending edit in the provided edits.
+    pub index: usize,
+    /// Start position of the offending edit.
+    pub start: usize,
+    /// End position of the offending edit.
+    pub end: usize,
+    pub message: String,
+}
+
+impl EditError {
+    pub(super) fn new(index: usize, start: usize, end: usize, message: &str) -> EditError {
+        EditError {
+            index,
+            start,
+            end,
+            message: String::from(message),
+        }
+    }
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Edit {} ({}..{}): {}", self.index, self.start, self.end, self.message)
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// Error that could occur while reading a value with an `Accessor`.
+///
+/// When displayed, the error is formatted as `"<path>": <message>`.
+#[derive(Debug, PartialEq)]
+pub struct AccessError {
+    /// JSON pointer path of the value (ex. `/server/port`).
+    pub path: String,
+    /// Range of the value when it was read from an AST.
+    pub range: Option<Range>,
+    pub message: String,
+}
+
+impl AccessError {
+    pub(super) fn new(path: String, range: Option<Range>, message: &str) -> AccessError {
+        AccessError {
+            path,
+            range,
+            message: String::from(message),
+        }
+    }
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\": {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for AccessError {}
+
+/// Error that could occur while decoding the escapes of a string literal.
+#[derive(Debug, PartialEq)]
+pub struct EscapeError {
+    /// Character offset of the invalid escape within the string literal's text.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl EscapeError {
+    pub(super) fn new(offset: usize, message: &str) -> EscapeError {
+        EscapeError {
+            offset,
+            message: String::from(message),
+        }
+    }
+}
+
+/// Error that could occur while parsing a number literal.
+#[derive(Debug, PartialEq)]
+pub struct NumberError {
+    /// Character offset of the error within the number literal's text.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl NumberError {
+    pub(super) fn new(offset: usize, message: &str) -> NumberError {
+        NumberError {
+            offset,
             message: String::from(message),
         }
     }
@@ -0,0 +1,830 @@
+use std::borrow::Cow;
+use super::ast::CommentKind;
+use super::common::{ImmutableString, Range};
+use super::errors::ParseError;
+use super::numbers::to_json_number_text;
+use super::parser::{create_scanner, LoneSurrogates, MissingValue, ParseOptions, RootKind};
+use super::scanner::{RawToken, ScannedToken, Scanner};
+use super::strings::{decode_lone_surrogate_string, decode_string, find_lone_surrogate, JsonString};
+use super::tokens::Token;
+use super::value::{JsonArray, JsonObject, JsonValue};
+
+/// Something found while parsing the text with an `EventParser`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Event {
+    /// The open brace of an object.
+    ObjectStart(Range),
+    /// The name of an object property with its escapes decoded and the range of its string.
+    Key(String, Range),
+    /// The close brace of an object.
+    ObjectEnd(Range),
+    /// The open bracket of an array.
+    ArrayStart(Range),
+    /// The close bracket of an array.
+    ArrayEnd(Range),
+    /// A string with its escapes decoded.
+    String(String, Range),
+    /// A string with escapes of lone surrogates, which is only created with `LoneSurrogates::PreserveAsWtf8`.
+    Wtf8String(JsonString, Range),
+    /// The text of a number (ex. `-1.2e+2`).
+    Number(ImmutableString, Range),
+    /// The keyword `true` or `false`.
+    Bool(bool, Range),
+    /// The keyword `null`.
+    Null(Range),
+    /// A line or block comment, which may appear between any tokens.
+    Comment(CommentKind, Range),
+}
+
+/// A container that the event parser is currently in.
+enum EventFrame {
+    /// An object with the range of its open brace.
+    Object(Range),
+    /// An array with the range of its open bracket.
+    Array(Range),
+}
+
+/// How a token moved the event parser forward, which is used to create an event.
+enum Step {
+    ObjectStart,
+    ObjectEnd,
+    ArrayStart,
+    ArrayEnd,
+    Key,
+    Value,
+}
+
+/// What the event parser expects from the next token.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum EventState {
+    /// A value, which is either the root value or the value of an object property.
+    Value,
+    /// A property name or close brace in an object, or an element or close bracket in an array.
+    ContainerItem,
+    /// The colon after a property name.
+    Colon,
+    /// A comma or the end of the container after a value, or the end of the text after the root value.
+    AfterValue,
+    /// Nothing, because the text was fully parsed or an error occurred.
+    Done,
+}
+
+/// Parses JSONC into a stream of events that are pulled one at a time without building an AST.
+///
+/// The structure of the text is validated as the events are pulled, so an error is returned at
+/// the first unexpected token and the events before it describe a valid prefix of the text.
+/// This is useful for reading a few values out of a large document.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::events::{Event, EventParser};
+/// use jsonc_parser::ParseOptions;
+///
+/// let mut parser = EventParser::new(r#"{ "a": [true] }"#, &ParseOptions::default());
+/// let mut events = Vec::new();
+/// while let Some(event) = parser.next_event().unwrap() {
+///     events.push(event);
+/// }
+/// assert!(matches!(&events[1], Event::Key(name, _) if name == "a"));
+/// assert!(matches!(events[3], Event::Bool(true, _)));
+/// assert_eq!(events.len(), 6);
+/// ```
+pub struct EventParser {
+    scanner: Scanner,
+    stack: Vec<EventFrame>,
+    state: EventState,
+    require_commas: bool,
+    require_value: bool,
+    root_kind: RootKind,
+    lone_surrogates: LoneSurrogates,
+    /// What `read_value` returns when the text doesn't contain a value.
+    missing_value: MissingValue,
+    /// End of the last token that wasn't a comment, which is where a missing separator or
+    /// closing token could be inserted.
+    previous_token_end: usize,
+}
+
+impl EventParser {
+    /// Creates an event parser for the text.
+    ///
+    /// The scanner options, `require_commas`, `require_value`, `root_kind`, and `lone_surrogates` of
+    /// the options are used, as well as `missing_value` by `read_value`. Other options for building
+    /// values, such as `key_transform`, are ignored.
+    pub fn new(text: &str, options: &ParseOptions) -> EventParser {
+        EventParser {
+            scanner: create_scanner(text, options),
+            stack: Vec::new(),
+            state: EventState::Value,
+            require_commas: options.require_commas,
+            require_value: options.require_value,
+            root_kind: options.root_kind,
+            lone_surrogates: options.lone_surrogates,
+            missing_value: options.missing_value,
+            previous_token_end: 0,
+        }
+    }
+
+    /// Gets the next event or `None` at the end of the text.
+    ///
+    /// After an error, `None` is returned.
+    pub fn next_event(&mut self) -> Result<Option<Event>, ParseError> {
+        let result = self.scan_event();
+        if result.is_err() {
+            self.state = EventState::Done;
+        }
+        result
+    }
+
+    /// Gets the number of containers that the last event is within, including a container it opened.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Reads the next value into a `JsonValue`, skipping comments.
+    ///
+    /// This should be called when a value is expected (ex. after a `Key` event). `None` is returned
+    /// when a container ends or the text ends instead. When the text doesn't contain a root value,
+    /// `ParseOptions::missing_value` is used like `parse_to_value_with_options`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::events::{Event, EventParser};
+    /// use jsonc_parser::ParseOptions;
+    ///
+    /// let mut parser = EventParser::new(r#"{ "a": { "b": [1, 2] } }"#, &ParseOptions::default());
+    /// assert!(matches!(parser.next_event().unwrap(), Some(Event::ObjectStart(_))));
+    /// assert!(matches!(parser.next_event().unwrap(), Some(Event::Key(..))));
+    /// assert_eq!(parser.read_value().unwrap().unwrap().to_string(), r#"{"b":[1,2]}"#);
+    /// ```
+    pub fn read_value(&mut self) -> Result<Option<JsonValue>, ParseError> {
+        let is_root = self.state == EventState::Value && self.stack.is_empty();
+        let mut containers: Vec<(Option<String>, JsonValue)> = Vec::new();
+        let mut key = None;
+        loop {
+            let event = match self.next_event()? {
+                Some(event) => event,
+                None if is_root => return self.get_missing_value(),
+                None => return Ok(None),
+            };
+            let value = match event {
+                Event::Comment(..) => continue,
+                Event::Key(name, _) => {
+                    key = Some(name);
+                    continue;
+                },
+                Event::ObjectStart(_) => {
+                    containers.push((key.take(), JsonValue::Object(JsonObject::new())));
+                    continue;
+                },
+                Event::ArrayStart(_) => {
+                    containers.push((key.take(), JsonValue::Array(JsonArray::new())));
+                    continue;
+                },
+                Event::ObjectEnd(_) | Event::ArrayEnd(_) => match containers.pop() {
+                    Some((container_key, value)) => {
+                        key = container_key;
+                        value
+                    },
+                    None => return Ok(None),
+                },
+                Event::String(value, _) => JsonValue::String(value),
+                Event::Wtf8String(value, _) => JsonValue::Wtf8String(value),
+                Event::Number(value, range) => match to_json_number_text(value.as_ref()) {
+                    Ok(text) => JsonValue::Number(text.into_owned()),
+                    Err(err) => return Err(self.error(&range, &err.message)),
+                },
+                Event::Bool(value, _) => JsonValue::Boolean(value),
+                Event::Null(_) => JsonValue::Null,
+            };
+
+            match containers.last_mut() {
+                Some((_, JsonValue::Object(obj))) => {
+                    obj.insert(key.take().unwrap_or_default(), value);
+                },
+                Some((_, JsonValue::Array(elements))) => elements.push(value),
+                Some(_) => unreachable!(),
+                None => return Ok(Some(value)),
+            }
+        }
+    }
+
+    /// Skips the next value without creating events or decoding its strings, and returns its range.
+    ///
+    /// The structure of the value is still validated. Comments before the value are skipped and,
+    /// when called before a property name, the name and its value are skipped. `None` is returned
+    /// when a container ends or the text ends instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::events::{Event, EventParser};
+    /// use jsonc_parser::ParseOptions;
+    ///
+    /// let mut parser = EventParser::new(r#"{ "a": { "b": [1, 2] }, "c": 3 }"#, &ParseOptions::default());
+    /// assert!(matches!(parser.next_event().unwrap(), Some(Event::ObjectStart(_))));
+    /// assert!(matches!(parser.next_event().unwrap(), Some(Event::Key(..))));
+    /// assert_eq!(parser.skip_value().unwrap().unwrap().start, 7);
+    /// assert!(matches!(parser.next_event().unwrap(), Some(Event::Key(name, _)) if name == "c"));
+    /// ```
+    pub fn skip_value(&mut self) -> Result<Option<Range>, ParseError> {
+        let result = self.skip_value_inner(0, None);
+        if result.is_err() {
+            self.state = EventState::Done;
+        }
+        result
+    }
+
+    /// Skips the rest of the container that was opened by the last event and returns the range of
+    /// the container from the provided range of its open brace or bracket.
+    pub(crate) fn skip_container(&mut self, start: Range) -> Result<Option<Range>, ParseError> {
+        let result = self.skip_value_inner(1, Some(start));
+        if result.is_err() {
+            self.state = EventState::Done;
+        }
+        result
+    }
+
+    fn skip_value_inner(&mut self, mut depth: usize, mut start: Option<Range>) -> Result<Option<Range>, ParseError> {
+        loop {
+            if self.state == EventState::Done {
+                return Ok(None);
+            }
+
+            let token = match self.scanner.scan_available()? {
+                ScannedToken::Token(token) => token,
+                ScannedToken::End => return self.handle_end().map(|_| None),
+                ScannedToken::Incomplete => return Ok(None),
+            };
+            let range = self.token_range();
+            match self.advance(&token, &range)? {
+                None | Some(Step::Key) => continue,
+                Some(Step::ObjectStart) | Some(Step::ArrayStart) => {
+                    start.get_or_insert_with(|| range.clone());
+                    depth += 1;
+                },
+                Some(Step::ObjectEnd) | Some(Step::ArrayEnd) => {
+                    if depth == 0 {
+                        return Ok(None);
+                    }
+                    depth -= 1;
+                },
+                Some(Step::Value) => {
+                    start.get_or_insert_with(|| range.clone());
+                },
+            }
+
+            if depth == 0 {
+                if let Some(start) = start {
+                    return Ok(Some(Range {
+                        start: start.start,
+                        end: range.end,
+                        start_line: start.start_line,
+                        end_line: range.end_line,
+                    }));
+                }
+            }
+        }
+    }
+
+    fn scan_event(&mut self) -> Result<Option<Event>, ParseError> {
+        loop {
+            if self.state == EventState::Done {
+                return Ok(None);
+            }
+
+            let token = match self.scanner.scan_available()? {
+                ScannedToken::Token(token) => token,
+                ScannedToken::End => return self.handle_end(),
+                ScannedToken::Incomplete => return Ok(None),
+            };
+            let range = self.token_range();
+            let step = match self.advance(&token, &range)? {
+                Some(step) => step,
+                None => match token {
+                    RawToken::CommentLine(..) => return Ok(Some(Event::Comment(CommentKind::Line, range))),
+                    RawToken::CommentBlock(..) => return Ok(Some(Event::Comment(CommentKind::Block, range))),
+                    _ => continue,
+                },
+            };
+            return Ok(Some(match (step, self.scanner.create_token(token)) {
+                (Step::ObjectStart, _) => Event::ObjectStart(range),
+                (Step::ObjectEnd, _) => Event::ObjectEnd(range),
+                (Step::ArrayStart, _) => Event::ArrayStart(range),
+                (Step::ArrayEnd, _) => Event::ArrayEnd(range),
+                (Step::Key, Token::String(name)) => Event::Key(self.decode_string(name.as_ref(), &range)?.into_owned(), range),
+                (Step::Value, Token::String(value)) => {
+                    let wtf8 = match self.lone_surrogates {
+                        LoneSurrogates::PreserveAsWtf8 => decode_lone_surrogate_string(value.as_ref(), self.scanner.token_quote()),
+                        _ => None,
+                    };
+                    match wtf8 {
+                        Some(value) => Event::Wtf8String(value, range),
+                        None => Event::String(self.decode_string(value.as_ref(), &range)?.into_owned(), range),
+                    }
+                },
+                (Step::Value, Token::Number(value)) => Event::Number(value, range),
+                (Step::Value, Token::Boolean(value)) => Event::Bool(value, range),
+                (Step::Value, Token::Null) => Event::Null(range),
+                _ => unreachable!(),
+            }));
+        }
+    }
+
+    /// Decodes the text of the string token at the range, erroring at a lone surrogate with `LoneSurrogates::Error`.
+    fn decode_string<'a>(&self, text: &'a str, range: &Range) -> Result<Cow<'a, str>, ParseError> {
+        let quote_char = self.scanner.token_quote();
+        if self.lone_surrogates == LoneSurrogates::Error {
+            if let Some(offset) = find_lone_surrogate(text, quote_char) {
+                let err = ParseError::new(range.start + 1 + offset, "Lone surrogates are not allowed in strings.");
+                return Err(self.scanner.locate_error(err));
+            }
+        }
+        Ok(decode_string(text, quote_char))
+    }
+
+    fn token_range(&self) -> Range {
+        Range {
+            start: self.scanner.token_start(),
+            end: self.scanner.token_end(),
+            start_line: self.scanner.token_start_line(),
+            end_line: self.scanner.token_end_line(),
+        }
+    }
+
+    /// Validates the token and moves to the next state, returning `None` for comments, commas, and colons.
+    fn advance(&mut self, token: &RawToken, range: &Range) -> Result<Option<Step>, ParseError> {
+        let step = self.advance_state(token, range)?;
+        if !matches!(token, RawToken::CommentLine(..) | RawToken::CommentBlock(..) | RawToken::Whitespace(..)) {
+            self.previous_token_end = range.end;
+        }
+        Ok(step)
+    }
+
+    fn advance_state(&mut self, token: &RawToken, range: &Range) -> Result<Option<Step>, ParseError> {
+        let token = match token {
+            RawToken::CommentLine(..) | RawToken::CommentBlock(..) | RawToken::Whitespace(..) => return Ok(None),
+            token => token,
+        };
+        match self.state {
+            EventState::Colon => match token {
+                RawToken::Token(Token::Colon) => {
+                    self.state = EventState::Value;
+                    Ok(None)
+                },
+                _ => {
+                    let message = "Expected a colon after the string in an object property.";
+                    Err(self.scanner.locate_error(ParseError::new_missing_colon(range.start, message, self.previous_token_end)))
+                },
+            },
+            EventState::AfterValue => match (token, self.stack.last()) {
+                (_, None) => Err(self.error(range, "Text cannot contain more than one JSON value.")),
+                (RawToken::Token(Token::Comma), Some(_)) => {
+                    self.state = EventState::ContainerItem;
+                    Ok(None)
+                },
+                (RawToken::Token(Token::CloseBrace), Some(EventFrame::Object(_))) => Ok(Some(self.end_container(Step::ObjectEnd))),
+                (RawToken::Token(Token::CloseBracket), Some(EventFrame::Array(_))) => Ok(Some(self.end_container(Step::ArrayEnd))),
+                (token, Some(frame)) => {
+                    if self.require_commas {
+                        let message = match frame {
+                            EventFrame::Object(_) => "Expected ',' or '}'.",
+                            EventFrame::Array(_) => "Expected ',' or ']'.",
+                        };
+                        return Err(self.scanner.locate_error(ParseError::new_missing_comma(range.start, message, self.previous_token_end)));
+                    }
+                    self.state = EventState::ContainerItem;
+                    self.advance_state(token, range)
+                },
+            },
+            EventState::ContainerItem => match (token, self.stack.last()) {
+                (RawToken::Token(Token::CloseBrace), Some(EventFrame::Object(_))) => Ok(Some(self.end_container(Step::ObjectEnd))),
+                (RawToken::Token(Token::CloseBracket), Some(EventFrame::Array(_))) => Ok(Some(self.end_container(Step::ArrayEnd))),
+                (RawToken::String(..), Some(EventFrame::Object(_))) => {
+                    self.state = EventState::Colon;
+                    Ok(Some(Step::Key))
+                },
+                (_, Some(EventFrame::Object(_))) => Err(self.error(range, "Unexpected token in object literal.")),
+                (token, _) => self.advance_value(token, range),
+            },
+            EventState::Value => self.advance_value(token, range),
+            EventState::Done => Ok(None),
+        }
+    }
+
+    fn advance_value(&mut self, token: &RawToken, range: &Range) -> Result<Option<Step>, ParseError> {
+        if self.stack.is_empty() {
+            let message = match (self.root_kind, token) {
+                (RootKind::Object, RawToken::Token(Token::OpenBrace)) | (RootKind::Array, RawToken::Token(Token::OpenBracket)) | (RootKind::Any, _) => None,
+                (RootKind::Object, _) => Some("Expected an object."),
+                (RootKind::Array, _) => Some("Expected an array."),
+            };
+            if let Some(message) = message {
+                return Err(self.error(range, message));
+            }
+        }
+
+        match token {
+            RawToken::Token(Token::OpenBrace) => {
+                self.stack.push(EventFrame::Object(range.clone()));
+                self.state = EventState::ContainerItem;
+                Ok(Some(Step::ObjectStart))
+            },
+            RawToken::Token(Token::OpenBracket) => {
+                self.stack.push(EventFrame::Array(range.clone()));
+                self.state = EventState::ContainerItem;
+                Ok(Some(Step::ArrayStart))
+            },
+            RawToken::String(..) | RawToken::Number(..) | RawToken::Token(Token::Boolean(_)) | RawToken::Token(Token::Null) => {
+                self.state = EventState::AfterValue;
+                Ok(Some(Step::Value))
+            },
+            RawToken::Token(token) => {
+                let is_property_end = matches!(token, Token::CloseBrace | Token::Comma);
+                Err(match self.stack.last() {
+                    Some(EventFrame::Object(_)) if is_property_end => self.error(range, "Expected value after colon in object property."),
+                    _ => self.error(range, match token {
+                        Token::CloseBracket => "Unexpected close bracket.",
+                        Token::CloseBrace => "Unexpected close brace.",
+                        Token::Comma => "Unexpected comma.",
+                        Token::Colon => "Unexpected colon.",
+                        _ => "Unexpected token.",
+                    }),
+                })
+            },
+            _ => Err(self.error(range, "Unexpected token.")),
+        }
+    }
+
+    fn end_container(&mut self, step: Step) -> Step {
+        self.stack.pop();
+        self.state = EventState::AfterValue;
+        step
+    }
+
+    fn handle_end(&mut self) -> Result<Option<Event>, ParseError> {
+        let pos = self.scanner.token_end();
+        let err = match (self.state, self.stack.last()) {
+            (EventState::Colon, _) => {
+                let err = ParseError::new_unexpected_eof(pos, "Expected a colon after the string in an object property.", "':'");
+                Some(err.with_insertion_point(self.previous_token_end))
+            },
+            (EventState::Value, Some(_)) => Some(ParseError::new_unexpected_eof(pos, "Expected value after colon in object property.", "a value")),
+            (_, Some(EventFrame::Object(opened_at))) => Some(ParseError::new_unclosed(pos, opened_at.clone(), "{", "'}'").with_insertion_point(self.previous_token_end)),
+            (_, Some(EventFrame::Array(opened_at))) => Some(ParseError::new_unclosed(pos, opened_at.clone(), "[", "']'").with_insertion_point(self.previous_token_end)),
+            (EventState::Value, None) if self.require_value => Some(ParseError::new_unexpected_eof(pos, "Expected a value.", "a value")),
+            _ => None,
+        };
+        self.state = EventState::Done;
+        match err {
+            Some(err) => Err(self.scanner.locate_error(err)),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the result of reading the root value of text without a value.
+    fn get_missing_value(&self) -> Result<Option<JsonValue>, ParseError> {
+        match self.missing_value {
+            MissingValue::Error => {
+                let err = ParseError::new_unexpected_eof(self.scanner.token_end(), "Expected a value.", "a value");
+                Err(self.scanner.locate_error(err))
+            },
+            MissingValue::Null => Ok(Some(JsonValue::Null)),
+            MissingValue::None => Ok(None),
+        }
+    }
+
+    /// Sets the line and column of an error found outside of the event parser.
+    pub(crate) fn locate_error(&self, err: ParseError) -> ParseError {
+        self.scanner.locate_error(err)
+    }
+
+    fn error(&self, range: &Range, message: &str) -> ParseError {
+        self.scanner.locate_error(ParseError::new(range.start, message))
+    }
+}
+
+/// Parses JSONC that's provided in chunks into events as the chunks are added (ex. as they're
+/// received over the network).
+///
+/// Each call to `feed` returns the events for the tokens that were completed by the chunk. A token
+/// at the end of the text so far is kept until it's known to be complete, so the events are the same
+/// as an `EventParser` on the joined text. Call `finish` after the last chunk to get the remaining
+/// events and errors for unterminated strings, comments, or containers.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::events::{ChunkedParser, Event};
+/// use jsonc_parser::ParseOptions;
+///
+/// let mut parser = ChunkedParser::new(&ParseOptions::default());
+/// assert_eq!(parser.feed(r#"{ "a": [tr"#).unwrap().len(), 3);
+/// let events = parser.feed("ue, 12").unwrap();
+/// assert!(matches!(events[..], [Event::Bool(true, _)]));
+/// let err = parser.finish().unwrap_err();
+/// assert_eq!(err.message, "Unexpected end of file; '[' opened on line 1 was never closed");
+/// ```
+pub struct ChunkedParser {
+    parser: EventParser,
+}
+
+impl ChunkedParser {
+    /// Creates a parser that chunks are fed to, which uses the same options as `EventParser::new`.
+    pub fn new(options: &ParseOptions) -> ChunkedParser {
+        let mut parser = EventParser::new("", options);
+        parser.scanner.set_partial(true);
+        ChunkedParser { parser }
+    }
+
+    /// Adds the chunk to the end of the text and returns the events that were completed.
+    ///
+    /// After an error, no more events are returned.
+    pub fn feed(&mut self, chunk: &str) -> Result<Vec<Event>, ParseError> {
+        self.parser.scanner.push_text(chunk);
+        self.take_events()
+    }
+
+    /// Ends the text and returns the remaining events.
+    pub fn finish(mut self) -> Result<Vec<Event>, ParseError> {
+        self.parser.scanner.set_partial(false);
+        self.take_events()
+    }
+
+    fn take_events(&mut self) -> Result<Vec<Event>, ParseError> {
+        let mut events = Vec::new();
+        while let Some(event) = self.parser.next_event()? {
+            events.push(event);
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::numbers::NumberDialect;
+    use super::super::{parse_to_value, parse_to_value_with_options};
+    use super::super::scanner::ScannerOptions;
+
+    fn get_events(text: &str, options: &ParseOptions) -> Result<Vec<Event>, ParseError> {
+        let mut parser = EventParser::new(text, options);
+        let mut events = Vec::new();
+        while let Some(event) = parser.next_event()? {
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    fn get_error(text: &str) -> String {
+        get_events(text, &ParseOptions::default().with_require_commas(true)).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn it_emits_events() {
+        let events = get_events("// a\n{ \"a\\u0062\": [1, \"s\", null, false], /* b */ }", &ParseOptions::default()).unwrap();
+        let descriptions = events.iter().map(|event| match event {
+            Event::ObjectStart(range) => format!("{{ {}", range.start),
+            Event::Key(name, range) => format!("key {} {}", name, range.start),
+            Event::ObjectEnd(range) => format!("}} {}", range.start),
+            Event::ArrayStart(range) => format!("[ {}", range.start),
+            Event::ArrayEnd(range) => format!("] {}", range.start),
+            Event::String(value, range) => format!("string {} {}", value, range.start),
+            Event::Wtf8String(value, range) => format!("wtf8 {} {}", value.to_string_lossy(), range.start),
+            Event::Number(value, range) => format!("number {} {}", value.as_ref(), range.start),
+            Event::Bool(value, range) => format!("bool {} {}", value, range.start),
+            Event::Null(range) => format!("null {}", range.start),
+            Event::Comment(kind, range) => format!("{:?} {}", kind, range.start),
+        }).collect::<Vec<_>>();
+        assert_eq!(descriptions, vec![
+            "Line 0", "{ 5", "key ab 7", "[ 18", "number 1 19", "string s 22", "null 27", "bool false 33", "] 38", "Block 41", "} 49",
+        ]);
+    }
+
+    #[test]
+    fn it_reads_values_like_parse_to_value() {
+        let text = r#"{ "a": [1, { "b": "c\n" }, [], {}], "a": true, "d": null } // end"#;
+        let mut parser = EventParser::new(text, &ParseOptions::default());
+        assert_eq!(parser.read_value().unwrap(), parse_to_value(text).unwrap());
+        assert_eq!(parser.next_event().unwrap().map(|event| matches!(event, Event::Comment(CommentKind::Line, _))), Some(true));
+        assert_eq!(parser.next_event().unwrap(), None);
+
+        for text in ["", " // a\n", "/* a */"].iter() {
+            for missing_value in [MissingValue::Error, MissingValue::Null, MissingValue::None].iter() {
+                let options = ParseOptions::default().with_missing_value(*missing_value);
+                let mut parser = EventParser::new(text, &options);
+                assert_eq!(parser.read_value(), parse_to_value_with_options(text, &options), "Text: {:?}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn it_skips_values() {
+        let mut text = String::from("{ \"skipped\": {");
+        for i in 0..1000 {
+            text.push_str(&format!("\"k{}\": [{{ \"a\\n\": [1, 2.5e3, \"x\"] }}, null, true], /* c */", i));
+        }
+        text.push_str("\"last\": {} }, // comment\n \"next\": 1 }");
+
+        let mut parser = EventParser::new(&text, &ParseOptions::default());
+        assert!(matches!(parser.next_event().unwrap(), Some(Event::ObjectStart(_))));
+        assert!(matches!(parser.next_event().unwrap(), Some(Event::Key(..))));
+        let range = parser.skip_value().unwrap().unwrap();
+        let skipped = text.chars().skip(range.start).take(range.end - range.start).collect::<String>();
+        assert!(skipped.starts_with("{\"k0\""));
+        assert!(skipped.ends_with("\"last\": {} }"));
+        assert_eq!(parser.depth(), 1);
+        assert!(matches!(parser.next_event().unwrap(), Some(Event::Comment(CommentKind::Line, _))));
+        assert!(matches!(parser.next_event().unwrap(), Some(Event::Key(name, _)) if name == "next"));
+        assert_eq!(parser.skip_value().unwrap().map(|range| range.start), Some(text.chars().count() - 3));
+        // the end of the container is consumed when there's no value
+        assert_eq!(parser.skip_value().unwrap(), None);
+        assert_eq!(parser.depth(), 0);
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn it_skips_the_root_value() {
+        let mut parser = EventParser::new("/* a */ [1, { \"b\": 2 }] // c", &ParseOptions::default());
+        let range = parser.skip_value().unwrap().unwrap();
+        assert_eq!((range.start, range.end), (8, 23));
+        assert!(matches!(parser.next_event().unwrap(), Some(Event::Comment(CommentKind::Line, _))));
+        assert_eq!(parser.next_event().unwrap(), None);
+
+        let mut parser = EventParser::new("5", &ParseOptions::default());
+        assert_eq!(parser.skip_value().unwrap().map(|range| (range.start, range.end)), Some((0, 1)));
+        assert_eq!(parser.skip_value().unwrap(), None);
+        assert_eq!(EventParser::new("", &ParseOptions::default()).skip_value().unwrap(), None);
+    }
+
+    #[test]
+    fn it_validates_skipped_values() {
+        let options = ParseOptions::default().with_require_commas(true);
+        let err = EventParser::new("[{ \"a\": 1 \"b\": 2 }]", &options).skip_value().unwrap_err();
+        assert_eq!(err.to_string(), "1:11: Expected ',' or '}'.");
+        let err = EventParser::new("[1, [2]", &options).skip_value().unwrap_err();
+        assert_eq!(err.to_string(), "1:8: Unexpected end of file; '[' opened on line 1 was never closed");
+        let mut parser = EventParser::new("[1 ] 2", &options);
+        assert!(parser.skip_value().is_ok());
+        assert_eq!(parser.next_event().unwrap_err().message, "Text cannot contain more than one JSON value.");
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn it_provides_insertion_points_in_errors() {
+        let options = ParseOptions::default().with_require_commas(true);
+        let get_insertion_point = |text: &str| EventParser::new(text, &options).skip_value().unwrap_err().insertion_point();
+        assert_eq!(get_insertion_point("[1 /* a */ 2]"), Some(2));
+        assert_eq!(get_insertion_point("{ \"a\" // a\n 1 }"), Some(5));
+        assert_eq!(get_insertion_point("{ \"a\"  "), Some(5));
+        assert_eq!(get_insertion_point("[[1, 2] // a\n"), Some(7));
+        assert_eq!(get_insertion_point("[1, }"), None);
+    }
+
+    #[test]
+    fn it_errors_for_invalid_structures() {
+        assert_eq!(get_error("{ \"a\": 1 ]"), "1:10: Expected ',' or '}'.");
+        assert_eq!(get_error("[1 }"), "1:4: Expected ',' or ']'.");
+        assert_eq!(get_error("{ \"a\" 1 }"), "1:7: Expected a colon after the string in an object property.");
+        assert_eq!(get_error("{ 1: 2 }"), "1:3: Unexpected token in object literal.");
+        assert_eq!(get_error("{ \"a\": }"), "1:8: Expected value after colon in object property.");
+        assert_eq!(get_error("[, 1]"), "1:2: Unexpected comma.");
+        assert_eq!(get_error("1 2"), "1:3: Text cannot contain more than one JSON value.");
+        assert_eq!(get_error("{ \"a\": [1"), "1:10: Unexpected end of file; '[' opened on line 1 was never closed");
+        assert_eq!(get_error("{\n  \"a\": [1]"), "2:11: Unexpected end of file; '{' opened on line 1 was never closed");
+        assert_eq!(get_error("{ \"a\""), "1:6: Expected a colon after the string in an object property.");
+
+        let mut parser = EventParser::new("[1, }", &ParseOptions::default());
+        assert!(parser.next_event().is_ok());
+        assert!(parser.next_event().is_ok());
+        assert!(parser.next_event().is_err());
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn it_uses_the_options() {
+        assert_eq!(get_events("[1 2]", &ParseOptions::default()).unwrap().len(), 4);
+
+        let options = ParseOptions::default().with_root_kind(RootKind::Object);
+        assert_eq!(EventParser::new("[]", &options).next_event().unwrap_err().message, "Expected an object.");
+        let options = ParseOptions::default().with_require_value(true);
+        assert_eq!(EventParser::new("// a", &options).read_value().unwrap_err().message, "Expected a value.");
+    }
+
+    #[test]
+    fn it_decodes_lone_surrogates_with_the_policy() {
+        let text = r#"{ "a\udc00": ["x\ud800", "\ud800😀", "😀"] }"#;
+        for lone_surrogates in [LoneSurrogates::ReplaceWithReplacementChar, LoneSurrogates::Error, LoneSurrogates::PreserveAsWtf8].iter() {
+            let options = ParseOptions::default().with_lone_surrogates(*lone_surrogates);
+            let mut parser = EventParser::new(text, &options);
+            assert_eq!(parser.read_value(), parse_to_value_with_options(text, &options), "{:?}", lone_surrogates);
+        }
+
+        let options = ParseOptions::default().with_lone_surrogates(LoneSurrogates::Error);
+        assert_eq!(get_events(text, &options).unwrap_err().pos, 4);
+        assert_eq!(get_events(r#"["😀", "x\ud800"]"#, &options).unwrap_err().pos, 8);
+
+        let options = ParseOptions::default().with_lone_surrogates(LoneSurrogates::PreserveAsWtf8);
+        let events = get_events(text, &options).unwrap();
+        assert!(matches!(&events[1], Event::Key(name, _) if name == "a\u{FFFD}"));
+        assert!(matches!(&events[3], Event::Wtf8String(value, _) if value.as_bytes() == b"x\xED\xA0\x80"));
+        assert!(matches!(&events[5], Event::String(value, _) if value == "\u{1F600}"));
+    }
+
+    fn get_chunked_events(chunks: &[&str], options: &ParseOptions) -> Result<Vec<Event>, ParseError> {
+        let mut parser = ChunkedParser::new(options);
+        let mut events = Vec::new();
+        for chunk in chunks.iter() {
+            events.extend(parser.feed(chunk)?);
+        }
+        events.extend(parser.finish()?);
+        Ok(events)
+    }
+
+    #[test]
+    fn it_parses_chunks_like_the_event_parser() {
+        let with_dialect = |dialect: NumberDialect| ParseOptions::default().with_scanner_options(ScannerOptions::default().with_number_dialect(dialect));
+        let cases = [
+            ("// header\r\n{\n  \"a\\\"\\u0062\": [1, -2.5e+3, true, false, null, \"\u{1F600}\"], /* b */\n  \"c\": { \"d\": {} },\r\n  \"e\": 0 // end\n}\n", ParseOptions::default()),
+            ("[1,2 , 3]", ParseOptions::default()),
+            ("10", ParseOptions::default()),
+            ("true", ParseOptions::default()),
+            ("// only a comment", ParseOptions::default()),
+            ("", ParseOptions::default()),
+            ("[1,5, 2, 3,1e1, -0,25E-2,7]", with_dialect(NumberDialect::default().with_comma_decimal_separator(true))),
+            ("3,1e1", with_dialect(NumberDialect::default().with_comma_decimal_separator(true))),
+            ("[0x1F, -0xa, .5, -.25e1, 0]", with_dialect(NumberDialect::default().with_hexadecimal(true).with_leading_decimal_point(true))),
+        ];
+        for (text, options) in cases.iter() {
+            let expected = get_events(text, options).unwrap();
+            let chars = text.chars().map(String::from).collect::<Vec<_>>();
+            let chunks = chars.iter().map(|chunk| chunk.as_str()).collect::<Vec<_>>();
+            assert_eq!(get_chunked_events(&chunks, options).unwrap(), expected, "{}", text);
+            assert_eq!(get_chunked_events(&[text], options).unwrap(), expected, "{}", text);
+        }
+
+        let options = with_dialect(NumberDialect::default().with_comma_decimal_separator(true));
+        let expected = get_events("[1,5, 2]", &options).unwrap();
+        assert_eq!(get_chunked_events(&["[1", ",", "5", ", 2]"], &options).unwrap(), expected);
+        let expected = get_events("3,1e1", &options).unwrap();
+        assert_eq!(get_chunked_events(&["3,1e", "1"], &options).unwrap(), expected);
+        assert_eq!(get_chunked_events(&["[1e", "]"], &ParseOptions::default()).unwrap_err().message, "Expected a digit in the exponent.");
+    }
+
+    #[test]
+    fn it_returns_events_as_chunks_complete_them() {
+        let mut parser = ChunkedParser::new(&ParseOptions::default());
+        assert!(matches!(parser.feed("[").unwrap()[..], [Event::ArrayStart(_)]));
+        assert!(parser.feed("12").unwrap().is_empty());
+        assert!(parser.feed("3").unwrap().is_empty());
+        assert!(matches!(&parser.feed(", \"a").unwrap()[..], [Event::Number(value, _)] if value.as_ref() == "123"));
+        assert!(parser.feed("b\\").unwrap().is_empty());
+        assert!(matches!(&parser.feed("n\" /").unwrap()[..], [Event::String(value, _)] if value == "ab\n"));
+        assert!(parser.feed("/ c").unwrap().is_empty());
+        assert!(matches!(parser.feed("\n]").unwrap()[..], [Event::Comment(CommentKind::Line, _), Event::ArrayEnd(_)]));
+        assert!(parser.finish().unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_scans_chunks_in_linear_time() {
+        // rescanning the token for each byte would read each character once per byte after it
+        for len in [1_000, 10_000, 80_000] {
+            let text = format!("[\"{}\", {}, // {}\n {}]", "a".repeat(len), "1".repeat(len), "c".repeat(len), " ".repeat(len));
+            let mut parser = ChunkedParser::new(&ParseOptions::default());
+            let mut event_count = 0;
+            for index in 0..text.len() {
+                event_count += parser.feed(&text[index..index + 1]).unwrap().len();
+            }
+            let chars_read = parser.parser.scanner.chars_read();
+            event_count += parser.finish().unwrap().len();
+            assert_eq!(event_count, 5);
+            assert!(chars_read < text.len() * 4, "{} characters read for {} bytes", chars_read, text.len());
+        }
+    }
+
+    #[test]
+    fn it_errors_for_unterminated_chunks() {
+        let options = ParseOptions::default();
+        assert_eq!(get_chunked_events(&["[\"a", "b"], &options).unwrap_err().message, "Unexpected end of file; '\"' opened on line 1 was never closed");
+        assert_eq!(get_chunked_events(&["/* a", "b"], &options).unwrap_err().message, "Unexpected end of file; '/*' opened on line 1 was never closed");
+        assert_eq!(get_chunked_events(&["{ \"a\": ", "[1"], &options).unwrap_err().message, "Unexpected end of file; '[' opened on line 1 was never closed");
+        assert_eq!(get_chunked_events(&["[1.", "]"], &options).unwrap_err().message, "Expected a digit after the decimal point.");
+        assert_eq!(get_chunked_events(&["[tr"], &options).unwrap_err().message, "Unexpected token.");
+
+        // the text before the current token is dropped as chunks are added, but errors are still located in the whole text
+        let text = "[\n  \"a\u{e9}\",\n  1, // c\n  tru ]";
+        let expected = get_events(text, &options).unwrap_err();
+        let chars = text.chars().map(String::from).collect::<Vec<_>>();
+        let chunks = chars.iter().map(|chunk| chunk.as_str()).collect::<Vec<_>>();
+        assert_eq!(get_chunked_events(&chunks, &options).unwrap_err(), expected);
+
+        // errors that more text can't fix are returned right away
+        let mut parser = ChunkedParser::new(&options);
+        assert_eq!(parser.feed("[\"a\\q\", ").unwrap_err().message, "Invalid escape.");
+        assert!(parser.feed("1]").unwrap().is_empty());
+        let mut parser = ChunkedParser::new(&options);
+        assert_eq!(parser.feed("[1").unwrap().len(), 1);
+        assert_eq!(parser.feed(" }").unwrap_err().message, "Unexpected close brace.");
+    }
+}
@@ -1,9 +1,41 @@
+//! JSONC to AST parser.
+//!
+//! The entry points (ex. `parse_text` and `parse_to_value`) and the `Scanner` are available
+//! at the crate root. The `prelude` module re-exports what's needed to read, change, and
+//! write JSONC, while the other modules contain the AST, values, edits, and tooling.
+
+pub mod access;
+pub mod comment_tags;
 pub mod common;
 pub mod ast;
+#[cfg(feature = "serde")]
+pub mod de;
+pub mod edits;
+pub mod diagnostics;
+pub mod env;
 pub mod errors;
+pub mod events;
+pub mod lint;
+pub mod numbers;
+pub mod path;
+pub mod prelude;
+pub mod schema;
+pub mod serializer;
+pub mod strings;
 pub mod tokens;
+pub mod tooling;
+pub mod truncate;
+pub mod value;
 mod parser;
 mod scanner;
 
-pub use parser::*;
-pub use scanner::*;
+pub use errors::ParseError;
+pub use parser::{
+    extract_matching, extract_matching_with_options, parse_bytes, parse_file, parse_preview, parse_text, parse_text_with_options, parse_to_ast, parse_to_value,
+    parse_to_value_with_options, parse_tokens_to_ast, parse_tokens_to_ast_with_options, parse_tokens_to_value_with_options, validate_fast, verify_ranges, DuplicateKeyPolicy,
+    LoneSurrogates, MissingValue, ParseMetrics, ParseOptions, ParseResult, RootKind, TextEncoding,
+};
+#[cfg(feature = "unicode-normalization")]
+pub use parser::StringNormalization;
+pub use scanner::{tokenize_soa, ChunkScanner, Scanner, ScannerBuilder, ScannerOptions, TokenIterator, TokenRefIterator};
+pub use value::JsonValue;
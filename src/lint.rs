@@ -0,0 +1,120 @@
+use super::common::Range;
+use super::tokens::{Token, TokenAndRange};
+
+/// Gets the ranges of whitespace found at the end of lines.
+///
+/// This expects a lossless token stream (see `Scanner::new_lossless`) and reports whitespace
+/// that precedes a newline, including trailing whitespace inside comments.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::Scanner;
+/// use jsonc_parser::common::Range;
+/// use jsonc_parser::lint::trailing_whitespace;
+/// use jsonc_parser::tokens::TokenAndRange;
+///
+/// let mut scanner = Scanner::new_lossless("[1, \n2]");
+/// let mut tokens = Vec::new();
+/// while let Some(token) = scanner.scan().unwrap() {
+///     tokens.push(TokenAndRange {
+///         token,
+///         range: Range {
+///             start: scanner.token_start(),
+///             end: scanner.token_end(),
+///             start_line: scanner.token_start_line(),
+///             end_line: scanner.token_end_line(),
+///         },
+///     });
+/// }
+///
+/// let ranges = trailing_whitespace(&tokens);
+/// assert_eq!(ranges.len(), 1);
+/// assert_eq!((ranges[0].start, ranges[0].end), (3, 4));
+/// ```
+pub fn trailing_whitespace(tokens: &[TokenAndRange]) -> Vec<Range> {
+    let mut ranges = Vec::new();
+
+    for token in tokens {
+        match &token.token {
+            Token::Whitespace(text) => add_line_trailing_whitespace(&mut ranges, text.as_ref(), token.range.start, token.range.start_line, false),
+            Token::CommentBlock(text) => add_line_trailing_whitespace(&mut ranges, text.as_ref(), token.range.start + 2, token.range.start_line, false),
+            Token::CommentLine(text) => add_line_trailing_whitespace(&mut ranges, text.as_ref(), token.range.start + 2, token.range.start_line, true),
+            _ => {},
+        }
+    }
+
+    ranges
+}
+
+/// Adds the ranges of whitespace that precede a newline in the text, along with the whitespace
+/// at the end of the text when it's known to be followed by a newline.
+fn add_line_trailing_whitespace(ranges: &mut Vec<Range>, text: &str, start: usize, start_line: usize, ends_line: bool) {
+    let mut pos = start;
+    let mut line = start_line;
+    let mut whitespace_start = None;
+
+    for c in text.chars() {
+        if c == '\n' || c == '\r' {
+            if let Some(whitespace_start) = whitespace_start.take() {
+                ranges.push(create_line_range(whitespace_start, pos, line));
+            }
+            if c == '\n' {
+                line += 1;
+            }
+        } else if c.is_whitespace() {
+            whitespace_start.get_or_insert(pos);
+        } else {
+            whitespace_start = None;
+        }
+        pos += 1;
+    }
+
+    if ends_line {
+        if let Some(whitespace_start) = whitespace_start {
+            ranges.push(create_line_range(whitespace_start, pos, line));
+        }
+    }
+}
+
+fn create_line_range(start: usize, end: usize, line: usize) -> Range {
+    Range {
+        start,
+        end,
+        start_line: line,
+        end_line: line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::scanner::{scan_tokens, Scanner};
+
+    #[test]
+    fn it_finds_trailing_whitespace() {
+        let text = "{  \n  \"a\": 1, \t\r\n  \"b\": 2\n}  ";
+        let ranges = trailing_whitespace(&scan_tokens(&mut Scanner::new_lossless(text)).unwrap());
+        assert_eq!(ranges, vec![
+            create_line_range(1, 3, 0),
+            create_line_range(13, 15, 1),
+        ]);
+    }
+
+    #[test]
+    fn it_finds_trailing_whitespace_in_comments() {
+        let text = "// test  \n/* a \n b */ 1";
+        let ranges = trailing_whitespace(&scan_tokens(&mut Scanner::new_lossless(text)).unwrap());
+        assert_eq!(ranges, vec![
+            create_line_range(7, 9, 0),
+            create_line_range(14, 15, 1),
+        ]);
+    }
+
+    #[test]
+    fn it_ignores_text_without_trailing_whitespace() {
+        let text = "[\n  1,\n  2\n]\n";
+        let ranges = trailing_whitespace(&scan_tokens(&mut Scanner::new_lossless(text)).unwrap());
+        assert_eq!(ranges, Vec::new());
+    }
+}
@@ -0,0 +1,422 @@
+use std::borrow::Cow;
+use std::num::IntErrorKind;
+use super::errors::{NumberError, NumberErrorKind};
+
+/// The forms of number literals that are allowed in addition to the JSON grammar.
+///
+/// The default only allows the JSON grammar.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct NumberDialect {
+    /// Allow hexadecimal numbers (ex. `0x1F`).
+    pub hexadecimal: bool,
+    /// Allow numbers that start with a decimal point (ex. `.5` or `-.25`).
+    ///
+    /// The decimal point still needs to be followed by a digit, so `.` and `-.` are errors. Tokens
+    /// and AST number literals keep the text as written (ex. `Token::Number(".5")`), while a
+    /// `JsonValue` gets the number with a leading zero (ex. `0.5`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::{parse_to_value_with_options, ParseOptions, ScannerOptions};
+    /// use jsonc_parser::numbers::NumberDialect;
+    /// use jsonc_parser::value::JsonValue;
+    ///
+    /// let dialect = NumberDialect::default().with_leading_decimal_point(true);
+    /// let options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_number_dialect(dialect));
+    /// let value = parse_to_value_with_options("[.5, -.25e1]", &options).unwrap().unwrap();
+    /// assert_eq!(value, JsonValue::Array(vec![JsonValue::Number(String::from("0.5")), JsonValue::Number(String::from("-0.25e1"))].into()));
+    /// assert!(parse_to_value_with_options(".5", &ParseOptions::default()).is_err());
+    /// ```
+    pub leading_decimal_point: bool,
+    /// Allow a comma as the decimal point (ex. `3,14`), which some tools write in locales that
+    /// use one.
+    ///
+    /// This isn't JSON. A comma is only part of a number when it directly follows the digits
+    /// of the integer part and is directly followed by a digit, so `[1,2]` is an array with a
+    /// single number while `[1, 2]` has two.
+    pub comma_decimal_separator: bool,
+}
+
+impl NumberDialect {
+    /// Sets whether hexadecimal numbers are allowed.
+    pub fn with_hexadecimal(mut self, value: bool) -> Self {
+        self.hexadecimal = value;
+        self
+    }
+
+    /// Sets whether numbers may start with a decimal point.
+    pub fn with_leading_decimal_point(mut self, value: bool) -> Self {
+        self.leading_decimal_point = value;
+        self
+    }
+
+    /// Sets whether a comma may be used as the decimal point.
+    pub fn with_comma_decimal_separator(mut self, value: bool) -> Self {
+        self.comma_decimal_separator = value;
+        self
+    }
+
+    fn all() -> NumberDialect {
+        NumberDialect {
+            hexadecimal: true,
+            leading_decimal_point: true,
+            comma_decimal_separator: true,
+        }
+    }
+}
+
+/// The value of a number literal.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JsonNumber {
+    /// A number without a fraction or exponent that fits in an `i64`.
+    Integer(i64),
+    /// Any other number.
+    Float(f64),
+}
+
+/// Gets if the text is exactly one number literal in the provided dialect.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::numbers::{is_valid_number_literal, NumberDialect};
+///
+/// assert!(is_valid_number_literal("-1.5e+3", &NumberDialect::default()));
+/// assert!(!is_valid_number_literal("0x1F", &NumberDialect::default()));
+/// assert!(is_valid_number_literal("0x1F", &NumberDialect::default().with_hexadecimal(true)));
+/// ```
+pub fn is_valid_number_literal(text: &str, dialect: &NumberDialect) -> bool {
+    !text.is_empty() && scan_number(text, 0, dialect, true).ok() == Some(text.len())
+}
+
+/// Parses the text of a number literal.
+///
+/// All the forms of number literals in `NumberDialect` are accepted.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::numbers::{parse_number_literal, JsonNumber};
+///
+/// assert_eq!(parse_number_literal("-12").unwrap(), JsonNumber::Integer(-12));
+/// assert_eq!(parse_number_literal("1.5e+1").unwrap(), JsonNumber::Float(15.0));
+/// assert_eq!(parse_number_literal("1.").unwrap_err().offset, 2);
+/// assert_eq!(parse_number_literal("3,14").unwrap(), JsonNumber::Float(3.14));
+/// ```
+pub fn parse_number_literal(text: &str) -> Result<JsonNumber, NumberError> {
+    let end = match scan_number(text, 0, &NumberDialect::all(), true) {
+        Ok(end) => end,
+        Err(err) => return Err(NumberError::new(err.pos, &err.message)),
+    };
+    if end < text.len() {
+        return Err(NumberError::new(end, &get_unexpected_character_message(text, 0)));
+    }
+
+    let (is_negative, unsigned_text) = match text.strip_prefix('-') {
+        Some(unsigned_text) => (true, unsigned_text),
+        None => (false, text),
+    };
+    if let Some(hex_digits) = unsigned_text.strip_prefix("0x").or_else(|| unsigned_text.strip_prefix("0X")) {
+        return match u64::from_str_radix(hex_digits, 16) {
+            Ok(value) if is_negative && value <= i64::MAX as u64 + 1 => Ok(JsonNumber::Integer((value as i64).wrapping_neg())),
+            Ok(value) if !is_negative && value <= i64::MAX as u64 => Ok(JsonNumber::Integer(value as i64)),
+            _ => Err(NumberError::new(0, "Hexadecimal number is too large.")),
+        };
+    }
+
+    if !text.contains(['.', ',', 'e', 'E']) {
+        if let Ok(value) = text.parse::<i64>() {
+            return Ok(JsonNumber::Integer(value));
+        }
+    }
+
+    match text.replace(',', ".").parse::<f64>() {
+        Ok(value) => Ok(JsonNumber::Float(value)),
+        Err(_) => Err(NumberError::new(0, "Invalid number literal.")),
+    }
+}
+
+/// Converts a number literal in any of the `NumberDialect` forms to the JSON grammar (ex. `0x1F`
+/// to `31`, `.5` to `0.5`, or `3,14` to `3.14`), keeping JSON number literals as they are.
+///
+/// Errors for a hexadecimal number that's too large for a `u64`, which has no exact JSON text.
+pub(crate) fn to_json_number_text(text: &str) -> Result<Cow<'_, str>, NumberError> {
+    let (sign, unsigned_text) = match text.strip_prefix('-') {
+        Some(unsigned_text) => ("-", unsigned_text),
+        None => ("", text),
+    };
+    if let Some(hex_digits) = unsigned_text.strip_prefix("0x").or_else(|| unsigned_text.strip_prefix("0X")) {
+        return match u64::from_str_radix(hex_digits, 16) {
+            Ok(value) => Ok(Cow::Owned(format!("{}{}", sign, value))),
+            Err(_) => Err(NumberError::new(0, "Hexadecimal number is too large.")),
+        };
+    }
+    if !unsigned_text.starts_with('.') && !unsigned_text.contains(',') {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let zero = if unsigned_text.starts_with('.') { "0" } else { "" };
+    Ok(Cow::Owned(format!("{}{}{}", sign, zero, unsigned_text.replace(',', "."))))
+}
+
+/// Gets the message for when a number literal starting at the provided byte position is followed
+/// by an unexpected character.
+///
+/// The message has a hint when the text looks like a number in a form that isn't allowed
+/// (ex. `08` or `0b101`).
+pub(crate) fn get_unexpected_character_message(text: &str, start: usize) -> String {
+    let sign = if text[start..].starts_with('-') { "-" } else { "" };
+    let word = text[start + sign.len()..].chars().take_while(|c| c.is_ascii_alphanumeric()).collect::<String>();
+    let with_radix = |name: &str, radix: u32, digits: &str, digits_description: &str| match u64::from_str_radix(digits, radix) {
+        Ok(value) => format!("{} numbers are not allowed (did you mean {}{}?)", name, sign, value),
+        Err(err) if *err.kind() == IntErrorKind::PosOverflow => format!("{} numbers are not allowed and {}{} is out of range.", name, sign, word),
+        Err(_) => format!("{} numbers are not allowed and {}{} has digits other than {}.", name, sign, word, digits_description),
+    };
+
+    if word.len() > 2 {
+        match &word[..2] {
+            "0b" | "0B" => return with_radix("Binary", 2, &word[2..], "0 and 1"),
+            "0o" | "0O" => return with_radix("Octal", 8, &word[2..], "0 to 7"),
+            "0x" | "0X" => return with_radix("Hexadecimal", 16, &word[2..], "0 to 9 and A to F"),
+            _ => {},
+        }
+    }
+
+    let digits = word.chars().take_while(|c| c.is_ascii_digit()).collect::<String>();
+    if digits.len() > 1 && digits.starts_with('0') {
+        let trimmed = digits.trim_start_matches('0');
+        let decimal = if trimmed.is_empty() { "0" } else { trimmed };
+        return match u64::from_str_radix(&digits, 8) {
+            Ok(value) if value.to_string() != decimal => {
+                format!("Leading zeros are not allowed (did you mean {}{}, or {}{} for octal {}?)", sign, decimal, sign, value, digits)
+            },
+            _ => format!("Leading zeros are not allowed (did you mean {}{}?)", sign, decimal),
+        };
+    }
+
+    String::from("Unexpected character in number literal.")
+}
+
+/// Error found while scanning a number literal.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ScanNumberError {
+    pub kind: NumberErrorKind,
+    /// Byte position of the character that made the number invalid.
+    pub pos: usize,
+    /// Byte end position of the attempted number literal.
+    pub end: usize,
+    pub message: String,
+}
+
+/// Scans a number literal starting at the provided byte position and returns its end byte position.
+///
+/// This is shared by the scanner and the standalone number functions so they accept the same text.
+/// A number literal only contains ASCII characters, so the number of bytes scanned is also the
+/// number of characters scanned. A zero followed by a digit (ex. `01`) is an error when
+/// `error_for_leading_zeros` is set, and otherwise ends the number after the zero.
+pub(crate) fn scan_number(text: &str, start: usize, dialect: &NumberDialect, error_for_leading_zeros: bool) -> Result<usize, ScanNumberError> {
+    let bytes = text.as_bytes();
+    let byte_at = |pos: usize| bytes.get(pos).copied();
+    let is_digit = |pos: usize| matches!(byte_at(pos), Some(c) if c.is_ascii_digit());
+    let error = |pos: usize, kind: NumberErrorKind, message: &str| {
+        let (kind, message) = match byte_at(pos) {
+            None => (NumberErrorKind::UnexpectedEnd, "Unexpected end of text in number literal; expected a digit."),
+            Some(_) => (kind, message),
+        };
+        Err(ScanNumberError {
+            kind,
+            pos,
+            end: get_attempted_number_end(bytes, pos),
+            message: String::from(message),
+        })
+    };
+    let mut pos = start;
+
+    let has_negative_sign = byte_at(pos) == Some(b'-');
+    if has_negative_sign {
+        pos += 1;
+    }
+
+    if dialect.hexadecimal && byte_at(pos) == Some(b'0') && matches!(byte_at(pos + 1), Some(b'x') | Some(b'X')) {
+        pos += 2;
+        let is_hex = |pos: usize| matches!(byte_at(pos), Some(c) if c.is_ascii_hexdigit());
+        if !is_hex(pos) {
+            return error(pos, NumberErrorKind::MissingHexDigits, "Expected a hexadecimal digit after the hexadecimal prefix.");
+        }
+        let digits_start = pos;
+        while is_hex(pos) {
+            pos += 1;
+        }
+        if u64::from_str_radix(&text[digits_start..pos], 16).is_err() {
+            return error(start, NumberErrorKind::HexOverflow, "Hexadecimal number is too large.");
+        }
+        return Ok(pos);
+    }
+
+    if byte_at(pos) == Some(b'0') {
+        pos += 1;
+        if error_for_leading_zeros && is_digit(pos) {
+            return error(pos, NumberErrorKind::LeadingZeros, &get_unexpected_character_message(text, start));
+        }
+    } else if is_digit(pos) {
+        while is_digit(pos) {
+            pos += 1;
+        }
+    } else if !(dialect.leading_decimal_point && byte_at(pos) == Some(b'.')) {
+        // the scanner only scans numbers that start with a negative sign, digit, or allowed decimal point
+        return if has_negative_sign {
+            error(pos, NumberErrorKind::BareMinus, "Expected a digit to follow a negative sign.")
+        } else {
+            error(pos, NumberErrorKind::MissingIntegerDigits, "Expected a digit.")
+        };
+    }
+
+    let is_comma_decimal_point = dialect.comma_decimal_separator && pos > start && is_digit(pos - 1) && byte_at(pos) == Some(b',') && is_digit(pos + 1);
+    if byte_at(pos) == Some(b'.') || is_comma_decimal_point {
+        pos += 1;
+
+        if !is_digit(pos) {
+            return error(pos, NumberErrorKind::MissingFractionDigits, "Expected a digit after the decimal point.");
+        }
+
+        while is_digit(pos) {
+            pos += 1;
+        }
+    }
+
+    if let Some(b'e') | Some(b'E') = byte_at(pos) {
+        pos += 1;
+        if let Some(b'-') | Some(b'+') = byte_at(pos) {
+            pos += 1;
+        }
+
+        if !is_digit(pos) {
+            return error(pos, NumberErrorKind::MissingExponentDigits, "Expected a digit in the exponent.");
+        }
+        while is_digit(pos) {
+            pos += 1;
+        }
+    }
+
+    Ok(pos)
+}
+
+/// Gets the end of the characters that look like they're part of a number literal, starting
+/// at a position inside it (ex. the end of `1.e5` from the position of the `e`).
+fn get_attempted_number_end(bytes: &[u8], mut pos: usize) -> usize {
+    while let Some(c) = bytes.get(pos) {
+        let is_exponent_sign = matches!(c, b'+' | b'-') && matches!(bytes.get(pos.wrapping_sub(1)), Some(b'e') | Some(b'E'));
+        if !(c.is_ascii_alphanumeric() || *c == b'.' || is_exponent_sign) {
+            break;
+        }
+        pos += 1;
+    }
+    pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_validates_json_numbers() {
+        let dialect = NumberDialect::default();
+        for text in ["0", "0.123", "-198", "0e-345", "0.3e+025", "-0", "10E+2", "1e5", "2E10"].iter() {
+            assert!(is_valid_number_literal(text, &dialect), "{}", text);
+        }
+        for text in ["", "-", "01", "1.", "1e", "1e+", "+1", ".5", "0x1F", "1 ", "Infinity", "NaN"].iter() {
+            assert!(!is_valid_number_literal(text, &dialect), "{}", text);
+        }
+    }
+
+    #[test]
+    fn it_validates_dialect_gated_numbers() {
+        let dialect = NumberDialect::default().with_hexadecimal(true);
+        assert!(is_valid_number_literal("0x1F", &dialect));
+        assert!(is_valid_number_literal("-0Xff", &dialect));
+        assert!(!is_valid_number_literal("0x", &dialect));
+        assert!(!is_valid_number_literal(".5", &dialect));
+
+        let dialect = NumberDialect::default().with_leading_decimal_point(true);
+        assert!(is_valid_number_literal(".5", &dialect));
+        assert!(is_valid_number_literal("-.5e+1", &dialect));
+        assert!(is_valid_number_literal(".5e3", &dialect));
+        assert!(!is_valid_number_literal(".", &dialect));
+        assert!(!is_valid_number_literal("-.", &dialect));
+        assert!(!is_valid_number_literal(".e3", &dialect));
+        assert!(!is_valid_number_literal("0x1F", &dialect));
+        assert_eq!(parse_number_literal("-.25").unwrap(), JsonNumber::Float(-0.25));
+
+        let cases = [
+            ("-1.5e+3", "-1.5e+3"), ("0x1F", "31"), ("-0X10", "-16"), ("0xFFFFFFFFFFFFFFFF", "18446744073709551615"),
+            (".5", "0.5"), ("-.25e1", "-0.25e1"), ("3,14", "3.14"), ("-0,5e+1", "-0.5e+1"),
+        ];
+        for (text, expected) in cases.iter() {
+            let json_text = to_json_number_text(text).unwrap();
+            assert_eq!(json_text, *expected);
+            assert!(is_valid_number_literal(&json_text, &NumberDialect::default()), "Text: {}", text);
+        }
+        assert!(!is_valid_number_literal("0x10000000000000000", &NumberDialect::default().with_hexadecimal(true)));
+        assert_eq!(to_json_number_text("-0x10000000000000000").unwrap_err(), NumberError::new(0, "Hexadecimal number is too large."));
+        assert_eq!(parse_number_literal(".5e3").unwrap(), JsonNumber::Float(500.0));
+
+        let dialect = NumberDialect::default().with_comma_decimal_separator(true);
+        assert!(is_valid_number_literal("3,14", &dialect));
+        assert!(is_valid_number_literal("-0,5e+1", &dialect));
+        assert!(!is_valid_number_literal("3,", &dialect));
+        assert!(!is_valid_number_literal("3, 14", &dialect));
+        assert!(!is_valid_number_literal("3,14,15", &dialect));
+        assert!(!is_valid_number_literal("-,5", &dialect.with_leading_decimal_point(true)));
+        assert!(!is_valid_number_literal("3,14", &NumberDialect::default()));
+    }
+
+    #[test]
+    fn it_parses_numbers() {
+        assert_eq!(parse_number_literal("0").unwrap(), JsonNumber::Integer(0));
+        assert_eq!(parse_number_literal("-198").unwrap(), JsonNumber::Integer(-198));
+        assert_eq!(parse_number_literal("0.123").unwrap(), JsonNumber::Float(0.123));
+        assert_eq!(parse_number_literal("0.3e+025").unwrap(), JsonNumber::Float(0.3e25));
+        assert_eq!(parse_number_literal("99999999999999999999").unwrap(), JsonNumber::Float(1e20));
+        assert_eq!(parse_number_literal("0x1F").unwrap(), JsonNumber::Integer(31));
+        assert_eq!(parse_number_literal("-0x8000000000000000").unwrap(), JsonNumber::Integer(i64::MIN));
+        assert_eq!(parse_number_literal("-.5").unwrap(), JsonNumber::Float(-0.5));
+        assert_eq!(parse_number_literal("-2,5").unwrap(), JsonNumber::Float(-2.5));
+    }
+
+    #[test]
+    fn it_errors_parsing_invalid_numbers() {
+        let end_message = "Unexpected end of text in number literal; expected a digit.";
+        assert_eq!(parse_number_literal("-").unwrap_err(), NumberError::new(1, end_message));
+        assert_eq!(parse_number_literal("-a").unwrap_err(), NumberError::new(1, "Expected a digit to follow a negative sign."));
+        assert_eq!(parse_number_literal("a").unwrap_err(), NumberError::new(0, "Expected a digit."));
+        let err = scan_number(".5", 0, &NumberDialect::default(), true).unwrap_err();
+        assert_eq!((err.kind, err.message.as_str()), (NumberErrorKind::MissingIntegerDigits, "Expected a digit."));
+        assert_eq!(parse_number_literal("1.e5").unwrap_err(), NumberError::new(2, "Expected a digit after the decimal point."));
+        assert_eq!(parse_number_literal("1e").unwrap_err(), NumberError::new(2, end_message));
+        assert_eq!(parse_number_literal("1e+a").unwrap_err(), NumberError::new(3, "Expected a digit in the exponent."));
+        assert_eq!(parse_number_literal("0123").unwrap_err(), NumberError::new(1, "Leading zeros are not allowed (did you mean 123, or 83 for octal 0123?)"));
+        assert_eq!(parse_number_literal("12a").unwrap_err(), NumberError::new(2, "Unexpected character in number literal."));
+        assert_eq!(parse_number_literal("0x10000000000000000").unwrap_err(), NumberError::new(0, "Hexadecimal number is too large."));
+    }
+
+    #[test]
+    fn it_hints_at_numbers_in_other_forms() {
+        let cases = [
+            ("08", "Leading zeros are not allowed (did you mean 8?)"),
+            ("-007", "Leading zeros are not allowed (did you mean -7?)"),
+            ("00", "Leading zeros are not allowed (did you mean 0?)"),
+            ("010", "Leading zeros are not allowed (did you mean 10, or 8 for octal 010?)"),
+            ("0b101", "Binary numbers are not allowed (did you mean 5?)"),
+            ("0b2", "Binary numbers are not allowed and 0b2 has digits other than 0 and 1."),
+            ("-0o17", "Octal numbers are not allowed (did you mean -15?)"),
+            ("0o8", "Octal numbers are not allowed and 0o8 has digits other than 0 to 7."),
+            ("-0o2000000000000000000000", "Octal numbers are not allowed and -0o2000000000000000000000 is out of range."),
+            ("0b", "Unexpected character in number literal."),
+            ("1b", "Unexpected character in number literal."),
+        ];
+        for (text, message) in cases.iter() {
+            assert_eq!(parse_number_literal(text).unwrap_err().message, *message, "{}", text);
+        }
+    }
+}
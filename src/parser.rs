@@ -1,10 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::rc::Rc;
-use super::scanner::Scanner;
-use super::common::{ImmutableString, Range};
+use std::time::{Duration, Instant};
+use super::scanner::{Scanner, ScannerOptions};
+use super::common::{ImmutableString, LineIndex, Range};
 use super::tokens::{Token, TokenAndRange};
 use super::ast::*;
 use super::errors::*;
+use super::diagnostics::Diagnostic;
+use super::events::{Event, EventParser};
+use super::strings::find_lone_surrogate;
+use super::value::JsonValue;
 
 /// Result of parsing the text.
 pub struct ParseResult {
@@ -16,20 +24,275 @@ pub struct ParseResult {
     pub value: Option<Value>,
     /// Collection of tokens (excluding any comments).
     pub tokens: Vec<TokenAndRange>,
+    /// Measurements of the parse when `ParseOptions::collect_metrics` is set.
+    pub metrics: Option<ParseMetrics>,
+    /// Encoding the text was decoded from when parsed with `parse_file` or `parse_bytes`.
+    pub encoding: Option<TextEncoding>,
 }
 
-struct Context {
-    scanner: Scanner,
+impl ParseResult {
+    /// Drops the result, dropping its value without recursing. See `Value::drop_nested`.
+    pub fn drop_nested(self) {
+        if let Some(value) = self.value {
+            value.drop_nested();
+        }
+    }
+}
+
+/// Encoding of the bytes of a file.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// Measurements taken while parsing, which are useful for finding pathological documents and choosing limits.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseMetrics {
+    /// Number of tokens scanned, including comments.
+    pub tokens: usize,
+    /// Length of the text in bytes.
+    pub bytes: usize,
+    /// Deepest nesting of objects and arrays (ex. 2 for `[{}]`).
+    pub max_depth_seen: usize,
+    /// Time spent parsing the text.
+    pub duration: Option<Duration>,
+}
+
+/// Provides tokens to the parser.
+trait TokenScanner {
+    fn scan(&mut self) -> Result<Option<Token>, ParseError>;
+    fn token(&self) -> Option<Token>;
+    fn token_start(&self) -> usize;
+    fn token_end(&self) -> usize;
+    fn token_start_line(&self) -> usize;
+    fn token_end_line(&self) -> usize;
+    /// Gets the quote the current string token is surrounded in.
+    fn string_quote(&self) -> char;
+    /// Moves past the text that caused the last scan error.
+    fn recover_from_error(&mut self);
+    /// Sets the line, column, and source name of an error found in the text.
+    fn locate_error(&self, err: ParseError) -> ParseError;
+    /// Takes the problems found while scanning that didn't stop it.
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic>;
+    /// Gets the number of tokens scanned so far, including comments.
+    fn tokens_scanned(&self) -> usize;
+}
+
+impl TokenScanner for Scanner {
+    fn scan(&mut self) -> Result<Option<Token>, ParseError> {
+        Scanner::scan(self)
+    }
+
+    fn token(&self) -> Option<Token> {
+        Scanner::token(self)
+    }
+
+    fn token_start(&self) -> usize {
+        Scanner::token_start(self)
+    }
+
+    fn token_end(&self) -> usize {
+        Scanner::token_end(self)
+    }
+
+    fn token_start_line(&self) -> usize {
+        Scanner::token_start_line(self)
+    }
+
+    fn token_end_line(&self) -> usize {
+        Scanner::token_end_line(self)
+    }
+
+    fn string_quote(&self) -> char {
+        match self.token_start_char() {
+            Some('\'') => '\'',
+            _ => '"',
+        }
+    }
+
+    fn recover_from_error(&mut self) {
+        Scanner::recover_from_error(self)
+    }
+
+    fn locate_error(&self, err: ParseError) -> ParseError {
+        Scanner::locate_error(self, err)
+    }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        Scanner::take_diagnostics(self)
+    }
+
+    fn tokens_scanned(&self) -> usize {
+        Scanner::tokens_scanned(self)
+    }
+}
+
+/// Scanner over tokens that were already scanned, skipping any whitespace tokens.
+struct TokenSliceScanner<'a> {
+    tokens: &'a [TokenAndRange],
+    text: &'a str,
+    next_index: usize,
+    current: Option<&'a TokenAndRange>,
+    /// Quote of the current token when it's a string.
+    current_quote: char,
+    /// Character position of `byte_pos`, used to find the quote of strings as the tokens move forward.
+    char_pos: usize,
+    byte_pos: usize,
+    /// Range of the end of the text, used once all the tokens have been scanned.
+    end_range: Range,
+    /// Number of tokens returned so far, which doesn't include whitespace tokens.
+    tokens_scanned: usize,
+    source_name: Option<ImmutableString>,
+}
+
+impl<'a> TokenSliceScanner<'a> {
+    fn new(tokens: &'a [TokenAndRange], text: &'a str) -> TokenSliceScanner<'a> {
+        let mut end = 0;
+        let mut end_line = 0;
+        for c in text.chars() {
+            end += 1;
+            if c == '\n' {
+                end_line += 1;
+            }
+        }
+
+        TokenSliceScanner {
+            tokens,
+            text,
+            next_index: 0,
+            current: None,
+            current_quote: '"',
+            char_pos: 0,
+            byte_pos: 0,
+            end_range: Range {
+                start: end,
+                end,
+                start_line: end_line,
+                end_line,
+            },
+            tokens_scanned: 0,
+            source_name: None,
+        }
+    }
+
+    fn range(&self) -> &Range {
+        match self.current {
+            Some(token) => &token.range,
+            None => &self.end_range,
+        }
+    }
+}
+
+impl<'a> TokenScanner for TokenSliceScanner<'a> {
+    fn scan(&mut self) -> Result<Option<Token>, ParseError> {
+        let previous_end = self.current.map(|token| token.range.end).unwrap_or(0);
+        self.current = None;
+
+        while let Some(token) = self.tokens.get(self.next_index) {
+            self.next_index += 1;
+
+            let range = &token.range;
+            if range.start < previous_end || range.end < range.start || range.end > self.end_range.end || range.end_line < range.start_line {
+                return Err(self.locate_error(ParseError::new(range.start, "Token stream is not well formed.")));
+            }
+
+            if let Token::Whitespace(_) = token.token {
+                continue;
+            }
+
+            if let Token::String(_) = token.token {
+                for c in self.text[self.byte_pos..].chars().take(range.start - self.char_pos) {
+                    self.byte_pos += c.len_utf8();
+                }
+                self.char_pos = range.start;
+                self.current_quote = match self.text[self.byte_pos..].chars().next() {
+                    Some('\'') => '\'',
+                    _ => '"',
+                };
+            }
+
+            self.current = Some(token);
+            self.tokens_scanned += 1;
+            break;
+        }
+
+        Ok(self.token())
+    }
+
+    fn token(&self) -> Option<Token> {
+        self.current.map(|token| token.token.clone())
+    }
+
+    fn token_start(&self) -> usize {
+        self.range().start
+    }
+
+    fn token_end(&self) -> usize {
+        self.range().end
+    }
+
+    fn token_start_line(&self) -> usize {
+        self.range().start_line
+    }
+
+    fn token_end_line(&self) -> usize {
+        self.range().end_line
+    }
+
+    fn string_quote(&self) -> char {
+        self.current_quote
+    }
+
+    fn recover_from_error(&mut self) {
+        // the invalid token was already skipped
+    }
+
+    fn locate_error(&self, err: ParseError) -> ParseError {
+        err.locate(self.text.chars(), self.source_name.as_ref())
+    }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        Vec::new()
+    }
+
+    fn tokens_scanned(&self) -> usize {
+        self.tokens_scanned
+    }
+}
+
+struct Context<T: TokenScanner> {
+    scanner: T,
     comments: HashMap<usize, Rc<Vec<Comment>>>,
     current_comments: Option<Vec<Comment>>,
     last_token_end: usize,
     range_stack: Vec<Range>,
     tokens: Vec<TokenAndRange>,
+    /// Errors found when parsing tolerantly or `None` to stop at the first error.
+    errors: Option<Vec<Diagnostic>>,
+    /// Ranges, messages, and container start positions of the stored errors, used to not
+    /// store an error twice.
+    error_keys: HashSet<(usize, usize, String, Option<usize>)>,
+    /// Maximum number of errors to store when parsing tolerantly.
+    max_errors: Option<usize>,
+    /// Whether to stop parsing once an error is found after storing the maximum number of errors.
+    stop_at_max_errors: bool,
+    /// Whether an error was found after storing the maximum number of errors.
+    errors_truncated: bool,
+    /// Whether text before the current token was skipped because it couldn't be scanned.
+    skipped_invalid_text: bool,
+    /// Number of objects and arrays that are currently open.
+    depth: usize,
+    max_depth_seen: usize,
+    /// Whether to error when a comma is missing between the items of an object or array.
+    require_commas: bool,
 }
 
-impl Context {
+impl<T: TokenScanner> Context<T> {
     pub fn scan(&mut self) -> Result<Option<Token>, ParseError> {
         let previous_end = self.last_token_end;
+        self.skipped_invalid_text = false;
         let token = self.scan_handling_comments()?;
         self.last_token_end = self.scanner.token_end();
 
@@ -80,13 +343,135 @@ impl Context {
         }
     }
 
-    pub fn create_parse_error(&self, text: &str) -> ParseError {
-        ParseError::new(self.scanner.token_start(), text)
+    /// Reports an error at the current token.
+    ///
+    /// This returns the error when not parsing tolerantly. Otherwise, the error is
+    /// stored and the caller should recover.
+    pub fn report_error(&mut self, text: &str) -> Result<(), ParseError> {
+        let range = self.create_range_from_last_token();
+        self.store_error(range, ParseError::new(self.scanner.token_start(), text))
+    }
+
+    /// Reports that the text ended while expecting something.
+    pub fn report_unexpected_eof(&mut self, text: &str, expected: &str) -> Result<(), ParseError> {
+        let range = self.create_range_from_last_token();
+        self.store_error(range, ParseError::new_unexpected_eof(self.scanner.token_start(), text, expected))
+    }
+
+    /// Reports that the text ended before the container at the top of the range stack was closed.
+    pub fn report_unclosed_container(&mut self, open_text: &str, expected: &str) -> Result<(), ParseError> {
+        let container_range = self.range_stack.last().expect("Expected the range of the container.");
+        let opened_at = Range {
+            start: container_range.start,
+            end: container_range.start + 1,
+            start_line: container_range.start_line,
+            end_line: container_range.start_line,
+        };
+        let range = self.create_range_from_last_token();
+        let err = ParseError::new_unclosed(self.scanner.token_start(), opened_at, open_text, expected);
+        self.store_error(range, err.with_insertion_point(self.get_insertion_point()))
+    }
+
+    /// Reports that a comma is missing before the current token.
+    pub fn report_missing_comma(&mut self, text: &str) -> Result<(), ParseError> {
+        let range = self.create_range_from_last_token();
+        self.store_error(range, ParseError::new_missing_comma(self.scanner.token_start(), text, self.get_insertion_point()))
+    }
+
+    /// Reports that the colon after the name of an object property is missing at the current token.
+    pub fn report_missing_colon(&mut self, text: &str) -> Result<(), ParseError> {
+        let range = self.create_range_from_last_token();
+        let insertion_point = self.get_insertion_point();
+        let err = match self.token() {
+            Some(_) => ParseError::new_missing_colon(self.scanner.token_start(), text, insertion_point),
+            None => ParseError::new_unexpected_eof(self.scanner.token_start(), text, "':'").with_insertion_point(insertion_point),
+        };
+        self.store_error(range, err)
+    }
+
+    /// Gets the end of the last token before the current token, which is where a missing
+    /// separator or closing token could be inserted.
+    fn get_insertion_point(&self) -> usize {
+        let skip = if self.token().is_some() { 1 } else { 0 };
+        self.tokens.iter().rev().nth(skip).map(|token| token.range.end).unwrap_or(0)
+    }
+
+    /// Starts the range of an object or array at the current token.
+    pub fn start_container(&mut self) {
+        self.start_range();
+        self.depth += 1;
+        self.max_depth_seen = std::cmp::max(self.max_depth_seen, self.depth);
+    }
+
+    /// Ends the range of the innermost open object or array at the current token.
+    pub fn end_container(&mut self) -> Range {
+        self.depth -= 1;
+        self.end_range()
+    }
+
+    /// Removes the range that was started without ending it.
+    pub fn discard_range(&mut self) {
+        self.range_stack.pop().expect("Range was popped from the stack, but the stack was empty.");
+    }
+
+    fn store_error(&mut self, range: Range, err: ParseError) -> Result<(), ParseError> {
+        let errors = match self.errors.as_mut() {
+            Some(errors) => errors,
+            None => return Err(self.scanner.locate_error(err)),
+        };
+
+        // don't repeat the same error (ex. for each unterminated container opened on the same line)
+        let is_duplicate = match errors.last() {
+            Some(last) => last.range.start == range.start && last.message == err.message,
+            None => false,
+        };
+        let opened_at = match &err.kind {
+            ErrorKind::UnexpectedEof { opened_at, .. } => opened_at.as_ref().map(|range| range.start),
+            _ => None,
+        };
+        let key = (range.start, range.end, err.message.clone(), opened_at);
+        if is_duplicate || self.error_keys.contains(&key) {
+            return Ok(());
+        }
+        if self.max_errors.map(|max_errors| errors.len() >= max_errors).unwrap_or(false) {
+            self.errors_truncated = true;
+            return if self.stop_at_max_errors { Err(self.scanner.locate_error(err)) } else { Ok(()) };
+        }
+        self.error_keys.insert(key);
+        errors.push(Diagnostic {
+            range,
+            kind: Some(err.kind),
+            message: err.message,
+        });
+        Ok(())
     }
 
     fn scan_handling_comments(&mut self) -> Result<Option<Token>, ParseError> {
         loop {
-            let token = self.scanner.scan()?;
+            let token = match self.scanner.scan() {
+                Ok(token) => {
+                    if self.errors.is_some() {
+                        for diagnostic in self.scanner.take_diagnostics() {
+                            let err = ParseError::new(diagnostic.range.start, &diagnostic.message);
+                            self.store_error(diagnostic.range, err)?;
+                        }
+                    }
+                    token
+                },
+                Err(err) => {
+                    let start_line = self.scanner.token_start_line();
+                    self.scanner.recover_from_error();
+                    let range = Range {
+                        start: err.pos,
+                        end: std::cmp::max(err.pos, self.scanner.token_end()),
+                        start_line,
+                        end_line: self.scanner.token_end_line(),
+                    };
+                    self.store_error(range, err)?;
+                    self.skipped_invalid_text = true;
+                    continue;
+                },
+            };
             match token {
                 Some(Token::CommentLine(text)) => {
                     self.handle_comment(Comment::Line(CommentLine {
@@ -114,169 +499,2172 @@ impl Context {
     }
 }
 
-/// Parses a string containing JSONC to an AST with comments and tokens.
+/// Options for parsing text.
 ///
 /// # Example
 ///
 /// ```
-/// use jsonc_parser::parse_text;
+/// use jsonc_parser::{parse_text_with_options, ParseOptions};
 ///
-/// let parse_result = parse_text(r#"{ "test": 5 } // test"#);
-/// // ...inspect parse_result for value, tokens, and comments here...
+/// let options = ParseOptions::default().with_source_name("tsconfig.json");
+/// let err = parse_text_with_options("{\n  \"a\" 5\n}", &options).err().unwrap();
+/// assert_eq!(err.to_string(), "tsconfig.json:2:7: Expected a colon after the string in an object property.");
 /// ```
-pub fn parse_text(text: &str) -> Result<ParseResult, ParseError> {
-    let mut context = Context {
-        scanner: Scanner::new(text),
-        comments: HashMap::new(),
-        current_comments: None,
-        last_token_end: 0,
-        range_stack: Vec::new(),
-        tokens: Vec::new(),
-    };
-    context.scan()?;
-    let value = parse_value(&mut context)?;
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Name of the source the text came from (ex. a file path), which is included in errors.
+    pub source_name: Option<String>,
+    /// Options for scanning the text. Whitespace tokens are never returned to the parser.
+    pub scanner_options: ScannerOptions,
+    /// Whether to measure the parse and provide the measurements in `ParseResult::metrics`.
+    pub collect_metrics: bool,
+    /// Whether to error when the text doesn't contain a value (ex. it only contains comments).
+    pub require_value: bool,
+    /// The kind of value the text must contain. A kind other than `RootKind::Any` also requires a value.
+    pub root_kind: RootKind,
+    /// Whether to error when a comma is missing between the items of an object or array (ex. `[1 2]`).
+    /// Defaults to `false`, which parses the items as if the comma was there.
+    pub require_commas: bool,
+    /// Function that transforms property names when parsing to a `JsonValue` with
+    /// `parse_to_value_with_options` (ex. from camelCase to snake_case). Defaults to `None`.
+    pub key_transform: Option<fn(&str) -> String>,
+    /// Whether to fold comment lines on consecutive lines into one comment line whose range spans
+    /// them, which is useful for extracting documentation. Defaults to `false`.
+    pub fold_comment_lines: bool,
+    /// How the values of properties with the same name in an object are combined when parsing to
+    /// a `JsonValue` with `parse_to_value_with_options`. Defaults to `DuplicateKeyPolicy::Replace`.
+    pub duplicate_key_policy: DuplicateKeyPolicy,
+    /// What to do with escapes of lone surrogates (ex. `"\uD800"`) when decoding strings to a
+    /// `JsonValue` or an `events::Event`, since they can't be stored in a `String`. Defaults to
+    /// `LoneSurrogates::ReplaceWithReplacementChar`.
+    pub lone_surrogates: LoneSurrogates,
+    /// What `parse_to_value_with_options` returns when the text doesn't contain a value (ex. it only
+    /// contains whitespace and comments). Defaults to `MissingValue::Error`.
+    pub missing_value: MissingValue,
+    /// The unicode normalization form that strings and property names are converted to when parsing
+    /// to a `JsonValue` with `parse_to_value_with_options`, so that text which differs only in how
+    /// characters are composed compares equal. Defaults to `None`, which keeps the text as written.
+    #[cfg(feature = "unicode-normalization")]
+    pub string_normalization: Option<StringNormalization>,
+}
 
-    if context.scan()?.is_some() {
-        return Err(context.create_parse_error("Text cannot contain more than one JSON value."));
-    }
+/// The kind of value the text must contain.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum RootKind {
+    /// Any value or, unless a value is required, no value.
+    #[default]
+    Any,
+    /// An object.
+    Object,
+    /// An array.
+    Array,
+}
 
-    debug_assert!(context.range_stack.is_empty());
+/// How the values of properties with the same name in an object are combined when parsing to a `JsonValue`.
+///
+/// The AST always contains every property, so this doesn't change `parse_text` and duplicate
+/// names are still reported by `diagnostics::parse_with_diagnostics`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum DuplicateKeyPolicy {
+    /// The last value is used, like most JSON parsers.
+    #[default]
+    Replace,
+    /// Objects are merged deeply and arrays are concatenated, while the last value is used when
+    /// the values aren't both objects or both arrays.
+    ///
+    /// This isn't standard JSON behavior. It's for hand-maintained files that intentionally repeat
+    /// a property to add to it (ex. an `"exclude"` array in two places).
+    Merge,
+}
 
-    Ok(ParseResult {
-        comments: context.comments,
-        tokens: context.tokens,
-        value,
-    })
+/// What to do with escapes of lone surrogates when decoding strings to a `JsonValue` or an
+/// `events::Event`.
+///
+/// The AST keeps the text of strings as written, so its strings can also be decoded with
+/// `strings::decode_string_literal_wtf8`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum LoneSurrogates {
+    /// Replace each lone surrogate with the unicode replacement character (`U+FFFD`).
+    #[default]
+    ReplaceWithReplacementChar,
+    /// Error at the first lone surrogate.
+    Error,
+    /// Keep strings with lone surrogates as WTF-8 in a `JsonValue::Wtf8String` or an
+    /// `Event::Wtf8String`. Property names are always a `String`, so lone surrogates in them are
+    /// still replaced with the unicode replacement character.
+    PreserveAsWtf8,
+}
+
+/// What to do when parsing to a `JsonValue` and the text doesn't contain a value.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum MissingValue {
+    /// Error with "Expected a value." at the end of the text.
+    #[default]
+    Error,
+    /// Use `JsonValue::Null`.
+    Null,
+    /// Return `None`.
+    None,
+}
+
+/// A unicode normalization form for the strings of a `JsonValue`.
+#[cfg(feature = "unicode-normalization")]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StringNormalization {
+    /// Normalization Form C, which composes characters (ex. `e` followed by a combining acute
+    /// accent becomes `é`).
+    Nfc,
+    /// Normalization Form D, which decomposes characters.
+    Nfd,
 }
 
-fn parse_value(context: &mut Context) -> Result<Option<Value>, ParseError> {
-    match context.token() {
-        None => Ok(None),
-        Some(token) => match token {
-            Token::OpenBrace => return Ok(Some(Value::Object(parse_object(context)?))),
-            Token::OpenBracket => return Ok(Some(Value::Array(parse_array(context)?))),
-            Token::String(value) => return Ok(Some(Value::StringLit(create_string_lit(context, value)))),
-            Token::Boolean(value) => return Ok(Some(Value::BooleanLit(create_boolean_lit(context, value)))),
-            Token::Number(value) => return Ok(Some(Value::NumberLit(create_number_lit(context, value)))),
-            Token::Null => return Ok(Some(Value::NullKeyword(create_null_keyword(context)))),
-            Token::CloseBracket => return Err(context.create_parse_error("Unexpected close bracket.")),
-            Token::CloseBrace => return Err(context.create_parse_error("Unexpected close brace.")),
-            Token::Comma => return Err(context.create_parse_error("Unexpected comma.")),
-            Token::Colon => return Err(context.create_parse_error("Unexpected colon.")),
-            Token::CommentLine(_) => unreachable!(),
-            Token::CommentBlock(_) => unreachable!(),
+#[cfg(feature = "unicode-normalization")]
+impl StringNormalization {
+    /// Gets the function that converts text to the normalization form.
+    fn normalizer(self) -> fn(&str) -> String {
+        use unicode_normalization::UnicodeNormalization;
+        match self {
+            StringNormalization::Nfc => |text| text.nfc().collect(),
+            StringNormalization::Nfd => |text| text.nfd().collect(),
         }
     }
 }
 
-fn parse_object(context: &mut Context) -> Result<Object, ParseError> {
-    debug_assert!(context.token() == Some(Token::OpenBrace));
-    let mut properties = Vec::new();
+/// Options are equal when all their fields are equal, except that key transforms are only
+/// compared by whether there is one, since function pointers can't be reliably compared.
+impl PartialEq for ParseOptions {
+    fn eq(&self, other: &ParseOptions) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        {
+            if self.string_normalization != other.string_normalization {
+                return false;
+            }
+        }
+        self.source_name == other.source_name
+            && self.scanner_options == other.scanner_options
+            && self.collect_metrics == other.collect_metrics
+            && self.require_value == other.require_value
+            && self.root_kind == other.root_kind
+            && self.require_commas == other.require_commas
+            && self.key_transform.is_some() == other.key_transform.is_some()
+            && self.fold_comment_lines == other.fold_comment_lines
+            && self.duplicate_key_policy == other.duplicate_key_policy
+            && self.lone_surrogates == other.lone_surrogates
+            && self.missing_value == other.missing_value
+    }
+}
 
-    context.start_range();
-    context.scan()?;
+impl ParseOptions {
+    /// Sets the name of the source the text came from.
+    pub fn with_source_name(mut self, value: &str) -> Self {
+        self.source_name = Some(String::from(value));
+        self
+    }
 
-    loop {
-        match context.token() {
-            Some(Token::CloseBrace) => break,
-            Some(Token::String(prop_name)) => {
-                properties.push(parse_object_property(context, prop_name)?);
+    /// Sets the options for scanning the text.
+    pub fn with_scanner_options(mut self, value: ScannerOptions) -> Self {
+        self.scanner_options = value;
+        self
+    }
+
+    /// Sets whether to measure the parse.
+    pub fn with_collect_metrics(mut self, value: bool) -> Self {
+        self.collect_metrics = value;
+        self
+    }
+
+    /// Sets whether to error when the text doesn't contain a value.
+    pub fn with_require_value(mut self, value: bool) -> Self {
+        self.require_value = value;
+        self
+    }
+
+    /// Sets the kind of value the text must contain.
+    pub fn with_root_kind(mut self, value: RootKind) -> Self {
+        self.root_kind = value;
+        self
+    }
+
+    /// Sets whether to error when a comma is missing between the items of an object or array.
+    pub fn with_require_commas(mut self, value: bool) -> Self {
+        self.require_commas = value;
+        self
+    }
+
+    /// Sets the function that transforms property names when parsing to a `JsonValue`.
+    pub fn with_key_transform(mut self, value: fn(&str) -> String) -> Self {
+        self.key_transform = Some(value);
+        self
+    }
+
+    /// Sets whether comment lines on consecutive lines are folded into one comment.
+    pub fn with_fold_comment_lines(mut self, value: bool) -> Self {
+        self.fold_comment_lines = value;
+        self
+    }
+
+    /// Sets how the values of properties with the same name are combined when parsing to a `JsonValue`.
+    pub fn with_duplicate_key_policy(mut self, value: DuplicateKeyPolicy) -> Self {
+        self.duplicate_key_policy = value;
+        self
+    }
+
+    /// Sets what to do with escapes of lone surrogates when decoding strings.
+    pub fn with_lone_surrogates(mut self, value: LoneSurrogates) -> Self {
+        self.lone_surrogates = value;
+        self
+    }
+
+    /// Sets what to return when parsing to a value and the text doesn't contain a value.
+    pub fn with_missing_value(mut self, value: MissingValue) -> Self {
+        self.missing_value = value;
+        self
+    }
+
+    /// Sets the unicode normalization form of strings and property names when parsing to a `JsonValue`.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn with_string_normalization(mut self, value: StringNormalization) -> Self {
+        self.string_normalization = Some(value);
+        self
+    }
+
+    /// Gets the function that normalizes strings and property names when parsing to a `JsonValue`.
+    fn string_normalizer(&self) -> Option<fn(&str) -> String> {
+        #[cfg(feature = "unicode-normalization")]
+        {
+            self.string_normalization.map(StringNormalization::normalizer)
+        }
+        #[cfg(not(feature = "unicode-normalization"))]
+        {
+            None
+        }
+    }
+}
+
+/// Verifies the guarantees the ranges of a parse result make about the text.
+///
+/// * Each range starts at or before its end, is within the text, and has the lines of its
+///   start and end positions.
+/// * The tokens and comments are in document order and don't overlap, so the end of each is
+///   at or before the start of the next.
+/// * The range of an object or array contains the ranges of its properties or elements, which
+///   are in document order and don't overlap, and the range of a property contains its name
+///   followed by its value.
+///
+/// `parse_text_with_options` asserts these in debug builds. This is public so that tools that
+/// produce or modify parse results can check them in tests.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{parse_text, verify_ranges};
+///
+/// let text = "{ /* a */ \"b\": [1] }";
+/// let mut result = parse_text(text).unwrap();
+/// assert_eq!(verify_ranges(&result, text), Ok(()));
+/// result.tokens[1].range.end += 1;
+/// assert_eq!(verify_ranges(&result, text).err().unwrap().to_string(), "Line 1: The token at 10..14 overlaps the next token.");
+/// ```
+pub fn verify_ranges(result: &ParseResult, text: &str) -> Result<(), RangeError> {
+    let line_index = LineIndex::new(text);
+    let text_len = text.chars().count();
+    let verify_range = |range: &Range, kind: &str| {
+        if range.start > range.end || range.end > text_len {
+            Err(RangeError::new(range, &format!("The {} at {}..{} is not within the text.", kind, range.start, range.end)))
+        } else if line_index.line_col(range.start).0 != range.start_line || line_index.line_col(range.end).0 != range.end_line {
+            Err(RangeError::new(range, &format!("The lines of the {} at {}..{} don't match its positions.", kind, range.start, range.end)))
+        } else {
+            Ok(())
+        }
+    };
+    let verify_order = |ranges: &[(&Range, &str)]| {
+        for pair in ranges.windows(2) {
+            if pair[0].0.end > pair[1].0.start {
+                let (range, kind) = pair[0];
+                return Err(RangeError::new(range, &format!("The {} at {}..{} overlaps the next {}.", kind, range.start, range.end, pair[1].1)));
             }
-            None => return Err(context.create_parse_error("Unterminated array literal.")),
-            _ => return Err(context.create_parse_error("Unexpected token in array literal.")),
         }
+        Ok(())
+    };
+
+    // the same comment may be in the comments of several positions
+    let mut comment_ranges = result.comments.values().flat_map(|comments| comments.iter()).map(|comment| match comment {
+        Comment::Line(line) => &line.range,
+        Comment::Block(block) => &block.range,
+    }).collect::<Vec<_>>();
+    comment_ranges.sort_by_key(|range| (range.start, range.end));
+    comment_ranges.dedup();
+    let mut ranges = result.tokens.iter().map(|token| (&token.range, "token"))
+        .chain(comment_ranges.into_iter().map(|range| (range, "comment")))
+        .collect::<Vec<_>>();
+    for (range, kind) in ranges.iter() {
+        verify_range(range, kind)?;
+    }
+    ranges.sort_by_key(|(range, _)| range.start);
+    verify_order(&ranges)?;
 
-        // skip the comma
-        match context.scan()? {
-            Some(Token::Comma) => { context.scan()?; },
-            _ => {}
+    let mut stack = Vec::new();
+    stack.extend(result.value.as_ref());
+    while let Some(value) = stack.pop() {
+        verify_range(value.range(), "value")?;
+        let mut children = Vec::new();
+        match value {
+            Value::Object(obj) => {
+                for prop in obj.properties.iter() {
+                    verify_range(&prop.range, "property")?;
+                    verify_order(&[(&prop.name.range, "property name"), (prop.value.range(), "value")])?;
+                    verify_contains(&prop.range, &prop.name.range, "property")?;
+                    verify_contains(&prop.range, prop.value.range(), "property")?;
+                    children.push((&prop.range, "property"));
+                    stack.push(&prop.value);
+                }
+            },
+            Value::Array(arr) => {
+                for element in arr.elements.iter() {
+                    children.push((element.range(), "element"));
+                    stack.push(element);
+                }
+            },
+            _ => {},
+        }
+        verify_order(&children)?;
+        for (child, _) in children {
+            verify_contains(value.range(), child, "value")?;
         }
     }
 
-    Ok(Object {
-        range: context.end_range(),
-        properties,
-    })
+    Ok(())
+}
+
+fn verify_contains(parent: &Range, child: &Range, kind: &str) -> Result<(), RangeError> {
+    if child.start < parent.start || child.end > parent.end {
+        Err(RangeError::new(parent, &format!("The {} at {}..{} doesn't contain its child at {}..{}.", kind, parent.start, parent.end, child.start, child.end)))
+    } else {
+        Ok(())
+    }
 }
 
-fn parse_object_property(context: &mut Context, prop_name: ImmutableString) -> Result<ObjectProp, ParseError> {
-    context.start_range();
+/// Parses a string containing JSONC to an AST with comments and tokens.
+///
+/// Text without a value (ex. empty, whitespace, or only comments) is not an error. The result
+/// has no value, but still contains the comments. Use `parse_text_with_options` with
+/// `ParseOptions::require_value` to error instead.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+///
+/// let parse_result = parse_text(r#"{ "test": 5 } // test"#);
+/// // ...inspect parse_result for value, tokens, and comments here...
+/// ```
+pub fn parse_text(text: &str) -> Result<ParseResult, ParseError> {
+    let result = parse_with_scanner(Scanner::new(text))?;
+    debug_assert_eq!(verify_ranges(&result, text), Ok(()));
+    Ok(result)
+}
 
-    let name = create_string_lit(context, prop_name);
+/// Parses a string containing JSONC to an AST with comments and tokens using the provided options.
+///
+/// When a value is required, either by `ParseOptions::require_value` or a root kind other than
+/// `RootKind::Any`, text without a value errors with "Expected a value." at the end of the text.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{parse_text_with_options, ParseOptions};
+///
+/// let options = ParseOptions::default().with_collect_metrics(true);
+/// let parse_result = parse_text_with_options("[[1], 2] // test", &options).unwrap();
+/// let metrics = parse_result.metrics.unwrap();
+/// assert_eq!(metrics.tokens, 8);
+/// assert_eq!(metrics.max_depth_seen, 2);
+/// ```
+///
+/// ```
+/// use jsonc_parser::{parse_text_with_options, ParseOptions, RootKind};
+///
+/// let options = ParseOptions::default().with_root_kind(RootKind::Object);
+/// let err = parse_text_with_options("// license\n", &options).err().unwrap();
+/// assert_eq!(err.to_string(), "2:1: Expected a value.");
+/// let err = parse_text_with_options("[]", &options).err().unwrap();
+/// assert_eq!(err.to_string(), "1:1: Expected an object.");
+/// ```
+pub fn parse_text_with_options(text: &str, options: &ParseOptions) -> Result<ParseResult, ParseError> {
+    let start_time = if options.collect_metrics { Some(Instant::now()) } else { None };
+    parse_with_options(create_scanner(text, options), text, options, start_time)
+}
 
-    match context.scan()? {
-        Some(Token::Colon) => {},
-        _ => return Err(context.create_parse_error("Expected a colon after the string in an object property.")),
+/// Parses the text from the scanner with the options that apply after scanning.
+fn parse_with_options(scanner: impl TokenScanner, text: &str, options: &ParseOptions, start_time: Option<Instant>) -> Result<ParseResult, ParseError> {
+    let mut context = create_context(scanner, false);
+    context.require_commas = options.require_commas;
+    let mut result = parse_with_context(&mut context)?;
+    if options.fold_comment_lines {
+        fold_comment_lines(&mut result.comments, text);
     }
+    debug_assert_eq!(verify_ranges(&result, text), Ok(()));
 
-    context.scan()?;
-    let value = parse_value(context)?;
+    let root_error = match (&result.value, options.root_kind) {
+        (None, RootKind::Any) if !options.require_value => None,
+        (None, _) => Some(ParseError::new_unexpected_eof(context.scanner.token_end(), "Expected a value.", "a value")),
+        (Some(_), RootKind::Any) | (Some(Value::Object(_)), RootKind::Object) | (Some(Value::Array(_)), RootKind::Array) => None,
+        (Some(value), RootKind::Object) => Some(ParseError::new(value.range().start, "Expected an object.")),
+        (Some(value), RootKind::Array) => Some(ParseError::new(value.range().start, "Expected an array.")),
+    };
+    if let Some(err) = root_error {
+        return Err(context.scanner.locate_error(err));
+    }
 
-    match value {
-        Some(value) => Ok(ObjectProp {
-            range: context.end_range(),
-            name,
-            value,
-        }),
-        None => Err(context.create_parse_error("Expected value after colon in object property.")),
+    if let Some(start_time) = start_time {
+        result.metrics = Some(ParseMetrics {
+            tokens: context.scanner.tokens_scanned(),
+            bytes: text.len(),
+            max_depth_seen: context.max_depth_seen,
+            duration: Some(start_time.elapsed()),
+        });
     }
+    Ok(result)
 }
 
-fn parse_array(context: &mut Context) -> Result<Array, ParseError> {
-    debug_assert!(context.token() == Some(Token::OpenBracket));
-    let mut elements = Vec::new();
+/// Folds the comment lines on consecutive lines in each collection of comments into one comment
+/// line whose text is the text of the source from after the first `//` to the end of the last line.
+fn fold_comment_lines(comments: &mut HashMap<usize, Rc<Vec<Comment>>>, text: &str) {
+    // byte position of each character, since the ranges are in characters
+    let byte_positions = text.char_indices().map(|(byte_pos, _)| byte_pos).chain(std::iter::once(text.len())).collect::<Vec<_>>();
+    // the collections are shared by the tokens before and after them, so fold each once
+    let mut folded_collections: HashMap<*const Vec<Comment>, Rc<Vec<Comment>>> = HashMap::new();
 
-    context.start_range();
-    context.scan()?;
+    for collection in comments.values_mut() {
+        if let Some(folded) = folded_collections.get(&Rc::as_ptr(collection)) {
+            *collection = folded.clone();
+            continue;
+        }
 
-    loop {
-        match context.token() {
-            Some(Token::CloseBracket) => break,
-            None => return Err(context.create_parse_error("Unterminated array literal.")),
-            _ => match parse_value(context)? {
-                Some(value) => elements.push(value),
-                None => return Err(context.create_parse_error("Unterminated array literal.")),
+        let mut folded: Vec<Comment> = Vec::with_capacity(collection.len());
+        for comment in collection.iter() {
+            match (folded.last_mut(), comment) {
+                (Some(Comment::Line(previous)), Comment::Line(line)) if line.range.start_line == previous.range.end_line + 1 => {
+                    // the text starts after the `//` of the first comment
+                    let start = byte_positions[previous.range.start + 2];
+                    let end = byte_positions[line.range.end];
+                    previous.text = ImmutableString::new(String::from(&text[start..end]));
+                    previous.range.end = line.range.end;
+                    previous.range.end_line = line.range.end_line;
+                },
+                _ => folded.push(comment.clone()),
             }
         }
 
-        // skip the comma
-        match context.scan()? {
-            Some(Token::Comma) => { context.scan()?; },
-            _ => {}
-        }
+        let folded = Rc::new(folded);
+        folded_collections.insert(Rc::as_ptr(collection), folded.clone());
+        *collection = folded;
     }
-
-    Ok(Array {
-        range: context.end_range(),
-        elements,
-    })
 }
 
-// factory functions
+/// Reads and parses a file containing JSONC using the provided options.
+///
+/// The file is decoded like `parse_bytes`. The path is used as the source name unless
+/// `ParseOptions::source_name` is set. Errors reading the file, including when it can't be
+/// decoded, have the kind `ErrorKind::Io`.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{parse_file, ParseOptions};
+/// use jsonc_parser::errors::ErrorKind;
+///
+/// let err = parse_file("missing.json", &ParseOptions::default()).err().unwrap();
+/// assert_eq!(err.kind, ErrorKind::Io(std::io::ErrorKind::NotFound));
+/// assert!(err.to_string().starts_with("missing.json:1:1: "));
+/// ```
+pub fn parse_file(path: impl AsRef<Path>, options: &ParseOptions) -> Result<ParseResult, ParseError> {
+    let path = path.as_ref();
+    let options_with_path;
+    let options = match options.source_name {
+        Some(_) => options,
+        None => {
+            options_with_path = options.clone().with_source_name(&path.to_string_lossy());
+            &options_with_path
+        },
+    };
 
-fn create_string_lit(context: &Context, value: ImmutableString) -> StringLit {
-    StringLit {
-        range: context.create_range_from_last_token(),
-        value,
-    }
+    let bytes = fs::read(path).map_err(|err| ParseError::new_io(&err, options.source_name.as_deref()))?;
+    parse_bytes(&bytes, options)
 }
 
-fn create_boolean_lit(context: &Context, value: bool) -> BooleanLit {
-    BooleanLit {
-        range: context.create_range_from_last_token(),
-        value,
-    }
+/// Decodes and parses bytes containing JSONC using the provided options.
+///
+/// The bytes may be UTF-8 or UTF-16 with a byte order mark. UTF-16 without one is detected by
+/// the null byte that precedes or follows the first character, which is always ASCII in valid
+/// JSON. Positions are relative to the decoded text after the byte order mark and the detected
+/// encoding is set on the result. Errors decoding the bytes have the kind `ErrorKind::Io`.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{parse_bytes, ParseOptions, TextEncoding};
+///
+/// let bytes = [0xFF, 0xFE, b'[', 0, b'1', 0, b']', 0];
+/// let result = parse_bytes(&bytes, &ParseOptions::default()).unwrap();
+/// assert_eq!(result.encoding, Some(TextEncoding::Utf16Le));
+/// assert_eq!(result.value.unwrap().range().end, 3);
+/// ```
+pub fn parse_bytes(bytes: &[u8], options: &ParseOptions) -> Result<ParseResult, ParseError> {
+    let (text, encoding) = decode_bytes(bytes).map_err(|err| ParseError::new_io(&err, options.source_name.as_deref()))?;
+    let mut result = parse_text_with_options(text.strip_prefix('\u{feff}').unwrap_or(&text), options)?;
+    result.encoding = Some(encoding);
+    Ok(result)
 }
 
-fn create_number_lit(context: &Context, value: ImmutableString) -> NumberLit {
-    NumberLit {
-        range: context.create_range_from_last_token(),
-        value,
-    }
+/// Decodes the bytes to text, which keeps a byte order mark.
+fn decode_bytes(bytes: &[u8]) -> Result<(String, TextEncoding), io::Error> {
+    let encoding = match bytes {
+        [0xFF, 0xFE, ..] | [_, 0, ..] => TextEncoding::Utf16Le,
+        [0xFE, 0xFF, ..] | [0, _, ..] => TextEncoding::Utf16Be,
+        _ => TextEncoding::Utf8,
+    };
+    let text = match encoding {
+        TextEncoding::Utf8 => String::from_utf8(bytes.to_vec()).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"))?,
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+            let invalid_error = || io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-16");
+            let chunks = bytes.chunks_exact(2);
+            if !chunks.remainder().is_empty() {
+                return Err(invalid_error());
+            }
+            let code_units = chunks.map(|pair| if encoding == TextEncoding::Utf16Le {
+                u16::from_le_bytes([pair[0], pair[1]])
+            } else {
+                u16::from_be_bytes([pair[0], pair[1]])
+            });
+            std::char::decode_utf16(code_units).collect::<Result<String, _>>().map_err(|_| invalid_error())?
+        },
+    };
+    Ok((text, encoding))
 }
 
-fn create_null_keyword(context: &Context) -> NullKeyword {
+/// Parses tokens that were already scanned from the text to an AST with comments and tokens.
+///
+/// This produces the same result as `parse_text` when provided the tokens scanned from the text
+/// and allows the tokens to be modified before parsing. Whitespace tokens are skipped. The text
+/// is used to validate the token ranges and to find the end of the text.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{parse_tokens_to_ast, Scanner};
+/// use jsonc_parser::common::Range;
+/// use jsonc_parser::tokens::TokenAndRange;
+///
+/// let text = r#"{ "test": 5 } // test"#;
+/// let mut scanner = Scanner::new(text);
+/// let mut tokens = Vec::new();
+/// while let Some(token) = scanner.scan().unwrap() {
+///     tokens.push(TokenAndRange {
+///         token,
+///         range: Range {
+///             start: scanner.token_start(),
+///             end: scanner.token_end(),
+///             start_line: scanner.token_start_line(),
+///             end_line: scanner.token_end_line(),
+///         },
+///     });
+/// }
+///
+/// let parse_result = parse_tokens_to_ast(&tokens, text).unwrap();
+/// assert!(parse_result.value.is_some());
+/// ```
+pub fn parse_tokens_to_ast(tokens: &[TokenAndRange], text: &str) -> Result<ParseResult, ParseError> {
+    parse_with_scanner(TokenSliceScanner::new(tokens, text))
+}
+
+/// Parses tokens that were already scanned from the text to an AST using the provided options.
+///
+/// This produces the same result as `parse_text_with_options` when provided the tokens scanned
+/// from the text. The scanner options are ignored since the tokens were already scanned.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{parse_tokens_to_ast_with_options, ParseOptions, RootKind, Scanner};
+/// use jsonc_parser::common::Range;
+/// use jsonc_parser::tokens::TokenAndRange;
+///
+/// let text = "[1 2]";
+/// let mut scanner = Scanner::new(text);
+/// let mut tokens = Vec::new();
+/// while let Some(token) = scanner.scan().unwrap() {
+///     let range = Range { start: scanner.token_start(), end: scanner.token_end(), start_line: 0, end_line: 0 };
+///     tokens.push(TokenAndRange { token, range });
+/// }
+///
+/// let options = ParseOptions::default().with_require_commas(true);
+/// let err = parse_tokens_to_ast_with_options(&tokens, text, &options).err().unwrap();
+/// assert_eq!(err.to_string(), "1:4: Expected ',' or ']'.");
+/// let options = ParseOptions::default().with_root_kind(RootKind::Object);
+/// let err = parse_tokens_to_ast_with_options(&tokens, text, &options).err().unwrap();
+/// assert_eq!(err.to_string(), "1:1: Expected an object.");
+/// ```
+pub fn parse_tokens_to_ast_with_options(tokens: &[TokenAndRange], text: &str, options: &ParseOptions) -> Result<ParseResult, ParseError> {
+    let start_time = if options.collect_metrics { Some(Instant::now()) } else { None };
+    let mut scanner = TokenSliceScanner::new(tokens, text);
+    scanner.source_name = options.source_name.as_ref().map(|name| ImmutableString::new(name.clone()));
+    parse_with_options(scanner, text, options, start_time)
+}
+
+/// Parses tokens that were already scanned from the text to a value using the provided options.
+///
+/// This produces the same result as `parse_to_value_with_options` when provided the tokens
+/// scanned from the text. The scanner options are ignored since the tokens were already scanned.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{parse_tokens_to_value_with_options, DuplicateKeyPolicy, ParseOptions, Scanner};
+/// use jsonc_parser::common::Range;
+/// use jsonc_parser::tokens::TokenAndRange;
+///
+/// let text = r#"{ "a": [1], "a": [2] }"#;
+/// let mut scanner = Scanner::new(text);
+/// let mut tokens = Vec::new();
+/// while let Some(token) = scanner.scan().unwrap() {
+///     let range = Range { start: scanner.token_start(), end: scanner.token_end(), start_line: 0, end_line: 0 };
+///     tokens.push(TokenAndRange { token, range });
+/// }
+///
+/// let options = ParseOptions::default().with_duplicate_key_policy(DuplicateKeyPolicy::Merge);
+/// let value = parse_tokens_to_value_with_options(&tokens, text, &options).unwrap();
+/// assert_eq!(value.unwrap().to_string(), r#"{"a":[1,2]}"#);
+/// ```
+pub fn parse_tokens_to_value_with_options(tokens: &[TokenAndRange], text: &str, options: &ParseOptions) -> Result<Option<JsonValue>, ParseError> {
+    let value = parse_tokens_to_ast_with_options(tokens, text, options)?.value;
+    ast_to_value(value, text, options)
+}
+
+/// Parses a string containing JSONC to an AST value without the comments and tokens.
+///
+/// Errors when the text doesn't contain a value (ex. it's empty or only contains comments). Use
+/// `parse_text` to also get the comments and tokens.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::ast::Value;
+/// use jsonc_parser::parse_to_ast;
+///
+/// let value = parse_to_ast(r#"{ "test": 5 } // test"#).unwrap();
+/// assert!(matches!(value, Value::Object(_)));
+/// assert_eq!(parse_to_ast("// test").unwrap_err().to_string(), "1:8: Expected a value.");
+/// ```
+pub fn parse_to_ast(text: &str) -> Result<Value, ParseError> {
+    let options = ParseOptions::default().with_require_value(true);
+    Ok(parse_text_with_options(text, &options)?.value.expect("Expected a value because one is required."))
+}
+
+/// Parses a string containing JSONC to a value without positional information or comments.
+///
+/// Errors when the text doesn't contain a value (ex. it's empty or only contains comments). Use
+/// `parse_to_value_with_options` with `ParseOptions::missing_value` to get `null` or `None` instead.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_to_value;
+///
+/// let value = parse_to_value(r#"{ "test": 5 } // test"#).unwrap();
+/// assert_eq!(value.unwrap().to_string(), r#"{"test":5}"#);
+/// assert_eq!(parse_to_value("// test").unwrap_err().to_string(), "1:8: Expected a value.");
+/// ```
+pub fn parse_to_value(text: &str) -> Result<Option<JsonValue>, ParseError> {
+    match parse_text(text)?.value {
+        Some(value) => Ok(Some(value.into_value())),
+        None => get_missing_value(text, &ParseOptions::default()),
+    }
+}
+
+/// Parses a string containing JSONC to a value without positional information or comments using the provided options.
+///
+/// Property names are decoded before being passed to `ParseOptions::key_transform`. When transformed
+/// names are the same, the values are combined with `ParseOptions::duplicate_key_policy`. Text
+/// without a value is handled with `ParseOptions::missing_value`. With the `unicode-normalization`
+/// feature, strings and names can be normalized first with `ParseOptions::string_normalization`.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{parse_to_value_with_options, MissingValue, ParseOptions};
+/// use jsonc_parser::JsonValue;
+///
+/// let options = ParseOptions::default().with_key_transform(|name| name.to_uppercase());
+/// let value = parse_to_value_with_options(r#"{ "a": { "b": 1 } }"#, &options).unwrap();
+/// assert_eq!(value.unwrap().to_string(), r#"{"A":{"B":1}}"#);
+///
+/// let options = ParseOptions::default().with_missing_value(MissingValue::Null);
+/// assert_eq!(parse_to_value_with_options("// just a comment", &options).unwrap(), Some(JsonValue::Null));
+/// ```
+pub fn parse_to_value_with_options(text: &str, options: &ParseOptions) -> Result<Option<JsonValue>, ParseError> {
+    let value = parse_text_with_options(text, options)?.value;
+    ast_to_value(value, text, options)
+}
+
+/// Converts the AST value parsed from the text to a `JsonValue` using the options for building values.
+fn ast_to_value(value: Option<Value>, text: &str, options: &ParseOptions) -> Result<Option<JsonValue>, ParseError> {
+    if options.lone_surrogates == LoneSurrogates::Error {
+        if let Some(pos) = value.as_ref().and_then(find_lone_surrogate_in_value) {
+            let err = ParseError::new(pos, "Lone surrogates are not allowed in strings.");
+            value.into_iter().for_each(Value::drop_nested);
+            return Err(err.locate(text.chars(), options.source_name.as_ref().map(|name| ImmutableString::new(name.clone())).as_ref()));
+        }
+    }
+    match value {
+        Some(value) => Ok(Some(value.into_value_with_options(options.string_normalizer(), options.key_transform, options.duplicate_key_policy, options.lone_surrogates))),
+        None => get_missing_value(text, options),
+    }
+}
+
+/// Gets the result of parsing text without a value to a `JsonValue`.
+fn get_missing_value(text: &str, options: &ParseOptions) -> Result<Option<JsonValue>, ParseError> {
+    match options.missing_value {
+        MissingValue::Error => {
+            let err = ParseError::new_unexpected_eof(text.chars().count(), "Expected a value.", "a value");
+            Err(err.locate(text.chars(), options.source_name.as_ref().map(|name| ImmutableString::new(name.clone())).as_ref()))
+        },
+        MissingValue::Null => Ok(Some(JsonValue::Null)),
+        MissingValue::None => Ok(None),
+    }
+}
+
+/// A string or value whose strings are left to check for lone surrogates.
+enum SurrogateSearchItem<'a> {
+    Name(&'a StringLit),
+    Value(&'a Value),
+}
+
+/// Gets the position of the first escape of a lone surrogate in the strings of the value.
+fn find_lone_surrogate_in_value(value: &Value) -> Option<usize> {
+    let mut stack = vec![SurrogateSearchItem::Value(value)];
+    while let Some(item) = stack.pop() {
+        match item {
+            SurrogateSearchItem::Name(lit) | SurrogateSearchItem::Value(Value::StringLit(lit)) => {
+                if let Some(offset) = find_lone_surrogate(lit.value.as_ref(), lit.quote) {
+                    return Some(lit.range.start + 1 + offset);
+                }
+            },
+            SurrogateSearchItem::Value(Value::Object(obj)) => {
+                // names are pushed after their values so the strings are checked in document order
+                for prop in obj.properties.iter().rev() {
+                    stack.push(SurrogateSearchItem::Value(&prop.value));
+                    stack.push(SurrogateSearchItem::Name(&prop.name));
+                }
+            },
+            SurrogateSearchItem::Value(Value::Array(arr)) => stack.extend(arr.elements.iter().rev().map(SurrogateSearchItem::Value)),
+            SurrogateSearchItem::Value(_) => {},
+        }
+    }
+    None
+}
+
+/// Parses only the first items of the root object or array, which is useful for previewing large documents.
+///
+/// Scanning stops once the root object has `max_items` properties or the root array has
+/// `max_items` elements. The returned flag is `true` when items were left out, in which case
+/// the range of the root container ends at its last parsed item.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::ast::Value;
+/// use jsonc_parser::parse_preview;
+///
+/// let (value, is_truncated) = parse_preview("[1, 2, 3, 4]", 2).unwrap();
+/// assert!(is_truncated);
+/// match value {
+///     Value::Array(arr) => assert_eq!(arr.elements.len(), 2),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn parse_preview(text: &str, max_items: usize) -> Result<(Value, bool), ParseError> {
+    let mut scanner = Scanner::new(text);
+    let mut tokens: Vec<TokenAndRange> = Vec::new();
+    let mut root_close_token = None;
+    let mut depth = 0;
+    let mut root_items = 0;
+    let mut is_truncated = false;
+
+    while let Some(token) = scanner.scan()? {
+        let is_root_item_start = depth == 1 && !matches!(token, Token::Comma | Token::Colon | Token::CloseBrace | Token::CloseBracket | Token::CommentLine(_) | Token::CommentBlock(_));
+        if is_root_item_start && root_items == max_items {
+            is_truncated = true;
+            break;
+        }
+
+        let previous_token = tokens.iter().rev().map(|token| &token.token).find(|token| !matches!(token, Token::CommentLine(_) | Token::CommentBlock(_)));
+        let is_property_name = root_close_token == Some(Token::CloseBrace) && previous_token != Some(&Token::Colon);
+        match token {
+            Token::OpenBrace | Token::OpenBracket => {
+                if depth == 0 {
+                    root_close_token = Some(if token == Token::OpenBrace { Token::CloseBrace } else { Token::CloseBracket });
+                }
+                depth += 1;
+            },
+            Token::CloseBrace | Token::CloseBracket => {
+                depth -= 1;
+                if depth == 1 {
+                    root_items += 1;
+                }
+            },
+            Token::String(_) | Token::Number(_) | Token::Boolean(_) | Token::Null if depth == 1 && !is_property_name => root_items += 1,
+            _ => {},
+        }
+
+        tokens.push(TokenAndRange {
+            token,
+            range: Range {
+                start: scanner.token_start(),
+                end: scanner.token_end(),
+                start_line: scanner.token_start_line(),
+                end_line: scanner.token_end_line(),
+            },
+        });
+    }
+
+    if is_truncated {
+        // close the root container directly after the last item
+        let last_range = tokens.iter().rev().find(|token| !matches!(token.token, Token::Comma | Token::CommentLine(_) | Token::CommentBlock(_))).map(|token| token.range.clone());
+        let last_range = last_range.expect("Expected a token before the truncated item.");
+        tokens.retain(|token| token.range.start < last_range.end);
+        tokens.push(TokenAndRange {
+            token: root_close_token.expect("Expected a root container."),
+            range: Range {
+                start: last_range.end,
+                end: last_range.end,
+                start_line: last_range.end_line,
+                end_line: last_range.end_line,
+            },
+        });
+    }
+
+    match parse_tokens_to_ast(&tokens, text)?.value {
+        Some(value) => Ok((value, is_truncated)),
+        None => Err(ParseError::new(0, "Expected a value.").locate(text.chars(), None)),
+    }
+}
+
+/// A container that `extract_matching` is looking for matching values in.
+enum ExtractFrame {
+    /// The name of the current property.
+    Object(String),
+    /// The index of the current element.
+    Array(usize),
+}
+
+/// Parses only the values whose path matches the predicate, which keeps memory low for large documents.
+///
+/// The predicate is called with the path of each value (property names and array indexes)
+/// until it matches. Matched values are parsed with their ranges and comments are ignored.
+/// Values that don't match are read with an `EventParser` without being built, except that the
+/// children of objects and arrays are still provided to the predicate. The children of a matched
+/// value are not provided to the predicate. The text is validated the same way as `parse_text`.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::extract_matching;
+///
+/// let text = r#"{ "features": { "a": true, "b": [1] }, "other": 2 }"#;
+/// let matches = extract_matching(text, |path| path.len() == 2 && path[0] == "features").unwrap();
+/// let paths = matches.iter().map(|(path, _)| path.join("/")).collect::<Vec<_>>();
+/// assert_eq!(paths, vec!["features/a", "features/b"]);
+/// ```
+pub fn extract_matching(text: &str, predicate: impl FnMut(&[String]) -> bool) -> Result<Vec<(Vec<String>, Value)>, ParseError> {
+    extract_matching_with_options(text, &ParseOptions::default(), predicate)
+}
+
+/// Parses only the values whose path matches the predicate using the provided options.
+///
+/// The options are used like `events::EventParser::new`. The matched values are AST values that
+/// keep their strings as written, so `ParseOptions::lone_surrogates` only changes the property
+/// names in the paths, except that `LoneSurrogates::Error` errors at a lone surrogate in any string.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{extract_matching_with_options, LoneSurrogates, ParseOptions};
+///
+/// let options = ParseOptions::default().with_lone_surrogates(LoneSurrogates::Error);
+/// let err = extract_matching_with_options(r#"{ "a": ["\ud800"] }"#, &options, |path| path.len() == 1).unwrap_err();
+/// assert_eq!(err.to_string(), "1:10: Lone surrogates are not allowed in strings.");
+/// ```
+pub fn extract_matching_with_options(
+    text: &str,
+    options: &ParseOptions,
+    mut predicate: impl FnMut(&[String]) -> bool,
+) -> Result<Vec<(Vec<String>, Value)>, ParseError> {
+    let mut parser = EventParser::new(text, options);
+    let mut frames: Vec<ExtractFrame> = Vec::new();
+    let mut matches = Vec::new();
+    // a character position and its byte position, which only move forward since the matches are in order
+    let mut cursor = (0, 0);
+
+    while let Some(event) = parser.next_event()? {
+        let (range, container) = match event {
+            Event::Comment(..) => continue,
+            Event::Key(name, _) => {
+                if let Some(ExtractFrame::Object(current_name)) = frames.last_mut() {
+                    *current_name = name;
+                }
+                continue;
+            },
+            Event::ObjectEnd(_) | Event::ArrayEnd(_) => {
+                frames.pop();
+                complete_extract_value(&mut frames);
+                continue;
+            },
+            Event::ObjectStart(range) => (range, Some(ExtractFrame::Object(String::new()))),
+            Event::ArrayStart(range) => (range, Some(ExtractFrame::Array(0))),
+            Event::String(_, range) | Event::Wtf8String(_, range) | Event::Number(_, range) | Event::Bool(_, range) | Event::Null(range) => (range, None),
+        };
+
+        let path = frames.iter().map(|frame| match frame {
+            ExtractFrame::Object(name) => name.clone(),
+            ExtractFrame::Array(index) => index.to_string(),
+        }).collect::<Vec<_>>();
+        if predicate(&path) {
+            let range = match container {
+                Some(_) => match parser.skip_container(range)? {
+                    Some(range) => range,
+                    None => break,
+                },
+                None => range,
+            };
+            let value = parse_extracted_value(text, &range, &options.scanner_options, &mut cursor)?;
+            if options.lone_surrogates == LoneSurrogates::Error {
+                if let Some(pos) = find_lone_surrogate_in_value(&value) {
+                    return Err(parser.locate_error(ParseError::new(pos, "Lone surrogates are not allowed in strings.")));
+                }
+            }
+            matches.push((path, value));
+            complete_extract_value(&mut frames);
+        } else if let Some(container) = container {
+            frames.push(container);
+        } else {
+            complete_extract_value(&mut frames);
+        }
+    }
+
+    Ok(matches)
+}
+
+/// Parses the value in the range of the text, which was already validated by an `EventParser`.
+fn parse_extracted_value(text: &str, range: &Range, scanner_options: &ScannerOptions, cursor: &mut (usize, usize)) -> Result<Value, ParseError> {
+    let mut byte_pos = |pos: usize| {
+        for c in text[cursor.1..].chars().take(pos - cursor.0) {
+            cursor.1 += c.len_utf8();
+        }
+        cursor.0 = pos;
+        cursor.1
+    };
+    let start = byte_pos(range.start);
+    let end = byte_pos(range.end);
+
+    let mut scanner = Scanner::new_with_options(&text[start..end], scanner_options.clone().with_whitespace(false));
+    let mut tokens = Vec::new();
+    while let Some(token) = scanner.scan()? {
+        tokens.push(TokenAndRange {
+            token,
+            range: Range {
+                start: range.start + scanner.token_start(),
+                end: range.start + scanner.token_end(),
+                start_line: range.start_line + scanner.token_start_line(),
+                end_line: range.start_line + scanner.token_end_line(),
+            },
+        });
+    }
+    Ok(parse_tokens_to_ast(&tokens, text)?.value.expect("Expected a value."))
+}
+
+/// Moves to the next element of the array the completed value was in.
+fn complete_extract_value(frames: &mut [ExtractFrame]) {
+    if let Some(ExtractFrame::Array(index)) = frames.last_mut() {
+        *index += 1;
+    }
+}
+
+/// Checks if the text is valid without creating an AST or tokens.
+///
+/// This errors the same way as `parse_text_with_options`, but is faster for when only the
+/// validity of the text is needed. The structure is validated by skipping the root value with an
+/// `events::EventParser`, and strings and numbers are validated by the scanner as usual.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{validate_fast, ParseOptions};
+///
+/// let options = ParseOptions::default();
+/// assert!(validate_fast(r#"{ "a": [1, 2] } // test"#, &options).is_ok());
+/// let err = validate_fast(r#"{ "a": [1, 2 }"#, &options).unwrap_err();
+/// assert_eq!(err.to_string(), "1:14: Unexpected close brace.");
+/// ```
+pub fn validate_fast(text: &str, options: &ParseOptions) -> Result<(), ParseError> {
+    // the kind of the root value is checked after the rest of the text like `parse_text_with_options`
+    // does, rather than at its first token like the event parser
+    let mut parser = EventParser::new(text, &ParseOptions {
+        root_kind: RootKind::Any,
+        require_value: options.require_value || options.root_kind != RootKind::Any,
+        // strings are only validated by the scanner, like `parse_text_with_options`
+        lone_surrogates: LoneSurrogates::ReplaceWithReplacementChar,
+        ..options.clone()
+    });
+    let mut root = None;
+    while let Some(event) = parser.next_event()? {
+        // skipping the contents of containers validates them without creating events or decoding strings
+        let (kind, start) = match event {
+            Event::Comment(..) => continue,
+            Event::ObjectStart(range) => (RootKind::Object, parser.skip_container(range)?),
+            Event::ArrayStart(range) => (RootKind::Array, parser.skip_container(range)?),
+            Event::String(_, range) | Event::Wtf8String(_, range) | Event::Number(_, range) | Event::Bool(_, range) | Event::Null(range) => (RootKind::Any, Some(range)),
+            Event::Key(..) | Event::ObjectEnd(_) | Event::ArrayEnd(_) => unreachable!(),
+        };
+        root = start.map(|range| (kind, range.start));
+    }
+
+    match (root, options.root_kind) {
+        (Some((kind, _)), expected_kind) if expected_kind == RootKind::Any || expected_kind == kind => Ok(()),
+        (Some((_, start)), RootKind::Object) => Err(parser.locate_error(ParseError::new(start, "Expected an object."))),
+        (Some((_, start)), _) => Err(parser.locate_error(ParseError::new(start, "Expected an array."))),
+        (None, _) => Ok(()),
+    }
+}
+
+pub(crate) fn create_scanner(text: &str, options: &ParseOptions) -> Scanner {
+    let mut builder = Scanner::builder(text).options(options.scanner_options.clone().with_whitespace(false));
+    if let Some(source_name) = &options.source_name {
+        builder = builder.source_name(source_name);
+    }
+    builder.build()
+}
+
+fn parse_with_scanner(scanner: impl TokenScanner) -> Result<ParseResult, ParseError> {
+    parse_with_context(&mut create_context(scanner, false))
+}
+
+/// Result of parsing the text tolerantly.
+pub(crate) struct TolerantParseResult {
+    pub parse_result: ParseResult,
+    pub errors: Vec<Diagnostic>,
+    /// Whether errors were found after storing the maximum number of errors.
+    pub errors_truncated: bool,
+}
+
+/// Parses the text, recovering from errors instead of stopping at the first one.
+///
+/// Returns the result with the best-effort value and up to the maximum number of errors found.
+/// When `stop_at_max_errors` is set, parsing stops at the first error found after that and
+/// the value is `None`. Strings with invalid escapes are kept with the escapes as they are.
+pub(crate) fn parse_text_tolerant(text: &str, max_errors: Option<usize>, stop_at_max_errors: bool) -> TolerantParseResult {
+    let scanner = Scanner::new_with_options(text, ScannerOptions::default().with_tolerate_invalid_escapes(true));
+    let mut context = create_context(scanner, true);
+    context.max_errors = max_errors;
+    context.stop_at_max_errors = stop_at_max_errors;
+    let parse_result = match parse_with_context(&mut context) {
+        Ok(parse_result) => parse_result,
+        Err(_) => {
+            debug_assert!(context.errors_truncated, "Tolerant parsing should only error after the maximum number of errors.");
+            ParseResult {
+                comments: std::mem::take(&mut context.comments),
+                tokens: std::mem::take(&mut context.tokens),
+                value: None,
+                metrics: None,
+                encoding: None,
+            }
+        },
+    };
+    TolerantParseResult {
+        parse_result,
+        errors: context.errors.take().unwrap_or_default(),
+        errors_truncated: context.errors_truncated,
+    }
+}
+
+fn create_context<T: TokenScanner>(scanner: T, tolerant: bool) -> Context<T> {
+    Context {
+        scanner,
+        comments: HashMap::new(),
+        current_comments: None,
+        last_token_end: 0,
+        range_stack: Vec::new(),
+        tokens: Vec::new(),
+        errors: if tolerant { Some(Vec::new()) } else { None },
+        error_keys: HashSet::new(),
+        max_errors: None,
+        stop_at_max_errors: false,
+        errors_truncated: false,
+        skipped_invalid_text: false,
+        depth: 0,
+        max_depth_seen: 0,
+        require_commas: false,
+    }
+}
+
+fn parse_with_context(context: &mut Context<impl TokenScanner>) -> Result<ParseResult, ParseError> {
+    context.scan()?;
+    let value = parse_value(context)?;
+    if let Err(err) = scan_after_value(context) {
+        value.into_iter().for_each(Value::drop_nested);
+        return Err(err);
+    }
+
+    debug_assert!(context.range_stack.is_empty());
+
+    Ok(ParseResult {
+        comments: std::mem::take(&mut context.comments),
+        tokens: std::mem::take(&mut context.tokens),
+        value,
+        metrics: None,
+        encoding: None,
+    })
+}
+
+fn scan_after_value(context: &mut Context<impl TokenScanner>) -> Result<(), ParseError> {
+    if context.scan()?.is_some() {
+        context.report_error("Text cannot contain more than one JSON value.")?;
+        // ignore the rest of the text
+        while context.scan()?.is_some() {}
+    }
+    Ok(())
+}
+
+/// A container that is currently being parsed.
+///
+/// The parser keeps these on an explicit stack instead of recursing so that deeply
+/// nested documents don't overflow the call stack. The other functions that walk values
+/// (ex. converting, writing, comparing, and dropping them) do the same.
+enum ContainerFrame {
+    Object(Vec<ObjectProp>),
+    ObjectProp(StringLit),
+    Array(Vec<Value>),
+}
+
+/// What the parser should do next.
+enum ParseState {
+    /// Parse a value at the current token.
+    Value,
+    /// Handle the current token within the container at the top of the stack.
+    ContainerNext,
+    /// Add the completed value to its parent.
+    Completed(Value),
+}
+
+fn parse_value(context: &mut Context<impl TokenScanner>) -> Result<Option<Value>, ParseError> {
+    let mut stack = Vec::new();
+    let result = parse_value_with_stack(context, &mut stack);
+    // the stack is only left with containers on an error, which may already hold deeply nested values
+    for frame in stack {
+        match frame {
+            ContainerFrame::Object(properties) => properties.into_iter().for_each(|prop| prop.value.drop_nested()),
+            ContainerFrame::Array(elements) => elements.into_iter().for_each(Value::drop_nested),
+            ContainerFrame::ObjectProp(_) => {},
+        }
+    }
+    result
+}
+
+fn parse_value_with_stack(context: &mut Context<impl TokenScanner>, stack: &mut Vec<ContainerFrame>) -> Result<Option<Value>, ParseError> {
+    let mut state = ParseState::Value;
+
+    loop {
+        state = match state {
+            ParseState::Value => match context.token() {
+                None => match stack.last() {
+                    None => return Ok(None),
+                    Some(ContainerFrame::ObjectProp(_)) => {
+                        context.report_unexpected_eof("Expected value after colon in object property.", "a value")?;
+                        // recover by dropping the property
+                        stack.pop();
+                        context.discard_range();
+                        ParseState::ContainerNext
+                    },
+                    Some(_) => ParseState::ContainerNext,
+                },
+                Some(token) => match token {
+                    Token::OpenBrace => {
+                        context.start_container();
+                        context.scan()?;
+                        stack.push(ContainerFrame::Object(Vec::new()));
+                        ParseState::ContainerNext
+                    },
+                    Token::OpenBracket => {
+                        context.start_container();
+                        context.scan()?;
+                        stack.push(ContainerFrame::Array(Vec::new()));
+                        ParseState::ContainerNext
+                    },
+                    Token::String(value) => ParseState::Completed(Value::StringLit(create_string_lit(context, value))),
+                    Token::Boolean(value) => ParseState::Completed(Value::BooleanLit(create_boolean_lit(context, value))),
+                    Token::Number(value) => ParseState::Completed(Value::NumberLit(create_number_lit(context, value))),
+                    Token::Null => ParseState::Completed(Value::NullKeyword(create_null_keyword(context))),
+                    Token::CloseBracket | Token::CloseBrace | Token::Comma | Token::Colon => {
+                        let is_property_end = matches!(token, Token::CloseBrace | Token::Comma);
+                        let is_element_end = matches!(token, Token::CloseBracket | Token::Comma);
+                        // when the value couldn't be scanned, that error was already reported
+                        let is_value_missing = !context.skipped_invalid_text;
+                        match stack.last() {
+                            Some(ContainerFrame::ObjectProp(_)) if is_property_end => {
+                                if is_value_missing {
+                                    context.report_error("Expected value after colon in object property.")?;
+                                }
+                                // recover by dropping the property
+                                stack.pop();
+                                context.discard_range();
+                                if token == Token::Comma {
+                                    context.scan()?;
+                                }
+                                ParseState::ContainerNext
+                            },
+                            Some(ContainerFrame::Array(_)) if is_element_end && !is_value_missing => {
+                                if token == Token::Comma {
+                                    context.scan()?;
+                                }
+                                ParseState::ContainerNext
+                            },
+                            _ => {
+                                context.report_error(match token {
+                                    Token::CloseBracket => "Unexpected close bracket.",
+                                    Token::CloseBrace => "Unexpected close brace.",
+                                    Token::Comma => "Unexpected comma.",
+                                    _ => "Unexpected colon.",
+                                })?;
+                                // recover by skipping the token, along with its comma when it's in place of an element
+                                let next_token = context.scan()?;
+                                match stack.last() {
+                                    Some(ContainerFrame::Array(_)) => {
+                                        if next_token == Some(Token::Comma) {
+                                            context.scan()?;
+                                        }
+                                        ParseState::ContainerNext
+                                    },
+                                    _ => ParseState::Value,
+                                }
+                            },
+                        }
+                    },
+                    Token::CommentLine(_) => unreachable!(),
+                    Token::CommentBlock(_) => unreachable!(),
+                    Token::Whitespace(_) => unreachable!(),
+                }
+            },
+            ParseState::ContainerNext => match parse_container_next(context, stack)? {
+                Some(container) => ParseState::Completed(container),
+                None => ParseState::Value,
+            },
+            ParseState::Completed(value) => {
+                match stack.pop() {
+                    None => return Ok(Some(value)),
+                    Some(ContainerFrame::ObjectProp(name)) => {
+                        let prop = ObjectProp {
+                            range: context.end_range(),
+                            name,
+                            value,
+                        };
+                        match stack.last_mut() {
+                            Some(ContainerFrame::Object(properties)) => properties.push(prop),
+                            _ => unreachable!(),
+                        }
+                    },
+                    Some(ContainerFrame::Array(mut elements)) => {
+                        elements.push(value);
+                        stack.push(ContainerFrame::Array(elements));
+                    },
+                    Some(ContainerFrame::Object(_)) => unreachable!(),
+                }
+
+                // skip the comma
+                match context.scan()? {
+                    Some(Token::Comma) => {
+                        context.scan()?;
+                    },
+                    Some(token) if context.require_commas => {
+                        let message = match (stack.last(), token) {
+                            (Some(ContainerFrame::Object(_)), Token::CloseBrace) | (Some(ContainerFrame::Array(_)), Token::CloseBracket) => None,
+                            (Some(ContainerFrame::Object(_)), _) => Some("Expected ',' or '}'."),
+                            (Some(ContainerFrame::Array(_)), _) => Some("Expected ',' or ']'."),
+                            _ => None,
+                        };
+                        if let Some(message) = message {
+                            // recover by parsing the token as if the comma was there
+                            context.report_missing_comma(message)?;
+                        }
+                    },
+                    _ => {},
+                }
+
+                ParseState::ContainerNext
+            },
+        };
+    }
+}
+
+/// Handles the current token within the container at the top of the stack.
+///
+/// Returns the container when it was closed or `None` when a value should be parsed next.
+fn parse_container_next(context: &mut Context<impl TokenScanner>, stack: &mut Vec<ContainerFrame>) -> Result<Option<Value>, ParseError> {
+    match stack.last() {
+        Some(ContainerFrame::Object(_)) => loop {
+            match context.token() {
+                Some(Token::CloseBrace) => return Ok(Some(close_object(context, stack))),
+                Some(Token::String(prop_name)) => {
+                    context.start_range();
+                    let name = create_string_lit(context, prop_name);
+
+                    match context.scan()? {
+                        Some(Token::Colon) => {
+                            context.scan()?;
+                        },
+                        token => {
+                            context.report_missing_colon("Expected a colon after the string in an object property.")?;
+                            // recover by using the current token as the value when it could be one
+                            if matches!(token, None | Some(Token::Comma) | Some(Token::CloseBrace)) {
+                                context.discard_range();
+                                if token == Some(Token::Comma) {
+                                    context.scan()?;
+                                }
+                                continue;
+                            }
+                        },
+                    }
+
+                    stack.push(ContainerFrame::ObjectProp(name));
+                    return Ok(None);
+                },
+                None => {
+                    context.report_unclosed_container("{", "'}'")?;
+                    return Ok(Some(close_object(context, stack)));
+                },
+                _ => {
+                    context.report_error("Unexpected token in object literal.")?;
+                    // recover by skipping the token
+                    context.scan()?;
+                },
+            }
+        },
+        Some(ContainerFrame::Array(_)) => match context.token() {
+            Some(Token::CloseBracket) => Ok(Some(close_array(context, stack))),
+            None => {
+                context.report_unclosed_container("[", "']'")?;
+                Ok(Some(close_array(context, stack)))
+            },
+            _ => Ok(None),
+        },
+        _ => unreachable!(),
+    }
+}
+
+fn close_object(context: &mut Context<impl TokenScanner>, stack: &mut Vec<ContainerFrame>) -> Value {
+    match stack.pop() {
+        Some(ContainerFrame::Object(properties)) => Value::Object(Object {
+            range: context.end_container(),
+            properties,
+        }),
+        _ => unreachable!(),
+    }
+}
+
+fn close_array(context: &mut Context<impl TokenScanner>, stack: &mut Vec<ContainerFrame>) -> Value {
+    match stack.pop() {
+        Some(ContainerFrame::Array(elements)) => Value::Array(Array {
+            range: context.end_container(),
+            elements,
+        }),
+        _ => unreachable!(),
+    }
+}
+
+// factory functions
+
+fn create_string_lit(context: &Context<impl TokenScanner>, value: ImmutableString) -> StringLit {
+    StringLit {
+        range: context.create_range_from_last_token(),
+        value,
+        quote: context.scanner.string_quote(),
+    }
+}
+
+fn create_boolean_lit(context: &Context<impl TokenScanner>, value: bool) -> BooleanLit {
+    BooleanLit {
+        range: context.create_range_from_last_token(),
+        value,
+    }
+}
+
+fn create_number_lit(context: &Context<impl TokenScanner>, value: ImmutableString) -> NumberLit {
+    NumberLit {
+        range: context.create_range_from_last_token(),
+        value,
+    }
+}
+
+fn create_null_keyword(context: &Context<impl TokenScanner>) -> NullKeyword {
     NullKeyword {
         range: context.create_range_from_last_token(),
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::{create_context, decode_bytes, extract_matching, extract_matching_with_options, parse_bytes, parse_preview, validate_fast, verify_ranges, LoneSurrogates, MissingValue, parse_text, parse_text_with_options, parse_to_value, parse_to_value_with_options, parse_tokens_to_ast, parse_tokens_to_ast_with_options, parse_tokens_to_value_with_options, DuplicateKeyPolicy, ParseOptions, RootKind, TextEncoding};
+    use super::super::scanner::{Scanner, ScannerOptions};
+    use super::super::numbers::NumberDialect;
+    use super::super::strings::decode_string_literal_wtf8;
+    use super::super::ast::{Comment, Value};
+    use super::super::common::Range;
+    use super::super::errors::{ErrorKind, ParseError};
+    use super::super::tokens::{Token, TokenAndRange};
+    use super::super::value::JsonValue;
+
+    #[test]
+    fn it_parses_comma_decimal_separators_when_enabled() {
+        use super::super::numbers::{parse_number_literal, JsonNumber, NumberDialect};
+
+        let options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_number_dialect(NumberDialect::default().with_comma_decimal_separator(true)));
+        let value = parse_text_with_options("{ \"pi\": 3,14 }", &options).unwrap().value.unwrap();
+        match value.get_path(&"pi".parse().unwrap()) {
+            Some(Value::NumberLit(lit)) => assert_eq!(parse_number_literal(lit.raw_text()).unwrap(), JsonNumber::Float("3.14".parse().unwrap())),
+            _ => unreachable!(),
+        }
+
+        let err = parse_text("3,14").err().unwrap();
+        assert_eq!((err.pos, err.message.as_str()), (1, "Text cannot contain more than one JSON value."));
+    }
+
+    #[test]
+    fn it_parses_bytes_in_each_encoding() {
+        let text = "\u{feff}{ \"\u{e9}\u{1F600}\": [1, true] }";
+        let utf16 = text.encode_utf16().collect::<Vec<_>>();
+        let utf16le = utf16.iter().flat_map(|code_unit| code_unit.to_le_bytes().to_vec()).collect::<Vec<_>>();
+        let utf16be = utf16.iter().flat_map(|code_unit| code_unit.to_be_bytes().to_vec()).collect::<Vec<_>>();
+        let cases = [
+            (text.as_bytes(), TextEncoding::Utf8),
+            (&text.as_bytes()[3..], TextEncoding::Utf8),
+            (&utf16le[..], TextEncoding::Utf16Le),
+            (&utf16le[2..], TextEncoding::Utf16Le),
+            (&utf16be[..], TextEncoding::Utf16Be),
+            (&utf16be[2..], TextEncoding::Utf16Be),
+        ];
+        for (bytes, encoding) in cases.iter() {
+            let result = parse_bytes(bytes, &ParseOptions::default()).unwrap();
+            assert_eq!(result.encoding, Some(*encoding));
+            let value = result.value.unwrap();
+            assert_eq!(value.range().end, 19);
+            assert_eq!(value.into_value().to_string(), "{\"\u{e9}\u{1F600}\":[1,true]}");
+        }
+
+        // error positions are in the decoded text
+        let utf16le = "[\"\u{1F600}\", tru]".encode_utf16().flat_map(|code_unit| code_unit.to_le_bytes().to_vec()).collect::<Vec<_>>();
+        let err = parse_bytes(&utf16le, &ParseOptions::default()).err().unwrap();
+        assert_eq!((err.pos, err.column), (6, 6));
+    }
+
+    #[test]
+    fn it_errors_decoding_invalid_bytes() {
+        let invalid = [&[b'[', 0xFF, b']'][..], &[0xFF, 0xFE, b'['][..], &[0xFF, 0xFE, 0x00, 0xD8][..], &[b'[', 0, 0x00, 0xDC][..]];
+        for bytes in invalid.iter() {
+            assert_eq!(decode_bytes(bytes).err().unwrap().kind(), std::io::ErrorKind::InvalidData);
+            assert_eq!(parse_bytes(bytes, &ParseOptions::default()).err().unwrap().kind, ErrorKind::Io(std::io::ErrorKind::InvalidData));
+        }
+        assert_eq!(decode_bytes(b"").unwrap(), (String::new(), TextEncoding::Utf8));
+        assert_eq!(decode_bytes(b"1").unwrap(), (String::from("1"), TextEncoding::Utf8));
+    }
+
+    #[test]
+    fn it_stores_identical_errors_once_when_parsing_tolerantly() {
+        let mut context = create_context(Scanner::new("[1]"), true);
+        let range = |start: usize, end: usize| Range { start, end, start_line: 0, end_line: 0 };
+        for (start, end, message) in [(1, 2, "a"), (2, 3, "b"), (1, 2, "a"), (1, 3, "a")].iter() {
+            context.store_error(range(*start, *end), ParseError::new(*start, message)).unwrap();
+        }
+        // errors for different containers are kept even when they're at the same range
+        let unclosed = |opened_at: usize| ParseError::new_unclosed(3, range(opened_at, opened_at + 1), "[", "']'");
+        context.store_error(range(3, 3), unclosed(0)).unwrap();
+        context.store_error(range(2, 3), ParseError::new(2, "c")).unwrap();
+        context.store_error(range(3, 3), unclosed(1)).unwrap();
+        context.store_error(range(3, 3), unclosed(0)).unwrap();
+        let errors = context.errors.unwrap().into_iter().map(|err| (err.range.start, err.range.end, err.message)).collect::<Vec<_>>();
+        let unclosed_message = String::from("Unexpected end of file; '[' opened on line 1 was never closed");
+        assert_eq!(errors, vec![
+            (1, 2, String::from("a")),
+            (2, 3, String::from("b")),
+            (1, 3, String::from("a")),
+            (3, 3, unclosed_message.clone()),
+            (2, 3, String::from("c")),
+            (3, 3, unclosed_message),
+        ]);
+    }
+
+    #[test]
+    fn it_parses_deeply_nested_arrays() {
+        let depth = 5000;
+        let text = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let parse_result = parse_text(&text).expect("Expected no error.");
+        let mut value = parse_result.value.expect("Expected a value.");
+        let mut found_depth = 1;
+
+        loop {
+            match value {
+                Value::Array(mut array) => match array.elements.pop() {
+                    Some(element) => {
+                        found_depth += 1;
+                        value = element;
+                    },
+                    None => break,
+                },
+                _ => panic!("Expected an array."),
+            }
+        }
+
+        assert_eq!(found_depth, depth);
+    }
+
+    #[test]
+    fn it_parses_deeply_nested_objects() {
+        let depth = 5000;
+        let text = format!("{}1{}", r#"{"a":"#.repeat(depth), "}".repeat(depth));
+        let parse_result = parse_text(&text).expect("Expected no error.");
+        let mut value = parse_result.value.expect("Expected a value.");
+        let mut found_depth = 0;
+
+        loop {
+            match value {
+                Value::Object(mut obj) => {
+                    found_depth += 1;
+                    value = obj.properties.pop().expect("Expected a property.").value;
+                },
+                Value::NumberLit(_) => break,
+                _ => panic!("Expected an object."),
+            }
+        }
+
+        assert_eq!(found_depth, depth);
+    }
+
+    #[test]
+    fn it_errors_on_overlapping_tokens() {
+        let tokens = vec![
+            TokenAndRange {
+                token: Token::OpenBracket,
+                range: Range { start: 0, end: 1, start_line: 0, end_line: 0 },
+            },
+            TokenAndRange {
+                token: Token::CloseBracket,
+                range: Range { start: 0, end: 1, start_line: 0, end_line: 0 },
+            },
+        ];
+        let err = parse_tokens_to_ast(&tokens, "[]").err().expect("Expected an error.");
+        assert_eq!(err.message, "Token stream is not well formed.");
+    }
+
+    #[test]
+    fn it_parses_tokens_like_text_with_options() {
+        let texts = ["[1 2]", "{ \"a\": 1, \"a\": { \"b\": 2 } } // c", "[1, 2]", "// a\n", "{ \"a\" }"];
+        let all_options = [
+            ParseOptions::default().with_source_name("file.json").with_require_commas(true),
+            ParseOptions::default().with_root_kind(RootKind::Object).with_collect_metrics(true),
+            ParseOptions::default().with_require_value(true).with_fold_comment_lines(true),
+            ParseOptions::default().with_duplicate_key_policy(DuplicateKeyPolicy::Merge).with_key_transform(|key| key.to_uppercase()),
+            ParseOptions::default().with_missing_value(MissingValue::Null),
+        ];
+        for text in texts.iter() {
+            let mut scanner = Scanner::new(*text);
+            let mut tokens = Vec::new();
+            while let Some(token) = scanner.scan().unwrap() {
+                let range = Range {
+                    start: scanner.token_start(),
+                    end: scanner.token_end(),
+                    start_line: scanner.token_start_line(),
+                    end_line: scanner.token_end_line(),
+                };
+                tokens.push(TokenAndRange { token, range });
+            }
+
+            for options in all_options.iter() {
+                let expected = parse_text_with_options(text, options);
+                let result = parse_tokens_to_ast_with_options(&tokens, text, options);
+                match (result, expected) {
+                    (Ok(result), Ok(expected)) => {
+                        assert_eq!(result.value.map(|value| value.into_value()), expected.value.map(|value| value.into_value()), "Text: {}", text);
+                        assert_eq!(result.comments.len(), expected.comments.len(), "Text: {}", text);
+                        assert_eq!(result.metrics.map(|metrics| metrics.tokens), expected.metrics.map(|metrics| metrics.tokens), "Text: {}", text);
+                    },
+                    (result, expected) => assert_eq!(result.err(), expected.err(), "Text: {}", text),
+                }
+                assert_eq!(parse_tokens_to_value_with_options(&tokens, text, options), parse_to_value_with_options(text, options), "Text: {}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn it_errors_at_end_of_text_for_incomplete_tokens() {
+        let tokens = vec![TokenAndRange {
+            token: Token::OpenBracket,
+            range: Range { start: 0, end: 1, start_line: 0, end_line: 0 },
+        }];
+        let err = parse_tokens_to_ast(&tokens, "[\n").err().expect("Expected an error.");
+        assert_eq!(err.pos, 2);
+        assert_eq!(err.message, "Unexpected end of file; '[' opened on line 1 was never closed");
+    }
+
+    #[test]
+    fn it_errors_on_missing_property_value() {
+        let err = parse_text(r#"{ "a": "#).err().expect("Expected an error.");
+        assert_eq!(err.message, "Expected value after colon in object property.");
+        assert_eq!(err.kind, ErrorKind::UnexpectedEof {
+            expected: String::from("a value"),
+            opened_at: None,
+            insertion_point: None,
+        });
+    }
+
+    #[test]
+    fn it_errors_on_unclosed_containers_at_end_of_text() {
+        let err = parse_text("[\n  1,\n  {\n    \"a\": 1").err().expect("Expected an error.");
+        assert_eq!(err.pos, 21);
+        assert_eq!(err.message, "Unexpected end of file; '{' opened on line 3 was never closed");
+        assert_eq!(err.kind, ErrorKind::UnexpectedEof {
+            expected: String::from("'}'"),
+            opened_at: Some(Box::new(Range { start: 9, end: 10, start_line: 2, end_line: 2 })),
+            insertion_point: Some(21),
+        });
+
+        let err = parse_text("{\n  \"a\": [1, 2").err().expect("Expected an error.");
+        assert_eq!(err.message, "Unexpected end of file; '[' opened on line 2 was never closed");
+        assert_eq!(err.kind, ErrorKind::UnexpectedEof {
+            expected: String::from("']'"),
+            opened_at: Some(Box::new(Range { start: 9, end: 10, start_line: 1, end_line: 1 })),
+            insertion_point: Some(14),
+        });
+    }
+
+    #[test]
+    fn it_uses_other_error_kind_before_end_of_text() {
+        let err = parse_text("[1 }").err().expect("Expected an error.");
+        assert_eq!(err.kind, ErrorKind::Other);
+    }
+
+    #[test]
+    fn it_formats_errors_with_source_name() {
+        let options = ParseOptions::default().with_source_name("tsconfig.json");
+        let err = parse_text_with_options("{\n  \"a\": 1,\n  \"b\" 2\n}", &options).err().expect("Expected an error.");
+        assert_eq!((err.pos, err.line, err.column), (18, 2, 6));
+        assert_eq!(err.to_string(), "tsconfig.json:3:7: Expected a colon after the string in an object property.");
+    }
+
+    #[test]
+    fn it_formats_errors_without_source_name() {
+        let err = parse_text("{\n  \"a\" 1}").err().expect("Expected an error.");
+        assert_eq!(err.source_name, None);
+        assert_eq!(err.to_string(), "2:7: Expected a colon after the string in an object property.");
+    }
+
+    #[test]
+    fn it_parses_a_preview_of_the_root_array() {
+        let elements = (0..100).map(|i| if i % 2 == 0 { i.to_string() } else { format!("[{}]", i) }).collect::<Vec<_>>();
+        let text = format!("[{}]", elements.join(", "));
+        let (value, is_truncated) = parse_preview(&text, 10).unwrap();
+        assert!(is_truncated);
+        let arr = match value {
+            Value::Array(arr) => arr,
+            _ => unreachable!(),
+        };
+        assert_eq!(arr.elements.len(), 10);
+        assert_eq!(arr.range.end, arr.elements[9].range().end);
+    }
+
+    #[test]
+    fn it_parses_a_preview_of_the_root_object() {
+        let text = r#"{ "a": { "b": [1] }, /* c */ "c": "d", "e": 5 }"#;
+        let (value, is_truncated) = parse_preview(text, 2).unwrap();
+        assert!(is_truncated);
+        let obj = match value {
+            Value::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        assert_eq!(obj.properties.iter().map(|prop| prop.name.value.as_ref()).collect::<Vec<_>>(), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn it_parses_a_full_preview_when_within_max_items() {
+        let (value, is_truncated) = parse_preview("[1, 2, 3,]", 3).unwrap();
+        assert!(!is_truncated);
+        assert_eq!(value, parse_text("[1, 2, 3,]").unwrap().value.unwrap());
+        let (_, is_truncated) = parse_preview("5", 0).unwrap();
+        assert!(!is_truncated);
+    }
+
+    #[test]
+    fn it_collects_metrics() {
+        let text = "{\n  // comment\n  \"a\": [1, { \"b\": [] }],\n  /* c */ \"d\": \"\u{e9}\"\n}";
+        let mut scanner = Scanner::new(text);
+        let mut token_count = 0;
+        while scanner.scan().unwrap().is_some() {
+            token_count += 1;
+        }
+        assert_eq!(scanner.tokens_scanned(), token_count);
+
+        let options = ParseOptions::default().with_collect_metrics(true);
+        let parse_result = parse_text_with_options(text, &options).unwrap();
+        let metrics = parse_result.metrics.unwrap();
+        assert_eq!(metrics.tokens, token_count);
+        assert_eq!(metrics.tokens, parse_result.tokens.len() + 2);
+        assert_eq!(metrics.bytes, text.len());
+        assert_eq!(metrics.max_depth_seen, 4);
+        assert!(metrics.duration.is_some());
+
+        assert!(parse_text_with_options(text, &ParseOptions::default()).unwrap().metrics.is_none());
+    }
+
+    #[test]
+    fn it_errors_for_a_comment_without_a_value_when_parsing_to_a_value() {
+        let err = parse_to_value("// just a comment").err().unwrap();
+        assert_eq!(err.to_string(), "1:18: Expected a value.");
+        let options = ParseOptions::default().with_source_name("a.jsonc");
+        assert_eq!(parse_to_value_with_options("// just a comment", &options).err().unwrap().to_string(), "a.jsonc:1:18: Expected a value.");
+        let options = ParseOptions::default().with_missing_value(MissingValue::Null);
+        assert_eq!(parse_to_value_with_options("// just a comment", &options).unwrap(), Some(JsonValue::Null));
+        // a value is still returned as is
+        assert_eq!(parse_to_value_with_options("// a\n[]", &options).unwrap(), Some(JsonValue::Array(Default::default())));
+    }
+
+    #[test]
+    fn it_handles_text_without_a_value() {
+        let texts = ["", "  \n\t", "// license\n/* header */\n"];
+        for text in texts.iter() {
+            let parse_result = parse_text(text).unwrap();
+            assert!(parse_result.value.is_none());
+            assert!(parse_result.tokens.is_empty());
+            assert!(parse_text_with_options(text, &ParseOptions::default()).unwrap().value.is_none());
+
+            let err = parse_to_value(text).err().unwrap();
+            assert_eq!(err.message, "Expected a value.");
+            assert_eq!(err.pos, text.chars().count());
+            let options = ParseOptions::default().with_missing_value(MissingValue::Null);
+            assert_eq!(parse_to_value_with_options(text, &options).unwrap(), Some(JsonValue::Null));
+            let options = ParseOptions::default().with_missing_value(MissingValue::None);
+            assert_eq!(parse_to_value_with_options(text, &options).unwrap(), None);
+
+            let options = ParseOptions::default().with_require_value(true);
+            let err = parse_text_with_options(text, &options).err().unwrap();
+            assert_eq!(err.message, "Expected a value.");
+            assert_eq!(err.pos, text.chars().count());
+            assert_eq!(err.kind, ErrorKind::UnexpectedEof { expected: String::from("a value"), opened_at: None, insertion_point: None });
+
+            for root_kind in [RootKind::Object, RootKind::Array].iter() {
+                let options = ParseOptions::default().with_root_kind(*root_kind);
+                assert_eq!(parse_text_with_options(text, &options).err().unwrap().message, "Expected a value.");
+            }
+        }
+
+        let parse_result = parse_text("// license\n/* header */\n").unwrap();
+        assert_eq!(parse_result.comments.get(&0).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn it_errors_when_the_root_is_not_the_required_kind() {
+        let object_options = ParseOptions::default().with_root_kind(RootKind::Object);
+        let array_options = ParseOptions::default().with_root_kind(RootKind::Array);
+        assert!(parse_text_with_options("{}", &object_options).is_ok());
+        assert!(parse_text_with_options("[]", &array_options).is_ok());
+
+        let err = parse_text_with_options("// a\n  [1]", &object_options).err().unwrap();
+        assert_eq!(err.to_string(), "2:3: Expected an object.");
+        assert_eq!(err.kind, ErrorKind::Other);
+        let err = parse_text_with_options("5", &array_options).err().unwrap();
+        assert_eq!(err.to_string(), "1:1: Expected an array.");
+    }
+
+    #[test]
+    fn it_errors_on_missing_commas_when_required() {
+        let options = ParseOptions::default().with_require_commas(true);
+        let err = parse_text_with_options("[1 2]", &options).err().unwrap();
+        assert_eq!((err.pos, err.message.as_str()), (3, "Expected ',' or ']'."));
+        let err = parse_text_with_options(r#"{"a":1 "b":2}"#, &options).err().unwrap();
+        assert_eq!((err.pos, err.message.as_str()), (7, "Expected ',' or '}'."));
+        let err = parse_text_with_options("[[1]\n  {}]", &options).err().unwrap();
+        assert_eq!(err.to_string(), "2:3: Expected ',' or ']'.");
+
+        assert!(parse_text_with_options(r#"[1, 2, { "a": [], "b": {} }]"#, &options).is_ok());
+        assert!(parse_text_with_options("[1 2]", &ParseOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn it_provides_insertion_points_for_missing_separators_and_closing_tokens() {
+        let options = ParseOptions::default().with_require_commas(true);
+        let cases = [
+            ("[1 /* a */ 2]", ErrorKind::MissingComma { insertion_point: 2 }),
+            ("{ \"a\": [1]\n  \"b\": 2 }", ErrorKind::MissingComma { insertion_point: 10 }),
+            ("{ \"a\" // a\n 1 }", ErrorKind::MissingColon { insertion_point: 5 }),
+        ];
+        for (text, kind) in cases.iter() {
+            assert_eq!(&parse_text_with_options(text, &options).err().unwrap().kind, kind, "Text: {}", text);
+            assert_eq!(&validate_fast(text, &options).unwrap_err().kind, kind, "Text: {}", text);
+        }
+
+        let cases = [("[[1, 2] ", 7), ("{ \"a\": [1, { \"b\": 2 } // c\n", 21), ("{ \"a\"  ", 5), ("[", 1)];
+        for (text, insertion_point) in cases.iter() {
+            assert_eq!(parse_text(text).err().unwrap().insertion_point(), Some(*insertion_point), "Text: {}", text);
+            assert_eq!(validate_fast(text, &ParseOptions::default()).unwrap_err().insertion_point(), Some(*insertion_point), "Text: {}", text);
+        }
+
+        assert_eq!(parse_text("[1, }").err().unwrap().insertion_point(), None);
+    }
+
+    #[test]
+    fn it_compares_options() {
+        assert_eq!(ParseOptions::default(), ParseOptions::default());
+        assert_ne!(ParseOptions::default(), ParseOptions::default().with_require_commas(true));
+        assert_ne!(ParseOptions::default(), ParseOptions::default().with_missing_value(MissingValue::Null));
+        let options = ParseOptions::default().with_key_transform(|key| key.to_uppercase());
+        assert_ne!(options, ParseOptions::default());
+        assert_eq!(options, ParseOptions::default().with_key_transform(|key| key.to_lowercase()));
+    }
+
+    #[test]
+    fn it_validates_the_same_as_parsing_with_representative_options() {        let texts = [
+            "", " ", "// a", "/* a */", "/* a", "1", "-1.5e3", "01", "1.", "-", ".5", "-.5", "1,5", "0x1F", "0x", "1e", "true false",
+            "null", "\"a\"", "'a'", "\"a", "\"\\q\"", "\"\\ud800\"", "{}", "[]", "{", "[", "}", "]", ",", ":",
+            "{ \"a\": 1 }", "{ 'a': 1 }", "{ \"a\" = 1 }", "{ \"a\" }", "{ \"a\": }", "{ \"a\": 1, }", "{ \"a\": 1 \"b\": 2 }",
+            "{ a: 1 }", "{ \"a\": 1, \"a\": { \"b\": [1] } }", "{,}", "[,]", "[1,,2]", "[1, 2,]", "[1 2]", "[1, 2", "[[1, 2] ",
+            "{ \"a\": [1, { \"b\": 2 } // c\n", "[1, 2] 3", "[1] // a\n/* b */", "[1, { \"a\": [0x1F, .5, 'b'] }]", "{ \"a\": 1 } }",
+        ];
+        let scanner = ScannerOptions::default;
+        let dialect = NumberDialect::default;
+        let all_dialect = dialect().with_hexadecimal(true).with_leading_decimal_point(true).with_comma_decimal_separator(true);
+        // each option on its own, then the pairs that interact
+        let scanner_options = [
+            scanner(),
+            scanner().with_comments(false),
+            scanner().with_single_quotes(true),
+            scanner().with_strict_numbers(true),
+            scanner().with_tolerate_invalid_escapes(true),
+            scanner().with_equals_separator(true),
+            scanner().with_number_dialect(dialect().with_hexadecimal(true)),
+            scanner().with_number_dialect(dialect().with_leading_decimal_point(true)),
+            scanner().with_number_dialect(dialect().with_comma_decimal_separator(true)),
+            scanner().with_number_dialect(all_dialect.clone()),
+            scanner().with_strict_numbers(true).with_number_dialect(all_dialect.clone()),
+            scanner().with_comments(false).with_single_quotes(true).with_equals_separator(true),
+            scanner().with_single_quotes(true).with_tolerate_invalid_escapes(true),
+        ];
+        let options = ParseOptions::default;
+        let mut parse_options = scanner_options.iter().map(|scanner_options| options().with_scanner_options(scanner_options.clone())).collect::<Vec<_>>();
+        parse_options.extend([
+            options().with_require_value(true),
+            options().with_root_kind(RootKind::Object),
+            options().with_root_kind(RootKind::Array),
+            options().with_require_commas(true),
+            options().with_require_value(true).with_root_kind(RootKind::Array),
+            options().with_require_commas(true).with_scanner_options(scanner().with_number_dialect(dialect().with_comma_decimal_separator(true))),
+            options().with_require_value(true).with_scanner_options(scanner().with_comments(false)),
+        ]);
+
+        let assert_same = |options: &ParseOptions| {
+            for text in texts.iter() {
+                let expected = parse_text_with_options(text, options).err();
+                assert_eq!(validate_fast(text, options).err(), expected, "Text: {:?}\nOptions: {:?}", text, options);
+            }
+        };
+        for options in parse_options.iter() {
+            assert_same(options);
+        }
+
+        let bools = [false, true];
+        // these options only change the values created from the AST, so they shouldn't change either result
+        for &fold_comment_lines in bools.iter() {
+            for &duplicate_key_policy in [DuplicateKeyPolicy::Replace, DuplicateKeyPolicy::Merge].iter() {
+                for &lone_surrogates in [LoneSurrogates::ReplaceWithReplacementChar, LoneSurrogates::Error, LoneSurrogates::PreserveAsWtf8].iter() {
+                    for &missing_value in [MissingValue::Error, MissingValue::Null, MissingValue::None].iter() {
+                        let options = ParseOptions::default()
+                            .with_source_name("file.json")
+                            .with_collect_metrics(true)
+                            .with_key_transform(|key| key.to_uppercase())
+                            .with_fold_comment_lines(fold_comment_lines)
+                            .with_duplicate_key_policy(duplicate_key_policy)
+                            .with_lone_surrogates(lone_surrogates)
+                            .with_missing_value(missing_value);
+                        assert_same(&options);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_folds_consecutive_comment_lines() {
+        let text = "{\n  // first\n  //second\n  //   third\n\n  // after blank\n  /* block */\n  // after block\n  \"a\": 1 // trailing\n}";
+        let options = ParseOptions::default().with_fold_comment_lines(true);
+        let result = parse_text_with_options(text, &options).unwrap();
+        let comments = result.comments.get(&1).unwrap();
+        let summaries = comments.iter().map(|comment| {
+            let range = match comment {
+                Comment::Line(line) => &line.range,
+                Comment::Block(block) => &block.range,
+            };
+            (range.start, range.end, range.start_line, range.end_line, comment.content())
+        }).collect::<Vec<_>>();
+        assert_eq!(summaries, vec![
+            (4, 36, 1, 3, String::from("first\nsecond\n  third")),
+            (40, 54, 5, 5, String::from("after blank")),
+            (57, 68, 6, 6, String::from("block")),
+            (71, 85, 7, 7, String::from("after block")),
+        ]);
+        assert_eq!(comments[0].raw_text(), "// first\n  //second\n  //   third");
+        // the collection is still shared with the next token
+        assert!(std::rc::Rc::ptr_eq(comments, result.comments.get(&88).unwrap()));
+
+        let result = parse_text_with_options(text, &ParseOptions::default()).unwrap();
+        assert_eq!(result.comments.get(&1).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn it_transforms_keys_when_parsing_to_a_value() {
+        fn to_snake_case(name: &str) -> String {
+            let mut result = String::new();
+            for c in name.chars() {
+                if c.is_uppercase() {
+                    result.push('_');
+                    result.extend(c.to_lowercase());
+                } else {
+                    result.push(c);
+                }
+            }
+            result
+        }
+
+        let options = ParseOptions::default().with_key_transform(to_snake_case);
+        let value = parse_to_value_with_options(r#"{ "myField": 1, "nested": { "otherField": [{ "deepValue": true }] }, "m\u0079Name": "keepsCase" }"#, &options).unwrap().unwrap();
+        let obj = match &value {
+            JsonValue::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        assert_eq!(obj.get("my_field").unwrap().to_string(), "1");
+        assert!(obj.get("myField").is_none());
+        assert_eq!(obj.get("nested").unwrap().to_string(), r#"{"other_field":[{"deep_value":true}]}"#);
+        assert_eq!(obj.get("my_name").unwrap().to_string(), r#""keepsCase""#);
+    }
+
+    #[test]
+    fn it_parses_equals_separators_when_enabled() {
+        let text = r#"{ "a" = 1, "b": { "c" = [true] } }"#;
+        let options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_equals_separator(true));
+        let value = parse_to_value_with_options(text, &options).unwrap().unwrap();
+        assert_eq!(value.to_string(), r#"{"a":1,"b":{"c":[true]}}"#);
+
+        let err = parse_text(r#"{ "a" = 1 }"#).err().unwrap();
+        assert_eq!(err.to_string(), "1:7: Unexpected token.");
+        // only as a separator
+        let err = parse_text_with_options(r#"{ "a": = }"#, &options).err().unwrap();
+        assert_eq!(err.to_string(), "1:8: Unexpected colon.");
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn it_normalizes_strings_with_the_form() {
+        use super::StringNormalization;
+
+        let composed = parse_to_value_with_options("{ \"caf\u{e9}\": \"caf\u{e9}\" }", &ParseOptions::default()).unwrap().unwrap();
+        let decomposed_text = "{ \"cafe\u{301}\": \"cafe\\u0301\" }";
+        assert_ne!(parse_to_value_with_options(decomposed_text, &ParseOptions::default()).unwrap().unwrap(), composed);
+
+        let options = ParseOptions::default().with_string_normalization(StringNormalization::Nfc);
+        assert_eq!(parse_to_value_with_options(decomposed_text, &options).unwrap().unwrap(), composed);
+        assert_ne!(options, ParseOptions::default());
+
+        let options = ParseOptions::default().with_string_normalization(StringNormalization::Nfd);
+        let value = parse_to_value_with_options("[\"caf\u{e9}\"]", &options).unwrap().unwrap();
+        assert_eq!(value.to_string(), "[\"cafe\u{301}\"]");
+    }
+
+    #[test]
+    fn it_handles_lone_surrogates_with_the_policy() {
+        let text = r#"{ "a": "x\ud800", "b\udc00": 1, "c": "\ud800\ud83d\ude00" }"#;
+        let value = parse_to_value_with_options(text, &ParseOptions::default()).unwrap().unwrap();
+        assert_eq!(value.to_string(), "{\"a\":\"x\u{FFFD}\",\"b\u{FFFD}\":1,\"c\":\"\u{FFFD}\u{1F600}\"}");
+
+        let options = ParseOptions::default().with_lone_surrogates(LoneSurrogates::Error).with_source_name("a.json");
+        let err = parse_to_value_with_options(text, &options).err().unwrap();
+        assert_eq!(err.to_string(), "a.json:1:10: Lone surrogates are not allowed in strings.");
+        let err = parse_to_value_with_options(r#"{ "a": "\ud83d\ude00", "b\udc00": "\ud800" }"#, &options).err().unwrap();
+        assert_eq!(err.pos, 25);
+        let err = parse_to_value_with_options(r#"["\ud800\ud83d\ude00"]"#, &options).err().unwrap();
+        assert_eq!(err.pos, 2);
+        assert!(parse_to_value_with_options(r#"["\ud83d\ude00A"]"#, &options).is_ok());
+        let err = extract_matching_with_options(text, &options, |path| path.len() == 1).err().unwrap();
+        assert_eq!(err.to_string(), "a.json:1:10: Lone surrogates are not allowed in strings.");
+        let err = extract_matching_with_options(r#"{ "a": "\ud83d\ude00", "b\udc00": "\ud800" }"#, &options, |_| false).err().unwrap();
+        assert_eq!(err.pos, 25);
+
+        // strings with lone surrogates are kept as WTF-8, while property names are replaced
+        let options = ParseOptions::default().with_lone_surrogates(LoneSurrogates::PreserveAsWtf8);
+        let value = parse_to_value_with_options(text, &options).unwrap().unwrap();
+        let obj = match &value {
+            JsonValue::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        match obj.get("a") {
+            Some(JsonValue::Wtf8String(value)) => assert_eq!((value.as_str(), value.as_bytes()), (None, &b"x\xED\xA0\x80"[..])),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(obj.get("b\u{FFFD}"), Some(&JsonValue::Number(String::from("1"))));
+        match obj.get("c") {
+            Some(JsonValue::Wtf8String(value)) => assert_eq!(value.as_bytes(), &[0xED, 0xA0, 0x80, 0xF0, 0x9F, 0x98, 0x80]),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(value.to_string(), "{\"a\":\"x\\ud800\",\"b\u{FFFD}\":1,\"c\":\"\\ud800\u{1F600}\"}");
+        let value = parse_to_value_with_options(r#"["\ud83d\ude00A"]"#, &options).unwrap().unwrap();
+        assert_eq!(value, JsonValue::Array(vec![JsonValue::String(String::from("\u{1F600}A"))].into()));
+
+        // the AST keeps the escapes, so they can be decoded without losing the surrogates
+        let result = parse_text(r#"["\ud800\ud83d\ude00"]"#).unwrap();
+        let lit = match result.value.as_ref().unwrap() {
+            Value::Array(arr) => match &arr.elements[0] {
+                Value::StringLit(lit) => lit,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        let decoded = decode_string_literal_wtf8(lit.value.as_ref(), lit.quote).unwrap();
+        assert_eq!(decoded.as_str(), None);
+        assert_eq!(decoded.as_bytes(), &[0xED, 0xA0, 0x80, 0xF0, 0x9F, 0x98, 0x80]);
+    }
+
+    #[test]
+    fn it_verifies_ranges() {
+        let text = "{\n  // a\n  \"b\": [1, /* c */ 2]\n}";
+        let result = parse_text(text).unwrap();
+        assert_eq!(verify_ranges(&result, text), Ok(()));
+
+        let mut changed = parse_text(text).unwrap();
+        changed.tokens[1].range.start_line = 0;
+        assert_eq!(verify_ranges(&changed, text).err().unwrap().message, "The lines of the token at 11..14 don't match its positions.");
+
+        let mut changed = parse_text(text).unwrap();
+        changed.tokens.last_mut().unwrap().range.end = 100;
+        assert_eq!(verify_ranges(&changed, text).err().unwrap().message, "The token at 31..100 is not within the text.");
+
+        let mut changed = parse_text(text).unwrap();
+        changed.tokens[5].range.end = 21;
+        assert_eq!(verify_ranges(&changed, text).err().unwrap().message, "The token at 18..21 overlaps the next comment.");
+
+        let mut changed = parse_text(text).unwrap();
+        match changed.value.as_mut().unwrap() {
+            Value::Object(obj) => obj.range.end = 20,
+            _ => unreachable!(),
+        }
+        assert_eq!(verify_ranges(&changed, text).err().unwrap().message, "The lines of the value at 0..20 don't match its positions.");
+    }
+
+    #[test]
+    fn it_merges_duplicate_keys_when_parsing_to_a_value() {
+        let text = r#"{
+  "compilerOptions": { "strict": true, "paths": { "a": ["x"] } },
+  "exclude": ["node_modules"],
+  "target": "es5",
+  "compilerOptions": { "noEmit": true, "paths": { "a": ["y"], "b": ["z"] } },
+  "exclude": ["dist"],
+  "target": "es2020",
+  "mixed": [1],
+  "mixed": { "a": 1 }
+}"#;
+        let options = ParseOptions::default().with_duplicate_key_policy(DuplicateKeyPolicy::Merge);
+        let value = parse_to_value_with_options(text, &options).unwrap().unwrap();
+        assert_eq!(
+            value.to_string(),
+            r#"{"compilerOptions":{"strict":true,"paths":{"a":["x","y"],"b":["z"]},"noEmit":true},"exclude":["node_modules","dist"],"target":"es2020","mixed":{"a":1}}"#
+        );
+
+        let value = parse_to_value_with_options(text, &ParseOptions::default()).unwrap().unwrap();
+        assert_eq!(value.get_path(&"exclude".parse().unwrap()).unwrap().to_string(), r#"["dist"]"#);
+
+        // the AST keeps every property and the duplicates are still reported
+        let result = parse_text_with_options(text, &options).unwrap();
+        match result.value.unwrap() {
+            Value::Object(obj) => assert_eq!(obj.properties.len(), 8),
+            _ => unreachable!(),
+        }
+        let result = super::super::diagnostics::parse_with_diagnostics(text, &super::super::diagnostics::DiagnosticOptions::default());
+        let messages = result.warnings.iter().map(|warning| warning.message.as_str()).collect::<Vec<_>>();
+        assert_eq!(messages, vec![
+            "Duplicate property name \"compilerOptions\".",
+            "Duplicate property name \"exclude\".",
+            "Duplicate property name \"target\".",
+            "Duplicate property name \"mixed\".",
+        ]);
+    }
+
+    #[test]
+    fn it_merges_deeply_nested_duplicate_keys() {
+        let depth = 50_000;
+        let nested = |name: &str| format!("{}{{\"{}\":1}}{}", "{\"a\":".repeat(depth), name, "}".repeat(depth));
+        let text = format!("{{\"x\":{},\"x\":{}}}", nested("b"), nested("c"));
+        let options = ParseOptions::default().with_duplicate_key_policy(DuplicateKeyPolicy::Merge);
+        let value = parse_to_value_with_options(&text, &options).unwrap().unwrap();
+        let expected = format!("{{\"x\":{}{{\"b\":1,\"c\":1}}{}}}", "{\"a\":".repeat(depth), "}".repeat(depth));
+        assert_eq!(value.to_string(), expected);
+    }
+
+    #[test]
+    fn it_extracts_values_matching_a_path() {
+        let text = r#"{
+  "features": {
+    // comment
+    "a": { "enabled": true },
+    "b": [1, 2],
+    "c\u0021": null
+  },
+  "other": { "features": { "d": 1 } },
+  "list": [{ "features": 1 }, { "x": 2 }]
+}"#;
+        let matches = extract_matching(text, |path| path.len() == 2 && path[0] == "features").unwrap();
+        let results = matches.into_iter().map(|(path, value)| (path.join("/"), value.range().start_line, value.into_value().to_string())).collect::<Vec<_>>();
+        assert_eq!(results, vec![
+            (String::from("features/a"), 3, String::from(r#"{"enabled":true}"#)),
+            (String::from("features/b"), 4, String::from("[1,2]")),
+            (String::from("features/c!"), 5, String::from("null")),
+        ]);
+
+        let matches = extract_matching(text, |path| path.last().map(|name| name == "features").unwrap_or(false)).unwrap();
+        let paths = matches.iter().map(|(path, _)| path.join("/")).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["features", "other/features", "list/0/features"]);
+
+        let matches = extract_matching("[1, [2, 3]]", |path| path == ["1", "0"]).unwrap();
+        assert_eq!(matches[0].1.range().start, 5);
+        assert_eq!(extract_matching(text, |path| path.is_empty()).unwrap().len(), 1);
+
+        // ranges are character positions in the whole text
+        let text = "{ \"\u{e9}\": [\"\u{1F600}\"],\n  \"b\": { \"c\": \"\u{fc}\" } }";
+        let matches = extract_matching(text, |path| path.len() == 1).unwrap();
+        let values = matches.into_iter().map(|(_, value)| (value.range().start, value.range().start_line, value.into_value().to_string())).collect::<Vec<_>>();
+        assert_eq!(values, vec![
+            (7, 0, String::from("[\"\u{1F600}\"]")),
+            (21, 1, String::from("{\"c\":\"\u{fc}\"}")),
+        ]);
+    }
+
+    #[test]
+    fn it_errors_extracting_from_invalid_text() {
+        assert_eq!(extract_matching("{ 1: 2 }", |_| false).err().unwrap().message, "Unexpected token in object literal.");
+        assert_eq!(extract_matching("[1}", |_| false).err().unwrap().message, "Unexpected close brace.");
+        assert_eq!(extract_matching("[1] 2", |_| false).err().unwrap().message, "Text cannot contain more than one JSON value.");
+        let err = extract_matching("{ \"a\": [", |_| false).err().unwrap();
+        assert_eq!(err.message, "Unexpected end of file; '[' opened on line 1 was never closed");
+        let err = extract_matching("{ \"a\": [", |path| path.len() == 1).err().unwrap();
+        assert_eq!(err.message, "Unexpected end of file; '[' opened on line 1 was never closed");
+
+        // the same as the parser
+        for text in ["{\"a\" 1}", "{\"a\"}", "{\"a\": 1 \"b\" 2}"].iter() {
+            let expected = parse_text(text).err().unwrap();
+            assert_eq!(extract_matching(text, |_| false).err().unwrap(), expected, "{}", text);
+            assert_eq!(extract_matching(text, |path| path.len() == 1).err().unwrap(), expected, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use super::common::ImmutableString;
+use super::errors::ParseError;
+use super::strings::decode_string_literal;
+
+/// A property name or array index in a `JsonPath`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PathSegment {
+    Key(ImmutableString),
+    Index(usize),
+}
+
+impl PathSegment {
+    /// Gets the array index the segment refers to, which is `None` for keys that aren't written like an index.
+    pub(crate) fn as_index(&self) -> Option<usize> {
+        match self {
+            PathSegment::Key(key) => parse_index(key.as_ref()),
+            PathSegment::Index(index) => Some(*index),
+        }
+    }
+
+    /// Gets the name of the object property the segment refers to.
+    pub(crate) fn as_key(&self) -> Cow<'_, str> {
+        match self {
+            PathSegment::Key(key) => Cow::Borrowed(key.as_ref()),
+            PathSegment::Index(index) => Cow::Owned(index.to_string()),
+        }
+    }
+}
+
+impl From<&str> for PathSegment {
+    fn from(name: &str) -> PathSegment {
+        PathSegment::Key(ImmutableString::new(String::from(name)))
+    }
+}
+
+impl From<usize> for PathSegment {
+    fn from(index: usize) -> PathSegment {
+        PathSegment::Index(index)
+    }
+}
+
+/// The path to a value in a document, which is empty for the root value.
+///
+/// Paths are parsed from either a JSON pointer (ex. `/a/0/b~1c`) or a dotted path
+/// (ex. `a[0]["b/c"]`) and are displayed as a JSON pointer.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::path::{JsonPath, PathSegment};
+///
+/// let path: JsonPath = r#"servers[0]["a/b"]"#.parse().unwrap();
+/// assert_eq!(path.to_string(), "/servers/0/a~1b");
+/// assert_eq!(path.parent().unwrap(), "/servers/0".parse().unwrap());
+/// assert_eq!(path.segments()[1], PathSegment::Index(0));
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct JsonPath(Vec<PathSegment>);
+
+impl JsonPath {
+    /// Creates the path of the root value.
+    pub fn new() -> JsonPath {
+        JsonPath::default()
+    }
+
+    /// Gets the segments of the path.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    /// Gets if this is the path of the root value.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the number of segments.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Adds a segment to the end of the path.
+    pub fn push(&mut self, segment: impl Into<PathSegment>) {
+        self.0.push(segment.into());
+    }
+
+    /// Removes the last segment of the path.
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.0.pop()
+    }
+
+    /// Gets the path of the containing value or `None` for the root value.
+    pub fn parent(&self) -> Option<JsonPath> {
+        self.0.split_last().map(|(_, parent)| JsonPath(parent.to_vec()))
+    }
+
+    /// Gets if the other path is this path or the path of one of its ancestors.
+    ///
+    /// Indexes and property names with the same text (ex. `0` and `"0"`) are the same, like
+    /// they are in a JSON pointer.
+    pub fn starts_with(&self, other: &JsonPath) -> bool {
+        other.0.len() <= self.0.len() && self.0.iter().zip(other.0.iter()).all(|(a, b)| is_same_segment(a, b))
+    }
+
+    /// Gets if the path is the same as the other path when written as a JSON pointer.
+    pub fn is_same_pointer(&self, other: &JsonPath) -> bool {
+        self.0.len() == other.0.len() && self.starts_with(other)
+    }
+
+    /// Gets if the path matches the pattern, where each `*` property name in the pattern matches
+    /// any one segment and the other segments are compared like `is_same_pointer`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::path::JsonPath;
+    ///
+    /// let pattern = "packages.*.config".parse::<JsonPath>().unwrap();
+    /// assert!("packages.a.config".parse::<JsonPath>().unwrap().matches(&pattern));
+    /// assert!("/packages/0/config".parse::<JsonPath>().unwrap().matches(&pattern));
+    /// assert!(!"packages.a.b.config".parse::<JsonPath>().unwrap().matches(&pattern));
+    /// ```
+    pub fn matches(&self, pattern: &JsonPath) -> bool {
+        self.0.len() == pattern.0.len() && self.0.iter().zip(pattern.0.iter()).all(|(segment, pattern_segment)| {
+            matches!(pattern_segment, PathSegment::Key(key) if key.as_ref() == "*") || is_same_segment(segment, pattern_segment)
+        })
+    }
+}
+
+fn is_same_segment(a: &PathSegment, b: &PathSegment) -> bool {
+    match (a, b) {
+        (PathSegment::Key(a), PathSegment::Key(b)) => a == b,
+        (PathSegment::Index(a), PathSegment::Index(b)) => a == b,
+        (PathSegment::Key(key), PathSegment::Index(index)) | (PathSegment::Index(index), PathSegment::Key(key)) => key.as_ref() == index.to_string(),
+    }
+}
+
+impl From<Vec<PathSegment>> for JsonPath {
+    fn from(segments: Vec<PathSegment>) -> JsonPath {
+        JsonPath(segments)
+    }
+}
+
+impl TryFrom<&str> for JsonPath {
+    type Error = ParseError;
+
+    /// Parses the path the same way as `str::parse`.
+    fn try_from(text: &str) -> Result<JsonPath, ParseError> {
+        text.parse()
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = ParseError;
+
+    /// Parses a JSON pointer when the text is empty or starts with a `/` and otherwise a dotted path.
+    fn from_str(text: &str) -> Result<JsonPath, ParseError> {
+        let result = if text.is_empty() || text.starts_with('/') {
+            parse_pointer(text)
+        } else {
+            parse_dotted(text)
+        };
+        result.map_err(|err| err.locate(text.chars(), None))
+    }
+}
+
+fn parse_pointer(text: &str) -> Result<JsonPath, ParseError> {
+    let mut segments = Vec::new();
+    let mut pos = 1;
+    for raw_segment in text.split('/').skip(1) {
+        let mut segment = String::with_capacity(raw_segment.len());
+        let mut chars = raw_segment.chars().enumerate();
+        while let Some((i, c)) = chars.next() {
+            if c != '~' {
+                segment.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some((_, '0')) => segment.push('~'),
+                Some((_, '1')) => segment.push('/'),
+                _ => return Err(ParseError::new(pos + i, "Invalid escape in JSON pointer.")),
+            }
+        }
+        pos += raw_segment.chars().count() + 1;
+        segments.push(create_segment(segment));
+    }
+    Ok(JsonPath(segments))
+}
+
+fn parse_dotted(text: &str) -> Result<JsonPath, ParseError> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut segments = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        if chars[pos] == '[' {
+            let start = pos + 1;
+            if chars.get(start) == Some(&'"') {
+                // a quoted property name with JSON string escapes
+                let mut end = start + 1;
+                while end < chars.len() && chars[end] != '"' {
+                    end += if chars[end] == '\\' { 2 } else { 1 };
+                }
+                if chars.get(end) != Some(&'"') || chars.get(end + 1) != Some(&']') {
+                    return Err(ParseError::new(start, "Expected a quoted property name followed by ']'."));
+                }
+                let raw_name = chars[start + 1..end].iter().collect::<String>();
+                match decode_string_literal(&raw_name, '"') {
+                    Ok(name) => segments.push(PathSegment::from(name.as_ref())),
+                    Err(err) => return Err(ParseError::new(start + 1 + err.offset, "Invalid escape.")),
+                }
+                pos = end + 2;
+            } else {
+                let end = start + chars[start..].iter().take_while(|c| c.is_ascii_digit()).count();
+                let index = chars[start..end].iter().collect::<String>().parse::<usize>();
+                match index {
+                    Ok(index) if chars.get(end) == Some(&']') => segments.push(PathSegment::Index(index)),
+                    _ => return Err(ParseError::new(start, "Expected an index or quoted property name followed by ']'.")),
+                }
+                pos = end + 1;
+            }
+        } else {
+            if !segments.is_empty() {
+                if chars[pos] != '.' {
+                    return Err(ParseError::new(pos, "Expected '.' or '['."));
+                }
+                pos += 1;
+            }
+            let start = pos;
+            while pos < chars.len() && chars[pos] != '.' && chars[pos] != '[' {
+                pos += 1;
+            }
+            if pos == start {
+                return Err(ParseError::new(start, "Expected a property name."));
+            }
+            segments.push(PathSegment::from(chars[start..pos].iter().collect::<String>().as_str()));
+        }
+    }
+
+    Ok(JsonPath(segments))
+}
+
+/// Creates an index segment for text that's written like an array index and otherwise a key segment.
+fn create_segment(text: String) -> PathSegment {
+    match parse_index(&text) {
+        Some(index) => PathSegment::Index(index),
+        None => PathSegment::Key(ImmutableString::new(text)),
+    }
+}
+
+/// Parses text that's written like an array index (ex. `0` or `12`, but not `01`).
+fn parse_index(text: &str) -> Option<usize> {
+    let is_index = !text.is_empty() && text.chars().all(|c| c.is_ascii_digit()) && (text == "0" || !text.starts_with('0'));
+    if is_index { text.parse().ok() } else { None }
+}
+
+/// Resolves the paths from the root in a single traversal and returns the values in the order of the paths.
+///
+/// The paths are sorted so that the paths sharing a prefix are next to each other, which
+/// allows the values along a shared prefix to be looked up once.
+pub(crate) fn get_many<'a, V>(root: &'a V, paths: &[JsonPath], get_child: impl Fn(&'a V, &PathSegment) -> Option<&'a V>) -> Vec<Option<&'a V>> {
+    let mut results = vec![None; paths.len()];
+    let mut order = (0..paths.len()).collect::<Vec<_>>();
+    order.sort_by_cached_key(|i| paths[*i].0.iter().map(|segment| segment.as_key()).collect::<Vec<_>>());
+
+    // the value at a depth along with the range of `order` containing the paths that lead to it
+    let mut stack = vec![(root, 0, 0..order.len())];
+    while let Some((value, depth, range)) = stack.pop() {
+        let mut i = range.start;
+        while i < range.end {
+            let segment = match paths[order[i]].0.get(depth) {
+                Some(segment) => segment,
+                None => {
+                    results[order[i]] = Some(value);
+                    i += 1;
+                    continue;
+                },
+            };
+            let group_len = order[i..range.end]
+                .iter()
+                .take_while(|j| matches!(paths[**j].0.get(depth), Some(other) if is_same_segment(segment, other)))
+                .count();
+            if let Some(child) = get_child(value, segment) {
+                stack.push((child, depth + 1, i..i + group_len));
+            }
+            i += group_len;
+        }
+    }
+
+    results
+}
+
+impl fmt::Display for JsonPath {
+    /// Formats the path as a JSON pointer (ex. `/a/0/b~1c`).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for segment in self.0.iter() {
+            match segment {
+                PathSegment::Key(key) => write!(f, "/{}", key.as_ref().replace('~', "~0").replace('/', "~1"))?,
+                PathSegment::Index(index) => write!(f, "/{}", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_round_trips_json_pointers() {
+        for pointer in ["", "/", "/a", "/a/0/b", "/a~1b/~0c/~01", "/0/00/-", "//"].iter() {
+            let path = pointer.parse::<JsonPath>().unwrap();
+            assert_eq!(path.to_string(), *pointer);
+        }
+
+        let path = "/a~1b/~0c/~01/12/012".parse::<JsonPath>().unwrap();
+        assert_eq!(path.segments(), &[
+            PathSegment::from("a/b"),
+            PathSegment::from("~c"),
+            PathSegment::from("~1"),
+            PathSegment::Index(12),
+            PathSegment::from("012"),
+        ]);
+        assert!("".parse::<JsonPath>().unwrap().is_empty());
+        assert_eq!("/".parse::<JsonPath>().unwrap().segments(), &[PathSegment::from("")]);
+    }
+
+    #[test]
+    fn it_errors_for_invalid_pointer_escapes() {
+        let err = "/a/b~2".parse::<JsonPath>().unwrap_err();
+        assert_eq!(err.to_string(), "1:5: Invalid escape in JSON pointer.");
+        assert_eq!("/~".parse::<JsonPath>().unwrap_err().pos, 1);
+    }
+
+    #[test]
+    fn it_parses_dotted_paths() {
+        let path = r#"a.b[0]["c.d"]["e\"f"][12].g"#.parse::<JsonPath>().unwrap();
+        assert_eq!(path.segments(), &[
+            PathSegment::from("a"),
+            PathSegment::from("b"),
+            PathSegment::Index(0),
+            PathSegment::from("c.d"),
+            PathSegment::from("e\"f"),
+            PathSegment::Index(12),
+            PathSegment::from("g"),
+        ]);
+        assert_eq!(path.to_string(), "/a/b/0/c.d/e\"f/12/g");
+        assert_eq!("[1][\"\"]".parse::<JsonPath>().unwrap().to_string(), "/1/");
+
+        assert_eq!("a..b".parse::<JsonPath>().unwrap_err().message, "Expected a property name.");
+        assert_eq!("a[x]".parse::<JsonPath>().unwrap_err().pos, 2);
+        assert_eq!("a[\"b]".parse::<JsonPath>().unwrap_err().pos, 2);
+        assert_eq!("a[0]b".parse::<JsonPath>().unwrap_err().message, "Expected '.' or '['.");
+        assert_eq!("a.".parse::<JsonPath>().unwrap_err().pos, 2);
+        assert_eq!("a[\"\u{e9}\\x\"]".parse::<JsonPath>().unwrap_err().to_string(), "1:5: Invalid escape.");
+    }
+
+    #[test]
+    fn it_builds_and_compares_paths() {
+        let mut path = JsonPath::new();
+        path.push("servers");
+        path.push(0);
+        path.push("a/b");
+        assert_eq!(path.to_string(), "/servers/0/a~1b");
+        assert_eq!(path.len(), 3);
+        path.push(1);
+        assert_eq!(path.pop(), Some(PathSegment::Index(1)));
+        assert_eq!(path.parent().unwrap().to_string(), "/servers/0");
+        assert_eq!(JsonPath::new().parent(), None);
+
+        assert!(path.starts_with(&"/servers".parse::<JsonPath>().unwrap()));
+        assert!(path.starts_with(&path));
+        assert!(path.starts_with(&JsonPath::new()));
+        assert!(!path.starts_with(&"/server".parse::<JsonPath>().unwrap()));
+        assert!(!"/servers".parse::<JsonPath>().unwrap().starts_with(&path));
+        // indexes and keys are the same when written the same way
+        assert!(path.starts_with(&JsonPath::from(vec![PathSegment::from("servers"), PathSegment::from("0")])));
+        assert!("a[0]".parse::<JsonPath>().unwrap().is_same_pointer(&"/a/0".parse::<JsonPath>().unwrap()));
+    }
+
+    #[test]
+    fn it_errors_converting_invalid_paths() {
+        let err = JsonPath::try_from("/~2").unwrap_err();
+        assert_eq!((err.pos, err.message.as_str()), (1, "Invalid escape in JSON pointer."));
+        assert_eq!(JsonPath::try_from("/a/0").unwrap(), JsonPath::from(vec![PathSegment::from("a"), PathSegment::from(0)]));
+    }
+}
@@ -0,0 +1,19 @@
+//! The items needed for the common workflow of reading, changing, and writing JSONC.
+//!
+//! # Example
+//!
+//! ```
+//! use jsonc_parser::prelude::*;
+//!
+//! let mut value = parse_to_value(r#"{ "port": 80 } // comment"#).unwrap().unwrap();
+//! if let JsonValue::Object(obj) = &mut value {
+//!     obj.insert(String::from("port"), JsonValue::Number(String::from("8080")));
+//! }
+//! assert_eq!(serialize_json_value(&value, &SerializeOptions::default()), r#"{"port":8080}"#);
+//! ```
+
+pub use super::edits::{apply_text_edits, TextEdit};
+pub use super::errors::ParseError;
+pub use super::parser::{parse_text, parse_text_with_options, parse_to_ast, parse_to_value, ParseOptions, ParseResult};
+pub use super::serializer::{serialize_json_value, SerializeOptions, SerializeStyle};
+pub use super::value::{JsonArray, JsonObject, JsonValue};
@@ -1,114 +1,283 @@
-use super::tokens::Token;
+use super::tokens::{Token, TokenAndRange, TokenRef, TokenRefAndRange};
 use super::errors::*;
-use super::common::ImmutableString;
+use super::common::{ImmutableString, Range};
+use super::diagnostics::Diagnostic;
+use super::numbers::{get_unexpected_character_message, scan_number, NumberDialect};
+use super::strings::read_escape;
+
+/// Options for scanning text.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{Scanner, ScannerOptions};
+///
+/// let options = ScannerOptions::default().with_single_quotes(true).with_comments(false);
+/// let mut scanner = Scanner::new_with_options("['a']", options);
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScannerOptions {
+    /// Allow comment lines and comment blocks. Defaults to `true`.
+    pub comments: bool,
+    /// Allow strings surrounded in single quotes. Defaults to `false`.
+    pub single_quotes: bool,
+    /// Return whitespace tokens, so that the original text can be reconstructed
+    /// from the tokens. Defaults to `false`.
+    pub whitespace: bool,
+    /// Forms of number literals allowed in addition to the JSON grammar.
+    pub number_dialect: NumberDialect,
+    /// Only allow numbers in the JSON grammar, ignoring `number_dialect`, and error for numbers
+    /// directly followed by a digit or letter (ex. `01`). Defaults to `false`.
+    pub strict_numbers: bool,
+    /// Keep invalid escapes in strings (ex. `\q`) as they are and continue scanning the string,
+    /// instead of erroring. The invalid escapes are provided by `Scanner::take_diagnostics`.
+    /// Defaults to `false`.
+    pub tolerate_invalid_escapes: bool,
+    /// Scan `=` as a colon, for dialects that separate property names and values with an equals
+    /// sign (ex. `{ "a" = 1 }`). This isn't JSON, so it defaults to `false`, which errors on `=`.
+    /// The token is a `Token::Colon`, so text reconstructed from the tokens has a colon in its place.
+    pub equals_separator: bool,
+}
+
+impl Default for ScannerOptions {
+    fn default() -> ScannerOptions {
+        ScannerOptions {
+            comments: true,
+            single_quotes: false,
+            whitespace: false,
+            number_dialect: NumberDialect::default(),
+            strict_numbers: false,
+            tolerate_invalid_escapes: false,
+            equals_separator: false,
+        }
+    }
+}
+
+impl ScannerOptions {
+    /// Sets whether comments are allowed.
+    pub fn with_comments(mut self, value: bool) -> Self {
+        self.comments = value;
+        self
+    }
+
+    /// Sets whether strings may be surrounded in single quotes.
+    pub fn with_single_quotes(mut self, value: bool) -> Self {
+        self.single_quotes = value;
+        self
+    }
+
+    /// Sets whether whitespace tokens are returned.
+    pub fn with_whitespace(mut self, value: bool) -> Self {
+        self.whitespace = value;
+        self
+    }
+
+    /// Sets the forms of number literals allowed in addition to the JSON grammar.
+    pub fn with_number_dialect(mut self, value: NumberDialect) -> Self {
+        self.number_dialect = value;
+        self
+    }
+
+    /// Sets whether only numbers in the JSON grammar are allowed.
+    pub fn with_strict_numbers(mut self, value: bool) -> Self {
+        self.strict_numbers = value;
+        self
+    }
+
+    /// Sets whether invalid escapes in strings are kept instead of erroring.
+    pub fn with_tolerate_invalid_escapes(mut self, value: bool) -> Self {
+        self.tolerate_invalid_escapes = value;
+        self
+    }
+
+    /// Sets whether `=` is scanned as a colon.
+    pub fn with_equals_separator(mut self, value: bool) -> Self {
+        self.equals_separator = value;
+        self
+    }
+}
+
+/// A scanned token whose payload is referenced by its range of bytes in the text.
+pub(crate) enum RawToken {
+    /// A token without a payload.
+    Token(Token),
+    String(usize, usize),
+    Number(usize, usize),
+    CommentLine(usize, usize),
+    CommentBlock(usize, usize),
+    Whitespace(usize, usize),
+}
+
+/// The result of scanning for the next token when more text may be added.
+pub(crate) enum ScannedToken {
+    Token(RawToken),
+    /// The end of the text.
+    End,
+    /// The text ends in what may be the middle of a token, so more text is needed.
+    Incomplete,
+}
+
+/// A token at the end of the text that's waiting for more text to be added.
+enum PendingToken {
+    /// A string, which is continued from the scanner's position.
+    String,
+    /// A comment line, which is continued from the scanner's position.
+    CommentLine,
+    /// A comment block, which is continued from the scanner's position.
+    CommentBlock,
+    /// Whitespace, which is continued from the scanner's position.
+    Whitespace,
+    /// A number, keyword, or unexpected character, which is scanned again from its start once a
+    /// delimiter is found, with the byte offset from its start to keep looking for one from.
+    Word { checked: usize },
+}
 
 /// Converts text into a stream of tokens.
 pub struct Scanner {
+    raw: RawScanner<String>,
+    current_token: Option<Token>,
+}
+
+/// Scans text into raw tokens, which is shared by the scanners that own their text and the
+/// iterator of tokens that borrow from it.
+pub(crate) struct RawScanner<T: AsRef<str>> {
+    text: T,
+    /// Position in characters, which is what ranges are in.
     pos: usize,
+    /// Position in bytes of `text`, which the text is read at.
+    byte_pos: usize,
     line_number: usize,
     token_start: usize,
+    token_start_byte: usize,
     token_start_line: usize,
-    chars: Vec<char>, // todo: use an iterator instead?
-    current_token: Option<Token>,
+    options: ScannerOptions,
+    source_name: Option<ImmutableString>,
+    tokens_scanned: usize,
+    /// Problems found while scanning that didn't stop it.
+    diagnostics: Vec<Diagnostic>,
+    /// The position after a number that's followed by a letter or digit (ex. the `b` of `0b101`)
+    /// and the message for the error at it, which hints at what the number was meant to be.
+    number_hint: Option<(usize, String)>,
+    /// Whether more text may be added, in which case a token at the end of the text is kept
+    /// until it's known that it's complete.
+    is_partial: bool,
+    pending: Option<PendingToken>,
+    /// Where the start of the text is in the document, which is after the start of the document
+    /// once text before the current token is dropped as more text is added.
+    text_start: TextLocation,
+    /// Number of characters read or moved, which tests use to check that text added in chunks is
+    /// scanned in linear time.
+    #[cfg(test)]
+    chars_read: std::cell::Cell<usize>,
+}
+
+/// Builds a scanner with options and the name of the source the text came from.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{Scanner, ScannerOptions};
+///
+/// let mut scanner = Scanner::builder("{\n  // a\n}")
+///     .source_name("tsconfig.json")
+///     .options(ScannerOptions::default().with_comments(false))
+///     .build();
+/// scanner.scan().unwrap();
+/// let err = scanner.scan().unwrap_err();
+/// assert_eq!(err.to_string(), "tsconfig.json:2:3: Comments are not allowed.");
+/// ```
+pub struct ScannerBuilder {
+    text: String,
+    options: ScannerOptions,
+    source_name: Option<ImmutableString>,
+}
+
+impl ScannerBuilder {
+    /// Sets the name of the source the text came from (ex. a file path), which is included in errors.
+    pub fn source_name(mut self, name: &str) -> Self {
+        self.source_name = Some(ImmutableString::new(String::from(name)));
+        self
+    }
+
+    /// Sets the options to scan with.
+    pub fn options(mut self, options: ScannerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Creates the scanner.
+    pub fn build(self) -> Scanner {
+        let mut scanner = Scanner::from_string(self.text, self.options);
+        scanner.raw.source_name = self.source_name;
+        scanner
+    }
 }
 
 impl Scanner {
-    /// Creates a new scanner based on the provided text.
-    pub fn new(text: &str) -> Scanner {
+    /// Creates a new scanner based on the provided text (ex. a `&str`, `String`, or `Cow<str>`).
+    ///
+    /// The scanner owns the text. An owned `String` or `Cow::Owned` is moved into it without
+    /// a copy, while borrowed text is copied, so the text can be dropped once the scanner is
+    /// created.
+    pub fn new(text: impl Into<String>) -> Scanner {
+        Scanner::new_with_options(text, ScannerOptions::default())
+    }
+
+    /// Creates a new scanner based on the provided text and options.
+    ///
+    /// Like `Scanner::new`, the scanner owns the text.
+    pub fn new_with_options(text: impl Into<String>, options: ScannerOptions) -> Scanner {
+        Scanner::from_string(text.into(), options)
+    }
+
+    fn from_string(text: String, options: ScannerOptions) -> Scanner {
         Scanner {
-            pos: 0,
-            line_number: 0,
-            token_start: 0,
-            token_start_line: 0,
-            chars: text.chars().collect(),
+            raw: RawScanner::new(text, options),
             current_token: None,
         }
     }
 
+    /// Creates a builder for a scanner based on the provided text, which the scanner owns like
+    /// in `Scanner::new`.
+    pub fn builder(text: impl Into<String>) -> ScannerBuilder {
+        ScannerBuilder {
+            text: text.into(),
+            options: ScannerOptions::default(),
+            source_name: None,
+        }
+    }
+
+    /// Creates a new scanner that also returns whitespace tokens, so that
+    /// the original text can be reconstructed from the tokens.
+    pub fn new_lossless(text: impl Into<String>) -> Scanner {
+        Scanner::new_with_options(text, ScannerOptions::default().with_whitespace(true))
+    }
+
     /// Moves to and returns the next token.
     pub fn scan(&mut self) -> Result<Option<Token>, ParseError> {
-        self.skip_whitespace();
-        self.token_start = self.pos;
-        self.token_start_line = self.line_number;
-        if let Some(current_char) = self.current_char() {
-            let token_result = match current_char {
-                '{' => {
-                    self.move_next_char();
-                    Ok(Token::OpenBrace)
-                },
-                '}' => {
-                    self.move_next_char();
-                    Ok(Token::CloseBrace)
-                },
-                '[' => {
-                    self.move_next_char();
-                    Ok(Token::OpenBracket)
-                },
-                ']' => {
-                    self.move_next_char();
-                    Ok(Token::CloseBracket)
-                },
-                ',' => {
-                    self.move_next_char();
-                    Ok(Token::Comma)
-                },
-                ':' => {
-                    self.move_next_char();
-                    Ok(Token::Colon)
-                },
-                '"' => self.parse_string(),
-                '/' => {
-                    match self.peek_char() {
-                        Some('/') => Ok(self.parse_comment_line()),
-                        Some('*') => self.parse_comment_block(),
-                        _ => Err(ParseError::new(self.token_start, "Unexpected token.")),
-                    }
-                },
-                _ => {
-                    if current_char == '-' || self.is_digit() {
-                        self.parse_number()
-                    } else if self.try_move_word("true") {
-                        Ok(Token::Boolean(true))
-                    } else if self.try_move_word("false") {
-                        Ok(Token::Boolean(false))
-                    } else if self.try_move_word("null") {
-                        Ok(Token::Null)
-                    } else {
-                        Err(ParseError::new(self.token_start, "Unexpected token."))
-                    }
-                }
-            };
-            match token_result {
-                Ok(token) => {
-                    self.current_token = Some(token.clone());
-                    Ok(Some(token))
-                },
-                Err(err) => Err(err),
-            }
-        } else {
-            self.current_token = None;
-            Ok(None)
-        }
+        let token = self.scan_raw()?.map(|raw_token| self.create_token(raw_token));
+        self.current_token = token.clone();
+        Ok(token)
     }
 
     /// Gets the start position of the token.
     pub fn token_start(&self) -> usize {
-        self.token_start
+        self.raw.token_start()
     }
 
     /// Gets the end position of the token.
     pub fn token_end(&self) -> usize {
-        self.pos
+        self.raw.token_end()
     }
 
     /// Gets the line the token starts on.
     pub fn token_start_line(&self) -> usize {
-        self.token_start_line
+        self.raw.token_start_line()
     }
 
     /// Gets the line the token ends on.
     pub fn token_end_line(&self) -> usize {
-        self.line_number
+        self.raw.token_end_line()
     }
 
     /// Gets the current token.
@@ -116,155 +285,587 @@ impl Scanner {
         self.current_token.as_ref().map(|x| x.to_owned())
     }
 
-    fn parse_string(&mut self) -> Result<Token, ParseError> {
-        #[cfg(debug_assertions)]
-        self.assert_char('"');
-        let start_pos = self.pos;
-        let mut text = String::new();
-        let mut last_was_backslash = false;
-        let mut found_end_string = false;
-
-        while let Some(current_char) = self.move_next_char() {
-            if last_was_backslash {
-                match current_char {
-                    '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' => {
-                        text.push(current_char);
-                    },
-                    'u' => {
-                        text.push(current_char);
-                        let hex_start_pos = self.pos - 1;
-                        // expect four hex values
-                        for _ in 0..4 {
-                            if let Some(current_char) = self.move_next_char() {
-                                text.push(current_char);
-                            }
-                            if !self.is_hex() {
-                                return Err(ParseError::new(hex_start_pos, "Expected four hex digits."));
-                            }
-                        }
-                    },
-                    _ => return Err(ParseError::new(start_pos, "Invalid escape.")),
+    /// Gets the number of tokens scanned so far, including comments and whitespace.
+    pub fn tokens_scanned(&self) -> usize {
+        self.raw.tokens_scanned()
+    }
+
+    /// Takes the problems found while scanning that didn't stop it, which are the invalid
+    /// escapes in strings when `ScannerOptions::tolerate_invalid_escapes` is set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::{Scanner, ScannerOptions};
+    /// use jsonc_parser::tokens::Token;
+    ///
+    /// let mut scanner = Scanner::new_with_options(r#""a\qb""#, ScannerOptions::default().with_tolerate_invalid_escapes(true));
+    /// assert!(matches!(scanner.scan().unwrap(), Some(Token::String(value)) if value.as_ref() == r"a\qb"));
+    /// let diagnostics = scanner.take_diagnostics();
+    /// assert_eq!((diagnostics[0].range.start, diagnostics[0].range.end), (2, 4));
+    /// assert_eq!(diagnostics[0].message, "Invalid escape.");
+    /// ```
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.raw.take_diagnostics()
+    }
+
+    /// Gets the name of the source the text came from.
+    pub fn source_name(&self) -> Option<&ImmutableString> {
+        self.raw.source_name()
+    }
+
+    /// Skips the rest of the current value to resynchronize on the next value, such as after
+    /// an error in a record of a stream of newline-delimited values.
+    ///
+    /// This moves to the start of the next line that begins with a character that could start
+    /// a value, ignoring indented lines, which are assumed to be within a value. Returns `false`
+    /// when the end of the text was reached.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::Scanner;
+    /// use jsonc_parser::tokens::Token;
+    ///
+    /// let mut scanner = Scanner::new("{ \"a\": tru }\n{ \"b\": 2 }");
+    /// while scanner.scan().is_ok() {}
+    /// assert!(scanner.skip_to_next_value());
+    /// assert_eq!(scanner.scan().unwrap(), Some(Token::OpenBrace));
+    /// assert_eq!(scanner.token_start_line(), 1);
+    /// ```
+    pub fn skip_to_next_value(&mut self) -> bool {
+        self.current_token = None;
+        self.raw.skip_to_next_value()
+    }
+
+    /// Moves to the next token without creating its payload.
+    pub(crate) fn scan_raw(&mut self) -> Result<Option<RawToken>, ParseError> {
+        self.raw.scan_raw()
+    }
+
+    pub(crate) fn create_token(&self, raw_token: RawToken) -> Token {
+        self.raw.create_token(raw_token)
+    }
+
+    /// Gets the quote that surrounds the string token.
+    pub(crate) fn token_quote(&self) -> char {
+        self.raw.token_quote()
+    }
+
+    /// Gets the character at the start of the current token.
+    pub(crate) fn token_start_char(&self) -> Option<char> {
+        self.raw.token_start_char()
+    }
+
+    /// Gets the last character of the current token.
+    pub(crate) fn token_last_char(&self) -> Option<char> {
+        self.raw.token_last_char()
+    }
+
+    /// Sets the line, column, and source name of an error found in the text.
+    pub(crate) fn locate_error(&self, err: ParseError) -> ParseError {
+        self.raw.locate_error(err)
+    }
+
+    /// Moves to the next token, or returns `ScannedToken::Incomplete` when more text may be added
+    /// and the text ends before it's known what the next token is.
+    pub(crate) fn scan_available(&mut self) -> Result<ScannedToken, ParseError> {
+        self.raw.scan_available()
+    }
+
+    /// Sets whether more text may be added with `push_text`.
+    pub(crate) fn set_partial(&mut self, value: bool) {
+        self.raw.is_partial = value;
+    }
+
+    /// Adds text to the end of the text being scanned.
+    pub(crate) fn push_text(&mut self, text: &str) {
+        self.raw.push_text(text);
+    }
+
+    /// Gets the number of characters read or moved so far.
+    #[cfg(test)]
+    pub(crate) fn chars_read(&self) -> usize {
+        self.raw.chars_read.get()
+    }
+
+    /// Moves past the text that caused the last scan error so scanning can continue.
+    pub(crate) fn recover_from_error(&mut self) {
+        self.current_token = None;
+        self.raw.recover_from_error()
+    }
+}
+
+impl<T: AsRef<str>> RawScanner<T> {
+    fn new(text: T, mut options: ScannerOptions) -> RawScanner<T> {
+        if options.strict_numbers {
+            options.number_dialect = NumberDialect::default();
+        }
+        RawScanner {
+            text,
+            pos: 0,
+            byte_pos: 0,
+            line_number: 0,
+            token_start: 0,
+            token_start_byte: 0,
+            token_start_line: 0,
+            options,
+            source_name: None,
+            tokens_scanned: 0,
+            diagnostics: Vec::new(),
+            number_hint: None,
+            is_partial: false,
+            pending: None,
+            text_start: TextLocation::default(),
+            #[cfg(test)]
+            chars_read: std::cell::Cell::new(0),
+        }
+    }
+
+    fn count_chars_read(&self, _count: usize) {
+        #[cfg(test)]
+        self.chars_read.set(self.chars_read.get() + _count);
+    }
+
+    /// Moves to the next token without creating its payload.
+    pub(crate) fn scan_raw(&mut self) -> Result<Option<RawToken>, ParseError> {
+        match self.scan_available()? {
+            ScannedToken::Token(token) => Ok(Some(token)),
+            ScannedToken::End | ScannedToken::Incomplete => Ok(None),
+        }
+    }
+
+    /// Moves to the next token, or returns `ScannedToken::Incomplete` when more text may be added
+    /// and the text ends before it's known what the next token is.
+    ///
+    /// An incomplete string or comment is kept and scanned from where it stopped once more text is
+    /// added. An incomplete number or keyword is scanned again from its start, but only once the text
+    /// after it has a character that ends it, so each token is scanned a bounded number of times.
+    pub(crate) fn scan_available(&mut self) -> Result<ScannedToken, ParseError> {
+        let result = match self.pending.take() {
+            Some(pending) => self.resume_token(pending),
+            None => self.scan_token(),
+        };
+        match result {
+            Ok(ScannedToken::Token(token)) => {
+                self.tokens_scanned += 1;
+                Ok(ScannedToken::Token(token))
+            },
+            Ok(result) => Ok(result),
+            Err(err) => Err(self.locate_error(err)),
+        }
+    }
+
+    fn scan_token(&mut self) -> Result<ScannedToken, ParseError> {
+        if !self.options.whitespace {
+            self.skip_whitespace();
+        }
+        self.token_start = self.pos;
+        self.token_start_byte = self.byte_pos;
+        self.token_start_line = self.line_number;
+        let current_char = match self.current_char() {
+            Some(current_char) => current_char,
+            None if self.is_partial => return Ok(ScannedToken::Incomplete),
+            None => return Ok(ScannedToken::End),
+        };
+        let result = match current_char {
+            _ if current_char.is_whitespace() => return Ok(self.continue_whitespace()),
+            '{' => return Ok(self.scan_char(Token::OpenBrace)),
+            '}' => return Ok(self.scan_char(Token::CloseBrace)),
+            '[' => return Ok(self.scan_char(Token::OpenBracket)),
+            ']' => return Ok(self.scan_char(Token::CloseBracket)),
+            ',' => return Ok(self.scan_char(Token::Comma)),
+            ':' => return Ok(self.scan_char(Token::Colon)),
+            '=' if self.options.equals_separator => return Ok(self.scan_char(Token::Colon)),
+            '"' => return self.parse_string(),
+            '\'' if self.options.single_quotes => return self.parse_string(),
+            '/' if !self.options.comments => Err(ParseError::new(self.token_start, "Comments are not allowed.")),
+            '/' if self.remaining_text().starts_with("//") => return Ok(self.parse_comment_line()),
+            '/' if self.remaining_text().starts_with("/*") => return self.parse_comment_block(),
+            _ => {
+                if current_char == '-' || self.is_digit() || (current_char == '.' && self.options.number_dialect.leading_decimal_point) {
+                    self.parse_number()
+                } else if self.try_move_word("true") {
+                    Ok(RawToken::Token(Token::Boolean(true)))
+                } else if self.try_move_word("false") {
+                    Ok(RawToken::Token(Token::Boolean(false)))
+                } else if self.try_move_word("null") {
+                    Ok(RawToken::Token(Token::Null))
+                } else {
+                    let message = match &self.number_hint {
+                        Some((pos, message)) if *pos == self.token_start => message.as_str(),
+                        _ => "Unexpected token.",
+                    };
+                    Err(ParseError::new(self.token_start, message))
                 }
-                last_was_backslash = false;
-            } else if current_char == '"' {
-                found_end_string = true;
-                break;
-            } else {
-                last_was_backslash = current_char == '\\';
-                text.push(current_char);
+            }
+        };
+
+        // numbers, keywords, and unexpected characters continue until a delimiter, so more text could change them until there is one
+        if self.is_partial {
+            if let Err(checked) = self.find_delimiter(self.token_start_byte + current_char.len_utf8()) {
+                self.pos = self.token_start;
+                self.byte_pos = self.token_start_byte;
+                self.line_number = self.token_start_line;
+                return Ok(self.wait_for_text(PendingToken::Word { checked: checked - self.token_start_byte }));
             }
         }
+        result.map(ScannedToken::Token)
+    }
 
-        if found_end_string {
-            self.move_next_char();
-            Ok(Token::String(ImmutableString::new(text)))
-        } else {
-            Err(ParseError::new(start_pos, "Unterminated string literal"))
+    /// Continues scanning the token that was waiting for more text.
+    fn resume_token(&mut self, pending: PendingToken) -> Result<ScannedToken, ParseError> {
+        match pending {
+            PendingToken::String => self.continue_string(),
+            PendingToken::CommentLine => Ok(self.continue_comment_line()),
+            PendingToken::CommentBlock => self.continue_comment_block(),
+            PendingToken::Whitespace => Ok(self.continue_whitespace()),
+            PendingToken::Word { checked } => match self.find_delimiter(self.token_start_byte + checked) {
+                Err(checked) if self.is_partial => Ok(self.wait_for_text(PendingToken::Word { checked: checked - self.token_start_byte })),
+                _ => self.scan_token(),
+            },
         }
     }
 
-    fn parse_number(&mut self) -> Result<Token, ParseError> {
-        let mut text = String::new();
+    /// Keeps the token to continue scanning it once more text is added.
+    fn wait_for_text(&mut self, pending: PendingToken) -> ScannedToken {
+        self.pending = Some(pending);
+        ScannedToken::Incomplete
+    }
 
-        if self.is_negative_sign() {
-            text.push('-');
-            self.move_next_char();
+    /// Finds the byte position of the first character at or after the provided byte position that
+    /// ends a number or keyword, or returns the byte position to continue looking from once more
+    /// text is added.
+    ///
+    /// A comma between digits isn't a delimiter when it's a decimal separator (see `NumberDialect`).
+    fn find_delimiter(&self, start: usize) -> Result<usize, usize> {
+        let text = self.text();
+        let is_digit_at = |pos: usize| matches!(text.as_bytes().get(pos), Some(c) if c.is_ascii_digit());
+        for (index, current_char) in text[start..].char_indices() {
+            self.count_chars_read(1);
+            let pos = start + index;
+            if current_char == ',' && self.options.number_dialect.comma_decimal_separator && is_digit_at(pos - 1) {
+                if pos + 1 == text.len() && self.is_partial {
+                    return Err(pos);
+                }
+                if is_digit_at(pos + 1) {
+                    continue;
+                }
+            }
+            if is_delimiter(current_char) {
+                return Ok(pos);
+            }
         }
+        Err(text.len())
+    }
 
-        if self.is_zero() {
-            text.push('0');
-            self.move_next_char();
-        } else if self.is_one_nine() {
-            text.push(self.current_char().unwrap());
-            self.move_next_char();
-            while self.is_digit() {
-                text.push(self.current_char().unwrap());
+    /// Moves past the character of a token without a payload.
+    fn scan_char(&mut self, token: Token) -> ScannedToken {
+        self.move_next_char();
+        ScannedToken::Token(RawToken::Token(token))
+    }
+
+    pub(crate) fn create_token(&self, raw_token: RawToken) -> Token {
+        match raw_token {
+            RawToken::Token(token) => token,
+            RawToken::String(start, end) => Token::String(self.text_in(start, end)),
+            RawToken::Number(start, end) => Token::Number(self.text_in(start, end)),
+            RawToken::CommentLine(start, end) => Token::CommentLine(self.text_in(start, end)),
+            RawToken::CommentBlock(start, end) => Token::CommentBlock(self.text_in(start, end)),
+            RawToken::Whitespace(start, end) => Token::Whitespace(self.text_in(start, end)),
+        }
+    }
+
+    /// Creates a range on the line the current token starts on.
+    fn create_range(&self, start: usize, end: usize) -> Range {
+        Range {
+            start,
+            end,
+            start_line: self.token_start_line,
+            end_line: self.token_start_line,
+        }
+    }
+
+    fn text_in(&self, start: usize, end: usize) -> ImmutableString {
+        ImmutableString::new(String::from(&self.text()[start..end]))
+    }
+
+    /// Gets the start position of the token.
+    pub(crate) fn token_start(&self) -> usize {
+        self.token_start
+    }
+
+    /// Gets the quote that surrounds the string token.
+    pub(crate) fn token_quote(&self) -> char {
+        self.token_start_char().unwrap_or('"')
+    }
+
+    /// Gets the end position of the token.
+    pub(crate) fn token_end(&self) -> usize {
+        self.pos
+    }
+
+    /// Gets the line the token starts on.
+    pub(crate) fn token_start_line(&self) -> usize {
+        self.token_start_line
+    }
+
+    /// Gets the line the token ends on.
+    pub(crate) fn token_end_line(&self) -> usize {
+        self.line_number
+    }
+
+    /// Gets the character at the start of the current token.
+    pub(crate) fn token_start_char(&self) -> Option<char> {
+        self.text()[self.token_start_byte..].chars().next()
+    }
+
+    /// Gets the last character of the current token.
+    pub(crate) fn token_last_char(&self) -> Option<char> {
+        self.text()[self.token_start_byte..self.byte_pos].chars().next_back()
+    }
+
+    /// Gets the number of tokens scanned so far, including comments and whitespace.
+    pub(crate) fn tokens_scanned(&self) -> usize {
+        self.tokens_scanned
+    }
+
+    /// Takes the problems found while scanning that didn't stop it.
+    pub(crate) fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Gets the name of the source the text came from.
+    pub(crate) fn source_name(&self) -> Option<&ImmutableString> {
+        self.source_name.as_ref()
+    }
+
+    /// Sets the line, column, and source name of an error found in the text.
+    pub(crate) fn locate_error(&self, err: ParseError) -> ParseError {
+        err.locate_in(self.text().chars(), self.text_start, self.source_name.as_ref())
+    }
+
+    /// Moves past the text that caused the last scan error so scanning can continue.
+    ///
+    /// Strings are skipped to their closing quote or the end of the line, numbers are
+    /// skipped to the end of the characters that could be part of them, and otherwise
+    /// the character at the start of the token is skipped.
+    pub(crate) fn recover_from_error(&mut self) {
+        let start_char = match self.token_start_char() {
+            Some(start_char) => start_char,
+            None => return,
+        };
+
+        if start_char == '"' || start_char == '\'' {
+            if self.pos == self.token_start {
+                self.move_next_char();
+            }
+            let mut last_was_backslash = false;
+            while let Some(current_char) = self.current_char() {
+                if current_char == '\n' {
+                    break;
+                }
                 self.move_next_char();
+                if current_char == start_char && !last_was_backslash {
+                    break;
+                }
+                last_was_backslash = current_char == '\\' && !last_was_backslash;
+            }
+        } else if start_char == '-' || start_char == '.' || start_char.is_ascii_digit() {
+            while let Some(current_char) = self.current_char() {
+                if current_char.is_ascii_alphanumeric() || current_char == '.' || current_char == '-' || current_char == '+' {
+                    self.move_next_char();
+                } else {
+                    break;
+                }
             }
-        } else {
-            return Err(ParseError::new(self.pos, "Expected a digit to follow a negative sign."));
+        } else if self.pos == self.token_start {
+            self.move_next_char();
         }
+    }
 
-        if self.is_decimal_point() {
-            text.push('.');
-            self.move_next_char();
+    /// Skips the rest of the current value to resynchronize on the next value.
+    pub(crate) fn skip_to_next_value(&mut self) -> bool {
+        loop {
+            while let Some(current_char) = self.current_char() {
+                self.move_next_char();
+                if current_char == '\n' {
+                    break;
+                }
+            }
+            self.token_start = self.pos;
+            self.token_start_byte = self.byte_pos;
+            self.token_start_line = self.line_number;
 
-            if !self.is_digit() {
-                return Err(ParseError::new(self.pos, "Expected a digit."));
+            match self.current_char() {
+                None => return false,
+                Some(current_char) if self.is_value_start(current_char) => return true,
+                Some(_) => {},
             }
+        }
+    }
+
+    fn is_value_start(&self, c: char) -> bool {
+        match c {
+            '{' | '[' | '"' | '-' | 't' | 'f' | 'n' => true,
+            '\'' => self.options.single_quotes,
+            '.' => self.options.number_dialect.leading_decimal_point,
+            _ => c.is_ascii_digit(),
+        }
+    }
 
-            while self.is_digit() {
-                text.push(self.current_char().unwrap());
+    fn parse_string(&mut self) -> Result<ScannedToken, ParseError> {
+        debug_assert!(matches!(self.current_char(), Some('"') | Some('\'')));
+        self.move_next_char();
+        self.continue_string()
+    }
+
+    /// Scans the rest of the string from the current position, which is after its opening quote.
+    fn continue_string(&mut self) -> Result<ScannedToken, ParseError> {
+        let quote_char = self.token_quote();
+
+        while let Some(current_char) = self.current_char() {
+            if current_char == quote_char {
+                let end_byte = self.byte_pos;
                 self.move_next_char();
+                return Ok(ScannedToken::Token(RawToken::String(self.token_start_byte + 1, end_byte)));
+            }
+            if current_char != '\\' {
+                self.move_next_char();
+                continue;
             }
-        }
 
-        match self.current_char() {
-            Some('e') | Some('E') => {
-                text.push(self.current_char().unwrap());
-                match self.move_next_char() {
-                    Some('-') | Some('+') => {
-                        text.push(self.current_char().unwrap());
-                        self.move_next_char();
-                        if !self.is_digit() {
-                            return Err(ParseError::new(self.pos, "Expected a digit."));
-                        }
-                        while self.is_digit() {
-                            text.push(self.current_char().unwrap());
-                            self.move_next_char();
-                        }
-                    }
-                    _ => {
-                        return Err(ParseError::new(self.pos, "Expected plus or minus symbol in number literal."));
+            let escape_start = (self.pos, self.byte_pos, self.line_number);
+            match read_escape(quote_char, || self.move_next_char()) {
+                Ok(_) => {
+                    self.move_next_char();
+                },
+                // the text ending within an escape is the same as it ending within the string
+                Err(_) if self.current_char().is_none() => {
+                    self.pos = escape_start.0;
+                    self.byte_pos = escape_start.1;
+                    self.line_number = escape_start.2;
+                    break;
+                },
+                Err(message) => {
+                    if !self.options.tolerate_invalid_escapes {
+                        return Err(ParseError::new(escape_start.0, message));
                     }
-                }
+
+                    let end = if self.current_char() == Some(quote_char) { self.pos } else { self.pos + 1 };
+                    self.diagnostics.push(Diagnostic {
+                        range: Range {
+                            start: escape_start.0,
+                            end,
+                            start_line: escape_start.2,
+                            end_line: self.line_number,
+                        },
+                        kind: None,
+                        message: String::from(message),
+                    });
+                    // keep the backslash and continue scanning from the character after it
+                    self.pos = escape_start.0 + 1;
+                    self.byte_pos = escape_start.1 + 1;
+                    self.line_number = escape_start.2;
+                },
             }
-            _ => {},
         }
 
+        if self.is_partial {
+            return Ok(self.wait_for_text(PendingToken::String));
+        }
+        let quote_text = quote_char.to_string();
+        let opened_at = self.create_range(self.token_start, self.token_start + 1);
+        Err(ParseError::new_unclosed(self.token_start, opened_at, &quote_text, &format!("'{}'", quote_text)))
+    }
 
-        Ok(Token::Number(ImmutableString::new(text)))
+    fn parse_number(&mut self) -> Result<RawToken, ParseError> {
+        // number literals are ASCII, so byte offsets from the start of one are also character offsets
+        let to_pos = |byte_pos: usize| self.pos + byte_pos - self.byte_pos;
+        match scan_number(self.text(), self.byte_pos, &self.options.number_dialect, self.options.strict_numbers) {
+            Ok(end) => {
+                self.count_chars_read(end - self.byte_pos);
+                let end_pos = to_pos(end);
+                if matches!(self.text()[end..].chars().next(), Some(c) if c.is_alphanumeric()) {
+                    let message = get_unexpected_character_message(self.text(), self.byte_pos);
+                    if self.options.strict_numbers {
+                        return Err(ParseError::new(end_pos, &message));
+                    }
+                    // the number is scanned, but the characters after it are an error (unless they're a keyword)
+                    self.number_hint = Some((end_pos, message));
+                }
+                let start = self.byte_pos;
+                self.pos = end_pos;
+                self.byte_pos = end;
+                Ok(RawToken::Number(start, end))
+            },
+            Err(err) => {
+                // when the text ends mid-number, point at where the missing digit was expected
+                let pos = if err.kind == NumberErrorKind::UnexpectedEnd { to_pos(err.pos) } else { self.pos };
+                Err(ParseError::new_invalid_number(pos, self.create_range(self.pos, to_pos(err.end)), err.kind, &err.message))
+            },
+        }
     }
 
-    fn parse_comment_line(&mut self) -> Token {
-        let mut text = String::new();
+    fn parse_comment_line(&mut self) -> ScannedToken {
         self.assert_then_move_char('/');
-        #[cfg(debug_assertions)]
-        self.assert_char('/');
+        self.assert_then_move_char('/');
+        self.continue_comment_line()
+    }
 
-        while let Some(current_char) = self.move_next_char() {
-            if self.is_new_line() {
-                break;
+    /// Scans the rest of the comment line from the current position, which is after its slashes.
+    fn continue_comment_line(&mut self) -> ScannedToken {
+        while !self.is_new_line() {
+            match self.current_char() {
+                // a carriage return at the end could be followed by a newline
+                Some('\r') if self.is_partial && self.remaining_text().len() == 1 => return self.wait_for_text(PendingToken::CommentLine),
+                Some(_) => {
+                    self.move_next_char();
+                },
+                None if self.is_partial => return self.wait_for_text(PendingToken::CommentLine),
+                None => break,
             }
-            text.push(current_char);
         }
 
-        Token::CommentLine(ImmutableString::new(text))
+        ScannedToken::Token(RawToken::CommentLine(self.token_start_byte + 2, self.byte_pos))
     }
 
-    fn parse_comment_block(&mut self) -> Result<Token, ParseError> {
-        let token_start = self.pos;
-        let mut text = String::new();
+    fn parse_comment_block(&mut self) -> Result<ScannedToken, ParseError> {
         self.assert_then_move_char('/');
-        #[cfg(debug_assertions)]
-        self.assert_char('*');
-        let mut found_end = false;
+        self.assert_then_move_char('*');
+        self.continue_comment_block()
+    }
 
-        while let Some(current_char) = self.move_next_char() {
-            if current_char == '*' && self.peek_char() == Some('/') {
-                found_end = true;
-                break;
+    /// Scans the rest of the comment block from the current position, which is after its opening `/*`.
+    fn continue_comment_block(&mut self) -> Result<ScannedToken, ParseError> {
+        while !self.remaining_text().starts_with("*/") {
+            match self.current_char() {
+                // an asterisk at the end could be followed by a slash
+                Some('*') if self.is_partial && self.remaining_text().len() == 1 => return Ok(self.wait_for_text(PendingToken::CommentBlock)),
+                Some(_) => {
+                    self.move_next_char();
+                },
+                None if self.is_partial => return Ok(self.wait_for_text(PendingToken::CommentBlock)),
+                None => {
+                    let opened_at = self.create_range(self.token_start, self.token_start + 2);
+                    return Err(ParseError::new_unclosed(self.token_start, opened_at, "/*", "'*/'"));
+                },
             }
-            text.push(current_char);
         }
 
-        if found_end {
-            self.assert_then_move_char('*');
-            self.assert_then_move_char('/');
-            Ok(Token::CommentBlock(ImmutableString::new(text)))
-        } else {
-            Err(ParseError::new(token_start, "Unterminated comment block."))
+        let end = self.byte_pos;
+        self.assert_then_move_char('*');
+        self.assert_then_move_char('/');
+        Ok(ScannedToken::Token(RawToken::CommentBlock(self.token_start_byte + 2, end)))
+    }
+
+    /// Scans whitespace from the current position, which is within a whitespace token.
+    fn continue_whitespace(&mut self) -> ScannedToken {
+        self.skip_whitespace();
+        if self.is_partial && self.current_char().is_none() {
+            return self.wait_for_text(PendingToken::Whitespace);
         }
+        ScannedToken::Token(RawToken::Whitespace(self.token_start_byte, self.byte_pos))
     }
 
     fn skip_whitespace(&mut self) {
@@ -277,27 +878,14 @@ impl Scanner {
         }
     }
 
-    fn try_move_word(&mut self, text: &str) -> bool {
-        // todo: debug assert no newlines
-        let mut i = self.pos;
-        for c in text.chars() {
-            if let Some(current_char) = self.chars.get(i) {
-                if *current_char != c {
-                    return false;
-                }
-            } else {
-                return false;
-            }
-            i += 1;
-        }
-
-        if let Some(next_char) = self.chars.get(i) {
-            if next_char.is_alphanumeric() {
-                return false;
-            }
+    fn try_move_word(&mut self, word: &str) -> bool {
+        debug_assert!(!word.contains('\n'));
+        if !starts_with_word(self.remaining_text(), word) {
+            return false;
         }
 
-        self.pos = i;
+        self.pos += word.chars().count();
+        self.byte_pos += word.len();
         true
     }
 
@@ -315,66 +903,396 @@ impl Scanner {
     }
 
     fn move_next_char(&mut self) -> Option<char> {
-        if self.current_char() == Some('\n') {
-            self.line_number += 1;
+        if let Some(current_char) = self.current_char() {
+            self.count_chars_read(1);
+            if current_char == '\n' {
+                self.line_number += 1;
+            }
+            self.pos += 1;
+            self.byte_pos += current_char.len_utf8();
         }
-        self.pos += 1;
         self.current_char()
     }
 
-    fn peek_char(&self) -> Option<char> {
-        self.chars.get(self.pos + 1).map(|x| x.to_owned())
+    fn text(&self) -> &str {
+        self.text.as_ref()
+    }
+
+    /// Gets the text from the current position to the end.
+    fn remaining_text(&self) -> &str {
+        &self.text()[self.byte_pos..]
     }
 
     fn current_char(&self) -> Option<char> {
-        self.chars.get(self.pos).map(|x| x.to_owned())
+        self.remaining_text().chars().next()
     }
 
     fn is_new_line(&self) -> bool {
+        let text = self.remaining_text();
+        text.starts_with('\n') || text.starts_with("\r\n")
+    }
+
+    fn is_digit(&self) -> bool {
         match self.current_char() {
-            Some('\n') => true,
-            Some('\r') => self.peek_char() == Some('\n'),
+            Some(current_char) => current_char.is_ascii_digit(),
             _ => false,
         }
     }
+}
 
-    fn is_hex(&self) -> bool {
-        self.is_digit() || match self.current_char() {
-            Some(current_char) => current_char >= 'a' && current_char <= 'f'
-                || current_char >= 'A' && current_char <= 'F',
-            _ => false,
+impl RawScanner<String> {
+    /// Adds text to the end of the text being scanned.
+    ///
+    /// The text before the current token was scanned, so it's dropped once it's at least as long as
+    /// the rest of the text. That keeps the text from growing with each chunk of a large document while
+    /// moving each character a bounded number of times.
+    fn push_text(&mut self, text: &str) {
+        let scanned_len = self.token_start_byte;
+        if scanned_len > 0 && scanned_len >= self.text.len() - scanned_len {
+            for c in self.text[..scanned_len].chars() {
+                if c == '\n' {
+                    self.text_start.line += 1;
+                    self.text_start.column = 0;
+                } else {
+                    self.text_start.column += 1;
+                }
+            }
+            self.text_start.pos = self.token_start;
+            self.count_chars_read(self.text.len());
+            self.text.drain(..scanned_len);
+            self.byte_pos -= scanned_len;
+            self.token_start_byte = 0;
         }
+        self.text.push_str(text);
     }
+}
 
-    fn is_digit(&self) -> bool {
-        self.is_one_nine() || self.is_zero()
+/// Gets if the character ends a number or keyword.
+fn is_delimiter(value: char) -> bool {
+    value.is_whitespace() || matches!(value, '{' | '}' | '[' | ']' | ',' | ':' | '=' | '/' | '"' | '\'')
+}
+
+/// Gets if the text starts with the word and the word isn't followed by an alphanumeric character.
+fn starts_with_word(text: &str, word: &str) -> bool {
+    text.starts_with(word) && !matches!(text[word.len()..].chars().next(), Some(c) if c.is_alphanumeric())
+}
+
+/// Lazily scans tokens whose payloads borrow from the text instead of being copied.
+///
+/// The text is scanned where it is without being copied, so this doesn't allocate for tokens
+/// (ex. to create the `ImmutableString` payloads of `Token`). The iterator ends after the first error.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::TokenRefIterator;
+/// use jsonc_parser::tokens::TokenRef;
+///
+/// let tokens = TokenRefIterator::new(r#"{ "a": 5 }"#).map(|token| token.unwrap().token).collect::<Vec<_>>();
+/// assert_eq!(tokens, vec![
+///     TokenRef::OpenBrace,
+///     TokenRef::String("a"),
+///     TokenRef::Colon,
+///     TokenRef::Number("5"),
+///     TokenRef::CloseBrace,
+/// ]);
+/// ```
+pub struct TokenRefIterator<'a> {
+    scanner: RawScanner<&'a str>,
+    is_done: bool,
+}
+
+impl<'a> TokenRefIterator<'a> {
+    /// Creates a new iterator over the tokens in the provided text.
+    pub fn new(text: &'a str) -> TokenRefIterator<'a> {
+        TokenRefIterator::new_with_options(text, ScannerOptions::default())
     }
 
-    fn is_zero(&self) -> bool {
-        self.current_char() == Some('0')
+    /// Creates a new iterator over the tokens in the provided text using the provided options.
+    pub fn new_with_options(text: &'a str, options: ScannerOptions) -> TokenRefIterator<'a> {
+        TokenRefIterator {
+            scanner: RawScanner::new(text, options),
+            is_done: false,
+        }
     }
 
-    fn is_one_nine(&self) -> bool {
-        match self.current_char() {
-            Some(current_char) => current_char >= '1' && current_char <= '9',
-            _ => false,
+    fn slice(&self, start: usize, end: usize) -> &'a str {
+        let text: &'a str = self.scanner.text;
+        &text[start..end]
+    }
+}
+
+impl<'a> Iterator for TokenRefIterator<'a> {
+    type Item = Result<TokenRefAndRange<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+
+        let raw_token = match self.scanner.scan_raw() {
+            Ok(Some(raw_token)) => raw_token,
+            Ok(None) => {
+                self.is_done = true;
+                return None;
+            },
+            Err(err) => {
+                self.is_done = true;
+                return Some(Err(err));
+            },
+        };
+        let token = match raw_token {
+            RawToken::Token(token) => match token {
+                Token::OpenBrace => TokenRef::OpenBrace,
+                Token::CloseBrace => TokenRef::CloseBrace,
+                Token::OpenBracket => TokenRef::OpenBracket,
+                Token::CloseBracket => TokenRef::CloseBracket,
+                Token::Comma => TokenRef::Comma,
+                Token::Colon => TokenRef::Colon,
+                Token::Boolean(value) => TokenRef::Boolean(value),
+                Token::Null => TokenRef::Null,
+                _ => unreachable!(),
+            },
+            RawToken::String(start, end) => TokenRef::String(self.slice(start, end)),
+            RawToken::Number(start, end) => TokenRef::Number(self.slice(start, end)),
+            RawToken::CommentLine(start, end) => TokenRef::CommentLine(self.slice(start, end)),
+            RawToken::CommentBlock(start, end) => TokenRef::CommentBlock(self.slice(start, end)),
+            RawToken::Whitespace(start, end) => TokenRef::Whitespace(self.slice(start, end)),
+        };
+
+        Some(Ok(TokenRefAndRange {
+            token,
+            range: Range {
+                start: self.scanner.token_start(),
+                end: self.scanner.token_end(),
+                start_line: self.scanner.token_start_line(),
+                end_line: self.scanner.token_end_line(),
+            },
+        }))
+    }
+}
+
+/// Lazily scans the remaining tokens of a scanner along with their ranges.
+///
+/// This is created by `Scanner::into_iter` and ends after the first error.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::Scanner;
+/// use jsonc_parser::tokens::Token;
+///
+/// let tokens = Scanner::new("[1, /* a\nb */ 2]").into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+/// assert!(matches!(&tokens[3].token, Token::CommentBlock(text) if text.as_ref() == " a\nb "));
+/// assert_eq!((tokens[3].range.start, tokens[3].range.end), (4, 13));
+/// assert_eq!((tokens[3].range.start_line, tokens[3].range.end_line), (0, 1));
+/// ```
+pub struct TokenIterator {
+    scanner: Scanner,
+    is_done: bool,
+}
+
+impl Iterator for TokenIterator {
+    type Item = Result<TokenAndRange, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+
+        match self.scanner.scan() {
+            Ok(Some(token)) => Some(Ok(TokenAndRange {
+                token,
+                range: Range {
+                    start: self.scanner.token_start(),
+                    end: self.scanner.token_end(),
+                    start_line: self.scanner.token_start_line(),
+                    end_line: self.scanner.token_end_line(),
+                },
+            })),
+            Ok(None) => {
+                self.is_done = true;
+                None
+            },
+            Err(err) => {
+                self.is_done = true;
+                Some(Err(err))
+            },
         }
     }
+}
+
+impl IntoIterator for Scanner {
+    type Item = Result<TokenAndRange, ParseError>;
+    type IntoIter = TokenIterator;
+
+    fn into_iter(self) -> TokenIterator {
+        TokenIterator { scanner: self, is_done: false }
+    }
+}
+
+/// Scanner for text provided in chunks (ex. the chunks of a rope in an editor), which doesn't
+/// join the chunks first.
+///
+/// Chunks are taken from the iterator as the scanner reaches the end of the text it has, and only
+/// the text from the start of the current token on is kept. Positions are relative to the start of
+/// the first chunk and tokens may span chunks, so the tokens and positions are the same as scanning
+/// the joined text with a `Scanner`.
+///
+/// To parse text as it's received, use `events::ChunkedParser` instead.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::{ChunkScanner, ScannerOptions};
+/// use jsonc_parser::tokens::Token;
+///
+/// let mut scanner = ChunkScanner::new(vec!["[tr", "ue, 1", "0]"], ScannerOptions::default());
+/// scanner.scan().unwrap();
+/// assert_eq!(scanner.scan().unwrap(), Some(Token::Boolean(true)));
+/// scanner.scan().unwrap();
+/// scanner.scan().unwrap();
+/// assert_eq!((scanner.token_start(), scanner.token_end()), (7, 9));
+/// ```
+pub struct ChunkScanner<I: Iterator> {
+    scanner: Scanner,
+    chunks: I,
+}
+
+impl<I: Iterator> ChunkScanner<I>
+where
+    I::Item: AsRef<str>,
+{
+    /// Creates a new scanner based on the chunks of the text.
+    pub fn new(chunks: impl IntoIterator<IntoIter = I>, options: ScannerOptions) -> ChunkScanner<I> {
+        let mut scanner = Scanner::from_string(String::new(), options);
+        scanner.set_partial(true);
+        ChunkScanner {
+            scanner,
+            chunks: chunks.into_iter(),
+        }
+    }
+
+    /// Moves to and returns the next token.
+    pub fn scan(&mut self) -> Result<Option<Token>, ParseError> {
+        loop {
+            match self.scanner.scan_available()? {
+                ScannedToken::Token(raw_token) => {
+                    let token = self.scanner.create_token(raw_token);
+                    self.scanner.current_token = Some(token.clone());
+                    return Ok(Some(token));
+                },
+                ScannedToken::End => {
+                    self.scanner.current_token = None;
+                    return Ok(None);
+                },
+                ScannedToken::Incomplete => match self.chunks.next() {
+                    Some(chunk) => self.scanner.push_text(chunk.as_ref()),
+                    None => self.scanner.set_partial(false),
+                },
+            }
+        }
+    }
+
+    /// Gets the start position of the token.
+    pub fn token_start(&self) -> usize {
+        self.scanner.token_start()
+    }
+
+    /// Gets the end position of the token.
+    pub fn token_end(&self) -> usize {
+        self.scanner.token_end()
+    }
+
+    /// Gets the line the token starts on.
+    pub fn token_start_line(&self) -> usize {
+        self.scanner.token_start_line()
+    }
 
-    fn is_negative_sign(&self) -> bool {
-        self.current_char() == Some('-')
+    /// Gets the line the token ends on.
+    pub fn token_end_line(&self) -> usize {
+        self.scanner.token_end_line()
     }
 
-    fn is_decimal_point(&self) -> bool {
-        self.current_char() == Some('.')
+    /// Gets the current token.
+    pub fn token(&self) -> Option<Token> {
+        self.scanner.token()
     }
+
+    /// Gets the number of tokens scanned so far, including comments and whitespace.
+    pub fn tokens_scanned(&self) -> usize {
+        self.scanner.tokens_scanned()
+    }
+
+    /// Takes the problems found while scanning that didn't stop it (see `Scanner::take_diagnostics`).
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.scanner.take_diagnostics()
+    }
+}
+
+/// Scans all the remaining tokens along with their ranges.
+pub(crate) fn scan_tokens(scanner: &mut Scanner) -> Result<Vec<TokenAndRange>, ParseError> {
+    let mut tokens = Vec::new();
+
+    while let Some(token) = scanner.scan()? {
+        tokens.push(TokenAndRange {
+            token,
+            range: Range {
+                start: scanner.token_start(),
+                end: scanner.token_end(),
+                start_line: scanner.token_start_line(),
+                end_line: scanner.token_end_line(),
+            },
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Scans the text to its tokens and, in a parallel vector, their ranges.
+///
+/// This holds the same tokens as the `ParseResult::tokens` of `parse_text`, but keeps the tokens
+/// and ranges in separate vectors for consumers that process them separately, which also avoids
+/// the padding of a `TokenAndRange`.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::tokenize_soa;
+/// use jsonc_parser::tokens::Token;
+///
+/// let (tokens, ranges) = tokenize_soa("[1, 2] // test").unwrap();
+/// assert_eq!(tokens.len(), ranges.len());
+/// assert_eq!(tokens[0], Token::OpenBracket);
+/// assert_eq!((ranges[5].start, ranges[5].end), (7, 14));
+/// ```
+pub fn tokenize_soa(text: &str) -> Result<(Vec<Token>, Vec<Range>), ParseError> {
+    let mut scanner = Scanner::new(text);
+    let mut tokens = Vec::new();
+    let mut ranges = Vec::new();
+
+    while let Some(token) = scanner.scan()? {
+        tokens.push(token);
+        ranges.push(Range {
+            start: scanner.token_start(),
+            end: scanner.token_end(),
+            start_line: scanner.token_start_line(),
+            end_line: scanner.token_end_line(),
+        });
+    }
+
+    Ok((tokens, ranges))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Scanner;
-    use super::super::common::{ImmutableString};
-    use super::super::tokens::{Token};
+    use std::borrow::Cow;
+    use super::{scan_tokens, ChunkScanner, starts_with_word, tokenize_soa, Scanner, ScannerOptions, TokenRefIterator};
+    use super::super::numbers::NumberDialect;
+    use super::super::common::{ImmutableString, Range};
+    use super::super::errors::{ErrorKind, NumberErrorKind, ParseError};
+    use super::super::parse_tokens_to_ast;
+    use super::super::tokens::{Token, TokenAndRange, TokenRef};
 
     #[test]
     fn it_tokenizes_string() {
@@ -389,6 +1307,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_errors_at_invalid_string_escapes() {
+        let err = Scanner::new(r#""ab\x""#).scan().err().unwrap();
+        assert_eq!((err.pos, err.message.as_str()), (3, "Invalid escape."));
+        let err = Scanner::new(r#""\u12G4""#).scan().err().unwrap();
+        assert_eq!((err.pos, err.message.as_str()), (1, "Expected four hex digits."));
+    }
+
+    #[test]
+    fn it_keeps_invalid_string_escapes_when_tolerated() {
+        let options = ScannerOptions::default().with_tolerate_invalid_escapes(true);
+        assert_has_tokens_with_options(
+            r#"["a\qb", "\u12G4\n", "\u1", "\\"]"#,
+            options.clone(),
+            vec![
+                Token::OpenBracket,
+                Token::String(ImmutableString::from(r"a\qb")),
+                Token::Comma,
+                Token::String(ImmutableString::from(r"\u12G4\n")),
+                Token::Comma,
+                Token::String(ImmutableString::from(r"\u1")),
+                Token::Comma,
+                Token::String(ImmutableString::from(r"\\")),
+                Token::CloseBracket,
+            ],
+        );
+
+        let mut scanner = Scanner::new_with_options(r#"["a\qb", "\u12G4\n", "\u1"]"#, options);
+        while scanner.scan().unwrap().is_some() {}
+        let diagnostics = scanner.take_diagnostics();
+        let diagnostics = diagnostics.iter().map(|d| (d.range.start, d.range.end, d.message.as_str())).collect::<Vec<_>>();
+        assert_eq!(diagnostics, vec![
+            (3, 5, "Invalid escape."),
+            (10, 15, "Expected four hex digits."),
+            (22, 25, "Expected four hex digits."),
+        ]);
+        assert!(scanner.take_diagnostics().is_empty());
+    }
+
     #[test]
     fn it_tokenizes_numbers() {
         assert_has_tokens(
@@ -408,6 +1365,217 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_tokenizes_dialect_gated_numbers() {
+        assert_has_tokens_with_options(
+            "0x1F, -.5,",
+            ScannerOptions::default().with_number_dialect(NumberDialect::default().with_hexadecimal(true).with_leading_decimal_point(true)),
+            vec![
+                Token::Number(ImmutableString::from("0x1F")),
+                Token::Comma,
+                Token::Number(ImmutableString::from("-.5")),
+                Token::Comma,
+            ]
+        );
+    }
+
+    /// Scans until the first error, failing when the end of the text is reached without one.
+    fn scan_until_error(scanner: &mut Scanner) -> ParseError {
+        loop {
+            match scanner.scan() {
+                Ok(Some(_)) => {},
+                Ok(None) => panic!("Expected an error before the end of the text."),
+                Err(err) => return err,
+            }
+        }
+    }
+
+    #[test]
+    fn it_tokenizes_numbers_with_a_leading_decimal_point_when_enabled() {
+        let options = ScannerOptions::default().with_number_dialect(NumberDialect::default().with_leading_decimal_point(true));
+        assert_has_tokens_with_options("[.5, -.25, .5e3]", options.clone(), vec![
+            Token::OpenBracket,
+            Token::Number(ImmutableString::from(".5")),
+            Token::Comma,
+            Token::Number(ImmutableString::from("-.25")),
+            Token::Comma,
+            Token::Number(ImmutableString::from(".5e3")),
+            Token::CloseBracket,
+        ]);
+
+        let cases = [
+            (".", NumberErrorKind::UnexpectedEnd, 1),
+            ("-.", NumberErrorKind::UnexpectedEnd, 2),
+            ("[., 1]", NumberErrorKind::MissingFractionDigits, 1),
+            ("[-.e5]", NumberErrorKind::MissingFractionDigits, 1),
+        ];
+        for (text, kind, pos) in cases.iter() {
+            let err = scan_until_error(&mut Scanner::new_with_options(*text, options.clone()));
+            assert!(matches!(&err.kind, ErrorKind::InvalidNumber { kind: found, .. } if found == kind), "Text: {}", text);
+            assert_eq!(err.pos, *pos, "Text: {}", text);
+        }
+
+        // the default is strict
+        assert_eq!(Scanner::new(".5").scan().unwrap_err().message, "Unexpected token.");
+        assert_eq!(Scanner::new("-.25").scan().unwrap_err().message, "Expected a digit to follow a negative sign.");
+    }
+
+    #[test]
+    fn it_tokenizes_numbers_with_a_comma_decimal_separator_when_enabled() {
+        let options = ScannerOptions::default().with_number_dialect(NumberDialect::default().with_comma_decimal_separator(true));
+        assert_has_tokens_with_options("[3,14, 2]", options.clone(), vec![
+            Token::OpenBracket,
+            Token::Number(ImmutableString::from("3,14")),
+            Token::Comma,
+            Token::Number(ImmutableString::from("2")),
+            Token::CloseBracket,
+        ]);
+        assert_has_tokens_with_options("[3,14, 2]", ScannerOptions::default(), vec![
+            Token::OpenBracket,
+            Token::Number(ImmutableString::from("3")),
+            Token::Comma,
+            Token::Number(ImmutableString::from("14")),
+            Token::Comma,
+            Token::Number(ImmutableString::from("2")),
+            Token::CloseBracket,
+        ]);
+        // strict numbers only allow JSON numbers
+        let mut scanner = Scanner::new_with_options("3,14", options.with_strict_numbers(true));
+        assert_eq!(scanner.scan().unwrap(), Some(Token::Number(ImmutableString::from("3"))));
+    }
+
+    #[test]
+    fn it_errors_for_unclosed_strings_and_comment_blocks() {
+        let mut scanner = Scanner::new("[\n  \"a");
+        scanner.scan().unwrap();
+        let err = scanner.scan().expect_err("Expected an error.");
+        assert_eq!(err.pos, 4);
+        assert_eq!(err.message, "Unexpected end of file; '\"' opened on line 2 was never closed");
+        assert_eq!(err.kind, ErrorKind::UnexpectedEof {
+            expected: String::from("'\"'"),
+            opened_at: Some(Box::new(Range { start: 4, end: 5, start_line: 1, end_line: 1 })),
+            insertion_point: None,
+        });
+
+        let err = Scanner::new("\n\n/* a\n b").scan().expect_err("Expected an error.");
+        assert_eq!(err.pos, 2);
+        assert_eq!(err.message, "Unexpected end of file; '/*' opened on line 3 was never closed");
+        assert_eq!(err.kind, ErrorKind::UnexpectedEof {
+            expected: String::from("'*/'"),
+            opened_at: Some(Box::new(Range { start: 2, end: 4, start_line: 2, end_line: 2 })),
+            insertion_point: None,
+        });
+    }
+
+    #[test]
+    fn it_errors_for_non_json_numbers_when_strict() {
+        let options = ScannerOptions::default()
+            .with_number_dialect(NumberDialect::default().with_hexadecimal(true).with_leading_decimal_point(true))
+            .with_strict_numbers(true);
+        let cases = [
+            ("+1", 0, "Unexpected token."),
+            (".5", 0, "Unexpected token."),
+            ("5.", 2, "Unexpected end of text in number literal; expected a digit."),
+            ("01", 0, "Leading zeros are not allowed (did you mean 1?)"),
+            ("-01", 0, "Leading zeros are not allowed (did you mean -1?)"),
+            ("08", 0, "Leading zeros are not allowed (did you mean 8?)"),
+            ("0b2", 1, "Binary numbers are not allowed and 0b2 has digits other than 0 and 1."),
+            ("Infinity", 0, "Unexpected token."),
+            ("-Infinity", 0, "Expected a digit to follow a negative sign."),
+            ("NaN", 0, "Unexpected token."),
+            ("0x1F", 1, "Hexadecimal numbers are not allowed (did you mean 31?)"),
+            ("1x", 1, "Unexpected character in number literal."),
+        ];
+        for (text, pos, message) in cases.iter() {
+            let err = Scanner::new_with_options(*text, options.clone()).scan().expect_err(text);
+            assert_eq!((err.pos, err.message.as_str()), (*pos, *message), "Text: {}", text);
+        }
+
+        let mut scanner = Scanner::new_with_options("-0.5e+1", options);
+        assert_eq!(scanner.scan().unwrap(), Some(Token::Number(ImmutableString::from("-0.5e+1"))));
+    }
+
+    #[test]
+    fn it_hints_at_numbers_in_other_forms_when_not_strict() {
+        let hex_options = ScannerOptions::default().with_number_dialect(NumberDialect::default().with_hexadecimal(true));
+        let cases = [
+            ("0b2", hex_options.clone(), "Binary numbers are not allowed and 0b2 has digits other than 0 and 1."),
+            ("0b101", hex_options.clone(), "Binary numbers are not allowed (did you mean 5?)"),
+            ("-0o17", hex_options, "Octal numbers are not allowed (did you mean -15?)"),
+            ("0b2", ScannerOptions::default(), "Binary numbers are not allowed and 0b2 has digits other than 0 and 1."),
+            ("0o17", ScannerOptions::default(), "Octal numbers are not allowed (did you mean 15?)"),
+            ("0x1F", ScannerOptions::default(), "Hexadecimal numbers are not allowed (did you mean 31?)"),
+            ("1x", ScannerOptions::default(), "Unexpected character in number literal."),
+        ];
+        for (text, options, message) in cases.iter() {
+            let mut scanner = Scanner::new_with_options(*text, options.clone());
+            assert!(matches!(scanner.scan().unwrap(), Some(Token::Number(_))), "Text: {}", text);
+            let err = scanner.scan().expect_err(text);
+            assert_eq!((err.pos, err.message.as_str()), (text.find(|c: char| c.is_alphabetic()).unwrap(), *message), "Text: {}", text);
+        }
+
+        // the hint is only for the characters right after the number
+        let err = scan_until_error(&mut Scanner::new("[0, x]"));
+        assert_eq!((err.pos, err.message.as_str()), (4, "Unexpected token."));
+    }
+
+    #[test]
+    fn it_errors_for_malformed_numbers() {
+        let options = ScannerOptions::default().with_strict_numbers(true);
+        let cases = [
+            ("[- 1]", NumberErrorKind::BareMinus, "Expected a digit to follow a negative sign.", 1, 1..2),
+            ("[-a]", NumberErrorKind::BareMinus, "Expected a digit to follow a negative sign.", 1, 1..3),
+            ("[0123]", NumberErrorKind::LeadingZeros, "Leading zeros are not allowed (did you mean 123, or 83 for octal 0123?)", 1, 1..5),
+            ("[-00]", NumberErrorKind::LeadingZeros, "Leading zeros are not allowed (did you mean -0?)", 1, 1..4),
+            ("[1.e5]", NumberErrorKind::MissingFractionDigits, "Expected a digit after the decimal point.", 1, 1..5),
+            ("[5.]", NumberErrorKind::MissingFractionDigits, "Expected a digit after the decimal point.", 1, 1..3),
+            ("[1e+]", NumberErrorKind::MissingExponentDigits, "Expected a digit in the exponent.", 1, 1..4),
+            ("[1.5E, 2]", NumberErrorKind::MissingExponentDigits, "Expected a digit in the exponent.", 1, 1..5),
+            ("[-", NumberErrorKind::UnexpectedEnd, "Unexpected end of text in number literal; expected a digit.", 2, 1..2),
+            ("[1.", NumberErrorKind::UnexpectedEnd, "Unexpected end of text in number literal; expected a digit.", 3, 1..3),
+            ("[1e-", NumberErrorKind::UnexpectedEnd, "Unexpected end of text in number literal; expected a digit.", 4, 1..4),
+            ("[1e", NumberErrorKind::UnexpectedEnd, "Unexpected end of text in number literal; expected a digit.", 3, 1..3),
+        ];
+        for (text, kind, message, pos, range) in cases.iter() {
+            let mut scanner = Scanner::new_with_options(*text, options.clone());
+            assert_eq!(scanner.scan().unwrap(), Some(Token::OpenBracket));
+            let err = scanner.scan().expect_err(text);
+            let expected_kind = ErrorKind::InvalidNumber {
+                kind: *kind,
+                range: Range { start: range.start, end: range.end, start_line: 0, end_line: 0 },
+            };
+            assert_eq!((err.pos, err.message.as_str(), &err.kind), (*pos, *message, &expected_kind), "Text: {}", text);
+        }
+
+        let options = ScannerOptions::default().with_number_dialect(NumberDialect::default().with_hexadecimal(true));
+        let err = Scanner::new_with_options("0x10000000000000000", options).scan().unwrap_err();
+        assert_eq!((err.pos, err.message.as_str()), (0, "Hexadecimal number is too large."));
+        assert_eq!(err.kind, ErrorKind::InvalidNumber {
+            kind: NumberErrorKind::HexOverflow,
+            range: Range { start: 0, end: 19, start_line: 0, end_line: 0 },
+        });
+
+        // numbers that aren't strict end before the digits after a leading zero
+        let mut scanner = Scanner::new("[08]");
+        assert_eq!(scanner.scan().unwrap(), Some(Token::OpenBracket));
+        assert_eq!(scanner.scan().unwrap(), Some(Token::Number(ImmutableString::from("0"))));
+        assert_eq!(scanner.scan().unwrap(), Some(Token::Number(ImmutableString::from("8"))));
+    }
+
+    #[test]
+    fn it_tokenizes_exponents_with_an_optional_sign() {
+        for text in ["1e5", "1E5", "0e0", "1e+5", "1e-5", "2E10"].iter() {
+            assert_has_tokens(text, vec![Token::Number(ImmutableString::from(text))]);
+        }
+
+        let err = Scanner::new("1e").scan().unwrap_err();
+        assert_eq!((err.pos, err.message.as_str()), (2, "Unexpected end of text in number literal; expected a digit."));
+        assert_eq!(err.kind, ErrorKind::InvalidNumber {
+            kind: NumberErrorKind::UnexpectedEnd,
+            range: Range { start: 0, end: 2, start_line: 0, end_line: 0 },
+        });
+    }
+
     #[test]
     fn it_tokenizes_simple_tokens() {
         assert_has_tokens(
@@ -451,18 +1619,310 @@ mod tests {
             ]);
     }
 
+    #[test]
+    fn it_tokenizes_whitespace_when_lossless() {
+        let mut scanner = Scanner::new_lossless(" {\n\t1 }");
+        let mut scanned_tokens = Vec::new();
+        while let Some(token) = scanner.scan().unwrap() {
+            scanned_tokens.push(token);
+        }
+
+        assert_eq!(scanned_tokens, vec![
+            Token::Whitespace(ImmutableString::from(" ")),
+            Token::OpenBrace,
+            Token::Whitespace(ImmutableString::from("\n\t")),
+            Token::Number(ImmutableString::from("1")),
+            Token::Whitespace(ImmutableString::from(" ")),
+            Token::CloseBrace,
+        ]);
+    }
+
+    #[test]
+    fn it_tokenizes_single_quoted_strings_when_enabled() {
+        assert_has_tokens_with_options(
+            r#"'a"b\'c', "d'e""#,
+            ScannerOptions::default().with_single_quotes(true),
+            vec![
+                Token::String(ImmutableString::from(r#"a"b\'c"#)),
+                Token::Comma,
+                Token::String(ImmutableString::from("d'e")),
+            ]);
+
+        let err = Scanner::new("'a'").scan().err().unwrap();
+        assert_eq!(err.message, "Unexpected token.");
+        let err = Scanner::new(r#""\'""#).scan().err().unwrap();
+        assert_eq!(err.message, "Invalid escape.");
+    }
+
+    fn scan_chunks<'a>(chunks: impl IntoIterator<Item = &'a str>, options: ScannerOptions) -> Result<Vec<TokenAndRange>, ParseError> {
+        let mut scanner = ChunkScanner::new(chunks, options);
+        let mut tokens = Vec::new();
+        while let Some(token) = scanner.scan()? {
+            tokens.push(TokenAndRange {
+                token,
+                range: Range {
+                    start: scanner.token_start(),
+                    end: scanner.token_end(),
+                    start_line: scanner.token_start_line(),
+                    end_line: scanner.token_end_line(),
+                },
+            });
+        }
+        Ok(tokens)
+    }
+
+    #[test]
+    fn it_scans_text_provided_in_chunks() {
+        let text = "{\n  \"k\u{e9}y\": [\"a\\\"b\", -1.5e+3, true, null], // c\u{f6}mment\r\n  /* \u{1F600}\n */ \"b\": false\n}";
+        let options = ScannerOptions::default().with_whitespace(true);
+        let expected = scan_tokens(&mut Scanner::new_with_options(text, options.clone())).unwrap();
+
+        let char_chunks = text.char_indices().map(|(i, c)| &text[i..i + c.len_utf8()]).collect::<Vec<_>>();
+        assert_eq!(scan_chunks(char_chunks, options.clone()).unwrap(), expected);
+        assert_eq!(scan_chunks(vec![text], options.clone()).unwrap(), expected);
+        assert_eq!(scan_chunks(vec!["", text, ""], options).unwrap(), expected);
+
+        let ascii_text = "[1, \"two\", {\"a\": null}, 0.5e-3] // end";
+        let byte_chunks = (0..ascii_text.len()).map(|i| &ascii_text[i..i + 1]);
+        assert_eq!(scan_chunks(byte_chunks, ScannerOptions::default()).unwrap(), scan_tokens(&mut Scanner::new(ascii_text)).unwrap());
+    }
+
+    #[test]
+    fn it_scans_borrowed_and_owned_text() {
+        let text = "[1, \"\u{e9}\"] // c";
+        let expected = scan_tokens(&mut Scanner::new(text)).unwrap();
+        let owned = String::from(text);
+        assert_eq!(scan_tokens(&mut Scanner::new(&owned)).unwrap(), expected);
+        assert_eq!(scan_tokens(&mut Scanner::new(owned)).unwrap(), expected);
+        assert_eq!(scan_tokens(&mut Scanner::new(Cow::Borrowed(text))).unwrap(), expected);
+        assert_eq!(scan_tokens(&mut Scanner::new(Cow::Owned(String::from(text)))).unwrap(), expected);
+        assert_eq!(scan_tokens(&mut Scanner::new_with_options(String::from(text), ScannerOptions::default())).unwrap(), expected);
+
+        // owned text is moved into the scanner instead of being copied
+        let owned = String::from(text);
+        let ptr = owned.as_ptr();
+        assert_eq!(Scanner::new(owned).raw.text.as_ptr(), ptr);
+        let owned: Cow<str> = Cow::Owned(String::from(text));
+        let ptr = owned.as_ptr();
+        assert_eq!(Scanner::builder(owned).build().raw.text.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn it_errors_at_global_positions_when_scanning_chunks() {
+        let mut scanner = ChunkScanner::new(vec!["[1,\n", " tr", "ux]"], ScannerOptions::default());
+        for _ in 0..3 {
+            scanner.scan().unwrap();
+        }
+        let err = scanner.scan().err().unwrap();
+        assert_eq!(err.to_string(), "2:2: Unexpected token.");
+    }
+
+    #[test]
+    fn it_matches_words_at_word_boundaries() {
+        assert_has_tokens("true,", vec![Token::Boolean(true), Token::Comma]);
+        assert_has_tokens("[null]", vec![Token::OpenBracket, Token::Null, Token::CloseBracket]);
+        for text in ["truex", "true\u{e4}", "nul", "nullnull"].iter() {
+            let err = Scanner::new(*text).scan().err().unwrap();
+            assert_eq!((err.pos, err.message.as_str()), (0, "Unexpected token."), "{}", text);
+        }
+
+        // positions stay correct after multi-byte characters
+        let mut scanner = Scanner::new("[\"\u{1F600}\", true\u{e4}]");
+        for _ in 0..3 {
+            scanner.scan().unwrap();
+        }
+        let err = scanner.scan().err().unwrap();
+        assert_eq!((err.pos, err.message.as_str()), (6, "Unexpected token."));
+    }
+
+    #[test]
+    fn it_checks_for_words_in_text() {
+        assert!(starts_with_word("true", "true"));
+        assert!(starts_with_word("true]", "true"));
+        assert!(starts_with_word("true\u{1F600}", "true"));
+        assert!(!starts_with_word("true\u{e4}", "true"));
+        assert!(!starts_with_word("tru", "true"));
+        assert!(!starts_with_word("\u{e4}true", "true"));
+    }
+
+    #[test]
+    fn it_errors_for_comments_when_disabled() {
+        let mut scanner = Scanner::new_with_options("[// a\n]", ScannerOptions::default().with_comments(false));
+        assert_eq!(scanner.scan().unwrap(), Some(Token::OpenBracket));
+        let err = scanner.scan().err().unwrap();
+        assert_eq!((err.pos, err.message.as_str()), (1, "Comments are not allowed."));
+    }
+
+    #[test]
+    fn it_borrows_token_payloads_from_the_text() {
+        let text = "{ \"k\u{e9}y\": [\"a\\\"b\", -1.5e+3, true, null], // c\u{f6}mment\r\n /* \u{1F600} */ }";
+        let options = ScannerOptions::default().with_whitespace(true);
+        let text_range = text.as_ptr() as usize..text.as_ptr() as usize + text.len();
+        let mut scanner = Scanner::new_with_options(text, options.clone());
+        let mut count = 0;
+
+        for token_and_range in TokenRefIterator::new_with_options(text, options) {
+            let token_and_range = token_and_range.unwrap();
+            let payload = match token_and_range.token {
+                TokenRef::String(payload) | TokenRef::Number(payload) | TokenRef::CommentLine(payload)
+                | TokenRef::CommentBlock(payload) | TokenRef::Whitespace(payload) => Some(payload),
+                _ => None,
+            };
+            if let Some(payload) = payload {
+                assert!(text_range.contains(&(payload.as_ptr() as usize)));
+            }
+            assert_eq!(Some(token_and_range.token.to_token()), scanner.scan().unwrap());
+            assert_eq!((token_and_range.range.start, token_and_range.range.end), (scanner.token_start(), scanner.token_end()));
+            count += 1;
+        }
+
+        assert_eq!(scanner.scan().unwrap(), None);
+        assert_eq!(count, 24);
+    }
+
+    #[test]
+    fn it_ends_borrowed_tokens_at_the_first_error() {
+        let mut tokens = TokenRefIterator::new("[1, tru]");
+        assert_eq!(tokens.next().unwrap().unwrap().token, TokenRef::OpenBracket);
+        assert_eq!(tokens.next().unwrap().unwrap().token, TokenRef::Number("1"));
+        assert_eq!(tokens.next().unwrap().unwrap().token, TokenRef::Comma);
+        assert_eq!(tokens.next().unwrap().unwrap_err().message, "Unexpected token.");
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn it_iterates_tokens_with_their_ranges() {
+        let text = "{\n  // a\n  \"b\": /* c\n d */ 1\n}";
+        let options = ScannerOptions::default();
+        let tokens = Scanner::new_with_options(text, options.clone()).into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(tokens, scan_tokens(&mut Scanner::new_with_options(text, options)).unwrap());
+        assert_eq!(tokens[1], TokenAndRange {
+            token: Token::CommentLine(ImmutableString::from(" a")),
+            range: Range { start: 4, end: 8, start_line: 1, end_line: 1 },
+        });
+        assert_eq!(tokens[4], TokenAndRange {
+            token: Token::CommentBlock(ImmutableString::from(" c\n d ")),
+            range: Range { start: 16, end: 26, start_line: 2, end_line: 3 },
+        });
+        assert_eq!(tokens[6].range, Range { start: 29, end: 30, start_line: 4, end_line: 4 });
+    }
+
+    #[test]
+    fn it_ends_token_iteration_at_the_first_error() {
+        let mut tokens = Scanner::new("[1, tru]").into_iter();
+        assert_eq!(tokens.next().unwrap().unwrap().token, Token::OpenBracket);
+        assert_eq!(tokens.next().unwrap().unwrap().token, Token::Number(ImmutableString::from("1")));
+        assert_eq!(tokens.next().unwrap().unwrap().token, Token::Comma);
+        assert_eq!(tokens.next().unwrap().unwrap_err().message, "Unexpected token.");
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn it_formats_errors_with_source_name() {
+        let err = scan_until_error(&mut Scanner::builder("[\n  1,\n  tru]").source_name("tsconfig.json").build());
+        assert_eq!((err.line, err.column), (2, 2));
+        assert_eq!(err.source_name.as_ref().map(|name| name.as_ref()), Some("tsconfig.json"));
+        assert_eq!(err.to_string(), "tsconfig.json:3:3: Unexpected token.");
+    }
+
+    #[test]
+    fn it_formats_errors_without_source_name() {
+        let mut scanner = Scanner::builder(String::from("\"a\" /")).options(ScannerOptions::default().with_comments(false)).build();
+        scanner.scan().unwrap();
+        let err = scanner.scan().err().unwrap();
+        assert_eq!(err.source_name, None);
+        assert_eq!(err.to_string(), "1:5: Comments are not allowed.");
+    }
+
+    #[test]
+    fn it_tokenizes_to_parallel_vectors() {
+        let text = "{\n  \"a\": [1, true], // comment\n  /* b */ \"b\": null\n}";
+        let (tokens, ranges) = tokenize_soa(text).unwrap();
+        let expected = scan_tokens(&mut Scanner::new(text)).unwrap();
+        assert_eq!(tokens.len(), ranges.len());
+        assert_eq!(tokens.len(), expected.len());
+        for ((token, range), expected) in tokens.into_iter().zip(ranges).zip(expected) {
+            assert_eq!(TokenAndRange { token, range }, expected);
+        }
+
+        let err = tokenize_soa("[1, 'a']").err().unwrap();
+        assert_eq!(err.to_string(), "1:5: Unexpected token.");
+    }
+
     fn assert_has_tokens(text: &str, tokens: Vec<Token>) {
-        let mut scanner = Scanner::new(text);
+        assert_has_tokens_with_options(text, ScannerOptions::default(), tokens);
+    }
+
+    fn assert_has_tokens_with_options(text: &str, options: ScannerOptions, tokens: Vec<Token>) {
+        let mut scanner = Scanner::new_with_options(text, options);
         let mut scanned_tokens = Vec::new();
 
         loop {
             match scanner.scan() {
                 Ok(Some(token)) => scanned_tokens.push(token),
                 Ok(None) => break,
-                Err(err) => panic!("Error parsing: {:?}", err),
+                Err(err) => panic!("Error parsing: {}", err.to_test_string(text)),
             }
         }
 
         assert_eq!(scanned_tokens, tokens);
     }
+
+    #[test]
+    #[should_panic(expected = "Error parsing: 2:4: Unexpected token.\n  [x]\n   ^")]
+    fn it_shows_the_line_of_errors_when_asserting_tokens() {
+        assert_has_tokens("{\n  [x]\n}", vec![Token::OpenBrace]);
+    }
+
+    #[test]
+    fn it_skips_to_the_next_value_after_a_broken_record() {
+        let text = "{ \"a\": [1,\n  \"b\" tru] }\n// comment\n  2\n{ \"c\": [\n  3\n] }\n";
+        let mut scanner = Scanner::new(text);
+        loop {
+            match scanner.scan() {
+                Ok(Some(_)) => {},
+                Ok(None) => unreachable!(),
+                Err(err) => {
+                    assert_eq!(err.line, 1);
+                    break;
+                },
+            }
+        }
+
+        assert!(scanner.skip_to_next_value());
+        let mut tokens = Vec::new();
+        while let Some(token) = scanner.scan().unwrap() {
+            tokens.push(TokenAndRange {
+                token,
+                range: Range {
+                    start: scanner.token_start(),
+                    end: scanner.token_end(),
+                    start_line: scanner.token_start_line(),
+                    end_line: scanner.token_end_line(),
+                },
+            });
+        }
+        assert_eq!(tokens[0].range.start_line, 4);
+        let value = parse_tokens_to_ast(&tokens, text).unwrap().value.unwrap().into_value();
+        assert_eq!(value.to_string(), r#"{"c":[3]}"#);
+
+        assert!(!scanner.skip_to_next_value());
+        assert_eq!(scanner.scan().unwrap(), None);
+    }
+
+    #[test]
+    fn it_does_not_end_strings_at_escaped_quotes() {
+        for text in [r#""a\""#, r#""a\"#, r#""a\u00"#].iter() {
+            let err = Scanner::new(*text).scan().expect_err("Expected an error.");
+            assert_eq!(err.pos, 0);
+            assert_eq!(err.message, "Unexpected end of file; '\"' opened on line 1 was never closed");
+        }
+
+        assert_has_tokens(r#""a\\""#, vec![Token::String(ImmutableString::from(r#"a\\"#))]);
+        assert_has_tokens(r#""a\\" "b""#, vec![
+            Token::String(ImmutableString::from(r#"a\\"#)),
+            Token::String(ImmutableString::from("b")),
+        ]);
+        assert_eq!(Scanner::new(r#""a\x""#).scan().expect_err("Expected an error.").message, "Invalid escape.");
+    }
 }
@@ -0,0 +1,247 @@
+use super::access::AccessValue;
+use super::ast::*;
+use super::diagnostics::Diagnostic;
+use super::strings::decode_string;
+
+/// A lightweight description of the expected shape of a value, which is much simpler than
+/// JSON Schema.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Schema {
+    /// Any value.
+    Any,
+    Null,
+    Boolean,
+    Number,
+    String,
+    /// An array whose elements match the schema.
+    Array(Box<Schema>),
+    Object(ObjectSchema),
+    /// A value that matches any of the schemas (ex. a string or a number).
+    OneOf(Vec<Schema>),
+}
+
+impl Schema {
+    /// Creates a schema for an array whose elements match the provided schema.
+    pub fn array_of(element: Schema) -> Schema {
+        Schema::Array(Box::new(element))
+    }
+
+    /// Gets if the kind of the value matches the schema, without checking its children.
+    fn matches_kind(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Schema::Any, _)
+            | (Schema::Null, Value::NullKeyword(_))
+            | (Schema::Boolean, Value::BooleanLit(_))
+            | (Schema::Number, Value::NumberLit(_))
+            | (Schema::String, Value::StringLit(_))
+            | (Schema::Array(_), Value::Array(_))
+            | (Schema::Object(_), Value::Object(_)) => true,
+            (Schema::OneOf(schemas), value) => schemas.iter().any(|schema| schema.matches_kind(value)),
+            _ => false,
+        }
+    }
+
+    /// Gets the description of the expected value (ex. `a string or a number`).
+    fn description(&self) -> String {
+        String::from(match self {
+            Schema::Any => "any value",
+            Schema::Null => "null",
+            Schema::Boolean => "a boolean",
+            Schema::Number => "a number",
+            Schema::String => "a string",
+            Schema::Array(_) => "an array",
+            Schema::Object(_) => "an object",
+            Schema::OneOf(schemas) => return schemas.iter().map(|schema| schema.description()).collect::<Vec<_>>().join(" or "),
+        })
+    }
+}
+
+/// The expected properties of an object.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ObjectSchema {
+    pub properties: Vec<PropertySchema>,
+    /// Whether properties that aren't described are allowed. Defaults to `true`.
+    pub allow_unknown_properties: bool,
+}
+
+impl Default for ObjectSchema {
+    fn default() -> ObjectSchema {
+        ObjectSchema {
+            properties: Vec::new(),
+            allow_unknown_properties: true,
+        }
+    }
+}
+
+impl ObjectSchema {
+    /// Adds a property that must be in the object.
+    pub fn with_required(mut self, name: &str, schema: Schema) -> Self {
+        self.properties.push(PropertySchema {
+            name: String::from(name),
+            schema,
+            required: true,
+        });
+        self
+    }
+
+    /// Adds a property that may be left out of the object.
+    pub fn with_optional(mut self, name: &str, schema: Schema) -> Self {
+        self.properties.push(PropertySchema {
+            name: String::from(name),
+            schema,
+            required: false,
+        });
+        self
+    }
+
+    /// Sets whether properties that aren't described are allowed.
+    pub fn with_allow_unknown_properties(mut self, value: bool) -> Self {
+        self.allow_unknown_properties = value;
+        self
+    }
+}
+
+/// The expected value of an object property.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PropertySchema {
+    pub name: String,
+    pub schema: Schema,
+    /// Whether the property must be in the object.
+    pub required: bool,
+}
+
+/// Validates the value against the schema and reports each mismatch at the range of the
+/// offending value.
+///
+/// Missing required properties are reported at the range of the object and unknown properties
+/// at the range of their name. The children of a value with the wrong kind aren't validated.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::schema::{validate_schema, ObjectSchema, Schema};
+///
+/// let schema = Schema::Object(ObjectSchema::default()
+///     .with_required("name", Schema::String)
+///     .with_optional("ports", Schema::array_of(Schema::Number)));
+/// let value = parse_text(r#"{ "name": "app", "ports": [80, "443"] }"#).unwrap().value.unwrap();
+/// let diagnostics = validate_schema(&value, &schema);
+/// assert_eq!(diagnostics[0].message, "Expected a number, but found a string.");
+/// assert_eq!(diagnostics[0].range.start, 31);
+/// ```
+pub fn validate_schema(value: &Value, schema: &Schema) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    validate_value(value, schema, &mut diagnostics);
+    diagnostics
+}
+
+fn validate_value(value: &Value, schema: &Schema, diagnostics: &mut Vec<Diagnostic>) {
+    if !schema.matches_kind(value) {
+        diagnostics.push(Diagnostic {
+            range: value.range().clone(),
+            kind: None,
+            message: format!("Expected {}, but found {}.", schema.description(), get_kind_description(value)),
+        });
+        return;
+    }
+
+    match (schema, value) {
+        (Schema::Array(element_schema), Value::Array(arr)) => {
+            for element in arr.elements.iter() {
+                validate_value(element, element_schema, diagnostics);
+            }
+        },
+        (Schema::Object(object_schema), Value::Object(obj)) => validate_object(obj, object_schema, diagnostics),
+        (Schema::OneOf(schemas), value) => {
+            // validate the children against the first schema of the value's kind
+            if let Some(schema) = schemas.iter().find(|schema| schema.matches_kind(value)) {
+                validate_value(value, schema, diagnostics);
+            }
+        },
+        _ => {},
+    }
+}
+
+fn validate_object(obj: &Object, schema: &ObjectSchema, diagnostics: &mut Vec<Diagnostic>) {
+    for prop_schema in schema.properties.iter() {
+        match obj.get(&prop_schema.name) {
+            Some(value) => validate_value(value, &prop_schema.schema, diagnostics),
+            None if prop_schema.required => diagnostics.push(Diagnostic {
+                range: obj.range.clone(),
+                kind: None,
+                message: format!("Missing required property \"{}\".", prop_schema.name),
+            }),
+            None => {},
+        }
+    }
+
+    if !schema.allow_unknown_properties {
+        for prop in obj.properties.iter() {
+            let name = decode_string(prop.name.value.as_ref(), prop.name.quote);
+            if !schema.properties.iter().any(|prop_schema| prop_schema.name == name) {
+                diagnostics.push(Diagnostic {
+                    range: prop.name.range.clone(),
+                    kind: None,
+                    message: format!("Unknown property \"{}\".", name),
+                });
+            }
+        }
+    }
+}
+
+fn get_kind_description(value: &Value) -> String {
+    match value {
+        Value::NullKeyword(_) => String::from("null"),
+        Value::Array(_) | Value::Object(_) => format!("an {}", value.kind_name()),
+        _ => format!("a {}", value.kind_name()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse_text;
+
+    fn get_diagnostics(text: &str, schema: &Schema) -> Vec<(String, String)> {
+        let value = parse_text(text).unwrap().value.unwrap();
+        validate_schema(&value, schema).into_iter().map(|diagnostic| {
+            let range_text = text.chars().skip(diagnostic.range.start).take(diagnostic.range.end - diagnostic.range.start).collect();
+            (range_text, diagnostic.message)
+        }).collect()
+    }
+
+    fn create_schema() -> Schema {
+        Schema::Object(ObjectSchema::default()
+            .with_required("name", Schema::String)
+            .with_optional("port", Schema::OneOf(vec![Schema::Number, Schema::String]))
+            .with_optional("options", Schema::Object(ObjectSchema::default()
+                .with_required("strict", Schema::Boolean)
+                .with_allow_unknown_properties(false)))
+            .with_optional("tags", Schema::array_of(Schema::String)))
+    }
+
+    #[test]
+    fn it_reports_type_mismatches() {
+        let text = r#"{ "name": 5, "port": true, "options": { "strict": null, "extra": 1 }, "tags": ["a", {}] }"#;
+        assert_eq!(get_diagnostics(text, &create_schema()), vec![
+            (String::from("5"), String::from("Expected a string, but found a number.")),
+            (String::from("true"), String::from("Expected a number or a string, but found a boolean.")),
+            (String::from("null"), String::from("Expected a boolean, but found null.")),
+            (String::from(r#""extra""#), String::from(r#"Unknown property "extra"."#)),
+            (String::from("{}"), String::from("Expected a string, but found an object.")),
+        ]);
+        assert!(get_diagnostics(r#"{ "name": "a", "port": "80", "other": 1, "tags": [] }"#, &create_schema()).is_empty());
+    }
+
+    #[test]
+    fn it_reports_missing_required_properties() {
+        let text = "{\n  \"options\": {}\n}";
+        let diagnostics = get_diagnostics(text, &create_schema());
+        assert_eq!(diagnostics, vec![
+            (String::from(text), String::from(r#"Missing required property "name"."#)),
+            (String::from("{}"), String::from(r#"Missing required property "strict"."#)),
+        ]);
+        assert_eq!(get_diagnostics("[]", &create_schema())[0].1, "Expected an object, but found an array.");
+    }
+}
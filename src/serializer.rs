@@ -0,0 +1,821 @@
+use std::fmt;
+use std::io;
+use super::ast::*;
+use super::parser::ParseResult;
+use super::strings::{decode_string, escape_string, write_escaped_string, write_escaped_wtf8_string, EscapeOptions};
+use super::value::JsonValue;
+
+/// How the output is laid out.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SerializeStyle {
+    /// No whitespace (ex. `{"a":[1,2]}`).
+    Compact,
+    /// Each property and element on its own indented line.
+    Pretty,
+    /// A single line with a space after each colon and comma (ex. `{"a": [1, 2]}`).
+    OneLine,
+}
+
+/// How property names are quoted when serializing or inserting properties.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum KeyQuoteStyle {
+    /// Use the style most of the sibling properties use, or double quotes when there are no
+    /// siblings or as many use each style. When serializing, names are quoted like strings.
+    Detect,
+    /// Surround the name in double quotes.
+    Double,
+    /// Surround the name in single quotes.
+    Single,
+    /// Leave the name unquoted when it's an identifier. Unquoted names can't be parsed yet, so
+    /// until they can, names are surrounded in double quotes the same as `Double`.
+    PreferUnquoted,
+}
+
+/// Options for serializing a `JsonValue`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SerializeOptions {
+    /// Layout of the output. Defaults to `SerializeStyle::Compact`.
+    pub style: SerializeStyle,
+    /// Number of spaces per indentation level in pretty output. Defaults to `2`.
+    pub indent_width: usize,
+    /// Options for escaping strings.
+    pub escape_options: EscapeOptions,
+    /// Whether to write a comma after the last property or element in pretty output,
+    /// which is only valid JSONC. Defaults to `false`.
+    pub trailing_commas: bool,
+    /// Whether to end the output with a newline, which many tools expect at the end of a file.
+    /// Defaults to `false`.
+    pub trailing_newline: bool,
+    /// Whether to write a space before each colon (ex. `"a" : 1`). Defaults to `false`.
+    pub space_before_colon: bool,
+    /// Whether to write a space after each colon. Defaults to `None`, which writes one for every
+    /// style except `SerializeStyle::Compact`.
+    pub space_after_colon: Option<bool>,
+    /// Whether to write a space after each comma that isn't followed by a newline. Defaults to
+    /// `None`, which writes one for `SerializeStyle::OneLine`.
+    pub space_after_comma: Option<bool>,
+    /// Whether to write empty arrays and objects as `[]` and `{}` in pretty output instead of
+    /// putting the close bracket or brace on its own line. Defaults to `true`.
+    pub compact_empty_containers: bool,
+    /// Whether to leave out object properties whose value is `null`. Defaults to `false`.
+    pub omit_null_fields: bool,
+    /// How property names are quoted. Defaults to `KeyQuoteStyle::Detect`, which quotes them
+    /// like strings.
+    pub key_quote_style: KeyQuoteStyle,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> SerializeOptions {
+        SerializeOptions {
+            style: SerializeStyle::Compact,
+            indent_width: 2,
+            escape_options: EscapeOptions::default(),
+            trailing_commas: false,
+            trailing_newline: false,
+            space_before_colon: false,
+            space_after_colon: None,
+            space_after_comma: None,
+            compact_empty_containers: true,
+            omit_null_fields: false,
+            key_quote_style: KeyQuoteStyle::Detect,
+        }
+    }
+}
+
+impl SerializeOptions {
+    /// Sets the layout of the output.
+    pub fn with_style(mut self, value: SerializeStyle) -> Self {
+        self.style = value;
+        self
+    }
+
+    /// Sets the number of spaces per indentation level in pretty output.
+    pub fn with_indent_width(mut self, value: usize) -> Self {
+        self.indent_width = value;
+        self
+    }
+
+    /// Sets the options for escaping strings.
+    pub fn with_escape_options(mut self, value: EscapeOptions) -> Self {
+        self.escape_options = value;
+        self
+    }
+
+    /// Sets whether to write a comma after the last property or element in pretty output.
+    pub fn with_trailing_commas(mut self, value: bool) -> Self {
+        self.trailing_commas = value;
+        self
+    }
+
+    /// Sets whether to end the output with a newline.
+    pub fn with_trailing_newline(mut self, value: bool) -> Self {
+        self.trailing_newline = value;
+        self
+    }
+
+    /// Sets whether to write a space before each colon.
+    pub fn with_space_before_colon(mut self, value: bool) -> Self {
+        self.space_before_colon = value;
+        self
+    }
+
+    /// Sets whether to write a space after each colon instead of using the style's default.
+    pub fn with_space_after_colon(mut self, value: bool) -> Self {
+        self.space_after_colon = Some(value);
+        self
+    }
+
+    /// Sets whether to write a space after each comma that isn't followed by a newline instead
+    /// of using the style's default.
+    pub fn with_space_after_comma(mut self, value: bool) -> Self {
+        self.space_after_comma = Some(value);
+        self
+    }
+
+    /// Sets whether to write empty arrays and objects on one line in pretty output.
+    pub fn with_compact_empty_containers(mut self, value: bool) -> Self {
+        self.compact_empty_containers = value;
+        self
+    }
+
+    /// Sets whether to leave out object properties whose value is `null`.
+    pub fn with_omit_null_fields(mut self, value: bool) -> Self {
+        self.omit_null_fields = value;
+        self
+    }
+
+    /// Sets how property names are quoted.
+    pub fn with_key_quote_style(mut self, value: KeyQuoteStyle) -> Self {
+        self.key_quote_style = value;
+        self
+    }
+}
+
+/// Serializes a value to JSON text using the provided options.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_to_value;
+/// use jsonc_parser::serializer::{serialize_json_value, SerializeOptions, SerializeStyle};
+///
+/// let value = parse_to_value(r#"{ "a": [1] }"#).unwrap().unwrap();
+/// let options = SerializeOptions::default().with_style(SerializeStyle::Pretty);
+/// assert_eq!(serialize_json_value(&value, &options), "{\n  \"a\": [\n    1\n  ]\n}");
+/// ```
+pub fn serialize_json_value(value: &JsonValue, options: &SerializeOptions) -> String {
+    let mut text = String::with_capacity(serialized_len(value, options));
+    write_json_value(&mut text, value, options, 0).expect("Writing to a string should not fail.");
+    if options.trailing_newline {
+        text.push('\n');
+    }
+    text
+}
+
+/// Gets the length in bytes of the text `serialize_json_value` produces without building it.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_to_value;
+/// use jsonc_parser::serializer::{serialized_len, SerializeOptions};
+///
+/// let value = parse_to_value(r#"{ "a": "\n" }"#).unwrap().unwrap();
+/// assert_eq!(serialized_len(&value, &SerializeOptions::default()), r#"{"a":"\n"}"#.len());
+/// ```
+pub fn serialized_len(value: &JsonValue, options: &SerializeOptions) -> usize {
+    let mut counter = LenCounter(0);
+    write_json_value(&mut counter, value, options, 0).expect("Counting should not fail.");
+    counter.0 + usize::from(options.trailing_newline)
+}
+
+/// Serializes a value to JSON text, writing it to the writer as it's produced.
+///
+/// This produces the same text as `serialize_json_value` without building it in memory. The
+/// output is written in chunks from a fixed-size buffer and the value is walked with an
+/// explicit stack, so the memory used besides the writer only grows with the nesting depth.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_to_value;
+/// use jsonc_parser::serializer::{write_json_value_to, SerializeOptions, SerializeStyle};
+///
+/// let value = parse_to_value(r#"{ "a": [1] }"#).unwrap().unwrap();
+/// let mut output = Vec::new();
+/// write_json_value_to(&mut output, &value, &SerializeOptions::default().with_style(SerializeStyle::Pretty)).unwrap();
+/// assert_eq!(String::from_utf8(output).unwrap(), "{\n  \"a\": [\n    1\n  ]\n}");
+/// ```
+pub fn write_json_value_to(writer: &mut impl io::Write, value: &JsonValue, options: &SerializeOptions) -> io::Result<()> {
+    let mut adapter = IoWriteAdapter {
+        writer,
+        buffer: [0; IO_BUFFER_SIZE],
+        len: 0,
+        error: None,
+    };
+    let result = write_json_value(&mut adapter, value, options, 0).and_then(|_| {
+        if options.trailing_newline {
+            fmt::Write::write_char(&mut adapter, '\n')?;
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => adapter.flush(),
+        Err(_) => Err(adapter.error.take().unwrap_or_else(|| io::Error::other("Formatting failed."))),
+    }
+}
+
+const IO_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Buffers the text written by the formatter and writes it to an `io::Write` in chunks, keeping
+/// the first error since `fmt::Error` can't hold it.
+struct IoWriteAdapter<'a, W: io::Write> {
+    writer: &'a mut W,
+    buffer: [u8; IO_BUFFER_SIZE],
+    len: usize,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write> IoWriteAdapter<'a, W> {
+    fn flush(&mut self) -> io::Result<()> {
+        let len = std::mem::replace(&mut self.len, 0);
+        self.writer.write_all(&self.buffer[..len])
+    }
+}
+
+impl<'a, W: io::Write> fmt::Write for IoWriteAdapter<'a, W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let bytes = text.as_bytes();
+        if self.len + bytes.len() > IO_BUFFER_SIZE {
+            if let Err(err) = self.flush() {
+                self.error = Some(err);
+                return Err(fmt::Error);
+            }
+            if bytes.len() > IO_BUFFER_SIZE {
+                return self.writer.write_all(bytes).map_err(|err| {
+                    self.error = Some(err);
+                    fmt::Error
+                });
+            }
+        }
+        self.buffer[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+        Ok(())
+    }
+}
+
+/// Counts the bytes written instead of storing them.
+struct LenCounter(usize);
+
+impl fmt::Write for LenCounter {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.0 += text.len();
+        Ok(())
+    }
+}
+
+/// Serializes the value of a parse result like `serialize_raw`, keeping the comments before and after
+/// it (ex. a license header or a note at the end of the file) so they survive a round trip.
+///
+/// A comment is written on a new line when it started on a later line than the text before it in
+/// the original text and otherwise after a space. Comments within the value are not included.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::serializer::serialize_raw_with_comments;
+///
+/// let parse_result = parse_text("// header\n{ \"a\": [1] } // eof note").unwrap();
+/// assert_eq!(serialize_raw_with_comments(&parse_result), "// header\n{\"a\":[1]} // eof note");
+/// ```
+pub fn serialize_raw_with_comments(parse_result: &ParseResult) -> String {
+    let mut text = String::new();
+    let value = match &parse_result.value {
+        Some(value) => value,
+        None => {
+            if let Some(comments) = parse_result.comments.get(&0) {
+                write_comments(&mut text, comments, None);
+            }
+            return text;
+        },
+    };
+
+    let range = value.range();
+    let mut last_line = None;
+    if let Some(comments) = parse_result.comments.get(&range.start) {
+        last_line = write_comments(&mut text, comments, None);
+    }
+    if let Some(last_line) = last_line {
+        text.push(if range.start_line > last_line { '\n' } else { ' ' });
+    }
+    write_value(&mut text, value, true);
+    if let Some(comments) = parse_result.comments.get(&range.end) {
+        write_comments(&mut text, comments, Some(range.end_line));
+    }
+    text
+}
+
+/// Writes the comments, each separated from the text before it by a new line when it started on a
+/// later line, and returns the line the last comment ended on.
+fn write_comments(text: &mut String, comments: &[Comment], mut last_line: Option<usize>) -> Option<usize> {
+    for comment in comments.iter() {
+        let range = match comment {
+            Comment::Line(line) => &line.range,
+            Comment::Block(block) => &block.range,
+        };
+        if let Some(last_line) = last_line {
+            text.push(if range.start_line > last_line { '\n' } else { ' ' });
+        }
+        text.push_str(&comment.raw_text());
+        last_line = Some(range.end_line);
+    }
+    last_line
+}
+
+/// A container whose children are being written.
+enum JsonWriteFrame<'a> {
+    Array(std::slice::Iter<'a, JsonValue>, bool),
+    Object(std::slice::Iter<'a, (String, JsonValue)>, bool),
+}
+
+/// Writes the value as JSON text, starting at the indent level.
+pub(crate) fn write_json_value(writer: &mut impl fmt::Write, value: &JsonValue, options: &SerializeOptions, indent_level: usize) -> fmt::Result {
+    let mut stack = Vec::new();
+    let mut next = Some(value);
+    let is_omitted = |value: &JsonValue| options.omit_null_fields && *value == JsonValue::Null;
+
+    loop {
+        match next.take() {
+            Some(JsonValue::Null) => writer.write_str("null")?,
+            Some(JsonValue::Boolean(value)) => writer.write_str(if *value { "true" } else { "false" })?,
+            Some(JsonValue::Number(value)) => writer.write_str(value)?,
+            Some(JsonValue::String(value)) => write_escaped_string(writer, value, &options.escape_options)?,
+            Some(JsonValue::Wtf8String(value)) => write_escaped_wtf8_string(writer, value, &options.escape_options)?,
+            Some(JsonValue::Array(elements)) if elements.is_empty() && options.compact_empty_containers => writer.write_str("[]")?,
+            Some(JsonValue::Array(elements)) => {
+                writer.write_char('[')?;
+                stack.push(JsonWriteFrame::Array(elements.iter(), true));
+            },
+            Some(JsonValue::Object(obj)) if options.compact_empty_containers && obj.entries().iter().all(|(_, value)| is_omitted(value)) => writer.write_str("{}")?,
+            Some(JsonValue::Object(obj)) => {
+                writer.write_char('{')?;
+                stack.push(JsonWriteFrame::Object(obj.entries().iter(), true));
+            },
+            None => {},
+        }
+
+        let child_indent_level = indent_level + stack.len();
+        match stack.last_mut() {
+            None => return Ok(()),
+            Some(JsonWriteFrame::Array(elements, is_first)) => match elements.next() {
+                Some(element) => {
+                    if !std::mem::replace(is_first, false) {
+                        write_comma(writer, options)?;
+                    }
+                    write_newline(writer, options, child_indent_level)?;
+                    next = Some(element);
+                },
+                None => {
+                    let is_empty = *is_first;
+                    stack.pop();
+                    if !is_empty {
+                        write_trailing_comma(writer, options)?;
+                    }
+                    write_newline(writer, options, child_indent_level - 1)?;
+                    writer.write_char(']')?;
+                },
+            },
+            Some(JsonWriteFrame::Object(entries, is_first)) => match entries.find(|(_, value)| !is_omitted(value)) {
+                Some((key, value)) => {
+                    if !std::mem::replace(is_first, false) {
+                        write_comma(writer, options)?;
+                    }
+                    write_newline(writer, options, child_indent_level)?;
+                    write_key(writer, key, options)?;
+                    write_colon(writer, options)?;
+                    next = Some(value);
+                },
+                None => {
+                    let is_empty = *is_first;
+                    stack.pop();
+                    if !is_empty {
+                        write_trailing_comma(writer, options)?;
+                    }
+                    write_newline(writer, options, child_indent_level - 1)?;
+                    writer.write_char('}')?;
+                },
+            },
+        }
+    }
+}
+
+fn write_key(writer: &mut impl fmt::Write, key: &str, options: &SerializeOptions) -> fmt::Result {
+    let escape_options = options.escape_options.clone();
+    match options.key_quote_style {
+        KeyQuoteStyle::Detect => write_escaped_string(writer, key, &escape_options),
+        KeyQuoteStyle::Single => write_escaped_string(writer, key, &escape_options.with_single_quotes(true)),
+        KeyQuoteStyle::Double | KeyQuoteStyle::PreferUnquoted => write_escaped_string(writer, key, &escape_options.with_single_quotes(false)),
+    }
+}
+
+fn write_colon(writer: &mut impl fmt::Write, options: &SerializeOptions) -> fmt::Result {
+    if options.space_before_colon {
+        writer.write_char(' ')?;
+    }
+    writer.write_char(':')?;
+    if options.space_after_colon.unwrap_or(options.style != SerializeStyle::Compact) {
+        writer.write_char(' ')?;
+    }
+    Ok(())
+}
+
+fn write_comma(writer: &mut impl fmt::Write, options: &SerializeOptions) -> fmt::Result {
+    writer.write_char(',')?;
+    // pretty output writes a newline after the comma instead
+    if options.style != SerializeStyle::Pretty && options.space_after_comma.unwrap_or(options.style == SerializeStyle::OneLine) {
+        writer.write_char(' ')?;
+    }
+    Ok(())
+}
+
+fn write_trailing_comma(writer: &mut impl fmt::Write, options: &SerializeOptions) -> fmt::Result {
+    if options.trailing_commas && options.style == SerializeStyle::Pretty {
+        writer.write_char(',')?;
+    }
+    Ok(())
+}
+
+/// Spaces that indentation is written from in chunks.
+const INDENT_SPACES: &str = "                                                                ";
+
+fn write_newline(writer: &mut impl fmt::Write, options: &SerializeOptions, indent_level: usize) -> fmt::Result {
+    if options.style == SerializeStyle::Pretty {
+        writer.write_char('\n')?;
+        let mut remaining = indent_level * options.indent_width;
+        while remaining > 0 {
+            let len = remaining.min(INDENT_SPACES.len());
+            writer.write_str(&INDENT_SPACES[..len])?;
+            remaining -= len;
+        }
+    }
+    Ok(())
+}
+
+/// Serializes a value to compact JSON text.
+///
+/// Strings keep the escapes they had in the original text and comments are not included.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::serializer::serialize_value;
+///
+/// let parse_result = parse_text(r#"{ "a": [1, 2] } // comment"#).unwrap();
+/// assert_eq!(serialize_value(&parse_result.value.unwrap()), r#"{"a":[1,2]}"#);
+/// ```
+pub fn serialize_value(value: &Value) -> String {
+    let mut text = String::new();
+    write_value(&mut text, value, false);
+    text
+}
+
+/// Serializes a value to compact text with the scalars written exactly as they were in the
+/// original text (ex. `1.0`, `'a'`, or `"\u0041"`).
+///
+/// Comments and whitespace are not included.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::serializer::serialize_raw;
+///
+/// let parse_result = parse_text(r#"{ "a": [1.0, "\u0041"] }"#).unwrap();
+/// assert_eq!(serialize_raw(&parse_result.value.unwrap()), r#"{"a":[1.0,"\u0041"]}"#);
+/// ```
+pub fn serialize_raw(value: &Value) -> String {
+    let mut text = String::new();
+    write_value(&mut text, value, true);
+    text
+}
+
+/// A container whose children are being written.
+enum WriteFrame<'a> {
+    Array(std::slice::Iter<'a, Value>, bool),
+    Object(std::slice::Iter<'a, ObjectProp>, bool),
+}
+
+/// Writes the AST value as compact text without its comments.
+///
+/// Strings are written with their original quotes when `is_raw` is true and otherwise in double quotes.
+fn write_value(text: &mut String, value: &Value, is_raw: bool) {
+    let mut stack = Vec::new();
+    let mut next = Some(value);
+
+    loop {
+        match next.take() {
+            Some(Value::StringLit(lit)) if is_raw => text.push_str(&lit.raw_text()),
+            Some(Value::StringLit(lit)) => write_string(text, lit.value.as_ref(), lit.quote),
+            Some(Value::NumberLit(lit)) => text.push_str(lit.value.as_ref()),
+            Some(Value::BooleanLit(lit)) => text.push_str(if lit.value { "true" } else { "false" }),
+            Some(Value::NullKeyword(_)) => text.push_str("null"),
+            Some(Value::Object(obj)) => {
+                text.push('{');
+                stack.push(WriteFrame::Object(obj.properties.iter(), true));
+            },
+            Some(Value::Array(arr)) => {
+                text.push('[');
+                stack.push(WriteFrame::Array(arr.elements.iter(), true));
+            },
+            None => {},
+        }
+
+        match stack.last_mut() {
+            None => return,
+            Some(WriteFrame::Object(properties, is_first)) => match properties.next() {
+                Some(prop) => {
+                    if !std::mem::replace(is_first, false) {
+                        text.push(',');
+                    }
+                    if is_raw {
+                        text.push_str(&prop.name.raw_text());
+                    } else {
+                        write_string(text, prop.name.value.as_ref(), prop.name.quote);
+                    }
+                    text.push(':');
+                    next = Some(&prop.value);
+                },
+                None => {
+                    stack.pop();
+                    text.push('}');
+                },
+            },
+            Some(WriteFrame::Array(elements, is_first)) => match elements.next() {
+                Some(element) => {
+                    if !std::mem::replace(is_first, false) {
+                        text.push(',');
+                    }
+                    next = Some(element);
+                },
+                None => {
+                    stack.pop();
+                    text.push(']');
+                },
+            },
+        }
+    }
+}
+
+fn write_string(text: &mut String, value: &str, quote_char: char) {
+    // strings scanned with single quotes may contain unescaped double quotes or escaped single quotes
+    if value.contains('\'') || value.contains('"') {
+        text.push_str(&escape_string(&decode_string(value, quote_char), &EscapeOptions::default()));
+    } else {
+        text.push('"');
+        text.push_str(value);
+        text.push('"');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse_text_with_options, parse_to_value, parse_tokens_to_ast, ParseOptions, ScannerOptions};
+
+    #[test]
+    fn it_serializes_pretty() {
+        let value = parse_to_value(r#"{"a": 1, "b": [true, null, {}], "c": {"d": []}}"#).unwrap().unwrap();
+        let options = SerializeOptions::default().with_style(SerializeStyle::Pretty).with_indent_width(4);
+        assert_eq!(serialize_json_value(&value, &options), r#"{
+    "a": 1,
+    "b": [
+        true,
+        null,
+        {}
+    ],
+    "c": {
+        "d": []
+    }
+}"#);
+    }
+
+    #[test]
+    fn it_serializes_on_one_line() {
+        let value = parse_to_value("{\n  \"a\": 1,\n  \"b\": [1, 2],\n  \"c\": [{}, []]\n}").unwrap().unwrap();
+        let options = SerializeOptions::default().with_style(SerializeStyle::OneLine);
+        assert_eq!(serialize_json_value(&value, &options), r#"{"a": 1, "b": [1, 2], "c": [{}, []]}"#);
+    }
+
+    #[test]
+    fn it_serializes_with_the_provided_spacing() {
+        let value = parse_to_value(r#"{"a": 1, "b": [1, 2]}"#).unwrap().unwrap();
+        let serialize = |options: SerializeOptions| serialize_json_value(&value, &options);
+        let one_line = SerializeOptions::default().with_style(SerializeStyle::OneLine);
+        assert_eq!(serialize(one_line.clone()), r#"{"a": 1, "b": [1, 2]}"#);
+        assert_eq!(serialize(one_line.clone().with_space_before_colon(true)), r#"{"a" : 1, "b" : [1, 2]}"#);
+        assert_eq!(serialize(one_line.with_space_after_comma(false)), r#"{"a": 1,"b": [1,2]}"#);
+        assert_eq!(serialize(SerializeOptions::default().with_space_after_colon(true)), r#"{"a": 1,"b": [1,2]}"#);
+        assert_eq!(serialize(SerializeOptions::default().with_space_after_comma(true)), r#"{"a":1, "b":[1, 2]}"#);
+
+        let pretty = SerializeOptions::default().with_style(SerializeStyle::Pretty).with_space_before_colon(true).with_space_after_comma(true);
+        assert_eq!(serialize(pretty.clone()), "{\n  \"a\" : 1,\n  \"b\" : [\n    1,\n    2\n  ]\n}");
+        assert_eq!(serialized_len(&value, &pretty), serialize(pretty).len());
+    }
+
+    #[test]
+    fn it_writes_the_same_text_to_an_io_writer() {
+        let long_string = "x".repeat(IO_BUFFER_SIZE + 10);
+        let texts = [
+            String::from("null"),
+            String::from(r#"{ "a": [1, { "b": "é\n", "c": [] }, {}], "d": true }"#),
+            format!("[{}1{}]", "[".repeat(40), "]".repeat(40)),
+            format!(r#"["{}", ["{}"]]"#, long_string, long_string),
+        ];
+        let styles = [SerializeStyle::Compact, SerializeStyle::OneLine, SerializeStyle::Pretty];
+        for text in texts.iter() {
+            let value = parse_to_value(text).unwrap().unwrap();
+            for style in styles.iter() {
+                for indent_width in [0, 2, 5].iter() {
+                    let options = SerializeOptions::default()
+                        .with_style(*style)
+                        .with_indent_width(*indent_width)
+                        .with_trailing_commas(true)
+                        .with_trailing_newline(*indent_width == 5)
+                        .with_escape_options(EscapeOptions::default().with_ascii_only(true));
+                    let mut output = Vec::new();
+                    write_json_value_to(&mut output, &value, &options).unwrap();
+                    assert_eq!(String::from_utf8(output).unwrap(), serialize_json_value(&value, &options));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_returns_errors_from_the_io_writer() {
+        struct FailingWriter;
+
+        impl io::Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::PermissionDenied, "denied"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let value = parse_to_value("[1, 2]").unwrap().unwrap();
+        let err = write_json_value_to(&mut FailingWriter, &value, &SerializeOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        let value = JsonValue::String("x".repeat(IO_BUFFER_SIZE * 2));
+        let err = write_json_value_to(&mut FailingWriter, &value, &SerializeOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn it_serializes_a_trailing_newline_when_enabled() {
+        let value = parse_to_value("{ \"a\": [1] }").unwrap().unwrap();
+        for style in [SerializeStyle::Compact, SerializeStyle::Pretty].iter() {
+            let options = SerializeOptions::default().with_style(*style);
+            let text = serialize_json_value(&value, &options);
+            assert!(!text.ends_with('\n'));
+
+            let options = options.with_trailing_newline(true);
+            let text_with_newline = serialize_json_value(&value, &options);
+            assert_eq!(text_with_newline, format!("{}\n", text));
+            assert_eq!(serialized_len(&value, &options), text_with_newline.len());
+        }
+    }
+
+    #[test]
+    fn it_serializes_trailing_commas_when_pretty() {
+        let value = parse_to_value("[1, { \"a\": [], \"b\": [2,], }, {},]").unwrap().unwrap();
+        let options = SerializeOptions::default().with_style(SerializeStyle::Pretty).with_trailing_commas(true);
+        assert_eq!(serialize_json_value(&value, &options), r#"[
+  1,
+  {
+    "a": [],
+    "b": [
+      2,
+    ],
+  },
+  {},
+]"#);
+        assert_eq!(serialized_len(&value, &options), serialize_json_value(&value, &options).len());
+        // other styles are unaffected
+        let options = SerializeOptions::default().with_trailing_commas(true);
+        assert_eq!(serialize_json_value(&value, &options), r#"[1,{"a":[],"b":[2]},{}]"#);
+    }
+
+    #[test]
+    fn it_computes_serialized_len() {
+        let texts = [
+            "null",
+            "-1.5e+10",
+            r#""""#,
+            r#""a\"b\\c\n\u0001\u00e9\ud83d\ude00""#,
+            r#"[1, "two", [], {}]"#,
+            r#"{"kéy": {"nested": [true, false, {"x": "\t"}]}, "b": []}"#,
+        ];
+        let all_options = [
+            SerializeOptions::default(),
+            SerializeOptions::default().with_style(SerializeStyle::Pretty),
+            SerializeOptions::default().with_style(SerializeStyle::Pretty).with_indent_width(3),
+            SerializeOptions::default().with_style(SerializeStyle::OneLine),
+            SerializeOptions::default().with_style(SerializeStyle::Pretty).with_trailing_commas(true),
+            SerializeOptions::default().with_escape_options(EscapeOptions::default().with_ascii_only(true)),
+        ];
+
+        for text in texts.iter() {
+            let value = parse_to_value(text).unwrap().unwrap();
+            assert_eq!(serialized_len(&value, &SerializeOptions::default()), value.to_string().len());
+            for options in all_options.iter() {
+                assert_eq!(serialized_len(&value, options), serialize_json_value(&value, options).len(), "Text: {}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn it_serializes_raw_scalars_exactly() {
+        let text = r#"{"a":1.0,"b":"a\u0041","c":true,'d':['x',-0.50E+01,null,false],"e":"\/"}"#;
+        let options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_single_quotes(true));
+        let value = parse_text_with_options(text, &options).unwrap().value.unwrap();
+        assert_eq!(serialize_raw(&value), text);
+        assert_eq!(serialize_value(&value), r#"{"a":1.0,"b":"a\u0041","c":true,"d":["x",-0.50E+01,null,false],"e":"\/"}"#);
+
+        // the quotes are also known when parsing tokens
+        let tokens = parse_text_with_options(text, &options).unwrap().tokens;
+        assert_eq!(serialize_raw(&parse_tokens_to_ast(&tokens, text).unwrap().value.unwrap()), text);
+    }
+
+    #[test]
+    fn it_keeps_the_comments_around_the_root_value() {
+        let text = "{ \"a\": 1 } // eof note";
+        let parse_result = parse_text_with_options(text, &ParseOptions::default()).unwrap();
+        let serialized = serialize_raw_with_comments(&parse_result);
+        assert_eq!(serialized, r#"{"a":1} // eof note"#);
+        let reparsed = parse_text_with_options(&serialized, &ParseOptions::default()).unwrap();
+        assert_eq!(reparsed.comments.get(&7).unwrap()[0].raw_text(), "// eof note");
+        assert_eq!(serialize_raw_with_comments(&reparsed), serialized);
+
+        let text = "/* a */ // b\n// c\n[1, /* inner */ 2] /* d */\n\n// e\n";
+        let parse_result = parse_text_with_options(text, &ParseOptions::default()).unwrap();
+        assert_eq!(serialize_raw_with_comments(&parse_result), "/* a */ // b\n// c\n[1,2] /* d */\n// e");
+        let parse_result = parse_text_with_options("/* a */ 5", &ParseOptions::default()).unwrap();
+        assert_eq!(serialize_raw_with_comments(&parse_result), "/* a */ 5");
+        let parse_result = parse_text_with_options("// a\n/* b */", &ParseOptions::default()).unwrap();
+        assert_eq!(serialize_raw_with_comments(&parse_result), "// a\n/* b */");
+        let parse_result = parse_text_with_options("[]", &ParseOptions::default()).unwrap();
+        assert_eq!(serialize_raw_with_comments(&parse_result), "[]");
+    }
+
+    #[test]
+    fn it_controls_how_empty_containers_are_written() {
+        let value = parse_to_value(r#"{ "a": [], "b": { "c": {} } }"#).unwrap().unwrap();
+        let options = SerializeOptions::default().with_style(SerializeStyle::Pretty).with_trailing_commas(true);
+        assert_eq!(serialize_json_value(&value, &options), "{\n  \"a\": [],\n  \"b\": {\n    \"c\": {},\n  },\n}");
+        let options = options.with_compact_empty_containers(false);
+        let text = serialize_json_value(&value, &options);
+        assert_eq!(text, "{\n  \"a\": [\n  ],\n  \"b\": {\n    \"c\": {\n    },\n  },\n}");
+        assert_eq!(serialized_len(&value, &options), text.len());
+        // the option only applies to pretty output
+        let options = SerializeOptions::default().with_compact_empty_containers(false);
+        assert_eq!(serialize_json_value(&value, &options), r#"{"a":[],"b":{"c":{}}}"#);
+    }
+
+    #[test]
+    fn it_omits_null_fields() {
+        let value = parse_to_value(r#"{ "a": null, "b": [null, { "c": null }], "d": 1, "e": null }"#).unwrap().unwrap();
+        let options = SerializeOptions::default().with_omit_null_fields(true);
+        assert_eq!(serialize_json_value(&value, &options), r#"{"b":[null,{}],"d":1}"#);
+        let options = options.with_style(SerializeStyle::Pretty);
+        let text = serialize_json_value(&value, &options);
+        assert_eq!(text, "{\n  \"b\": [\n    null,\n    {}\n  ],\n  \"d\": 1\n}");
+        assert_eq!(serialized_len(&value, &options), text.len());
+        assert_eq!(serialize_json_value(&value, &SerializeOptions::default()), r#"{"a":null,"b":[null,{"c":null}],"d":1,"e":null}"#);
+    }
+
+    #[test]
+    fn it_quotes_property_names_with_the_provided_style() {
+        let value = parse_to_value(r#"{ "a": "b", "it's": 1, "é": 2 }"#).unwrap().unwrap();
+        let serialize = |options: SerializeOptions| serialize_json_value(&value, &options);
+        let single_quotes = EscapeOptions::default().with_single_quotes(true);
+        assert_eq!(serialize(SerializeOptions::default()), r#"{"a":"b","it's":1,"é":2}"#);
+        assert_eq!(serialize(SerializeOptions::default().with_escape_options(single_quotes.clone())), r#"{'a':'b','it\'s':1,'é':2}"#);
+        assert_eq!(serialize(SerializeOptions::default().with_key_quote_style(KeyQuoteStyle::Single)), r#"{'a':"b",'it\'s':1,'é':2}"#);
+        assert_eq!(
+            serialize(SerializeOptions::default().with_escape_options(single_quotes).with_key_quote_style(KeyQuoteStyle::Double)),
+            r#"{"a":'b',"it's":1,"é":2}"#
+        );
+        // unquoted names can't be parsed yet, so they're quoted
+        let options = SerializeOptions::default()
+            .with_key_quote_style(KeyQuoteStyle::PreferUnquoted)
+            .with_escape_options(EscapeOptions::default().with_ascii_only(true));
+        let text = serialize(options.clone());
+        assert_eq!(text, r#"{"a":"b","it's":1,"\u00e9":2}"#);
+        assert_eq!(serialized_len(&value, &options), text.len());
+        assert_eq!(parse_to_value(&text).err(), None);
+    }
+}
@@ -0,0 +1,728 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
+use super::common::Range;
+use super::errors::EscapeError;
+
+/// Options for escaping a string.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct EscapeOptions {
+    /// Escape all non-ASCII characters using `\uXXXX` escapes.
+    pub ascii_only: bool,
+    /// Surround the string in single quotes instead of double quotes.
+    pub single_quotes: bool,
+}
+
+impl EscapeOptions {
+    /// Sets whether all non-ASCII characters are escaped.
+    pub fn with_ascii_only(mut self, value: bool) -> Self {
+        self.ascii_only = value;
+        self
+    }
+
+    /// Sets whether the string is surrounded in single quotes.
+    pub fn with_single_quotes(mut self, value: bool) -> Self {
+        self.single_quotes = value;
+        self
+    }
+}
+
+/// A character or UTF-16 code unit read from an escape sequence.
+pub(crate) enum EscapedChar {
+    Char(char),
+    CodeUnit(u32),
+}
+
+/// Reads an escape sequence after its backslash using the provided function to get each character.
+///
+/// The quote character that surrounds the string may also be escaped. This is shared by the
+/// scanner and `decode_string_literal` so both handle escapes the same way.
+pub(crate) fn read_escape(quote_char: char, mut next_char: impl FnMut() -> Option<char>) -> Result<EscapedChar, &'static str> {
+    match next_char() {
+        Some('"') => Ok(EscapedChar::Char('"')),
+        Some(c) if c == quote_char => Ok(EscapedChar::Char(c)),
+        Some('\\') => Ok(EscapedChar::Char('\\')),
+        Some('/') => Ok(EscapedChar::Char('/')),
+        Some('b') => Ok(EscapedChar::Char('\u{08}')),
+        Some('f') => Ok(EscapedChar::Char('\u{0C}')),
+        Some('n') => Ok(EscapedChar::Char('\n')),
+        Some('r') => Ok(EscapedChar::Char('\r')),
+        Some('t') => Ok(EscapedChar::Char('\t')),
+        Some('u') => {
+            // expect four hex values
+            let mut code = 0;
+            for _ in 0..4 {
+                match next_char().and_then(|c| c.to_digit(16)) {
+                    Some(digit) => code = code * 16 + digit,
+                    None => return Err("Expected four hex digits."),
+                }
+            }
+            Ok(EscapedChar::CodeUnit(code))
+        },
+        _ => Err("Invalid escape."),
+    }
+}
+
+/// Decodes the escapes in the text of a string literal.
+///
+/// The text should not include the surrounding quotes, which are provided as `quote_char` so
+/// escaped single quotes are only accepted for strings that were surrounded in single quotes.
+/// Unpaired surrogates are replaced with the unicode replacement character. The text is
+/// borrowed when it contains no escapes.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::strings::decode_string_literal;
+///
+/// assert_eq!(decode_string_literal(r#"a\"bé"#, '"').unwrap(), "a\"b\u{e9}");
+/// assert_eq!(decode_string_literal(r#"a\xb"#, '"').unwrap_err().offset, 1);
+/// assert_eq!(decode_string_literal(r#"a\'b"#, '\'').unwrap(), "a'b");
+/// assert!(decode_string_literal(r#"a\'b"#, '"').is_err());
+/// ```
+pub fn decode_string_literal(text: &str, quote_char: char) -> Result<Cow<'_, str>, EscapeError> {
+    if !text.contains('\\') {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut offset = 0;
+    let mut high_surrogate = None;
+
+    while let Some(current_char) = chars.next() {
+        let escape_offset = offset;
+        offset += 1;
+
+        if current_char != '\\' {
+            push_unpaired_surrogate(&mut result, high_surrogate.take());
+            result.push(current_char);
+            continue;
+        }
+
+        let escaped_char = read_escape(quote_char, || {
+            let c = chars.next();
+            if c.is_some() {
+                offset += 1;
+            }
+            c
+        });
+        match escaped_char {
+            Ok(EscapedChar::Char(c)) => {
+                push_unpaired_surrogate(&mut result, high_surrogate.take());
+                result.push(c);
+            },
+            Ok(EscapedChar::CodeUnit(code)) => {
+                if let Some(high) = high_surrogate.take() {
+                    if (0xDC00..0xE000).contains(&code) {
+                        let code = 0x10000 + ((high - 0xD800) << 10) + (code - 0xDC00);
+                        result.push(std::char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        continue;
+                    }
+                    push_unpaired_surrogate(&mut result, Some(high));
+                }
+
+                if (0xD800..0xDC00).contains(&code) {
+                    high_surrogate = Some(code);
+                } else {
+                    result.push(std::char::from_u32(code).unwrap_or('\u{FFFD}'));
+                }
+            },
+            Err(message) => return Err(EscapeError::new(escape_offset, message)),
+        }
+    }
+    push_unpaired_surrogate(&mut result, high_surrogate);
+
+    Ok(Cow::Owned(result))
+}
+
+/// Decodes the string literal at the range of the text, such as the range of a string token or
+/// `StringLit`, without scanning the rest of the text.
+///
+/// The range includes the quotes. The offset of an error is within the text between the quotes,
+/// like `decode_string_literal`.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::strings::decode_string_at;
+///
+/// let text = r#"{ "name": "caf\u00e9" }"#;
+/// let range = parse_text(text).unwrap().tokens[3].range.clone();
+/// assert_eq!(decode_string_at(text, &range).unwrap(), "caf\u{e9}");
+/// ```
+pub fn decode_string_at(text: &str, range: &Range) -> Result<String, EscapeError> {
+    let mut char_indices = text.char_indices().map(|(index, _)| index).chain(std::iter::once(text.len()));
+    let start = char_indices.nth(range.start);
+    let end = range.end.checked_sub(range.start).and_then(|len| if len == 0 { start } else { char_indices.nth(len - 1) });
+    let literal = match (start, end) {
+        (Some(start), Some(end)) => &text[start..end],
+        _ => return Err(EscapeError::new(0, "Expected the range to be within the text.")),
+    };
+
+    let mut chars = literal.chars();
+    match (chars.next(), chars.next_back()) {
+        (Some(quote @ '"'), Some(end_quote)) | (Some(quote @ '\''), Some(end_quote)) if quote == end_quote => {
+            Ok(decode_string_literal(chars.as_str(), quote)?.into_owned())
+        },
+        _ => Err(EscapeError::new(0, "Expected a quoted string at the range.")),
+    }
+}
+
+/// Decodes the escapes in the text of a string literal along with the range of characters in the
+/// text that each decoded character came from.
+///
+/// Invalid escapes are kept as they are, so this works on the strings of a tolerant parse.
+pub(crate) fn decode_string_with_offsets(text: &str, quote_char: char) -> Vec<(char, usize, usize)> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut result = Vec::with_capacity(chars.len());
+    let mut high_surrogate: Option<(u32, usize, usize)> = None;
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let start = pos;
+        pos += 1;
+        if chars[start] != '\\' {
+            result.extend(high_surrogate.take().map(|(_, start, end)| ('\u{FFFD}', start, end)));
+            result.push((chars[start], start, pos));
+            continue;
+        }
+
+        let mut escape_pos = pos;
+        let escaped_char = read_escape(quote_char, || {
+            let c = chars.get(escape_pos).copied();
+            escape_pos += 1;
+            c
+        });
+        match escaped_char {
+            Ok(EscapedChar::Char(c)) => {
+                pos = escape_pos;
+                result.extend(high_surrogate.take().map(|(_, start, end)| ('\u{FFFD}', start, end)));
+                result.push((c, start, pos));
+            },
+            Ok(EscapedChar::CodeUnit(code)) => {
+                pos = escape_pos;
+                if let Some((high, high_start, high_end)) = high_surrogate.take() {
+                    if (0xDC00..0xE000).contains(&code) {
+                        let code = 0x10000 + ((high - 0xD800) << 10) + (code - 0xDC00);
+                        result.push((std::char::from_u32(code).unwrap_or('\u{FFFD}'), high_start, pos));
+                        continue;
+                    }
+                    result.push(('\u{FFFD}', high_start, high_end));
+                }
+
+                if (0xD800..0xDC00).contains(&code) {
+                    high_surrogate = Some((code, start, pos));
+                } else {
+                    result.push((std::char::from_u32(code).unwrap_or('\u{FFFD}'), start, pos));
+                }
+            },
+            Err(_) => {
+                result.extend(high_surrogate.take().map(|(_, start, end)| ('\u{FFFD}', start, end)));
+                result.push(('\\', start, pos));
+            },
+        }
+    }
+    result.extend(high_surrogate.map(|(_, start, end)| ('\u{FFFD}', start, end)));
+
+    result
+}
+
+/// A decoded string that may contain lone surrogates, stored as WTF-8.
+///
+/// WTF-8 is UTF-8 that also allows the three byte encodings of surrogates, so strings without
+/// lone surrogates are stored as plain UTF-8.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct JsonString {
+    bytes: Vec<u8>,
+}
+
+impl JsonString {
+    /// Gets the string when it has no lone surrogates.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.bytes).ok()
+    }
+
+    /// Gets the WTF-8 bytes of the string.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Gets the string with each lone surrogate replaced with the unicode replacement character.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::strings::decode_string_literal_wtf8;
+    ///
+    /// let value = decode_string_literal_wtf8(r#"a\ud800b"#, '"').unwrap();
+    /// assert_eq!(value.to_string_lossy(), "a\u{FFFD}b");
+    /// ```
+    pub fn to_string_lossy(&self) -> String {
+        let mut result = String::with_capacity(self.bytes.len());
+        for chunk in wtf8_chunks(&self.bytes) {
+            match chunk {
+                Ok(text) => result.push_str(text),
+                Err(_) => result.push('\u{FFFD}'),
+            }
+        }
+        result
+    }
+}
+
+/// Splits WTF-8 bytes into text and the code units of the lone surrogates between it.
+fn wtf8_chunks(mut bytes: &[u8]) -> impl Iterator<Item = Result<&str, u32>> {
+    std::iter::from_fn(move || {
+        let valid_len = match std::str::from_utf8(bytes) {
+            _ if bytes.is_empty() => return None,
+            Ok(text) => text.len(),
+            Err(err) => err.valid_up_to(),
+        };
+        if valid_len == 0 {
+            // the bytes are WTF-8, so the invalid sequence is the three byte encoding of a surrogate
+            let code = ((bytes[0] as u32 & 0x0F) << 12) | ((bytes[1] as u32 & 0x3F) << 6) | (bytes[2] as u32 & 0x3F);
+            bytes = &bytes[3..];
+            return Some(Err(code));
+        }
+        let (text, rest) = bytes.split_at(valid_len);
+        bytes = rest;
+        Some(Ok(std::str::from_utf8(text).expect("Expected the bytes to be valid UTF-8.")))
+    })
+}
+
+/// Decodes the escapes in the text of a string literal, keeping lone surrogates instead of
+/// replacing them with the unicode replacement character like `decode_string_literal`.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::strings::decode_string_literal_wtf8;
+///
+/// assert_eq!(decode_string_literal_wtf8(r#"a\ud83d\ude00"#, '"').unwrap().as_str(), Some("a\u{1F600}"));
+/// let value = decode_string_literal_wtf8(r#"a\ud800"#, '"').unwrap();
+/// assert_eq!(value.as_str(), None);
+/// assert_eq!(value.as_bytes(), b"a\xED\xA0\x80");
+/// ```
+pub fn decode_string_literal_wtf8(text: &str, quote_char: char) -> Result<JsonString, EscapeError> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+    let mut offset = 0;
+    let mut high_surrogate = None;
+
+    while let Some(current_char) = chars.next() {
+        let escape_offset = offset;
+        offset += 1;
+
+        if current_char != '\\' {
+            push_wtf8_surrogate(&mut bytes, high_surrogate.take());
+            push_wtf8_char(&mut bytes, current_char);
+            continue;
+        }
+
+        let escaped_char = read_escape(quote_char, || {
+            let c = chars.next();
+            if c.is_some() {
+                offset += 1;
+            }
+            c
+        });
+        match escaped_char {
+            Ok(EscapedChar::Char(c)) => {
+                push_wtf8_surrogate(&mut bytes, high_surrogate.take());
+                push_wtf8_char(&mut bytes, c);
+            },
+            Ok(EscapedChar::CodeUnit(code)) => {
+                if let Some(high) = high_surrogate.take() {
+                    if (0xDC00..0xE000).contains(&code) {
+                        let code = 0x10000 + ((high - 0xD800) << 10) + (code - 0xDC00);
+                        push_wtf8_char(&mut bytes, std::char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        continue;
+                    }
+                    push_wtf8_surrogate(&mut bytes, Some(high));
+                }
+
+                if (0xD800..0xDC00).contains(&code) {
+                    high_surrogate = Some(code);
+                } else {
+                    match std::char::from_u32(code) {
+                        Some(c) => push_wtf8_char(&mut bytes, c),
+                        None => push_wtf8_surrogate(&mut bytes, Some(code)),
+                    }
+                }
+            },
+            Err(message) => return Err(EscapeError::new(escape_offset, message)),
+        }
+    }
+    push_wtf8_surrogate(&mut bytes, high_surrogate);
+
+    Ok(JsonString { bytes })
+}
+
+fn push_wtf8_char(bytes: &mut Vec<u8>, c: char) {
+    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+fn push_wtf8_surrogate(bytes: &mut Vec<u8>, surrogate: Option<u32>) {
+    if let Some(code) = surrogate {
+        bytes.extend_from_slice(&[0xE0 | (code >> 12) as u8, 0x80 | ((code >> 6) & 0x3F) as u8, 0x80 | (code & 0x3F) as u8]);
+    }
+}
+
+/// Gets the character offset of the escape of the first lone surrogate in the text of a string literal.
+pub(crate) fn find_lone_surrogate(text: &str, quote_char: char) -> Option<usize> {
+    let mut chars = text.chars();
+    let mut offset = 0;
+    let mut high_surrogate_offset = None;
+
+    while let Some(current_char) = chars.next() {
+        let escape_offset = offset;
+        offset += 1;
+        if current_char != '\\' {
+            if high_surrogate_offset.is_some() {
+                return high_surrogate_offset;
+            }
+            continue;
+        }
+
+        let escaped_char = read_escape(quote_char, || {
+            let c = chars.next();
+            if c.is_some() {
+                offset += 1;
+            }
+            c
+        });
+        match escaped_char {
+            Ok(EscapedChar::CodeUnit(code)) if high_surrogate_offset.is_some() => {
+                if !(0xDC00..0xE000).contains(&code) {
+                    return high_surrogate_offset;
+                }
+                high_surrogate_offset = None;
+            },
+            Ok(EscapedChar::CodeUnit(code)) if (0xD800..0xDC00).contains(&code) => high_surrogate_offset = Some(escape_offset),
+            Ok(EscapedChar::CodeUnit(code)) if (0xDC00..0xE000).contains(&code) => return Some(escape_offset),
+            _ if high_surrogate_offset.is_some() => return high_surrogate_offset,
+            _ => {},
+        }
+    }
+
+    high_surrogate_offset
+}
+
+fn push_unpaired_surrogate(result: &mut String, surrogate: Option<u32>) {
+    if surrogate.is_some() {
+        result.push('\u{FFFD}');
+    }
+}
+
+/// Escapes the provided value to a string literal surrounded in double quotes (or single
+/// quotes when `single_quotes` is set).
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::strings::{escape_string, EscapeOptions};
+///
+/// assert_eq!(escape_string("a\"b\n", &EscapeOptions::default()), r#""a\"b\n""#);
+/// ```
+pub fn escape_string(value: &str, options: &EscapeOptions) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    write_escaped_string(&mut result, value, options).expect("Writing to a string should not fail.");
+    result
+}
+
+/// Writes the value as a string literal surrounded in quotes.
+///
+/// This is shared by `escape_string` and the serializer so the output doesn't need to be
+/// built to know its length.
+pub(crate) fn write_escaped_string(writer: &mut impl fmt::Write, value: &str, options: &EscapeOptions) -> fmt::Result {
+    let quote_char = if options.single_quotes { '\'' } else { '"' };
+    writer.write_char(quote_char)?;
+    write_escaped_chars(writer, value, quote_char, options)?;
+    writer.write_char(quote_char)
+}
+
+/// Writes the WTF-8 string as a string literal surrounded in quotes, where lone surrogates are
+/// written as `\u` escapes.
+pub(crate) fn write_escaped_wtf8_string(writer: &mut impl fmt::Write, value: &JsonString, options: &EscapeOptions) -> fmt::Result {
+    let quote_char = if options.single_quotes { '\'' } else { '"' };
+    writer.write_char(quote_char)?;
+    for chunk in wtf8_chunks(&value.bytes) {
+        match chunk {
+            Ok(text) => write_escaped_chars(writer, text, quote_char, options)?,
+            Err(code) => write!(writer, "\\u{:04x}", code)?,
+        }
+    }
+    writer.write_char(quote_char)
+}
+
+fn write_escaped_chars(writer: &mut impl fmt::Write, value: &str, quote_char: char, options: &EscapeOptions) -> fmt::Result {
+    for c in value.chars() {
+        match c {
+            '"' if quote_char == '"' => writer.write_str("\\\"")?,
+            '\'' if quote_char == '\'' => writer.write_str("\\'")?,
+            '\\' => writer.write_str("\\\\")?,
+            '\u{08}' => writer.write_str("\\b")?,
+            '\u{0C}' => writer.write_str("\\f")?,
+            '\n' => writer.write_str("\\n")?,
+            '\r' => writer.write_str("\\r")?,
+            '\t' => writer.write_str("\\t")?,
+            _ if c < ' ' || (options.ascii_only && !c.is_ascii()) => {
+                let mut code_units = [0; 2];
+                for code_unit in c.encode_utf16(&mut code_units) {
+                    write!(writer, "\\u{:04x}", code_unit)?;
+                }
+            },
+            _ => writer.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// A set of the kinds of escape sequences used in a string literal.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::strings::{get_escapes_used, EscapesUsed};
+///
+/// let escapes = get_escapes_used(r#"a\u0041\n"#);
+/// assert!(escapes.contains(EscapesUsed::UNICODE | EscapesUsed::CONTROL));
+/// assert!(!escapes.contains(EscapesUsed::QUOTE));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+pub struct EscapesUsed(u8);
+
+impl EscapesUsed {
+    /// No escapes.
+    pub const NONE: EscapesUsed = EscapesUsed(0);
+    /// An escaped quote (`\"` or `\'`).
+    pub const QUOTE: EscapesUsed = EscapesUsed(1);
+    /// An escaped backslash (`\\`).
+    pub const BACKSLASH: EscapesUsed = EscapesUsed(1 << 1);
+    /// An escaped control character (`\b`, `\f`, `\n`, `\r`, or `\t`).
+    pub const CONTROL: EscapesUsed = EscapesUsed(1 << 2);
+    /// A unicode escape (ex. `\u0041`).
+    pub const UNICODE: EscapesUsed = EscapesUsed(1 << 3);
+    /// An escaped solidus (`\/`).
+    pub const SOLIDUS: EscapesUsed = EscapesUsed(1 << 4);
+
+    /// Gets if every kind of escape in the other set is in this set.
+    pub fn contains(self, other: EscapesUsed) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Gets if the set has no escapes.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Gets the flags as bits.
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+impl BitOr for EscapesUsed {
+    type Output = EscapesUsed;
+
+    fn bitor(self, other: EscapesUsed) -> EscapesUsed {
+        EscapesUsed(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for EscapesUsed {
+    fn bitor_assign(&mut self, other: EscapesUsed) {
+        self.0 |= other.0;
+    }
+}
+
+/// Gets the kinds of escape sequences in the text of a string literal, which shouldn't include the
+/// surrounding quotes. Invalid escapes are ignored.
+pub fn get_escapes_used(text: &str) -> EscapesUsed {
+    let mut escapes = EscapesUsed::NONE;
+    let mut chars = text.chars();
+    while let Some(current_char) = chars.next() {
+        if current_char != '\\' {
+            continue;
+        }
+        escapes |= match chars.next() {
+            Some('"') | Some('\'') => EscapesUsed::QUOTE,
+            Some('\\') => EscapesUsed::BACKSLASH,
+            Some('b') | Some('f') | Some('n') | Some('r') | Some('t') => EscapesUsed::CONTROL,
+            Some('u') => EscapesUsed::UNICODE,
+            Some('/') => EscapesUsed::SOLIDUS,
+            _ => EscapesUsed::NONE,
+        };
+    }
+    escapes
+}
+
+/// Decodes the escapes in the text of a string literal that has already been validated by the scanner.
+///
+/// Invalid escapes, which are kept when scanning with `tolerate_invalid_escapes`, are kept as
+/// they are while the valid escapes around them are decoded.
+pub(crate) fn decode_string(text: &str, quote_char: char) -> Cow<'_, str> {
+    match decode_string_literal(text, quote_char) {
+        Ok(value) => value,
+        Err(_) => Cow::Owned(decode_string_with_offsets(text, quote_char).into_iter().map(|(c, _, _)| c).collect()),
+    }
+}
+
+/// Decodes the text of a string literal that has already been validated by the scanner to WTF-8
+/// when it contains lone surrogates, or `None` when it can be decoded to a `String` instead.
+///
+/// Strings with invalid escapes kept by `tolerate_invalid_escapes` are also `None`, so their lone
+/// surrogates are replaced by `decode_string`.
+pub(crate) fn decode_lone_surrogate_string(text: &str, quote_char: char) -> Option<JsonString> {
+    find_lone_surrogate(text, quote_char)?;
+    decode_string_literal_wtf8(text, quote_char).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse_text_with_options, parse_to_value_with_options, ParseOptions, ScannerOptions};
+
+    #[test]
+    fn it_decodes_every_escape() {
+        assert_eq!(
+            decode_string_literal(r#"\"\'\\\/\b\f\n\r\t\u0041é"#, '\'').unwrap(),
+            "\"'\\/\u{08}\u{0C}\n\r\tA\u{e9}",
+        );
+    }
+
+    #[test]
+    fn it_only_decodes_escaped_single_quotes_in_single_quoted_strings() {
+        assert_eq!(decode_string_literal(r#"\'"#, '"').unwrap_err(), EscapeError::new(0, "Invalid escape."));
+        assert_eq!(decode_string_literal(r#"\'"#, '\'').unwrap(), "'");
+        assert!(decode_string_literal_wtf8(r#"\'"#, '"').is_err());
+        assert_eq!(decode_string_with_offsets(r#"\'"#, '"'), vec![('\\', 0, 1), ('\'', 1, 2)]);
+        assert_eq!(decode_string(r#"\'"#, '"'), r#"\'"#);
+
+        let text = r#"["\'", '\'']"#;
+        assert!(decode_string_at(text, &Range { start: 1, end: 5, start_line: 0, end_line: 0 }).is_err());
+        assert_eq!(decode_string_at(text, &Range { start: 7, end: 11, start_line: 0, end_line: 0 }).unwrap(), "'");
+    }
+
+    #[test]
+    fn it_gets_the_escapes_used() {
+        assert_eq!(get_escapes_used("test é"), EscapesUsed::NONE);
+        assert!(get_escapes_used("test").is_empty());
+        assert_eq!(get_escapes_used(r#"a\"b"#), EscapesUsed::QUOTE);
+        assert_eq!(get_escapes_used(r#"a\'b"#), EscapesUsed::QUOTE);
+        assert_eq!(get_escapes_used(r#"a\\n"#), EscapesUsed::BACKSLASH);
+        assert_eq!(get_escapes_used(r#"\b\f\n\r\t"#), EscapesUsed::CONTROL);
+        assert_eq!(get_escapes_used(r#"\u0041"#), EscapesUsed::UNICODE);
+        assert_eq!(get_escapes_used(r#"a\/b"#), EscapesUsed::SOLIDUS);
+        assert_eq!(get_escapes_used(r#"\q\"#), EscapesUsed::NONE);
+
+        let all = get_escapes_used(r#"\"\\\/\n\u00e9"#);
+        assert_eq!(all, EscapesUsed::QUOTE | EscapesUsed::BACKSLASH | EscapesUsed::CONTROL | EscapesUsed::UNICODE | EscapesUsed::SOLIDUS);
+        assert_eq!(all.bits(), 0b11111);
+        assert!(all.contains(EscapesUsed::SOLIDUS | EscapesUsed::QUOTE));
+        assert!(!get_escapes_used(r#"\n"#).contains(EscapesUsed::CONTROL | EscapesUsed::UNICODE));
+    }
+
+    #[test]
+    fn it_borrows_text_without_escapes() {
+        assert!(matches!(decode_string_literal("test", '"'), Ok(Cow::Borrowed("test"))));
+    }
+
+    #[test]
+    fn it_decodes_surrogate_pairs() {
+        assert_eq!(decode_string_literal(r#"\ud83d\ude00"#, '"').unwrap(), "\u{1F600}");
+        assert_eq!(decode_string_literal(r#"\ud83d"#, '"').unwrap(), "\u{FFFD}");
+        assert_eq!(decode_string_literal(r#"\ud83da"#, '"').unwrap(), "\u{FFFD}a");
+        assert_eq!(decode_string_literal(r#"\ude00\ud83d\n"#, '"').unwrap(), "\u{FFFD}\u{FFFD}\n");
+    }
+
+    #[test]
+    fn it_finds_lone_surrogates() {
+        assert_eq!(find_lone_surrogate(r#"\ud83d\ude00 A"#, '"'), None);
+        assert_eq!(find_lone_surrogate(r#"ab\ud83d"#, '"'), Some(2));
+        assert_eq!(find_lone_surrogate(r#"\ud83dx\ude00"#, '"'), Some(0));
+        assert_eq!(find_lone_surrogate(r#"\ud83d\n"#, '"'), Some(0));
+        assert_eq!(find_lone_surrogate(r#"a\ude00"#, '"'), Some(1));
+        assert_eq!(find_lone_surrogate(r#"\ud800\ud83d\ude00"#, '"'), Some(0));
+    }
+
+    #[test]
+    fn it_decodes_lone_surrogates_to_wtf8() {
+        let decoded = decode_string_literal_wtf8(r#"a\ud800\ud83d\ude00\udfff"#, '"').unwrap();
+        assert_eq!(decoded.as_bytes(), b"a\xED\xA0\x80\xF0\x9F\x98\x80\xED\xBF\xBF");
+        assert_eq!(decode_string_literal_wtf8(r#"\té"#, '"').unwrap().as_str(), Some("\t\u{e9}"));
+        assert_eq!(decode_string_literal_wtf8(r#"a\x"#, '"').unwrap_err(), EscapeError::new(1, "Invalid escape."));
+    }
+
+    #[test]
+    fn it_errors_with_offset_of_invalid_escape() {
+        assert_eq!(decode_string_literal(r#"ab\x"#, '"').unwrap_err(), EscapeError::new(2, "Invalid escape."));
+        assert_eq!(decode_string_literal(r#"é\u12"#, '"').unwrap_err(), EscapeError::new(1, "Expected four hex digits."));
+        assert_eq!(decode_string_literal(r#"a\"#, '"').unwrap_err(), EscapeError::new(1, "Invalid escape."));
+    }
+
+    #[test]
+    fn it_keeps_only_the_invalid_escapes_of_tolerated_strings() {
+        assert_eq!(decode_string(r#"a\q\n"#, '"'), "a\\q\n");
+        assert_eq!(decode_string(r#"\u00e9\u12\t"#, '"'), "\u{e9}\\u12\t");
+
+        let options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_tolerate_invalid_escapes(true));
+        let value = parse_to_value_with_options(r#"{ "a\q\n": ["a\q\n", "\x\"\u0041"] }"#, &options).unwrap().unwrap();
+        assert_eq!(value.to_string(), r#"{"a\\q\n":["a\\q\n","\\x\"A"]}"#);
+    }
+
+    #[test]
+    fn it_decodes_the_string_at_a_range() {
+        let text = "{\n  \"\u{e9}\": 'a',\n  \"b\": [\"x\\ty\\u0041\", 1, \"\\q\"]\n}";
+        let tokens = parse_text_with_options(text, &ParseOptions::default().with_scanner_options(ScannerOptions::default().with_single_quotes(true).with_tolerate_invalid_escapes(true))).unwrap().tokens;
+        let decode = |index: usize| decode_string_at(text, &tokens[index].range);
+        assert_eq!(decode(1).unwrap(), "\u{e9}");
+        assert_eq!(decode(3).unwrap(), "a");
+        assert_eq!(decode(8).unwrap(), "x\tyA");
+        assert_eq!(decode(12).unwrap_err(), EscapeError::new(0, "Invalid escape."));
+        assert_eq!(decode(10).unwrap_err(), EscapeError::new(0, "Expected a quoted string at the range."));
+        let range = Range { start: 50, end: 60, start_line: 0, end_line: 0 };
+        assert_eq!(decode_string_at(text, &range).unwrap_err(), EscapeError::new(0, "Expected the range to be within the text."));
+    }
+
+    #[test]
+    fn it_decodes_with_offsets() {
+        assert_eq!(decode_string_with_offsets(r#"a\n\ud83d\ude00\ud83db\q"#, '"'), vec![
+            ('a', 0, 1),
+            ('\n', 1, 3),
+            ('\u{1F600}', 3, 15),
+            ('\u{FFFD}', 15, 21),
+            ('b', 21, 22),
+            ('\\', 22, 23),
+            ('q', 23, 24),
+        ]);
+    }
+
+    #[test]
+    fn it_escapes_strings() {
+        let options = EscapeOptions::default();
+        assert_eq!(escape_string("\"\\/\u{08}\u{0C}\n\r\t\u{1}é", &options), r#""\"\\/\b\f\n\r\t\u0001é""#);
+    }
+
+    #[test]
+    fn it_escapes_non_ascii_when_ascii_only() {
+        let options = EscapeOptions::default().with_ascii_only(true);
+        assert_eq!(escape_string("\u{e9}\u{1F600}", &options), r#""\u00e9\ud83d\ude00""#);
+    }
+
+    #[test]
+    fn it_escapes_with_single_quotes() {
+        let options = EscapeOptions::default().with_single_quotes(true);
+        assert_eq!(escape_string("it's \"a\"", &options), r#"'it\'s "a"'"#);
+    }
+
+    #[test]
+    fn it_round_trips_escaped_strings() {
+        let value = "a\"\\\u{1}\u{1F600}\n";
+        for ascii_only in [false, true].iter() {
+            let escaped = escape_string(value, &EscapeOptions::default().with_ascii_only(*ascii_only));
+            assert_eq!(decode_string_literal(&escaped[1..escaped.len() - 1], '"').unwrap(), value);
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use super::ast::{get_comment_style, CommentKind, CommentStyle};
 use super::common::{ImmutableString, Range};
+use super::strings::{get_escapes_used, EscapesUsed};
 
 /// A token found while scanning.
 #[derive(Debug, PartialEq, Clone)]
@@ -15,10 +17,353 @@ pub enum Token {
     Null,
     CommentLine(ImmutableString),
     CommentBlock(ImmutableString),
+    /// Whitespace between tokens, which is only returned by a lossless scanner.
+    Whitespace(ImmutableString),
 }
 
 /// A token with positional information.
+#[derive(Debug, PartialEq, Clone)]
 pub struct TokenAndRange {
     pub range: Range,
     pub token: Token,
 }
+
+/// A token found while scanning whose payload borrows from the scanned text.
+///
+/// The payloads are the same as the payloads of `Token` (ex. the text of a string
+/// without its quotes and with its escapes unprocessed).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TokenRef<'a> {
+    OpenBrace,
+    CloseBrace,
+    OpenBracket,
+    CloseBracket,
+    Comma,
+    Colon,
+    String(&'a str),
+    Boolean(bool),
+    Number(&'a str),
+    Null,
+    CommentLine(&'a str),
+    CommentBlock(&'a str),
+    /// Whitespace between tokens, which is only returned when scanning with whitespace.
+    Whitespace(&'a str),
+}
+
+impl Token {
+    /// Gets the style of the comment when the token is a comment.
+    pub fn comment_style(&self) -> Option<CommentStyle> {
+        match self {
+            Token::CommentLine(text) => Some(get_comment_style(CommentKind::Line, text.as_ref())),
+            Token::CommentBlock(text) => Some(get_comment_style(CommentKind::Block, text.as_ref())),
+            _ => None,
+        }
+    }
+
+    /// Gets the kinds of escapes the string used when the token is a string.
+    pub fn escapes_used(&self) -> Option<EscapesUsed> {
+        match self {
+            Token::String(text) => Some(get_escapes_used(text.as_ref())),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> TokenRef<'a> {
+    /// Gets the style of the comment when the token is a comment.
+    pub fn comment_style(&self) -> Option<CommentStyle> {
+        match self {
+            TokenRef::CommentLine(text) => Some(get_comment_style(CommentKind::Line, text)),
+            TokenRef::CommentBlock(text) => Some(get_comment_style(CommentKind::Block, text)),
+            _ => None,
+        }
+    }
+
+    /// Gets the kinds of escapes the string used when the token is a string.
+    pub fn escapes_used(&self) -> Option<EscapesUsed> {
+        match self {
+            TokenRef::String(text) => Some(get_escapes_used(text)),
+            _ => None,
+        }
+    }
+
+    /// Creates the equivalent owned token.
+    pub fn to_token(&self) -> Token {
+        match *self {
+            TokenRef::OpenBrace => Token::OpenBrace,
+            TokenRef::CloseBrace => Token::CloseBrace,
+            TokenRef::OpenBracket => Token::OpenBracket,
+            TokenRef::CloseBracket => Token::CloseBracket,
+            TokenRef::Comma => Token::Comma,
+            TokenRef::Colon => Token::Colon,
+            TokenRef::String(text) => Token::String(ImmutableString::new(String::from(text))),
+            TokenRef::Boolean(value) => Token::Boolean(value),
+            TokenRef::Number(text) => Token::Number(ImmutableString::new(String::from(text))),
+            TokenRef::Null => Token::Null,
+            TokenRef::CommentLine(text) => Token::CommentLine(ImmutableString::new(String::from(text))),
+            TokenRef::CommentBlock(text) => Token::CommentBlock(ImmutableString::new(String::from(text))),
+            TokenRef::Whitespace(text) => Token::Whitespace(ImmutableString::new(String::from(text))),
+        }
+    }
+}
+
+/// A borrowed token with positional information.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TokenRefAndRange<'a> {
+    pub range: Range,
+    pub token: TokenRef<'a>,
+}
+
+/// A scanned item that can be filtered with `TokenFilters`.
+///
+/// This is implemented for tokens, tokens with ranges, references to them, and the results of
+/// scanning them, so the filters work over a `TokenRefIterator` and over collected tokens alike.
+pub trait FilterableToken {
+    /// Gets if the item is a comment line or comment block.
+    fn is_comment(&self) -> bool;
+    /// Gets if the item is whitespace, which is only scanned by a lossless scanner.
+    fn is_whitespace(&self) -> bool;
+    /// Gets if the item is a scanning error, which the filters always keep.
+    fn is_error(&self) -> bool {
+        false
+    }
+    /// Gets if the item is a comment or whitespace, which doesn't affect the value.
+    fn is_trivia(&self) -> bool {
+        self.is_comment() || self.is_whitespace()
+    }
+}
+
+impl FilterableToken for Token {
+    fn is_comment(&self) -> bool {
+        matches!(self, Token::CommentLine(_) | Token::CommentBlock(_))
+    }
+
+    fn is_whitespace(&self) -> bool {
+        matches!(self, Token::Whitespace(_))
+    }
+}
+
+impl<'a> FilterableToken for TokenRef<'a> {
+    fn is_comment(&self) -> bool {
+        matches!(self, TokenRef::CommentLine(_) | TokenRef::CommentBlock(_))
+    }
+
+    fn is_whitespace(&self) -> bool {
+        matches!(self, TokenRef::Whitespace(_))
+    }
+}
+
+impl FilterableToken for TokenAndRange {
+    fn is_comment(&self) -> bool {
+        self.token.is_comment()
+    }
+
+    fn is_whitespace(&self) -> bool {
+        self.token.is_whitespace()
+    }
+}
+
+impl<'a> FilterableToken for TokenRefAndRange<'a> {
+    fn is_comment(&self) -> bool {
+        self.token.is_comment()
+    }
+
+    fn is_whitespace(&self) -> bool {
+        self.token.is_whitespace()
+    }
+}
+
+impl<T: FilterableToken> FilterableToken for &T {
+    fn is_comment(&self) -> bool {
+        (*self).is_comment()
+    }
+
+    fn is_whitespace(&self) -> bool {
+        (*self).is_whitespace()
+    }
+
+    fn is_error(&self) -> bool {
+        (*self).is_error()
+    }
+}
+
+impl<T: FilterableToken, E> FilterableToken for Result<T, E> {
+    fn is_comment(&self) -> bool {
+        self.as_ref().map(|token| token.is_comment()).unwrap_or(false)
+    }
+
+    fn is_whitespace(&self) -> bool {
+        self.as_ref().map(|token| token.is_whitespace()).unwrap_or(false)
+    }
+
+    fn is_error(&self) -> bool {
+        self.is_err()
+    }
+}
+
+/// Adapters that filter an iterator of tokens.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::TokenRefIterator;
+/// use jsonc_parser::tokens::{TokenFilters, TokenRef};
+///
+/// let text = "[1, /* a */ 2] // b";
+/// let tokens = TokenRefIterator::new(text).significant().map(|token| token.unwrap().token).collect::<Vec<_>>();
+/// assert_eq!(tokens, vec![TokenRef::OpenBracket, TokenRef::Number("1"), TokenRef::Comma, TokenRef::Number("2"), TokenRef::CloseBracket]);
+/// let comments = TokenRefIterator::new(text).comments_only().map(|token| token.unwrap().token).collect::<Vec<_>>();
+/// assert_eq!(comments, vec![TokenRef::CommentBlock(" a "), TokenRef::CommentLine(" b")]);
+/// ```
+pub trait TokenFilters: Iterator + Sized
+where
+    Self::Item: FilterableToken,
+{
+    /// Skips comments and whitespace, keeping the tokens that make up the value.
+    fn significant(self) -> Significant<Self> {
+        Significant { iter: self }
+    }
+
+    /// Keeps only the comments.
+    fn comments_only(self) -> CommentsOnly<Self> {
+        CommentsOnly { iter: self }
+    }
+
+    /// Counts the tokens that aren't comments or whitespace, which includes any scanning errors.
+    fn count_significant(self) -> usize {
+        self.filter(|token| !token.is_trivia()).count()
+    }
+}
+
+impl<I> TokenFilters for I
+where
+    I: Iterator,
+    I::Item: FilterableToken,
+{
+}
+
+/// Iterator over the tokens that aren't comments or whitespace. See `TokenFilters::significant`.
+#[derive(Debug, Clone)]
+pub struct Significant<I> {
+    iter: I,
+}
+
+impl<I> Iterator for Significant<I>
+where
+    I: Iterator,
+    I::Item: FilterableToken,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.iter.find(|token| !token.is_trivia())
+    }
+}
+
+/// Iterator over the comments. See `TokenFilters::comments_only`.
+#[derive(Debug, Clone)]
+pub struct CommentsOnly<I> {
+    iter: I,
+}
+
+impl<I> Iterator for CommentsOnly<I>
+where
+    I: Iterator,
+    I::Item: FilterableToken,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.iter.find(|token| token.is_comment() || token.is_error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::scanner::scan_tokens;
+    use super::super::{Scanner, ScannerOptions, TokenRefIterator};
+
+    const TEXT: &str = "// header\n{\n  \"a\": 1, /* inline */\n  \"b\": [true] // trailing\n}";
+
+    #[test]
+    fn it_filters_significant_tokens() {
+        let options = ScannerOptions::default().with_whitespace(true);
+        let tokens = TokenRefIterator::new_with_options(TEXT, options).significant().map(|token| token.unwrap().token).collect::<Vec<_>>();
+        assert_eq!(tokens, vec![
+            TokenRef::OpenBrace,
+            TokenRef::String("a"),
+            TokenRef::Colon,
+            TokenRef::Number("1"),
+            TokenRef::Comma,
+            TokenRef::String("b"),
+            TokenRef::Colon,
+            TokenRef::OpenBracket,
+            TokenRef::Boolean(true),
+            TokenRef::CloseBracket,
+            TokenRef::CloseBrace,
+        ]);
+    }
+
+    #[test]
+    fn it_counts_significant_tokens() {
+        let without_comments = "{\n  \"a\": 1,\n  \"b\": [true]\n}";
+        let options = ScannerOptions::default().with_whitespace(true);
+        assert_eq!(TokenRefIterator::new_with_options(TEXT, options.clone()).count_significant(), 11);
+        assert_eq!(TokenRefIterator::new(TEXT).count_significant(), TokenRefIterator::new(without_comments).count_significant());
+        let tokens = scan_tokens(&mut Scanner::new_with_options(TEXT, options)).unwrap();
+        assert_eq!(tokens.iter().count_significant(), 11);
+        assert!(tokens[0].is_trivia());
+        assert!(!tokens.last().unwrap().is_trivia());
+        // errors aren't trivia
+        assert_eq!(TokenRefIterator::new("[1, 'b'] // c").count_significant(), 4);
+    }
+
+    #[test]
+    fn it_filters_comments() {
+        let tokens = scan_tokens(&mut Scanner::new(TEXT)).unwrap();
+        let comments = tokens.iter().comments_only().map(|token| &token.token).collect::<Vec<_>>();
+        assert_eq!(comments, vec![
+            &Token::CommentLine(ImmutableString::new(String::from(" header"))),
+            &Token::CommentBlock(ImmutableString::new(String::from(" inline "))),
+            &Token::CommentLine(ImmutableString::new(String::from(" trailing"))),
+        ]);
+        // filtering the comments out of the significant tokens leaves nothing
+        assert_eq!(tokens.iter().significant().comments_only().count(), 0);
+    }
+
+    #[test]
+    fn it_keeps_errors() {
+        let items = TokenRefIterator::new("[1, /* a */ 'b']").comments_only().collect::<Vec<_>>();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_ref().unwrap().token, TokenRef::CommentBlock(" a "));
+        assert!(items[1].is_err());
+        assert!(TokenRefIterator::new("[1, 'b']").significant().last().unwrap().is_err());
+    }
+
+    #[test]
+    fn it_gets_the_style_of_comment_tokens() {
+        let text = "/** a */ /*! b */ /* c */\n/// d\n//! e\n// f\n[]";
+        let styles = TokenRefIterator::new(text).comments_only().map(|item| item.unwrap().token.comment_style().unwrap()).collect::<Vec<_>>();
+        assert_eq!(styles, vec![
+            CommentStyle::Doc,
+            CommentStyle::InnerDoc,
+            CommentStyle::Normal,
+            CommentStyle::Doc,
+            CommentStyle::InnerDoc,
+            CommentStyle::Normal,
+        ]);
+        let tokens = scan_tokens(&mut Scanner::new("/*! a */[]")).unwrap();
+        assert_eq!(tokens[0].token.comment_style(), Some(CommentStyle::InnerDoc));
+        assert_eq!(tokens[1].token.comment_style(), None);
+    }
+
+    #[test]
+    fn it_gets_the_escapes_used_by_string_tokens() {
+        let text = r#"["a\u0041", "b\n", "c", 1]"#;
+        let escapes = TokenRefIterator::new(text).map(|item| item.unwrap().token.escapes_used()).collect::<Vec<_>>();
+        assert_eq!(escapes, vec![None, Some(EscapesUsed::UNICODE), None, Some(EscapesUsed::CONTROL), None, Some(EscapesUsed::NONE), None, None, None]);
+        let tokens = scan_tokens(&mut Scanner::new(r#""\/\\""#)).unwrap();
+        assert_eq!(tokens[0].token.escapes_used(), Some(EscapesUsed::SOLIDUS | EscapesUsed::BACKSLASH));
+    }
+}
@@ -0,0 +1,1160 @@
+use std::collections::HashMap;
+use std::fmt;
+use super::ast::{StringLit, Value};
+use super::common::Range;
+use super::errors::{ErrorKind, ParseError};
+use super::events::{Event, EventParser};
+use super::parser::{ParseOptions, RootKind};
+use super::path::JsonPath;
+use super::scanner::{RawToken, Scanner};
+use super::strings::{decode_string, decode_string_with_offsets, escape_string, EscapeOptions};
+use super::tokens::Token;
+use super::value::JsonValue;
+
+/// Something that may appear at a position in the text.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ExpectedKind {
+    /// Any value (object, array, string, number, boolean, or null).
+    Value,
+    /// A string for the name of an object property.
+    PropertyName,
+    Colon,
+    Comma,
+    CloseBrace,
+    CloseBracket,
+}
+
+/// The kinds of tokens and values that are legal at a position in the text.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ExpectedSet {
+    kinds: Vec<ExpectedKind>,
+}
+
+impl ExpectedSet {
+    fn new(kinds: &[ExpectedKind]) -> ExpectedSet {
+        ExpectedSet { kinds: kinds.to_vec() }
+    }
+
+    /// Gets if the kind is legal at the position.
+    pub fn contains(&self, kind: ExpectedKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+
+    /// Gets if nothing more is expected (ex. after the root value).
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    /// Iterates over the legal kinds.
+    pub fn iter(&self) -> impl Iterator<Item = &ExpectedKind> {
+        self.kinds.iter()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ObjectState {
+    /// After the open brace or a comma.
+    Start,
+    /// After a property name.
+    Name,
+    /// After the colon of a property.
+    Colon,
+    /// After the value of a property.
+    Value,
+}
+
+#[derive(Clone, Copy)]
+enum ArrayState {
+    /// After the open bracket or a comma.
+    Start,
+    /// After an element.
+    Value,
+}
+
+enum Frame {
+    Object(ObjectState),
+    Array(ArrayState),
+}
+
+/// Gets what may appear at the provided position in the text, which is useful for completions.
+///
+/// The text before the position is parsed tolerantly, so this works in incomplete and broken
+/// documents. The position is a character offset and tokens that end after it (ex. a word being
+/// typed) are ignored.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::tooling::{expected_at, ExpectedKind};
+///
+/// let text = r#"{ "a": 1 "#;
+/// let expected = expected_at(text, text.len());
+/// assert!(expected.contains(ExpectedKind::Comma));
+/// assert!(expected.contains(ExpectedKind::CloseBrace));
+/// ```
+pub fn expected_at(text: &str, pos: usize) -> ExpectedSet {
+    let mut scanner = Scanner::new(text);
+    let mut stack = Vec::new();
+    let mut is_root_complete = false;
+
+    loop {
+        let token = match scanner.scan() {
+            Ok(Some(token)) => token,
+            Ok(None) => break,
+            Err(_) => {
+                scanner.recover_from_error();
+                if scanner.token_end() > pos {
+                    break;
+                }
+                continue;
+            },
+        };
+        if scanner.token_end() > pos {
+            break;
+        }
+
+        match token {
+            Token::CommentLine(_) | Token::CommentBlock(_) | Token::Whitespace(_) => {},
+            token => handle_token(&mut stack, &mut is_root_complete, token),
+        }
+    }
+
+    match stack.last() {
+        None if is_root_complete => ExpectedSet::default(),
+        None => ExpectedSet::new(&[ExpectedKind::Value]),
+        Some(Frame::Object(ObjectState::Start)) => ExpectedSet::new(&[ExpectedKind::PropertyName, ExpectedKind::CloseBrace]),
+        Some(Frame::Object(ObjectState::Name)) => ExpectedSet::new(&[ExpectedKind::Colon]),
+        Some(Frame::Object(ObjectState::Colon)) => ExpectedSet::new(&[ExpectedKind::Value]),
+        Some(Frame::Object(ObjectState::Value)) => ExpectedSet::new(&[ExpectedKind::Comma, ExpectedKind::CloseBrace]),
+        Some(Frame::Array(ArrayState::Start)) => ExpectedSet::new(&[ExpectedKind::Value, ExpectedKind::CloseBracket]),
+        Some(Frame::Array(ArrayState::Value)) => ExpectedSet::new(&[ExpectedKind::Comma, ExpectedKind::CloseBracket]),
+    }
+}
+
+/// Moves the state forward for a token, recovering the same way the parser does
+/// (ex. a missing comma between elements is allowed and unexpected tokens are skipped).
+fn handle_token(stack: &mut Vec<Frame>, is_root_complete: &mut bool, token: Token) {
+    let is_expecting_value = match stack.last() {
+        None => !*is_root_complete,
+        Some(Frame::Object(ObjectState::Colon)) | Some(Frame::Array(_)) => true,
+        Some(Frame::Object(_)) => false,
+    };
+
+    match token {
+        Token::OpenBrace if is_expecting_value => stack.push(Frame::Object(ObjectState::Start)),
+        Token::OpenBracket if is_expecting_value => stack.push(Frame::Array(ArrayState::Start)),
+        Token::String(_) | Token::Number(_) | Token::Boolean(_) | Token::Null if is_expecting_value => complete_value(stack, is_root_complete),
+        Token::String(_) => {
+            if let Some(Frame::Object(state @ ObjectState::Start)) | Some(Frame::Object(state @ ObjectState::Value)) = stack.last_mut() {
+                *state = ObjectState::Name;
+            }
+        },
+        Token::Colon => {
+            if let Some(Frame::Object(state @ ObjectState::Name)) = stack.last_mut() {
+                *state = ObjectState::Colon;
+            }
+        },
+        Token::Comma => match stack.last_mut() {
+            Some(Frame::Object(state)) => *state = ObjectState::Start,
+            Some(Frame::Array(state)) => *state = ArrayState::Start,
+            None => {},
+        },
+        Token::CloseBrace => {
+            if let Some(Frame::Object(_)) = stack.last() {
+                stack.pop();
+                complete_value(stack, is_root_complete);
+            }
+        },
+        Token::CloseBracket => {
+            if let Some(Frame::Array(_)) = stack.last() {
+                stack.pop();
+                complete_value(stack, is_root_complete);
+            }
+        },
+        _ => {},
+    }
+}
+
+fn complete_value(stack: &mut [Frame], is_root_complete: &mut bool) {
+    match stack.last_mut() {
+        None => *is_root_complete = true,
+        Some(Frame::Object(state)) => *state = ObjectState::Value,
+        Some(Frame::Array(state)) => *state = ArrayState::Value,
+    }
+}
+
+/// Receives the tokens of a document along with how deeply they're nested.
+///
+/// The depth of the root value is zero and the depth of a container's children is one more
+/// than the depth of the container. Comments, commas, and colons aren't visited.
+pub trait TokenVisitor {
+    /// Called for an open brace or bracket.
+    fn on_open(&mut self, _token: &Token, _depth: usize) {}
+    /// Called for the close brace or bracket that matches an open token at the same depth.
+    fn on_close(&mut self, _token: &Token, _depth: usize) {}
+    /// Called for a string, number, boolean, or null.
+    fn on_scalar(&mut self, _token: &Token, _depth: usize) {}
+}
+
+/// Scans the text and calls the visitor for each token without building an AST.
+///
+/// Errors when the text can't be scanned or a close brace or bracket doesn't match the
+/// last open one, but otherwise doesn't validate the structure (ex. missing commas are allowed).
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::tokens::Token;
+/// use jsonc_parser::tooling::{visit_tokens, TokenVisitor};
+///
+/// struct MaxDepth(usize);
+///
+/// impl TokenVisitor for MaxDepth {
+///     fn on_scalar(&mut self, _token: &Token, depth: usize) {
+///         self.0 = self.0.max(depth);
+///     }
+/// }
+///
+/// let mut visitor = MaxDepth(0);
+/// visit_tokens(r#"{ "a": [1, [2]] }"#, &mut visitor).unwrap();
+/// assert_eq!(visitor.0, 3);
+/// ```
+pub fn visit_tokens(text: &str, visitor: &mut impl TokenVisitor) -> Result<(), ParseError> {
+    let mut scanner = Scanner::new(text);
+    let mut open_stack: Vec<(Token, Range)> = Vec::new();
+
+    while let Some(token) = scanner.scan()? {
+        let depth = open_stack.len();
+        match token {
+            Token::OpenBrace | Token::OpenBracket => {
+                visitor.on_open(&token, depth);
+                let range = Range {
+                    start: scanner.token_start(),
+                    end: scanner.token_end(),
+                    start_line: scanner.token_start_line(),
+                    end_line: scanner.token_end_line(),
+                };
+                open_stack.push((token, range));
+            },
+            Token::CloseBrace | Token::CloseBracket => {
+                let is_match = match open_stack.last() {
+                    Some((Token::OpenBrace, _)) => token == Token::CloseBrace,
+                    Some((Token::OpenBracket, _)) => token == Token::CloseBracket,
+                    _ => false,
+                };
+                if !is_match {
+                    let message = if token == Token::CloseBrace { "Unexpected close brace." } else { "Unexpected close bracket." };
+                    return Err(scanner.locate_error(ParseError::new(scanner.token_start(), message)));
+                }
+                open_stack.pop();
+                visitor.on_close(&token, depth - 1);
+            },
+            Token::String(_) | Token::Number(_) | Token::Boolean(_) | Token::Null => visitor.on_scalar(&token, depth),
+            Token::Comma | Token::Colon | Token::CommentLine(_) | Token::CommentBlock(_) | Token::Whitespace(_) => {},
+        }
+    }
+
+    match open_stack.pop() {
+        Some((Token::OpenBrace, range)) => Err(scanner.locate_error(ParseError::new_unclosed(scanner.token_end(), range, "{", "'}'"))),
+        Some((_, range)) => Err(scanner.locate_error(ParseError::new_unclosed(scanner.token_end(), range, "[", "']'"))),
+        None => Ok(()),
+    }
+}
+
+/// Gets if the root object has a property with the provided name without building any values.
+///
+/// The values of the other properties are skipped with `EventParser::skip_value`, so they're
+/// validated like the parser does, but their strings aren't decoded. Returns as soon as the
+/// property is found, so text after it isn't scanned or validated. Errors when the root value
+/// isn't an object.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::tooling::has_top_level_key;
+///
+/// let text = r#"{ "a": { "flag": true }, "flag": false }"#;
+/// assert!(has_top_level_key(text, "flag").unwrap());
+/// assert!(!has_top_level_key(text, "b").unwrap());
+/// ```
+pub fn has_top_level_key(text: &str, key: &str) -> Result<bool, ParseError> {
+    let options = ParseOptions::default().with_root_kind(RootKind::Object).with_require_value(true);
+    let mut parser = EventParser::new(text, &options);
+    // the value of each property is skipped, so every key is in the root object
+    while let Some(event) = parser.next_event()? {
+        match event {
+            Event::Key(name, _) if name == key => return Ok(true),
+            Event::Key(..) => {
+                parser.skip_value()?;
+            },
+            Event::ObjectEnd(_) => return Ok(false),
+            _ => {},
+        }
+    }
+    Ok(false)
+}
+
+/// What a position in the text is within.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PositionClass {
+    /// Between the quotes of a string, which includes an unterminated string.
+    InString,
+    /// After the slashes of a comment line, which includes the end of the line.
+    InLineComment,
+    /// Between the delimiters of a comment block, which includes an unterminated comment block.
+    InBlockComment,
+    /// Anywhere else (ex. between tokens or in a number).
+    Code,
+}
+
+/// Gets whether a position is in a string, a comment, or code, which is useful for editors to
+/// decide whether to offer completions.
+///
+/// The text is scanned up to the position, so this works in incomplete documents. The position
+/// is a character offset and is in a string or comment when it's after the opening delimiter.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::tooling::{classify_position, PositionClass};
+///
+/// let text = r#"{ "a": 1 } // b"#;
+/// assert_eq!(classify_position(text, 3), PositionClass::InString);
+/// assert_eq!(classify_position(text, 6), PositionClass::Code);
+/// assert_eq!(classify_position(text, text.len()), PositionClass::InLineComment);
+/// ```
+pub fn classify_position(text: &str, pos: usize) -> PositionClass {
+    let mut scanner = Scanner::new(text);
+    loop {
+        let result = scanner.scan_raw();
+        let start = scanner.token_start();
+        if start >= pos {
+            return PositionClass::Code;
+        }
+
+        match result {
+            Ok(Some(token)) => {
+                let end = scanner.token_end();
+                match token {
+                    RawToken::String(..) if pos < end => return PositionClass::InString,
+                    RawToken::CommentLine(..) if pos > start + 1 && pos <= end => return PositionClass::InLineComment,
+                    RawToken::CommentBlock(..) if pos > start + 1 && pos < end - 1 => return PositionClass::InBlockComment,
+                    _ if pos <= end => return PositionClass::Code,
+                    _ => {},
+                }
+            },
+            Ok(None) => return PositionClass::Code,
+            Err(err) => {
+                let start_char = scanner.token_start_char();
+                if let ErrorKind::UnexpectedEof { opened_at: Some(_), .. } = err.kind {
+                    // an unterminated string or comment block continues to the end of the text
+                    return match start_char {
+                        Some('/') if pos > start + 1 => PositionClass::InBlockComment,
+                        Some('/') => PositionClass::Code,
+                        _ => PositionClass::InString,
+                    };
+                }
+
+                scanner.recover_from_error();
+                let end = scanner.token_end();
+                if let Some(quote) = start_char.filter(|c| *c == '"' || *c == '\'') {
+                    let is_closed = end > start + 1 && scanner.token_last_char() == Some(quote);
+                    if pos < end || (pos == end && !is_closed) {
+                        return PositionClass::InString;
+                    }
+                } else if pos <= end {
+                    return PositionClass::Code;
+                }
+            },
+        }
+    }
+}
+
+/// How lines end in a document.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+    /// `\r`
+    Cr,
+}
+
+/// How lines are indented in a document.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IndentStyle {
+    Tabs,
+    /// Spaces with the width of one level of indentation.
+    Spaces(usize),
+}
+
+/// Information about the encoding and layout of a document.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DocumentInfo {
+    /// Whether the text starts with a byte order mark.
+    pub has_bom: bool,
+    /// The most common line ending or `None` when the text is a single line.
+    pub line_ending: Option<LineEnding>,
+    /// The most common indentation or `None` when no lines are indented.
+    ///
+    /// The width of space indentation is the smallest indentation of a line indented with spaces.
+    pub indent_style: Option<IndentStyle>,
+}
+
+/// Gets the byte order mark, line ending, and indentation of the text in a single pass.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::tooling::{analyze, IndentStyle, LineEnding};
+///
+/// let info = analyze("{\r\n  \"a\": [\r\n    1\r\n  ]\r\n}");
+/// assert!(!info.has_bom);
+/// assert_eq!(info.line_ending, Some(LineEnding::CrLf));
+/// assert_eq!(info.indent_style, Some(IndentStyle::Spaces(2)));
+/// ```
+pub fn analyze(text: &str) -> DocumentInfo {
+    let has_bom = text.starts_with('\u{feff}');
+    let mut line_ending_counts = [0usize; 3];
+    let mut tab_lines = 0;
+    let mut space_lines = 0;
+    let mut min_space_width: Option<usize> = None;
+    let mut chars = text.chars().skip(if has_bom { 1 } else { 0 }).peekable();
+
+    loop {
+        // measure the indentation at the start of the line
+        let first_char = chars.peek().copied();
+        let mut width = 0;
+        while let Some(c) = chars.peek().copied() {
+            if c != ' ' && c != '\t' {
+                break;
+            }
+            width += 1;
+            chars.next();
+        }
+        let is_blank = matches!(chars.peek(), None | Some('\n') | Some('\r'));
+        if width > 0 && !is_blank {
+            if first_char == Some('\t') {
+                tab_lines += 1;
+            } else {
+                space_lines += 1;
+                min_space_width = Some(min_space_width.map(|min| min.min(width)).unwrap_or(width));
+            }
+        }
+
+        // move to the start of the next line
+        loop {
+            match chars.next() {
+                None => {
+                    return DocumentInfo {
+                        has_bom,
+                        line_ending: get_most_common_line_ending(&line_ending_counts),
+                        indent_style: match (tab_lines, min_space_width) {
+                            (0, None) => None,
+                            (tab_lines, Some(width)) if space_lines > tab_lines => Some(IndentStyle::Spaces(width)),
+                            _ => Some(IndentStyle::Tabs),
+                        },
+                    };
+                },
+                Some('\n') => {
+                    line_ending_counts[0] += 1;
+                    break;
+                },
+                Some('\r') => {
+                    if chars.peek() == Some(&'\n') {
+                        chars.next();
+                        line_ending_counts[1] += 1;
+                    } else {
+                        line_ending_counts[2] += 1;
+                    }
+                    break;
+                },
+                Some(_) => {},
+            }
+        }
+    }
+}
+
+fn get_most_common_line_ending(counts: &[usize; 3]) -> Option<LineEnding> {
+    let line_endings = [LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr];
+    let mut result = None;
+    let mut max_count = 0;
+    for (line_ending, count) in line_endings.iter().zip(counts.iter()) {
+        if *count > max_count {
+            result = Some(*line_ending);
+            max_count = *count;
+        }
+    }
+    result
+}
+
+/// An occurrence of text in a string found by `find_in_strings`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StringMatch {
+    /// Path of the value, or of the property when the match is in a property name.
+    pub path: JsonPath,
+    /// Whether the match is in a property name instead of a string value.
+    pub is_property_name: bool,
+    /// Range of the string literal, including its quotes.
+    pub node_range: Range,
+    /// Range of the characters in the text that decode to the match, including any escapes.
+    ///
+    /// Each decoded character comes from a run of characters in the text, and the runs of
+    /// consecutive characters are next to each other, so a match always has a single range.
+    pub source_range: Range,
+}
+
+/// Finds the occurrences of the needle in the decoded property names and string values, in
+/// document order, so that matches are found even when escapes were used for some characters.
+///
+/// Occurrences in the same string don't overlap.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_text;
+/// use jsonc_parser::tooling::find_in_strings;
+///
+/// let text = r#"{ "db": { "host": "local\u0068ost" } }"#;
+/// let value = parse_text(text).unwrap().value.unwrap();
+/// let matches = find_in_strings(&value, "localhost");
+/// assert_eq!(matches[0].path.to_string(), "/db/host");
+/// assert_eq!((matches[0].source_range.start, matches[0].source_range.end), (19, 33));
+/// ```
+pub fn find_in_strings(value: &Value, needle: &str) -> Vec<StringMatch> {
+    let needle = needle.chars().collect::<Vec<_>>();
+    let mut matches = Vec::new();
+    if needle.is_empty() {
+        return matches;
+    }
+
+    let mut find_in_string = |lit: &StringLit, path: &JsonPath, is_property_name: bool| {
+        let decoded = decode_string_with_offsets(lit.value.as_ref(), lit.quote);
+        let content_start = lit.range.start + 1;
+        let mut index = 0;
+        while index + needle.len() <= decoded.len() {
+            let candidate = &decoded[index..index + needle.len()];
+            if !candidate.iter().zip(needle.iter()).all(|((c, _, _), needle_char)| c == needle_char) {
+                index += 1;
+                continue;
+            }
+            matches.push(StringMatch {
+                path: path.clone(),
+                is_property_name,
+                node_range: lit.range.clone(),
+                source_range: Range {
+                    start: content_start + candidate[0].1,
+                    end: content_start + candidate[needle.len() - 1].2,
+                    start_line: lit.range.start_line,
+                    end_line: lit.range.end_line,
+                },
+            });
+            index += needle.len();
+        }
+    };
+
+    let mut stack = vec![(value, JsonPath::new())];
+    while let Some((value, path)) = stack.pop() {
+        match value {
+            Value::StringLit(lit) => find_in_string(lit, &path, false),
+            Value::Object(obj) => {
+                for prop in obj.properties.iter() {
+                    let mut prop_path = path.clone();
+                    prop_path.push(decode_string(prop.name.value.as_ref(), prop.name.quote).as_ref());
+                    find_in_string(&prop.name, &prop_path, true);
+                    stack.push((&prop.value, prop_path));
+                }
+            },
+            Value::Array(arr) => {
+                for (index, element) in arr.elements.iter().enumerate() {
+                    let mut element_path = path.clone();
+                    element_path.push(index);
+                    stack.push((element, element_path));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    matches.sort_by_key(|string_match| string_match.source_range.start);
+    matches
+}
+
+/// The shape of values inferred from sample values by `infer_shape`, which is much simpler than
+/// JSON Schema.
+///
+/// When displayed, the shape is formatted like a TypeScript type (ex. `{ a: number, b?: string }[]`).
+#[derive(Debug, PartialEq, Clone)]
+pub enum Shape {
+    /// No values were seen (ex. the elements of an empty array).
+    Unknown,
+    Null,
+    Boolean,
+    Number,
+    String,
+    /// An array with the merged shape of its elements.
+    Array(Box<Shape>),
+    /// An object with its properties in the order they were first seen.
+    Object(Vec<ShapeProperty>),
+    /// Values of different kinds, which contains at most one shape of each kind in the order of
+    /// null, boolean, number, string, array, and object.
+    Union(Vec<Shape>),
+}
+
+/// A property of an object shape.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ShapeProperty {
+    pub name: String,
+    pub shape: Shape,
+    /// Whether the property was in every object that was merged.
+    pub required: bool,
+}
+
+impl Shape {
+    /// Merges the shape with the shape of other values at the same path (ex. another element of
+    /// the same array).
+    ///
+    /// Objects are merged property by property, where a property is only required when it's
+    /// required in both objects, and arrays are merged by merging their elements.
+    pub fn merge(self, other: Shape) -> Shape {
+        let mut tasks = vec![MergeTask::Merge(self, other)];
+        let mut results = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                MergeTask::Merge(shape, other) => match (shape, other) {
+                    (Shape::Unknown, other) => results.push(other),
+                    (shape, Shape::Unknown) => results.push(shape),
+                    (Shape::Array(elements), Shape::Array(other_elements)) => {
+                        tasks.push(MergeTask::BuildArray);
+                        tasks.push(MergeTask::Merge(*elements, *other_elements));
+                    },
+                    (Shape::Object(properties), Shape::Object(other_properties)) => {
+                        let (names, merges): (Vec<_>, Vec<_>) = pair_shape_properties(properties, other_properties)
+                            .into_iter()
+                            .map(|(prop, other)| ((prop.name, prop.required), MergeTask::Merge(prop.shape, other)))
+                            .unzip();
+                        tasks.push(MergeTask::BuildObject(names));
+                        // reversed so the results are in the order of the properties
+                        tasks.extend(merges.into_iter().rev());
+                    },
+                    (shape, other) if !matches!(shape, Shape::Union(_)) && !matches!(other, Shape::Union(_)) && shape.kind_order() == other.kind_order() => {
+                        results.push(shape);
+                    },
+                    (shape, other) => {
+                        let mut pairs = shape.into_union_members().into_iter().map(|shape| (shape, Shape::Unknown)).collect::<Vec<_>>();
+                        for shape in other.into_union_members() {
+                            match pairs.iter().position(|(existing, _)| existing.kind_order() == shape.kind_order()) {
+                                Some(index) => pairs[index].1 = shape,
+                                None => pairs.push((shape, Shape::Unknown)),
+                            }
+                        }
+                        pairs.sort_by_key(|(shape, _)| shape.kind_order());
+                        tasks.push(MergeTask::BuildUnion(pairs.len()));
+                        tasks.extend(pairs.into_iter().rev().map(|(shape, other)| MergeTask::Merge(shape, other)));
+                    },
+                },
+                MergeTask::BuildArray => {
+                    let elements = results.pop().unwrap();
+                    results.push(Shape::Array(Box::new(elements)));
+                },
+                MergeTask::BuildObject(names) => {
+                    let shapes = results.split_off(results.len() - names.len());
+                    let properties = names.into_iter().zip(shapes).map(|((name, required), shape)| ShapeProperty { name, shape, required });
+                    results.push(Shape::Object(properties.collect()));
+                },
+                MergeTask::BuildUnion(len) => {
+                    let mut shapes = results.split_off(results.len() - len);
+                    results.push(if shapes.len() == 1 { shapes.remove(0) } else { Shape::Union(shapes) });
+                },
+            }
+        }
+        results.pop().unwrap()
+    }
+
+    /// Drops the shape using an explicit stack so that the shape of a deeply nested value doesn't
+    /// overflow the call stack, which dropping it normally does because that recurses once per
+    /// level of nesting.
+    pub fn drop_nested(self) {
+        let mut shapes = vec![self];
+        while let Some(shape) = shapes.pop() {
+            match shape {
+                Shape::Array(elements) => shapes.push(*elements),
+                Shape::Object(properties) => shapes.extend(properties.into_iter().map(|prop| prop.shape)),
+                Shape::Union(members) => shapes.extend(members),
+                _ => {},
+            }
+        }
+    }
+
+    fn into_union_members(self) -> Vec<Shape> {
+        match self {
+            Shape::Union(shapes) => shapes,
+            shape => vec![shape],
+        }
+    }
+
+    /// Gets the position of the shape's kind in a union.
+    fn kind_order(&self) -> usize {
+        match self {
+            Shape::Unknown | Shape::Union(_) => 0,
+            Shape::Null => 1,
+            Shape::Boolean => 2,
+            Shape::Number => 3,
+            Shape::String => 4,
+            Shape::Array(_) => 5,
+            Shape::Object(_) => 6,
+        }
+    }
+}
+
+/// A step of `Shape::merge`, where the build steps combine the shapes merged by the steps after them.
+enum MergeTask {
+    Merge(Shape, Shape),
+    BuildArray,
+    /// Builds an object from the names and whether each property is required.
+    BuildObject(Vec<(String, bool)>),
+    /// Builds a union from the number of members.
+    BuildUnion(usize),
+}
+
+/// Pairs the properties with the shapes of the other properties with the same name, where a
+/// property is only required when it's required in both.
+fn pair_shape_properties(properties: Vec<ShapeProperty>, other_properties: Vec<ShapeProperty>) -> Vec<(ShapeProperty, Shape)> {
+    let mut indexes = HashMap::with_capacity(properties.len());
+    let mut pairs = Vec::with_capacity(properties.len());
+    let mut is_in_other = Vec::with_capacity(properties.len());
+    for prop in properties {
+        indexes.entry(prop.name.clone()).or_insert(pairs.len());
+        pairs.push((prop, Shape::Unknown));
+        is_in_other.push(false);
+    }
+    for other_prop in other_properties {
+        match indexes.get(&other_prop.name) {
+            Some(&index) => {
+                let (prop, other_shape) = &mut pairs[index];
+                prop.required &= other_prop.required;
+                *other_shape = other_prop.shape;
+                is_in_other[index] = true;
+            },
+            None => {
+                pairs.push((ShapeProperty { required: false, ..other_prop }, Shape::Unknown));
+                is_in_other.push(true);
+            },
+        }
+    }
+    for ((prop, _), is_in_other) in pairs.iter_mut().zip(is_in_other) {
+        prop.required &= is_in_other;
+    }
+    pairs
+}
+
+impl fmt::Display for Shape {
+    /// Formats the shape like a TypeScript type.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pieces = vec![ShapePiece::Shape(self)];
+        while let Some(piece) = pieces.pop() {
+            let shape = match piece {
+                ShapePiece::Text(text) => {
+                    write!(f, "{}", text)?;
+                    continue;
+                },
+                ShapePiece::Name(name) => {
+                    let mut chars = name.chars();
+                    let is_identifier = chars.next().map(|c| c.is_ascii_alphabetic() || c == '_' || c == '$').unwrap_or(false)
+                        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+                    if is_identifier {
+                        write!(f, "{}", name)?;
+                    } else {
+                        write!(f, "{}", escape_string(name, &EscapeOptions::default()))?;
+                    }
+                    continue;
+                },
+                ShapePiece::Shape(shape) => shape,
+            };
+
+            // the pieces are pushed in reverse so they're written in order
+            match shape {
+                Shape::Unknown => write!(f, "unknown")?,
+                Shape::Null => write!(f, "null")?,
+                Shape::Boolean => write!(f, "boolean")?,
+                Shape::Number => write!(f, "number")?,
+                Shape::String => write!(f, "string")?,
+                Shape::Array(elements) => match elements.as_ref() {
+                    Shape::Union(_) => pieces.extend(vec![ShapePiece::Text(")[]"), ShapePiece::Shape(elements), ShapePiece::Text("(")]),
+                    elements => pieces.extend(vec![ShapePiece::Text("[]"), ShapePiece::Shape(elements)]),
+                },
+                Shape::Object(properties) if properties.is_empty() => write!(f, "{{}}")?,
+                Shape::Object(properties) => {
+                    pieces.push(ShapePiece::Text(" }"));
+                    for (index, prop) in properties.iter().enumerate().rev() {
+                        pieces.push(ShapePiece::Shape(&prop.shape));
+                        pieces.push(ShapePiece::Text(if prop.required { ": " } else { "?: " }));
+                        pieces.push(ShapePiece::Name(&prop.name));
+                        pieces.push(ShapePiece::Text(if index > 0 { ", " } else { "{ " }));
+                    }
+                },
+                Shape::Union(shapes) => {
+                    for (index, shape) in shapes.iter().enumerate().rev() {
+                        pieces.push(ShapePiece::Shape(shape));
+                        if index > 0 {
+                            pieces.push(ShapePiece::Text(" | "));
+                        }
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Text that's left to write when formatting a shape.
+enum ShapePiece<'a> {
+    Shape(&'a Shape),
+    Text(&'static str),
+    /// The name of a property, which is quoted when it isn't an identifier.
+    Name(&'a str),
+}
+
+/// Infers the shape of a value, which summarizes the kinds of values at each path (ex. to generate
+/// the skeleton of a struct from a sample config).
+///
+/// The shapes of the elements of an array are merged with `Shape::merge`, so a property that's
+/// missing from some objects in an array is optional.
+///
+/// # Example
+///
+/// ```
+/// use jsonc_parser::parse_to_value;
+/// use jsonc_parser::tooling::infer_shape;
+///
+/// let value = parse_to_value(r#"[{ "a": 1, "b": "x" }, { "a": 2.5, "c": [true] }]"#).unwrap().unwrap();
+/// assert_eq!(infer_shape(&value).to_string(), "{ a: number, b?: string, c?: boolean[] }[]");
+/// ```
+pub fn infer_shape(value: &JsonValue) -> Shape {
+    // containers whose children are being inferred
+    let mut frames: Vec<InferFrame> = Vec::new();
+    let mut next_value = Some(value);
+    loop {
+        let mut shape = match next_value.take() {
+            Some(JsonValue::Null) => Some(Shape::Null),
+            Some(JsonValue::Boolean(_)) => Some(Shape::Boolean),
+            Some(JsonValue::Number(_)) => Some(Shape::Number),
+            Some(JsonValue::String(_)) | Some(JsonValue::Wtf8String(_)) => Some(Shape::String),
+            Some(JsonValue::Array(elements)) => {
+                frames.push(InferFrame::Array(elements.iter(), Shape::Unknown));
+                None
+            },
+            Some(JsonValue::Object(obj)) => {
+                frames.push(InferFrame::Object(Box::new(obj.iter()), Vec::with_capacity(obj.len())));
+                None
+            },
+            None => None,
+        };
+
+        // add the shape to its container and move to the container's next child
+        while next_value.is_none() {
+            let frame = match frames.last_mut() {
+                Some(frame) => frame,
+                None => return shape.unwrap(),
+            };
+            match frame {
+                InferFrame::Array(elements, merged) => {
+                    if let Some(shape) = shape.take() {
+                        *merged = std::mem::replace(merged, Shape::Unknown).merge(shape);
+                    }
+                    next_value = elements.next();
+                },
+                InferFrame::Object(entries, properties) => {
+                    if let Some(shape) = shape.take() {
+                        properties.last_mut().unwrap().shape = shape;
+                    }
+                    next_value = entries.next().map(|(name, value)| {
+                        properties.push(ShapeProperty { name: name.clone(), shape: Shape::Unknown, required: true });
+                        value
+                    });
+                },
+            }
+            if next_value.is_none() {
+                shape = Some(match frames.pop().unwrap() {
+                    InferFrame::Array(_, merged) => Shape::Array(Box::new(merged)),
+                    InferFrame::Object(_, properties) => Shape::Object(properties),
+                });
+            }
+        }
+    }
+}
+
+/// A container whose children are being inferred by `infer_shape`, along with the shape so far.
+enum InferFrame<'a> {
+    Array(std::slice::Iter<'a, JsonValue>, Shape),
+    Object(Box<dyn Iterator<Item = (&'a String, &'a JsonValue)> + 'a>, Vec<ShapeProperty>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze, classify_position, expected_at, find_in_strings, has_top_level_key, visit_tokens, infer_shape, DocumentInfo, ExpectedKind, IndentStyle, LineEnding, PositionClass, Shape, ShapeProperty, TokenVisitor};
+    use super::super::{parse_text, parse_to_value};
+    use super::super::tokens::Token;
+
+    #[test]
+    fn it_finds_text_in_strings() {
+        let text = "{\n  \"localhost\": [\"http://localhost:80\", \"local\\u0068ost\", \"a\\u00e9localhost\\nlocalhost\"],\n  \"other\": \"lo\\/calhost\"\n}";
+        let value = parse_text(text).unwrap().value.unwrap();
+        let matches = find_in_strings(&value, "localhost");
+        let summary = matches.iter().map(|found| {
+            let source = text.chars().skip(found.source_range.start).take(found.source_range.end - found.source_range.start).collect::<String>();
+            (found.path.to_string(), found.is_property_name, source)
+        }).collect::<Vec<_>>();
+        assert_eq!(summary, vec![
+            (String::from("/localhost"), true, String::from("localhost")),
+            (String::from("/localhost/0"), false, String::from("localhost")),
+            (String::from("/localhost/1"), false, String::from("local\\u0068ost")),
+            (String::from("/localhost/2"), false, String::from("localhost")),
+            (String::from("/localhost/2"), false, String::from("localhost")),
+        ]);
+        assert_eq!((matches[1].node_range.start, matches[1].node_range.end), (18, 39));
+        assert_eq!((matches[1].source_range.start, matches[1].source_range.end, matches[1].source_range.start_line), (26, 35, 1));
+        assert_eq!(find_in_strings(&value, "h\u{e9}l").len(), 0);
+        assert_eq!(find_in_strings(&value, "a\u{e9}l").len(), 1);
+        assert_eq!(find_in_strings(&value, "o/c").len(), 1);
+        assert!(find_in_strings(&value, "").is_empty());
+    }
+
+    #[test]
+    fn it_checks_for_top_level_keys() {
+        let text = "// config\n{ \"a\": { \"b\": 1 }, \"c\": [\"b\"], /* d */ \"\\u0064\": null }";
+        assert!(has_top_level_key(text, "a").unwrap());
+        assert!(has_top_level_key(text, "d").unwrap());
+        assert!(!has_top_level_key(text, "b").unwrap());
+        assert!(!has_top_level_key("{}", "a").unwrap());
+
+        // returns before scanning the rest of the text
+        let large_text = format!("{{ \"a\": 1, \"b\": [{}", "tru, ".repeat(100_000));
+        assert!(has_top_level_key(&large_text, "a").unwrap());
+        assert_eq!(has_top_level_key(&large_text, "c").unwrap_err().message, "Unexpected token.");
+
+        assert_eq!(has_top_level_key("[\"a\"]", "a").unwrap_err().to_string(), "1:1: Expected an object.");
+        assert!(has_top_level_key("", "a").is_err());
+        assert!(has_top_level_key("{ \"a\": 1", "b").is_err());
+
+        // agrees with the parser
+        assert!(has_top_level_key("{ \"a\": 1 \"b\": 2 }", "b").unwrap());
+        assert_eq!(has_top_level_key("{ \"a\": [1 } , \"b\": 1", "b").unwrap_err().message, "Unexpected close brace.");
+        assert!(has_top_level_key("{ \"a\" 1 }", "b").is_err());
+    }
+
+    #[test]
+    fn it_expects_a_value_after_a_colon() {
+        assert_expected(r#"{ "a": |"#, &[ExpectedKind::Value]);
+        assert_expected(r#"{ "a": [1, { "b": | } ]"#, &[ExpectedKind::Value]);
+    }
+
+    #[test]
+    fn it_expects_a_comma_or_close_after_a_value() {
+        assert_expected(r#"{ "a": 1 |"#, &[ExpectedKind::Comma, ExpectedKind::CloseBrace]);
+        assert_expected(r#"[1, "a" |"#, &[ExpectedKind::Comma, ExpectedKind::CloseBracket]);
+        assert_expected(r#"{ "a": { "b": [] } | }"#, &[ExpectedKind::Comma, ExpectedKind::CloseBrace]);
+    }
+
+    #[test]
+    fn it_expects_property_names_and_colons() {
+        assert_expected(r#"{ |"#, &[ExpectedKind::PropertyName, ExpectedKind::CloseBrace]);
+        assert_expected(r#"{ "a": 1, | }"#, &[ExpectedKind::PropertyName, ExpectedKind::CloseBrace]);
+        assert_expected(r#"{ "a" | }"#, &[ExpectedKind::Colon]);
+    }
+
+    #[test]
+    fn it_expects_elements_in_arrays() {
+        assert_expected(r#"[|"#, &[ExpectedKind::Value, ExpectedKind::CloseBracket]);
+        assert_expected(r#"[1, // comment
+        |"#, &[ExpectedKind::Value, ExpectedKind::CloseBracket]);
+    }
+
+    #[test]
+    fn it_expects_values_at_the_root() {
+        assert_expected(r#"|"#, &[ExpectedKind::Value]);
+        assert_expected(r#"{ "a": 1 } |"#, &[]);
+    }
+
+    #[test]
+    fn it_ignores_tokens_after_the_position_and_invalid_text() {
+        assert_expected(r#"{ "a": tr|ue }"#, &[ExpectedKind::Value]);
+        assert_expected(r#"{ "a": @ |"#, &[ExpectedKind::Value]);
+        assert_expected(r#"{ "a": 1 @ |"#, &[ExpectedKind::Comma, ExpectedKind::CloseBrace]);
+    }
+
+    fn assert_class(text_with_cursor: &str, expected: PositionClass) {
+        let pos = text_with_cursor.find('|').unwrap();
+        let text = text_with_cursor.replace('|', "");
+        assert_eq!(classify_position(&text, pos), expected, "Text: {}", text_with_cursor);
+    }
+
+    #[test]
+    fn it_classifies_positions_in_strings() {
+        assert_class(r#"{ "|a": 1 }"#, PositionClass::InString);
+        assert_class(r#"{ "a|": 1 }"#, PositionClass::InString);
+        assert_class(r#"{ "a": "b\"|c" }"#, PositionClass::InString);
+        assert_class(r#"{ "a": "b\q|c" }"#, PositionClass::InString);
+        assert_class(r#"{ "a": "b|"#, PositionClass::InString);
+        assert_class(r#"{ "a": "b\q|"#, PositionClass::InString);
+        assert_class(r#"{ |"a": 1 }"#, PositionClass::Code);
+        assert_class(r#"{ "a"|: 1 }"#, PositionClass::Code);
+        assert_class(r#"{ "a\q"|: 1 }"#, PositionClass::Code);
+    }
+
+    #[test]
+    fn it_classifies_positions_in_comments() {
+        assert_class("{ // a|\n}", PositionClass::InLineComment);
+        assert_class("{ //| a\n}", PositionClass::InLineComment);
+        assert_class("{ // a\n|}", PositionClass::Code);
+        assert_class("{ /|/ a\n}", PositionClass::Code);
+        assert_class("[1] // a|", PositionClass::InLineComment);
+        assert_class("{ /* a| */ }", PositionClass::InBlockComment);
+        assert_class("{ /*|*/ }", PositionClass::InBlockComment);
+        assert_class("{ /* a\n b| */ }", PositionClass::InBlockComment);
+        assert_class("{ /* a *|/ }", PositionClass::Code);
+        assert_class("{ /* a */| }", PositionClass::Code);
+        assert_class("{ /* a|", PositionClass::InBlockComment);
+        assert_class("\"// a\" /|* b */", PositionClass::Code);
+    }
+
+    #[test]
+    fn it_classifies_positions_in_code() {
+        assert_class("|{}", PositionClass::Code);
+        assert_class("{ \"a\": 1|2 }", PositionClass::Code);
+        assert_class("{ \"a\": tr|", PositionClass::Code);
+        assert_class("[1, |]", PositionClass::Code);
+        assert_class("[1, 2]  |", PositionClass::Code);
+        assert_class("|", PositionClass::Code);
+    }
+
+    fn assert_expected(text_with_cursor: &str, expected: &[ExpectedKind]) {
+        let pos = text_with_cursor.find('|').unwrap();
+        let text = text_with_cursor.replace('|', "");
+        let set = expected_at(&text, pos);
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), expected, "Text: {}", text_with_cursor);
+    }
+
+    #[test]
+    fn it_analyzes_documents() {
+        let info = analyze("\u{feff}{\r\n\t\"a\": [\r\n\t\t1\r\n\t],\r\n  \"b\": 2\n}\r\n");
+        assert_eq!(info, DocumentInfo {
+            has_bom: true,
+            line_ending: Some(LineEnding::CrLf),
+            indent_style: Some(IndentStyle::Tabs),
+        });
+
+        let info = analyze("[\r    1,\r\n\r    [\r      2\r    ]\r]");
+        assert_eq!(info, DocumentInfo {
+            has_bom: false,
+            line_ending: Some(LineEnding::Cr),
+            indent_style: Some(IndentStyle::Spaces(4)),
+        });
+    }
+
+    #[test]
+    fn it_analyzes_single_line_documents() {
+        assert_eq!(analyze(r#"{ "a": 1 }"#), DocumentInfo {
+            has_bom: false,
+            line_ending: None,
+            indent_style: None,
+        });
+        assert_eq!(analyze("").line_ending, None);
+        // blank lines aren't indented lines
+        assert_eq!(analyze("[\n  \n1]").indent_style, None);
+    }
+
+    /// Records the events as text (ex. `{0` for an open brace at depth zero).
+    #[derive(Default)]
+    struct RecordingVisitor(Vec<String>);
+
+    impl TokenVisitor for RecordingVisitor {
+        fn on_open(&mut self, token: &Token, depth: usize) {
+            self.0.push(format!("{}{}", if *token == Token::OpenBrace { "{" } else { "[" }, depth));
+        }
+
+        fn on_close(&mut self, token: &Token, depth: usize) {
+            self.0.push(format!("{}{}", if *token == Token::CloseBrace { "}" } else { "]" }, depth));
+        }
+
+        fn on_scalar(&mut self, _token: &Token, depth: usize) {
+            self.0.push(format!("s{}", depth));
+        }
+    }
+
+    #[test]
+    fn it_visits_tokens_with_their_depth() {
+        let mut visitor = RecordingVisitor::default();
+        visit_tokens("{\n  // comment\n  \"a\": [1, { \"b\": null }, []],\n  \"c\": /* c */ true\n}", &mut visitor).unwrap();
+        assert_eq!(visitor.0.join(" "), "{0 s1 [1 s2 {2 s3 s3 }2 [2 ]2 ]1 s1 s1 }0");
+    }
+
+    #[test]
+    fn it_errors_visiting_mismatched_brackets() {
+        let err = visit_tokens("{\n  \"a\": [1}\n}", &mut RecordingVisitor::default()).err().unwrap();
+        assert_eq!(err.to_string(), "2:10: Unexpected close brace.");
+        let err = visit_tokens("[1]]", &mut RecordingVisitor::default()).err().unwrap();
+        assert_eq!(err.to_string(), "1:4: Unexpected close bracket.");
+        let err = visit_tokens("[{", &mut RecordingVisitor::default()).err().unwrap();
+        assert_eq!(err.message, "Unexpected end of file; '{' opened on line 1 was never closed");
+    }
+
+    #[test]
+    fn it_merges_the_shapes_of_heterogeneous_objects() {
+        let text = r#"[
+            { "id": 1, "name": "a", "tags": ["x"], "meta": { "b": true } },
+            { "id": 2, "tags": [], "extra": null, "meta": {} },
+            { "id": "3", "name": null, "tags": [1, "y"], "meta": { "b": false, "c": [[1], []] } }
+        ]"#;
+        let shape = infer_shape(&parse_to_value(text).unwrap().unwrap());
+        let prop = |name: &str, shape: Shape, required: bool| ShapeProperty { name: String::from(name), shape, required };
+        assert_eq!(shape, Shape::Array(Box::new(Shape::Object(vec![
+            prop("id", Shape::Union(vec![Shape::Number, Shape::String]), true),
+            prop("name", Shape::Union(vec![Shape::Null, Shape::String]), false),
+            prop("tags", Shape::Array(Box::new(Shape::Union(vec![Shape::Number, Shape::String]))), true),
+            prop("meta", Shape::Object(vec![
+                prop("b", Shape::Boolean, false),
+                prop("c", Shape::Array(Box::new(Shape::Array(Box::new(Shape::Number)))), false),
+            ]), true),
+            prop("extra", Shape::Null, false),
+        ]))));
+        assert_eq!(
+            shape.to_string(),
+            "{ id: number | string, name?: null | string, tags: (number | string)[], meta: { b?: boolean, c?: number[][] }, extra?: null }[]",
+        );
+    }
+
+    #[test]
+    fn it_infers_the_shapes_of_deeply_nested_values() {
+        let depth = 100_000;
+        let nested = |value: &str| format!("{}{}{}", "[{\"a\":".repeat(depth), value, "}]".repeat(depth));
+        let value = parse_to_value(&format!("[{}, {}]", nested("1"), nested("\"s\""))).unwrap().unwrap();
+        let shape = infer_shape(&value);
+        let expected = format!("{}number | string{}", "{ a: ".repeat(depth), " }[]".repeat(depth));
+        assert_eq!(shape.to_string(), format!("{}[]", expected));
+
+        let merged = shape.merge(infer_shape(&value));
+        assert_eq!(merged.to_string(), format!("{}[]", expected));
+        merged.drop_nested();
+    }
+
+    #[test]
+    fn it_moves_shapes_out_of_shapes() {
+        let shape = infer_shape(&parse_to_value("[[1]]").unwrap().unwrap());
+        let elements = match shape {
+            Shape::Array(elements) => *elements,
+            other => other,
+        };
+        assert_eq!(elements, Shape::Array(Box::new(Shape::Number)));
+    }
+
+    #[test]
+    fn it_merges_unions_of_containers() {
+        let value = parse_to_value(r#"[1, { "a": 1 }, [true], "s", { "a": "b", "c d": 1 }, ["t"], null]"#).unwrap().unwrap();
+        assert_eq!(infer_shape(&value).to_string(), r#"(null | number | string | (boolean | string)[] | { a: number | string, "c d"?: number })[]"#);
+        assert_eq!(infer_shape(&parse_to_value("[]").unwrap().unwrap()).to_string(), "unknown[]");
+        assert_eq!(infer_shape(&parse_to_value("{}").unwrap().unwrap()).to_string(), "{}");
+        assert_eq!(Shape::Union(vec![Shape::Null, Shape::Number]).merge(Shape::Union(vec![Shape::Boolean, Shape::Null])), Shape::Union(vec![Shape::Null, Shape::Boolean, Shape::Number]));
+    }
+}
@@ -0,0 +1,290 @@
+use super::path::JsonPath;
+use super::strings::{escape_string, EscapeOptions};
+use super::value::JsonValue;
+
+/// Limits for printing a value with `JsonValue::to_string_truncated`.
+///
+/// All limits are unset by default.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TruncateLimits {
+    /// Depth at which objects and arrays are replaced with `"{...}"` or `"[...]"`.
+    pub max_depth: Option<usize>,
+    /// Maximum number of characters in a string before it's truncated with `…`.
+    pub max_string_length: Option<usize>,
+    /// Maximum number of elements printed for an array.
+    pub max_array_elements: Option<usize>,
+    /// Length in bytes after which the remaining values are replaced with `"…"`.
+    pub max_output_length: Option<usize>,
+    /// Paths (ex. `/db/password`) of the values to replace with `"***"`.
+    pub redact: Vec<JsonPath>,
+}
+
+impl TruncateLimits {
+    /// Sets the depth at which objects and arrays are replaced with a marker.
+    pub fn with_max_depth(mut self, value: usize) -> Self {
+        self.max_depth = Some(value);
+        self
+    }
+
+    /// Sets the maximum number of characters in a string.
+    pub fn with_max_string_length(mut self, value: usize) -> Self {
+        self.max_string_length = Some(value);
+        self
+    }
+
+    /// Sets the maximum number of elements printed for an array.
+    pub fn with_max_array_elements(mut self, value: usize) -> Self {
+        self.max_array_elements = Some(value);
+        self
+    }
+
+    /// Sets the length in bytes after which the remaining values are replaced with a marker.
+    pub fn with_max_output_length(mut self, value: usize) -> Self {
+        self.max_output_length = Some(value);
+        self
+    }
+
+    /// Adds the path of a value to replace with `"***"`.
+    pub fn with_redacted(mut self, path: &JsonPath) -> Self {
+        self.redact.push(path.clone());
+        self
+    }
+}
+
+impl JsonValue {
+    /// Formats the value as compact JSON text that respects the provided limits.
+    ///
+    /// The output is always valid JSON. Values that exceed a limit are replaced with string
+    /// markers. The maximum output length is checked before each value in an object or array,
+    /// so the output may exceed it by the length of one scalar value and the closing characters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_to_value;
+    /// use jsonc_parser::truncate::TruncateLimits;
+    ///
+    /// let value = parse_to_value(r#"{ "user": "david", "password": "secret", "ids": [1, 2, 3] }"#).unwrap().unwrap();
+    /// let limits = TruncateLimits::default().with_max_array_elements(2).with_redacted(&"/password".parse().unwrap());
+    /// assert_eq!(
+    ///     value.to_string_truncated(&limits),
+    ///     r#"{"user":"david","password":"***","ids":[1,2,"… 1 more"]}"#,
+    /// );
+    /// ```
+    pub fn to_string_truncated(&self, limits: &TruncateLimits) -> String {
+        let mut writer = TruncatedWriter {
+            limits,
+            text: String::new(),
+            path: JsonPath::new(),
+        };
+        writer.write_value(self);
+        writer.text
+    }
+}
+
+struct TruncatedWriter<'a> {
+    limits: &'a TruncateLimits,
+    text: String,
+    path: JsonPath,
+}
+
+/// A container whose children are being written, with the index of the next child.
+enum TruncateFrame<'v> {
+    Array(&'v [JsonValue], usize),
+    Object(&'v [(String, JsonValue)], usize),
+}
+
+impl<'a> TruncatedWriter<'a> {
+    /// Writes the value and the values nested in it up to the limits.
+    fn write_value(&mut self, value: &JsonValue) {
+        let mut stack = Vec::new();
+        let mut next = Some(value);
+
+        loop {
+            if let Some(value) = next.take() {
+                let is_max_depth = self.limits.max_depth.map(|max_depth| stack.len() >= max_depth).unwrap_or(false);
+                match value {
+                    _ if self.is_redacted() => self.text.push_str("\"***\""),
+                    JsonValue::Null => self.text.push_str("null"),
+                    JsonValue::Boolean(value) => self.text.push_str(if *value { "true" } else { "false" }),
+                    JsonValue::Number(value) => self.text.push_str(value),
+                    JsonValue::String(value) => self.write_string(value),
+                    JsonValue::Wtf8String(value) => self.write_string(&value.to_string_lossy()),
+                    JsonValue::Array(_) if is_max_depth => self.text.push_str("\"[...]\""),
+                    JsonValue::Object(_) if is_max_depth => self.text.push_str("\"{...}\""),
+                    JsonValue::Array(elements) => {
+                        self.text.push('[');
+                        stack.push(TruncateFrame::Array(elements, 0));
+                    },
+                    JsonValue::Object(obj) => {
+                        self.text.push('{');
+                        stack.push(TruncateFrame::Object(obj.entries(), 0));
+                    },
+                }
+            }
+
+            match stack.last_mut() {
+                None => return,
+                Some(TruncateFrame::Array(elements, index)) => {
+                    let i = *index;
+                    if i > 0 {
+                        self.path.pop();
+                        if i < elements.len() {
+                            self.text.push(',');
+                        }
+                    }
+                    if i == elements.len() {
+                        // the end of the array
+                    } else if Some(i) == self.limits.max_array_elements {
+                        self.write_marker(&format!("… {} more", elements.len() - i));
+                    } else if self.is_over_output_length() {
+                        self.write_marker("…");
+                    } else {
+                        self.path.push(i);
+                        *index += 1;
+                        next = Some(&elements[i]);
+                        continue;
+                    }
+                    self.text.push(']');
+                    stack.pop();
+                },
+                Some(TruncateFrame::Object(entries, index)) => {
+                    let i = *index;
+                    if i > 0 {
+                        self.path.pop();
+                        if i < entries.len() {
+                            self.text.push(',');
+                        }
+                    }
+                    if i == entries.len() {
+                        // the end of the object
+                    } else if self.is_over_output_length() {
+                        self.text.push_str("\"…\":\"…\"");
+                    } else {
+                        let (key, value) = &entries[i];
+                        self.write_marker(key);
+                        self.text.push(':');
+                        self.path.push(key.as_str());
+                        *index += 1;
+                        next = Some(value);
+                        continue;
+                    }
+                    self.text.push('}');
+                    stack.pop();
+                },
+            }
+        }
+    }
+
+    fn write_string(&mut self, value: &str) {
+        let mut max_length = self.limits.max_string_length;
+        if let Some(max_output_length) = self.limits.max_output_length {
+            let remaining = max_output_length.saturating_sub(self.text.len());
+            max_length = Some(max_length.map(|max_length| max_length.min(remaining)).unwrap_or(remaining));
+        }
+
+        match max_length {
+            Some(max_length) if value.chars().count() > max_length => {
+                let mut truncated = value.chars().take(max_length).collect::<String>();
+                truncated.push('…');
+                self.write_marker(&truncated);
+            },
+            _ => self.write_marker(value),
+        }
+    }
+
+    fn write_marker(&mut self, value: &str) {
+        self.text.push_str(&escape_string(value, &EscapeOptions::default()));
+    }
+
+    fn is_over_output_length(&self) -> bool {
+        match self.limits.max_output_length {
+            Some(max_output_length) => self.text.len() >= max_output_length,
+            None => false,
+        }
+    }
+
+    fn is_redacted(&self) -> bool {
+        self.limits.redact.iter().any(|path| path.is_same_pointer(&self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse_text, parse_to_value};
+
+    #[test]
+    fn it_limits_depth() {
+        assert_truncates(
+            r#"{ "a": { "b": [1] }, "c": [[]] }"#,
+            &TruncateLimits::default().with_max_depth(1),
+            r#"{"a":"{...}","c":"[...]"}"#,
+        );
+        assert_truncates("[[1]]", &TruncateLimits::default().with_max_depth(0), r#""[...]""#);
+    }
+
+    #[test]
+    fn it_limits_string_length() {
+        assert_truncates(
+            r#"["abcdef", "abc", "éééé"]"#,
+            &TruncateLimits::default().with_max_string_length(3),
+            r#"["abc…","abc","ééé…"]"#,
+        );
+    }
+
+    #[test]
+    fn it_limits_array_elements() {
+        assert_truncates(
+            "[[1, 2, 3], 4, 5, 6]",
+            &TruncateLimits::default().with_max_array_elements(2),
+            r#"[[1,2,"… 1 more"],4,"… 2 more"]"#,
+        );
+    }
+
+    #[test]
+    fn it_limits_output_length() {
+        let text = format!("[{}]", vec!["12345"; 100].join(","));
+        let value = parse_to_value(&text).unwrap().unwrap();
+        let output = value.to_string_truncated(&TruncateLimits::default().with_max_output_length(20));
+        assert_eq!(output, r#"[12345,12345,12345,12345,"…"]"#);
+        assert_valid(&output);
+
+        assert_truncates(
+            r#"{ "a": "abcdefghijklmnop", "b": 1 }"#,
+            &TruncateLimits::default().with_max_output_length(10),
+            r#"{"a":"abcde…","…":"…"}"#,
+        );
+    }
+
+    #[test]
+    fn it_redacts_values() {
+        assert_truncates(
+            r#"{ "db": { "password": "secret", "a/b": [1, 2] }, "password": "other" }"#,
+            &TruncateLimits::default().with_redacted(&"/db/password".parse().unwrap()).with_redacted(&"/db/a~1b/1".parse().unwrap()),
+            r#"{"db":{"password":"***","a/b":[1,"***"]},"password":"other"}"#,
+        );
+    }
+
+    #[test]
+    fn it_truncates_deeply_nested_values() {
+        let depth = 100_000;
+        let text = format!("{}[1, 2, 3]{}", "[{\"a\":".repeat(depth), "}]".repeat(depth));
+        let value = parse_to_value(&text).unwrap().unwrap();
+        let output = value.to_string_truncated(&TruncateLimits::default().with_max_array_elements(2));
+        assert_eq!(output, format!("{}[1,2,\"… 1 more\"]{}", "[{\"a\":".repeat(depth), "}]".repeat(depth)));
+        let output = value.to_string_truncated(&TruncateLimits::default().with_max_depth(3));
+        assert_eq!(output, r#"[{"a":["{...}"]}]"#);
+    }
+
+    fn assert_truncates(text: &str, limits: &TruncateLimits, expected: &str) {
+        let value = parse_to_value(text).unwrap().unwrap();
+        let output = value.to_string_truncated(limits);
+        assert_eq!(output, expected);
+        assert_valid(&output);
+    }
+
+    fn assert_valid(text: &str) {
+        assert!(parse_text(text).is_ok(), "Invalid output: {}", text);
+    }
+}
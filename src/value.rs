@@ -0,0 +1,1013 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "serde")]
+use super::access::Accessor;
+use super::ast::{ObjectProp, Value};
+#[cfg(feature = "serde")]
+use super::de::Deserializer;
+#[cfg(feature = "serde")]
+use super::errors::AccessError;
+use super::errors::ParseError;
+use super::numbers::to_json_number_text;
+use super::parser::{parse_to_value, DuplicateKeyPolicy, LoneSurrogates};
+use super::path::{self, JsonPath, PathSegment};
+use super::serializer::{write_json_value, SerializeOptions};
+use super::strings::{decode_lone_surrogate_string, decode_string, JsonString};
+
+/// A JSON value without any positional information or comments.
+#[derive(Debug, PartialEq, Clone)]
+pub enum JsonValue {
+    Null,
+    Boolean(bool),
+    /// The text of a number (ex. `-1.2e+2`).
+    ///
+    /// Numbers parsed with a `NumberDialect` are converted to the JSON grammar (ex. `0x1F` to `31`).
+    Number(String),
+    /// A string with its escapes decoded.
+    String(String),
+    /// A string with escapes of lone surrogates, which is only created when parsing with
+    /// `LoneSurrogates::PreserveAsWtf8`. Other strings are always a `JsonValue::String`.
+    Wtf8String(JsonString),
+    Array(JsonArray),
+    Object(JsonObject),
+}
+
+/// The elements of an array, which derefs to a `Vec` of them.
+///
+/// Like `JsonObject`, dropping an array drops the values nested in it without recursing. Use
+/// `into_vec` or `std::mem::take` to move the elements out of it.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct JsonArray {
+    elements: Vec<JsonValue>,
+}
+
+impl JsonArray {
+    /// Creates an empty array.
+    pub fn new() -> JsonArray {
+        JsonArray::default()
+    }
+
+    /// Moves the elements out of the array.
+    pub fn into_vec(mut self) -> Vec<JsonValue> {
+        std::mem::take(&mut self.elements)
+    }
+}
+
+impl Deref for JsonArray {
+    type Target = Vec<JsonValue>;
+
+    fn deref(&self) -> &Vec<JsonValue> {
+        &self.elements
+    }
+}
+
+impl DerefMut for JsonArray {
+    fn deref_mut(&mut self) -> &mut Vec<JsonValue> {
+        &mut self.elements
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonArray {
+    fn from(elements: Vec<JsonValue>) -> JsonArray {
+        JsonArray { elements }
+    }
+}
+
+impl FromIterator<JsonValue> for JsonArray {
+    fn from_iter<I: IntoIterator<Item = JsonValue>>(iter: I) -> JsonArray {
+        JsonArray { elements: iter.into_iter().collect() }
+    }
+}
+
+impl IntoIterator for JsonArray {
+    type Item = JsonValue;
+    type IntoIter = std::vec::IntoIter<JsonValue>;
+
+    fn into_iter(self) -> std::vec::IntoIter<JsonValue> {
+        self.into_vec().into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a JsonArray {
+    type Item = &'a JsonValue;
+    type IntoIter = std::slice::Iter<'a, JsonValue>;
+
+    fn into_iter(self) -> std::slice::Iter<'a, JsonValue> {
+        self.elements.iter()
+    }
+}
+
+/// The properties of an object in the order they appeared in the text.
+///
+/// Dropping an object drops the values nested in it without recursing.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct JsonObject {
+    entries: Vec<(String, JsonValue)>,
+    /// Index of each property in the entries by name, so properties are found without scanning them.
+    indexes: HashMap<String, usize>,
+}
+
+impl JsonObject {
+    /// Creates an empty object.
+    pub fn new() -> JsonObject {
+        JsonObject::default()
+    }
+
+    /// Gets the value of the property with the provided name.
+    pub fn get(&self, name: &str) -> Option<&JsonValue> {
+        self.indexes.get(name).map(|index| &self.entries[*index].1)
+    }
+
+    /// Gets a mutable reference to the value of the property with the provided name.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut JsonValue> {
+        let entries = &mut self.entries;
+        self.indexes.get(name).map(move |index| &mut entries[*index].1)
+    }
+
+    /// Sets the value of a property, returning the previous value.
+    ///
+    /// An existing property keeps its position and a new property is added to the end.
+    pub fn insert(&mut self, name: String, value: JsonValue) -> Option<JsonValue> {
+        match self.get_mut(&name) {
+            Some(existing) => Some(std::mem::replace(existing, value)),
+            None => {
+                self.push(name, value);
+                None
+            },
+        }
+    }
+
+    /// Adds a property, merging the value into the value of an existing property with the same
+    /// name as described by `DuplicateKeyPolicy::Merge`.
+    pub(crate) fn insert_merged(&mut self, name: String, value: JsonValue) {
+        match self.get_mut(&name) {
+            Some(existing) => merge_value(existing, value),
+            None => self.push(name, value),
+        }
+    }
+
+    /// Adds a property that isn't in the object.
+    fn push(&mut self, name: String, value: JsonValue) {
+        self.indexes.insert(name.clone(), self.entries.len());
+        self.entries.push((name, value));
+    }
+
+    /// Gets the number of properties.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Gets if the object has no properties.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the properties in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &JsonValue)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// Deserializes the value of each property with serde and collects them with their names into a
+    /// collection chosen by the caller, which requires the `serde` feature.
+    ///
+    /// The properties are collected in order, so an order-preserving collection (ex. a `Vec<(String, T)>`)
+    /// keeps the order of the text. Errors have the path of the value that failed, which starts with
+    /// the name of its property.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use jsonc_parser::parse_to_value;
+    /// use jsonc_parser::value::JsonValue;
+    ///
+    /// let value = parse_to_value(r#"{ "b": 443, "a": 80 }"#).unwrap().unwrap();
+    /// if let JsonValue::Object(obj) = &value {
+    ///     let ports: BTreeMap<String, u16> = obj.deserialize_into_map().unwrap();
+    ///     assert_eq!(ports.keys().collect::<Vec<_>>(), ["a", "b"]);
+    /// }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn deserialize_into_map<T, M>(&self) -> Result<M, AccessError>
+    where
+        T: serde::de::DeserializeOwned,
+        M: FromIterator<(String, T)>,
+    {
+        self.entries.iter().map(|(name, value)| {
+            let deserializer = Deserializer::from_accessor(Accessor::new_property(value, name));
+            Ok((name.clone(), T::deserialize(deserializer)?))
+        }).collect()
+    }
+
+    pub(crate) fn entries(&self) -> &[(String, JsonValue)] {
+        &self.entries
+    }
+
+    /// Converts the properties of an AST object, keeping every property with a duplicate name.
+    ///
+    /// Nested objects are converted with `Value::into_value`, so only the properties of this
+    /// object keep their duplicates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::ast::Value;
+    /// use jsonc_parser::parse_text;
+    /// use jsonc_parser::value::JsonObject;
+    ///
+    /// let value = parse_text(r#"{ "a": 1, "a": 2 }"#).unwrap().value.unwrap();
+    /// let obj = match value {
+    ///     Value::Object(obj) => JsonObject::from_properties_keeping_duplicates(obj.properties),
+    ///     _ => unreachable!(),
+    /// };
+    /// assert_eq!(obj.get_all("a").len(), 2);
+    /// ```
+    pub fn from_properties_keeping_duplicates(properties: Vec<ObjectProp>) -> JsonMultiObject {
+        let entries = properties.into_iter().map(|prop| {
+            let name = decode_string(prop.name.value.as_ref(), prop.name.quote).into_owned();
+            (name, prop.value.into_value())
+        }).collect();
+        JsonMultiObject { entries }
+    }
+}
+
+fn merge_value(target: &mut JsonValue, source: JsonValue) {
+    // the pairs of nested objects that are left to merge
+    let mut merges = vec![(target, source)];
+    while let Some(merge) = merges.pop() {
+        match merge {
+            (JsonValue::Object(target), JsonValue::Object(mut source)) => {
+                let mut existing = Vec::new();
+                for (name, value) in std::mem::take(&mut source.entries) {
+                    match target.indexes.get(&name) {
+                        Some(index) => existing.push((*index, value)),
+                        None => target.push(name, value),
+                    }
+                }
+
+                // the properties are found by index so each value of the target is only borrowed once
+                existing.sort_by_key(|(index, _)| *index);
+                let mut target_entries = target.entries.iter_mut().enumerate();
+                for (index, value) in existing {
+                    let (_, (_, target_value)) = target_entries.find(|(target_index, _)| *target_index == index).unwrap();
+                    merges.push((target_value, value));
+                }
+            },
+            (JsonValue::Array(target), JsonValue::Array(mut source)) => target.append(&mut source),
+            (target, source) => *target = source,
+        }
+    }
+}
+
+/// The properties of an object in the order they appeared in the text, including properties with duplicate names.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct JsonMultiObject {
+    entries: Vec<(String, JsonValue)>,
+}
+
+impl JsonMultiObject {
+    /// Gets the value of the last property with the provided name, which is the value a `JsonObject` keeps.
+    pub fn get(&self, name: &str) -> Option<&JsonValue> {
+        self.entries.iter().rev().find(|(key, _)| key == name).map(|(_, value)| value)
+    }
+
+    /// Gets the values of every property with the provided name in order.
+    pub fn get_all(&self, name: &str) -> Vec<&JsonValue> {
+        self.entries.iter().filter(|(key, _)| key == name).map(|(_, value)| value).collect()
+    }
+
+    /// Gets the number of properties, including duplicates.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Gets if the object has no properties.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the properties in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &JsonValue)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// Converts to an object that keeps the last value of each duplicate property.
+    pub fn into_object(self) -> JsonObject {
+        let mut obj = JsonObject::new();
+        for (key, value) in self.entries {
+            obj.insert(key, value);
+        }
+        obj
+    }
+}
+
+impl JsonValue {
+    /// Parses the value of a string as a JSON document (ex. the value of
+    /// `"config": "{\"nested\": true}"`).
+    ///
+    /// Errors when this isn't a string or the string doesn't contain a value. Use
+    /// `StringLit::parse_embedded` to report errors at their position in the outer text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_to_value;
+    ///
+    /// let value = parse_to_value(r#"{ "config": "{\"nested\": true}" }"#).unwrap().unwrap();
+    /// let config = value.get_path(&"config".parse().unwrap()).unwrap().parse_embedded().unwrap();
+    /// assert_eq!(config.to_string(), r#"{"nested":true}"#);
+    /// ```
+    pub fn parse_embedded(&self) -> Result<JsonValue, ParseError> {
+        let text = match self {
+            JsonValue::String(text) => Cow::Borrowed(text.as_str()),
+            JsonValue::Wtf8String(text) => Cow::Owned(text.to_string_lossy()),
+            _ => return Err(ParseError::new(0, "Expected a string.")),
+        };
+        parse_to_value(&text)?.ok_or_else(|| ParseError::new(0, "Expected a value."))
+    }
+
+    /// Gets the value at the path or `None` when there is no value at the path.
+    pub fn get_path(&self, path: &JsonPath) -> Option<&JsonValue> {
+        path.segments().iter().try_fold(self, |value, segment| value.get_child(segment))
+    }
+
+    /// Gets the values at the paths in a single traversal, in the same order as the paths.
+    ///
+    /// This is faster than `get_path` for each path when the paths share prefixes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_to_value;
+    /// use jsonc_parser::path::JsonPath;
+    ///
+    /// let value = parse_to_value(r#"{ "db": { "host": "a", "port": 5432 } }"#).unwrap().unwrap();
+    /// let paths = ["/db/port", "/db/user", "/db/host"].iter().map(|path| path.parse::<JsonPath>().unwrap()).collect::<Vec<_>>();
+    /// let values = value.get_many(&paths).into_iter().map(|value| value.map(|value| value.to_string())).collect::<Vec<_>>();
+    /// assert_eq!(values, vec![Some(String::from("5432")), None, Some(String::from("\"a\""))]);
+    /// ```
+    pub fn get_many<'a>(&'a self, paths: &[JsonPath]) -> Vec<Option<&'a JsonValue>> {
+        path::get_many(self, paths, JsonValue::get_child)
+    }
+
+    fn get_child(&self, segment: &PathSegment) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(obj) => obj.get(&segment.as_key()),
+            JsonValue::Array(elements) => segment.as_index().and_then(|index| elements.get(index)),
+            _ => None,
+        }
+    }
+
+    /// Replaces every string value and property name with the result of the provided function.
+    ///
+    /// This is meant for normalizing strings to a unicode normalization form, so that strings
+    /// that only differ in composition compare equal. When parsing, prefer enabling the
+    /// `unicode-normalization` feature and setting `ParseOptions::string_normalization`. This is
+    /// for values that were built in other ways or need a custom normalization. When property
+    /// names become the same after normalizing, the last value is used. Strings with lone surrogates
+    /// are left as they are.
+    pub fn normalize_strings(&mut self, normalize: &impl Fn(&str) -> String) {
+        let mut values = vec![self];
+        while let Some(value) = values.pop() {
+            match value {
+                JsonValue::String(value) => *value = normalize(value),
+                JsonValue::Array(elements) => values.extend(elements.iter_mut()),
+                JsonValue::Object(obj) => {
+                    obj.indexes.clear();
+                    for (key, value) in std::mem::take(&mut obj.entries) {
+                        obj.insert(normalize(&key), value);
+                    }
+                    values.extend(obj.entries.iter_mut().map(|(_, value)| value));
+                },
+                JsonValue::Null | JsonValue::Boolean(_) | JsonValue::Number(_) | JsonValue::Wtf8String(_) => {},
+            }
+        }
+    }
+}
+
+/// Options for comparing values with `JsonValue::equals_with`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct EqOptions {
+    /// Whether a property with a null value equals a missing property. Defaults to `false`.
+    pub null_equals_missing: bool,
+    /// Whether arrays with the same elements in a different order are equal. Defaults to `false`.
+    pub ignore_array_order: bool,
+    /// The largest difference between numbers that are equal. Defaults to `0.0`.
+    pub number_tolerance: f64,
+}
+
+impl EqOptions {
+    /// Sets whether a property with a null value equals a missing property.
+    pub fn with_null_equals_missing(mut self, value: bool) -> Self {
+        self.null_equals_missing = value;
+        self
+    }
+
+    /// Sets whether arrays with the same elements in a different order are equal.
+    pub fn with_ignore_array_order(mut self, value: bool) -> Self {
+        self.ignore_array_order = value;
+        self
+    }
+
+    /// Sets the largest difference between numbers that are equal.
+    pub fn with_number_tolerance(mut self, value: f64) -> Self {
+        self.number_tolerance = value;
+        self
+    }
+}
+
+impl JsonValue {
+    /// Compares the value to another value using the provided options.
+    ///
+    /// Unlike `==`, numbers are compared by their value (ex. `1.0` equals `1`) and the order of
+    /// object properties is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jsonc_parser::parse_to_value;
+    /// use jsonc_parser::value::EqOptions;
+    ///
+    /// let defaults = parse_to_value(r#"{ "a": [1, 2] }"#).unwrap().unwrap();
+    /// let config = parse_to_value(r#"{ "a": [2, 1.0], "b": null }"#).unwrap().unwrap();
+    /// let options = EqOptions::default().with_null_equals_missing(true).with_ignore_array_order(true);
+    /// assert!(config.equals_with(&defaults, &options));
+    /// ```
+    pub fn equals_with(&self, other: &JsonValue, options: &EqOptions) -> bool {
+        // the comparisons of containers in progress, where each receives the result of the last comparison it started
+        let mut frames = vec![EqFrame::All(vec![(self, other)])];
+        let mut last_result = None;
+        while let Some(frame) = frames.last_mut() {
+            // gets the next pair to compare or the result of the container
+            let next_pair = match frame {
+                EqFrame::All(pairs) => match last_result.take() {
+                    Some(false) => Err(false),
+                    _ => pairs.pop().ok_or(true),
+                },
+                EqFrame::Unordered { a, b, is_equal } => {
+                    is_equal.extend(last_result.take());
+                    let (a_index, b_index) = (is_equal.len() / b.len(), is_equal.len() % b.len());
+                    match a.get(a_index) {
+                        Some(a_element) => Ok((a_element, &b[b_index])),
+                        None => Err(has_perfect_matching(a.len(), is_equal)),
+                    }
+                },
+            };
+            let (a, b) = match next_pair {
+                Ok(pair) => pair,
+                Err(result) => {
+                    frames.pop();
+                    last_result = Some(result);
+                    continue;
+                },
+            };
+
+            last_result = match (a, b) {
+                (JsonValue::Null, JsonValue::Null) => Some(true),
+                (JsonValue::Boolean(a), JsonValue::Boolean(b)) => Some(a == b),
+                (JsonValue::String(a), JsonValue::String(b)) => Some(a == b),
+                (JsonValue::Wtf8String(a), JsonValue::Wtf8String(b)) => Some(a == b),
+                (JsonValue::Number(a), JsonValue::Number(b)) => Some(a == b || match (a.parse::<f64>(), b.parse::<f64>()) {
+                    (Ok(a), Ok(b)) => a == b || (a - b).abs() <= options.number_tolerance,
+                    _ => false,
+                }),
+                (JsonValue::Array(a), JsonValue::Array(b)) if a.len() != b.len() => Some(false),
+                (JsonValue::Array(a), JsonValue::Array(b)) => {
+                    if options.ignore_array_order && !a.is_empty() {
+                        frames.push(EqFrame::Unordered { a, b, is_equal: Vec::with_capacity(a.len() * b.len()) });
+                    } else {
+                        frames.push(EqFrame::All(a.iter().zip(b.iter()).rev().collect()));
+                    }
+                    None
+                },
+                (JsonValue::Object(a), JsonValue::Object(b)) => {
+                    // a property that's null in one object can equal a missing property in the other
+                    let is_null_and_missing = |value: &JsonValue| options.null_equals_missing && *value == JsonValue::Null;
+                    let mut pairs = Vec::with_capacity(a.len());
+                    let has_missing = a.iter().any(|(name, value)| match b.get(name) {
+                        Some(other) => {
+                            pairs.push((value, other));
+                            false
+                        },
+                        None => !is_null_and_missing(value),
+                    }) || !b.iter().all(|(name, value)| a.get(name).is_some() || is_null_and_missing(value));
+                    if has_missing {
+                        Some(false)
+                    } else {
+                        pairs.reverse();
+                        frames.push(EqFrame::All(pairs));
+                        None
+                    }
+                },
+                _ => Some(false),
+            };
+        }
+        last_result.unwrap()
+    }
+}
+
+/// The comparison of a container in `JsonValue::equals_with`.
+enum EqFrame<'a> {
+    /// Every pair of values must be equal.
+    All(Vec<(&'a JsonValue, &'a JsonValue)>),
+    /// The elements of arrays whose order is ignored, which are compared with every element of the
+    /// other array before they're matched.
+    Unordered {
+        a: &'a [JsonValue],
+        b: &'a [JsonValue],
+        /// Whether each element of `a` equals each element of `b`, row by row.
+        is_equal: Vec<bool>,
+    },
+}
+
+/// Gets if every element of an array can be matched with a different equal element of the other
+/// array, where `is_equal[a_index * len + b_index]` is whether the elements are equal.
+///
+/// Matching the first equal element isn't enough because a number tolerance can make an element
+/// equal several others (ex. `[1.1, 1.0]` and `[1.0, 1.2]` with a tolerance of `0.15`), so this
+/// finds a perfect matching by searching for augmenting paths.
+fn has_perfect_matching(len: usize, is_equal: &[bool]) -> bool {
+    // the element of the first array that each element of the second array is matched with
+    let mut matched_with = vec![None; len];
+    for a_index in 0..len {
+        // a depth first search with an explicit stack of the elements along the path and the next
+        // element of the second array to try for each
+        let mut is_visited = vec![false; len];
+        let mut path = vec![(a_index, 0)];
+        while let Some(&(a_index, next_b_index)) = path.last() {
+            match (next_b_index..len).find(|&b_index| !is_visited[b_index] && is_equal[a_index * len + b_index]) {
+                Some(b_index) => {
+                    path.last_mut().unwrap().1 = b_index + 1;
+                    is_visited[b_index] = true;
+                    match matched_with[b_index] {
+                        Some(other_a_index) => path.push((other_a_index, 0)),
+                        None => {
+                            // each element along the path takes the element it last tried
+                            for &(a_index, next_b_index) in path.iter() {
+                                matched_with[next_b_index - 1] = Some(a_index);
+                            }
+                            break;
+                        },
+                    }
+                },
+                None => {
+                    path.pop();
+                },
+            }
+        }
+        if path.is_empty() {
+            return false;
+        }
+    }
+    true
+}
+
+impl JsonValue {
+    /// Moves the container's children to the provided vector.
+    fn take_children(&mut self, values: &mut Vec<JsonValue>) {
+        match self {
+            JsonValue::Array(arr) => arr.take_children(values),
+            JsonValue::Object(obj) => obj.take_children(values),
+            _ => {},
+        }
+    }
+}
+
+impl JsonObject {
+    /// Moves the values of the properties to the provided vector.
+    fn take_children(&mut self, values: &mut Vec<JsonValue>) {
+        self.indexes.clear();
+        values.extend(std::mem::take(&mut self.entries).into_iter().map(|(_, value)| value));
+    }
+}
+
+impl JsonArray {
+    /// Moves the elements to the provided vector.
+    fn take_children(&mut self, values: &mut Vec<JsonValue>) {
+        values.append(&mut self.elements);
+    }
+}
+
+impl Drop for JsonObject {
+    fn drop(&mut self) {
+        let mut values = Vec::new();
+        self.take_children(&mut values);
+        drop_values(values);
+    }
+}
+
+impl Drop for JsonArray {
+    fn drop(&mut self) {
+        let mut values = Vec::new();
+        self.take_children(&mut values);
+        drop_values(values);
+    }
+}
+
+/// Drops the values, moving the children of each container to the stack before it's dropped.
+fn drop_values(mut values: Vec<JsonValue>) {
+    while let Some(mut value) = values.pop() {
+        value.take_children(&mut values);
+    }
+}
+
+impl fmt::Display for JsonValue {
+    /// Formats the value as compact JSON text.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_json_value(f, self, &SerializeOptions::default(), 0)
+    }
+}
+
+/// A container whose children are being converted.
+enum LowerFrame {
+    Array(std::vec::IntoIter<Value>, Vec<JsonValue>),
+    /// The properties left, the converted properties, and the name of the property being converted.
+    Object(std::vec::IntoIter<ObjectProp>, JsonObject, String),
+}
+
+impl Value {
+    /// Converts the AST value to a value without positional information.
+    ///
+    /// String escapes are decoded and, for duplicate property names, the last value is used.
+    pub fn into_value(self) -> JsonValue {
+        self.into_value_with_options(None, None, DuplicateKeyPolicy::Replace, LoneSurrogates::ReplaceWithReplacementChar)
+    }
+
+    /// Converts the AST value to a value, normalizing each decoded string and property name with the
+    /// provided function, transforming each normalized property name with the provided function and
+    /// combining the values of duplicate properties with the provided policy.
+    ///
+    /// With `LoneSurrogates::PreserveAsWtf8`, strings with lone surrogates become a
+    /// `JsonValue::Wtf8String` and aren't normalized. Other policies replace the lone surrogates.
+    pub(crate) fn into_value_with_options(
+        self,
+        normalize: Option<fn(&str) -> String>,
+        key_transform: Option<fn(&str) -> String>,
+        duplicate_key_policy: DuplicateKeyPolicy,
+        lone_surrogates: LoneSurrogates,
+    ) -> JsonValue {
+        let mut stack = Vec::new();
+        let mut next = Some(self);
+
+        loop {
+            let mut completed = match next.take() {
+                Some(Value::StringLit(lit)) => {
+                    let wtf8 = match lone_surrogates {
+                        LoneSurrogates::PreserveAsWtf8 => decode_lone_surrogate_string(lit.value.as_ref(), lit.quote),
+                        _ => None,
+                    };
+                    Some(match wtf8 {
+                        Some(value) => JsonValue::Wtf8String(value),
+                        None => {
+                            let decoded = decode_string(lit.value.as_ref(), lit.quote);
+                            JsonValue::String(match normalize {
+                                Some(normalize) => normalize(&decoded),
+                                None => decoded.into_owned(),
+                            })
+                        },
+                    })
+                },
+                Some(Value::NumberLit(lit)) => {
+                    // the scanner errors for numbers without JSON text, so only a hand-built literal keeps its text
+                    let text = to_json_number_text(lit.value.as_ref()).map(Cow::into_owned);
+                    Some(JsonValue::Number(text.unwrap_or_else(|_| lit.value.as_ref().to_string())))
+                },
+                Some(Value::BooleanLit(lit)) => Some(JsonValue::Boolean(lit.value)),
+                Some(Value::NullKeyword(_)) => Some(JsonValue::Null),
+                Some(Value::Array(arr)) => {
+                    let values = Vec::with_capacity(arr.elements.len());
+                    stack.push(LowerFrame::Array(arr.elements.into_iter(), values));
+                    None
+                },
+                Some(Value::Object(obj)) => {
+                    stack.push(LowerFrame::Object(obj.properties.into_iter(), JsonObject::new(), String::new()));
+                    None
+                },
+                None => None,
+            };
+
+            // add the completed values to their containers until there is another child to convert
+            while next.is_none() {
+                match stack.last_mut() {
+                    None => return completed.expect("Expected a completed value."),
+                    Some(LowerFrame::Array(elements, values)) => {
+                        values.extend(completed.take());
+                        next = elements.next();
+                    },
+                    Some(LowerFrame::Object(properties, obj, name)) => {
+                        if let Some(value) = completed.take() {
+                            match duplicate_key_policy {
+                                DuplicateKeyPolicy::Replace => {
+                                    obj.insert(std::mem::take(name), value);
+                                },
+                                DuplicateKeyPolicy::Merge => obj.insert_merged(std::mem::take(name), value),
+                            }
+                        }
+                        next = properties.next().map(|prop| {
+                            let mut decoded_name = decode_string(prop.name.value.as_ref(), prop.name.quote);
+                            if let Some(normalize) = normalize {
+                                decoded_name = Cow::Owned(normalize(&decoded_name));
+                            }
+                            *name = match key_transform {
+                                Some(key_transform) => key_transform(&decoded_name),
+                                None => decoded_name.into_owned(),
+                            };
+                            prop.value
+                        });
+                    },
+                }
+
+                if next.is_none() {
+                    completed = match stack.pop() {
+                        Some(LowerFrame::Array(_, values)) => Some(JsonValue::Array(JsonArray::from(values))),
+                        Some(LowerFrame::Object(_, obj, _)) => Some(JsonValue::Object(obj)),
+                        None => unreachable!(),
+                    };
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{parse_text, parse_to_value};
+    use super::super::serializer::{serialize_json_value, serialize_value, serialized_len, SerializeStyle};
+
+    #[test]
+    fn it_lowers_and_prints_deeply_nested_values() {
+        let depth = 50_000;
+        let text = format!("{}null{}", "[{\"a\":".repeat(depth), "}]".repeat(depth));
+        let parse_result = parse_text(&text).unwrap();
+        assert_eq!(serialize_value(parse_result.value.as_ref().unwrap()), text);
+
+        let value = parse_result.value.unwrap().into_value();
+        let compact_text = value.to_string();
+        assert_eq!(compact_text, text);
+        // without indentation so the output doesn't grow quadratically
+        let pretty_options = SerializeOptions::default().with_style(SerializeStyle::Pretty).with_indent_width(0);
+        let pretty_text = serialize_json_value(&value, &pretty_options);
+        assert_eq!(serialized_len(&value, &pretty_options), pretty_text.len());
+
+        let reparsed_value = parse_to_value(&pretty_text).unwrap().unwrap();
+        assert_eq!(reparsed_value.to_string(), compact_text);
+
+        // the AST is dropped without recursing with `drop_nested`, while values are dropped normally
+        parse_text(&text).unwrap().drop_nested();
+        let text = format!("{}{}", "[".repeat(depth * 4), "]".repeat(depth * 4));
+        parse_text(&text).unwrap().drop_nested();
+        drop(parse_to_value(&text).unwrap().unwrap());
+        // the values parsed before an error and the values of passes that parse the text internally
+        assert!(parse_text(&format!("{}]", text)).is_err());
+        assert!(parse_text(&format!("[{}, x]", text)).is_err());
+        assert!(crate::edits::add_trailing_commas(&text).unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_converts_dialect_numbers_to_json() {
+        use super::super::events::EventParser;
+        use super::super::numbers::NumberDialect;
+        use super::super::parse_to_value_with_options;
+        use super::super::{ParseOptions, ScannerOptions};
+
+        let dialect = NumberDialect::default().with_hexadecimal(true).with_leading_decimal_point(true).with_comma_decimal_separator(true);
+        let options = ParseOptions::default().with_scanner_options(ScannerOptions::default().with_number_dialect(dialect));
+        let value = parse_to_value_with_options(r#"{"a": [3,14, 0x10, -.5]}"#, &options).unwrap().unwrap();
+        let text = value.to_string();
+        assert_eq!(text, r#"{"a":[3.14,16,-0.5]}"#);
+        assert_eq!(parse_to_value(&text).unwrap().unwrap(), value);
+        let text = r#"{"a": [3,14, 0x10, -.5]}"#;
+        assert_eq!(EventParser::new(text, &options).read_value().unwrap().unwrap(), value);
+    }
+
+    #[test]
+    fn it_moves_fields_out_of_values() {
+        let value = parse_to_value(r#"{ "a": [1, 2] }"#).unwrap().unwrap();
+        let mut obj = match value {
+            JsonValue::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        let elements = match std::mem::replace(obj.get_mut("a").unwrap(), JsonValue::Null) {
+            JsonValue::Array(arr) => arr.into_vec(),
+            _ => unreachable!(),
+        };
+        assert_eq!(elements.len(), 2);
+
+        let properties = match parse_text(r#"{ "a": [1] }"#).unwrap().value.unwrap() {
+            Value::Object(obj) => obj.properties,
+            _ => unreachable!(),
+        };
+        let elements = match properties.into_iter().next().map(|prop| prop.value) {
+            Some(Value::Array(arr)) => arr.elements,
+            _ => unreachable!(),
+        };
+        assert_eq!(elements.len(), 1);
+    }
+
+    #[test]
+    fn it_converts_ast_to_value() {
+        let value = parse_to_value(r#"{ "a\n": [1, "t\"", true, null], "b": {}, "a\n": 2 } // test"#).unwrap().unwrap();
+        let mut expected = JsonObject::new();
+        expected.insert(String::from("a\n"), JsonValue::Number(String::from("2")));
+        expected.insert(String::from("b"), JsonValue::Object(JsonObject::new()));
+        assert_eq!(value, JsonValue::Object(expected));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_deserializes_objects_into_maps() {
+        use std::collections::BTreeMap;
+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Server {
+            port: u16,
+        }
+
+        let value = parse_to_value(r#"{ "b": { "port": 443 }, "a/c": { "port": 80 } }"#).unwrap().unwrap();
+        let obj = match &value {
+            JsonValue::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        let servers: Vec<(String, Server)> = obj.deserialize_into_map().unwrap();
+        assert_eq!(servers, vec![(String::from("b"), Server { port: 443 }), (String::from("a/c"), Server { port: 80 })]);
+        let servers: BTreeMap<String, Server> = obj.deserialize_into_map().unwrap();
+        assert_eq!(servers.into_iter().collect::<Vec<_>>(), vec![(String::from("a/c"), Server { port: 80 }), (String::from("b"), Server { port: 443 })]);
+
+        let value = parse_to_value(r#"{ "b": { "port": 443 }, "a/c": { "port": "80" } }"#).unwrap().unwrap();
+        let err = match &value {
+            JsonValue::Object(obj) => obj.deserialize_into_map::<Server, BTreeMap<_, _>>().unwrap_err(),
+            _ => unreachable!(),
+        };
+        assert_eq!(err.to_string(), r#""/a~1c/port": invalid type: string "80", expected u16"#);
+        let err = match &value {
+            JsonValue::Object(obj) => obj.deserialize_into_map::<u16, Vec<_>>().unwrap_err(),
+            _ => unreachable!(),
+        };
+        assert_eq!(err.to_string(), r#""/b": invalid type: map, expected u16"#);
+    }
+
+    #[test]
+    fn it_normalizes_strings() {
+        // composes an "e" followed by a combining acute accent, which is enough for this test
+        let nfc = |text: &str| text.replace("e\u{301}", "\u{e9}");
+        let mut composed = parse_to_value(r#"{ "caf\u00e9": ["caf\u00e9"] }"#).unwrap().unwrap();
+        let mut decomposed = parse_to_value(r#"{ "cafe\u0301": ["cafe\u0301"] }"#).unwrap().unwrap();
+        assert_ne!(composed, decomposed);
+
+        composed.normalize_strings(&nfc);
+        decomposed.normalize_strings(&nfc);
+        assert_eq!(composed, decomposed);
+
+        let depth = 100_000;
+        let nested = |name: &str| format!("{}\"{}\"{}", format!("[{{\"{}\":", name).repeat(depth), name, "}]".repeat(depth));
+        let mut value = parse_to_value(&nested("e\u{301}")).unwrap().unwrap();
+        value.normalize_strings(&nfc);
+        assert_eq!(value.to_string(), nested("\u{e9}"));
+    }
+
+    #[test]
+    fn it_compares_null_properties_as_missing() {
+        let options = EqOptions::default().with_null_equals_missing(true);
+        assert!(value(r#"{"a": null}"#).equals_with(&value("{}"), &options));
+        assert!(value("{}").equals_with(&value(r#"{"a": null}"#), &options));
+        assert!(value(r#"{"a": {"b": 1, "c": null}}"#).equals_with(&value(r#"{"a": {"b": 1}, "d": null}"#), &options));
+        assert!(!value(r#"{"a": null}"#).equals_with(&value("{}"), &EqOptions::default()));
+        assert!(!value(r#"{"a": false}"#).equals_with(&value("{}"), &options));
+        // elements aren't missing
+        assert!(!value("[null]").equals_with(&value("[]"), &options));
+    }
+
+    #[test]
+    fn it_compares_arrays_ignoring_order() {
+        let options = EqOptions::default().with_ignore_array_order(true);
+        assert!(value("[1, [2, 3], 1, 4]").equals_with(&value("[[3, 2], 1, 4, 1]"), &options));
+        assert!(!value("[1, 1, 2]").equals_with(&value("[1, 2, 2]"), &options));
+        assert!(!value("[1, 2]").equals_with(&value("[2, 1]"), &EqOptions::default()));
+    }
+
+    #[test]
+    fn it_compares_numbers_with_a_tolerance() {
+        assert!(value("[1.0, 1e2]").equals_with(&value("[1, 100]"), &EqOptions::default()));
+        assert!(!value("0.3").equals_with(&value("0.30001"), &EqOptions::default()));
+        let options = EqOptions::default().with_number_tolerance(0.001);
+        assert!(value(r#"{"a": [0.3]}"#).equals_with(&value(r#"{"a": [0.30001]}"#), &options));
+        assert!(!value("0.3").equals_with(&value("0.302"), &options));
+        assert!(!value("1").equals_with(&value(r#""1""#), &options));
+    }
+
+    #[test]
+    fn it_matches_elements_within_a_tolerance_in_any_order() {
+        // the first element equals both elements of the other array, so it must take the second
+        let options = EqOptions::default().with_ignore_array_order(true).with_number_tolerance(0.15);
+        assert!(value("[1.1, 1.0]").equals_with(&value("[1.0, 1.2]"), &options));
+        assert!(value("[1.0, 1.1, 1.2]").equals_with(&value("[1.25, 1.05, 0.95]"), &options));
+        assert!(!value("[1.1, 1.0, 1.0]").equals_with(&value("[1.0, 1.2, 1.2]"), &options));
+        assert!(!value("[1.1, 1.0]").equals_with(&value("[1.0, 1.2]"), &options.with_ignore_array_order(false)));
+    }
+
+    #[test]
+    fn it_compares_deeply_nested_values() {
+        let depth = 100_000;
+        let nested = |value: &str| format!("{}[{}, 2]{}", "[{\"a\":".repeat(depth), value, "}]".repeat(depth));
+        let (a, b, c) = (value(&nested("1")), value(&nested("1.0")), value(&nested("3")));
+        for options in [EqOptions::default(), EqOptions::default().with_ignore_array_order(true)].iter() {
+            assert!(a.equals_with(&b, options));
+            assert!(!a.equals_with(&c, options));
+        }
+    }
+
+    #[test]
+    fn it_compares_with_combined_options() {
+        let options = EqOptions::default().with_null_equals_missing(true).with_ignore_array_order(true).with_number_tolerance(0.01);
+        let user_config = value(r#"{"plugins": [{"name": "b", "weight": 0.5, "extra": null}, {"name": "a"}], "timeout": null}"#);
+        let default_config = value(r#"{"plugins": [{"name": "a"}, {"name": "b", "weight": 0.499}]}"#);
+        assert!(user_config.equals_with(&default_config, &options));
+        assert!(!user_config.equals_with(&default_config, &options.clone().with_ignore_array_order(false)));
+        assert!(!user_config.equals_with(&default_config, &options.clone().with_null_equals_missing(false)));
+        assert!(!user_config.equals_with(&default_config, &options.with_number_tolerance(0.0)));
+    }
+
+    fn value(text: &str) -> JsonValue {
+        parse_to_value(text).unwrap().unwrap()
+    }
+
+    #[test]
+    fn it_gets_many_values_in_the_order_of_the_paths() {
+        let value = value(r#"{ "a": { "b": [1, { "c": 2 }], "d": 3 }, "e": [4], "0": 5, "f/g": 6 }"#);
+        let paths = [
+            "/a/b/1/c",
+            "/e/0",
+            "/a/d",
+            "",
+            "/a/b/1/c",
+            "/a/x/y",
+            "/a/b/01",
+            "/0",
+            "/f~1g",
+            "a.b[0]",
+            "a.b[\"1\"].c",
+            "/e/0/z",
+            "/a",
+        ].iter().map(|path| path.parse::<JsonPath>().unwrap()).collect::<Vec<_>>();
+
+        let values = value.get_many(&paths);
+        let naive_values = paths.iter().map(|path| value.get_path(path)).collect::<Vec<_>>();
+        assert_eq!(values, naive_values);
+        let texts = values.iter().map(|value| value.map(|value| value.to_string())).collect::<Vec<_>>();
+        assert_eq!(texts[..4].to_vec(), vec![Some(String::from("2")), Some(String::from("4")), Some(String::from("3")), Some(value.to_string())]);
+        assert_eq!(texts[4..8].to_vec(), vec![Some(String::from("2")), None, None, Some(String::from("5"))]);
+        assert_eq!(texts[8..12].to_vec(), vec![Some(String::from("6")), Some(String::from("1")), Some(String::from("2")), None]);
+        assert!(std::ptr::eq(values[12].unwrap(), value.get_path(&"/a".parse::<JsonPath>().unwrap()).unwrap()));
+
+        assert_eq!(value.get_many(&[]), Vec::<Option<&JsonValue>>::new());
+        assert_eq!(JsonValue::Null.get_many(&paths[..2]), vec![None, None]);
+    }
+
+    #[test]
+    fn it_displays_compact_json() {
+        let value = parse_to_value(r#"{ "aA": [1, "t\"\/", true, null], "b": {} }"#).unwrap().unwrap();
+        assert_eq!(value.to_string(), r#"{"aA":[1,"t\"/",true,null],"b":{}}"#);
+    }
+
+    #[test]
+    fn it_inserts_properties_by_name() {
+        let mut obj = JsonObject::new();
+        for index in 0..100_000 {
+            assert_eq!(obj.insert(format!("p{}", index), JsonValue::Number(index.to_string())), None);
+        }
+        assert_eq!(obj.insert(String::from("p5"), JsonValue::Null), Some(JsonValue::Number(String::from("5"))));
+        assert_eq!(obj.len(), 100_000);
+        assert_eq!(obj.get("p5"), Some(&JsonValue::Null));
+        assert_eq!(obj.get("p99999"), Some(&JsonValue::Number(String::from("99999"))));
+        assert_eq!(obj.get("p100000"), None);
+        assert_eq!(obj.iter().nth(5).map(|(name, _)| name.as_str()), Some("p5"));
+        *obj.get_mut("p0").unwrap() = JsonValue::Boolean(true);
+        assert_eq!(obj.iter().next(), Some((&String::from("p0"), &JsonValue::Boolean(true))));
+    }
+
+    #[test]
+    fn it_keeps_duplicate_properties() {
+        let text = r#"{ "env": "a", "b": 1, "env": "b", "env": { "c": 1, "c": 2 } }"#;
+        let parse_result = parse_text(text).unwrap();
+        let obj = match parse_result.value.unwrap() {
+            Value::Object(obj) => obj,
+            _ => unreachable!(),
+        };
+        let ranges = obj.get_all("env").iter().map(|prop| (prop.range.start, prop.range.end)).collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(2, 12), (22, 32), (34, 59)]);
+
+        let multi_obj = JsonObject::from_properties_keeping_duplicates(obj.properties);
+        assert_eq!(multi_obj.len(), 4);
+        let values = multi_obj.get_all("env").into_iter().map(|value| value.to_string()).collect::<Vec<_>>();
+        assert_eq!(values, vec![r#""a""#, r#""b""#, r#"{"c":2}"#]);
+        assert_eq!(multi_obj.get("env").unwrap().to_string(), r#"{"c":2}"#);
+
+        let value = parse_to_value(text).unwrap().unwrap();
+        assert_eq!(value, JsonValue::Object(multi_obj.into_object()));
+        assert_eq!(value.to_string(), r#"{"env":{"c":2},"b":1}"#);
+    }
+}
@@ -0,0 +1,89 @@
+//! Tests that count allocations, which need a counting global allocator and so live in their own
+//! test binary instead of the crate's unit tests.
+
+extern crate jsonc_parser;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use jsonc_parser::events::{Event, EventParser};
+use jsonc_parser::*;
+
+/// Allocator that counts the allocations made on each thread, so tests running in parallel
+/// don't affect each other's counts.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Gets the number of allocations made by the closure on the current thread.
+fn count_allocations<T>(action: impl FnOnce() -> T) -> (T, usize) {
+    let start = ALLOCATIONS.with(|count| count.get());
+    let result = action();
+    (result, ALLOCATIONS.with(|count| count.get()) - start)
+}
+
+#[test]
+fn it_extracts_a_nested_value_from_a_large_document() {
+    let mut text = String::from("{ \"items\": [");
+    for i in 0..2000 {
+        text.push_str(&format!("{{ \"id\": {}, \"name\": \"item {}\", \"tags\": [\"a\", \"b\"] }},", i, i));
+    }
+    text.push_str("], \"config\": { \"other\": [1, 2], \"target\": { \"value\": 5 } } }");
+
+    let (found, allocations) = count_allocations(|| {
+        let mut parser = EventParser::new(&text, &ParseOptions::default());
+        let mut path = Vec::new();
+        let mut found = None;
+        while let Some(event) = parser.next_event().unwrap() {
+            match event {
+                Event::Key(name, _) => {
+                    path.truncate(parser.depth() - 1);
+                    path.push(name);
+                    if path == ["config", "target"] {
+                        found = parser.read_value().unwrap();
+                    } else if path != ["config"] {
+                        parser.skip_value().unwrap();
+                    }
+                },
+                Event::ObjectEnd(_) => path.truncate(parser.depth()),
+                _ => {},
+            }
+        }
+        found
+    });
+    assert_eq!(found.unwrap().to_string(), r#"{"value":5}"#);
+    // the items aren't materialized, so the allocations don't depend on their number
+    assert!(allocations < 50, "Allocations: {}", allocations);
+    let (_, parse_allocations) = count_allocations(|| parse_to_value(&text).unwrap());
+    assert!(parse_allocations > 2000, "Allocations: {}", parse_allocations);
+}
+
+#[test]
+fn it_borrows_token_payloads_without_allocating() {
+    let text = "{ \"a\": [\"b\\\"c\", -1.5e+3, true, null], // d\n /* \u{1F600} */ \"e\": \"f\" }";
+    let (count, allocations) = count_allocations(|| {
+        let mut count = 0;
+        for token in TokenRefIterator::new(text) {
+            token.unwrap();
+            count += 1;
+        }
+        count
+    });
+    assert_eq!(count, 19);
+    assert_eq!(allocations, 0);
+}
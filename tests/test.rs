@@ -6,10 +6,11 @@ use std::path::{Path, PathBuf};
 use jsonc_parser::*;
 use jsonc_parser::ast::*;
 use jsonc_parser::common::*;
+use jsonc_parser::tokens::*;
 
 #[test]
 fn test_specs() {
-    for json_path in get_json_file_paths_in_dir(&Path::new("./tests/specs")) {
+    for json_path in get_json_file_paths_in_dir(Path::new("./tests/specs")) {
         let text_file_path = json_path.with_extension("txt");
         let json_file_text = fs::read_to_string(&json_path).expect("Expected to read file.").replace("\r\n", "\n");
         let result = parse_text(&json_file_text).expect("Expected no error.");
@@ -20,24 +21,313 @@ fn test_specs() {
     }
 }
 
+#[test]
+fn test_specs_from_tokens() {
+    for json_path in get_json_file_paths_in_dir(Path::new("./tests/specs")) {
+        let json_file_text = fs::read_to_string(&json_path).expect("Expected to read file.").replace("\r\n", "\n");
+        let mut scanner = Scanner::new_lossless(&json_file_text);
+        let mut tokens = Vec::new();
+        while let Some(token) = scanner.scan().expect("Expected no error.") {
+            tokens.push(TokenAndRange {
+                token,
+                range: Range {
+                    start: scanner.token_start(),
+                    end: scanner.token_end(),
+                    start_line: scanner.token_start_line(),
+                    end_line: scanner.token_end_line(),
+                },
+            });
+        }
+
+        let expected = parse_text(&json_file_text).expect("Expected no error.");
+        let result = parse_tokens_to_ast(&tokens, &json_file_text).expect("Expected no error.");
+        assert_eq!(parse_result_to_test_str(&result), parse_result_to_test_str(&expected));
+        assert_eq!(
+            result.tokens.iter().map(|t| (&t.token, &t.range)).collect::<Vec<_>>(),
+            expected.tokens.iter().map(|t| (&t.token, &t.range)).collect::<Vec<_>>(),
+        );
+    }
+}
+
+#[test]
+fn test_parse_file() {
+    let options = ParseOptions::default();
+    let result = parse_file("./tests/fixtures/bom-config.jsonc", &options).expect("Expected no error.");
+    let value = result.value.expect("Expected a value.");
+    assert_eq!(value.range().start, 12);
+    assert_eq!(serializer::serialize_value(&value), r#"{"name":"app","port":8080}"#);
+    assert_eq!(result.comments.values().next().unwrap()[0].raw_text(), "// settings");
+
+    let err = parse_file("./tests/fixtures/bom-config.jsonc", &options.clone().with_root_kind(RootKind::Array)).err().unwrap();
+    assert_eq!(err.to_string(), "./tests/fixtures/bom-config.jsonc:2:1: Expected an array.");
+    let err = parse_file(Path::new("./tests/fixtures/bom-config.jsonc"), &options.clone().with_source_name("config.jsonc").with_root_kind(RootKind::Array)).err().unwrap();
+    assert_eq!(err.to_string(), "config.jsonc:2:1: Expected an array.");
+
+    let err = parse_file("./tests/fixtures/missing.json", &options).err().unwrap();
+    assert_eq!(err.kind, errors::ErrorKind::Io(std::io::ErrorKind::NotFound));
+    assert_eq!(err.source_name.unwrap().as_ref(), "./tests/fixtures/missing.json");
+    let err = parse_file("./tests/fixtures/not-utf8.json", &options).err().unwrap();
+    assert_eq!(err.kind, errors::ErrorKind::Io(std::io::ErrorKind::InvalidData));
+}
+
+#[test]
+fn test_parse_file_in_each_encoding() {
+    let options = ParseOptions::default();
+    let utf8_result = parse_file("./tests/fixtures/bom-config.jsonc", &options).expect("Expected no error.");
+    assert_eq!(utf8_result.encoding, Some(TextEncoding::Utf8));
+    let utf8_value = utf8_result.value.expect("Expected a value.");
+
+    for (path, encoding) in [("./tests/fixtures/bom-config.utf16le.jsonc", TextEncoding::Utf16Le), ("./tests/fixtures/bom-config.utf16be.jsonc", TextEncoding::Utf16Be)].iter() {
+        let result = parse_file(path, &options).expect("Expected no error.");
+        assert_eq!(result.encoding, Some(*encoding));
+        // positions are relative to the decoded text
+        assert_eq!(result.value.expect("Expected a value."), utf8_value);
+        let err = parse_file(path, &options.clone().with_root_kind(RootKind::Array)).err().unwrap();
+        assert_eq!(err.to_string(), format!("{}:2:1: Expected an array.", path));
+    }
+}
+
+#[test]
+fn test_comments_containing_delimiters() {
+    // comments are trivia, so the delimiters in them must not change the structure
+    let text = r#"/* { [ */ {
+  "a": [ // ] } ,
+    1, /* ], "x": { */ 2 /* , : */
+  ], /* } */
+  // "b": [
+  "c": { /* * / ] */ "d" /* : */ : /*{*/ true /*}*/ } // }
+  /* ]]]] */
+} // {"#;
+    let expected = r#"{"a":[1,2],"c":{"d":true}}"#;
+    assert_eq!(parse_to_value(text).unwrap().unwrap().to_string(), expected);
+    assert!(validate_fast(text, &ParseOptions::default()).is_ok());
+    let result = diagnostics::parse_with_diagnostics(text, &diagnostics::DiagnosticOptions::default().with_trailing_whitespace(false));
+    assert!(result.errors.is_empty());
+    assert!(result.warnings.is_empty());
+
+    let (value, is_truncated) = parse_preview(text, 1).unwrap();
+    assert!(is_truncated);
+    assert_eq!(serializer::serialize_value(&value), r#"{"a":[1,2]}"#);
+    let extracted = extract_matching(text, |path| path == ["c", "d"]).unwrap();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(serializer::serialize_value(&extracted[0].1), "true");
+
+    // each comment is still collected once
+    let result = parse_text(text).unwrap();
+    let mut comment_starts = result.comments.values().flat_map(|comments| comments.iter()).map(|comment| match comment {
+        Comment::Line(line) => line.range.start,
+        Comment::Block(block) => block.range.start,
+    }).collect::<Vec<_>>();
+    comment_starts.sort_unstable();
+    comment_starts.dedup();
+    assert_eq!(comment_starts.len(), 13);
+}
+
+#[test]
+fn test_rename_all_keys() {
+    use jsonc_parser::edits::{apply_text_edits, rename_all_keys, KeyMatcher};
+
+    let text = fs::read_to_string("./tests/fixtures/rename-keys.jsonc").unwrap();
+    let (edits, warnings) = rename_all_keys(&text, &KeyMatcher::new("foo"), "bar").expect("Expected no error.");
+    assert_eq!(edits.len(), 3);
+    assert_eq!(apply_text_edits(&text, &edits).unwrap(), fs::read_to_string("./tests/fixtures/rename-keys.expected.jsonc").unwrap());
+    assert_eq!(warnings.len(), 1);
+    assert_eq!((warnings[0].range.start_line, warnings[0].message.as_str()), (12, "The object already has a property named \"bar\"."));
+
+    // only the objects whose path matches the pattern
+    let matcher = KeyMatcher::new("foo").with_parent_pattern(&"packages.*".parse().unwrap());
+    let (edits, warnings) = rename_all_keys(&text, &matcher, "bar").expect("Expected no error.");
+    assert_eq!(edits.iter().map(|edit| edit.start).collect::<Vec<_>>(), vec![98]);
+    assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn test_ranges() {
+    let fold_options = ParseOptions::default().with_fold_comment_lines(true);
+    for json_path in get_json_file_paths_in_dir(Path::new("./tests/specs")) {
+        let text = fs::read_to_string(&json_path).expect("Expected to read file.");
+        let result = parse_text(&text).expect("Expected no error.");
+        assert_eq!(verify_ranges(&result, &text), Ok(()), "{}", json_path.display());
+        let result = parse_text_with_options(&text, &fold_options).expect("Expected no error.");
+        assert_eq!(verify_ranges(&result, &text), Ok(()), "{}", json_path.display());
+    }
+
+    // the trees recovered from invalid documents keep the guarantees too
+    for entry in Path::new("./tests/corpus/invalid").read_dir().expect("read dir failed").flatten() {
+        let path = entry.path();
+        if path.extension().map(|ext| ext != "jsonc").unwrap_or(true) {
+            continue;
+        }
+        let text = fs::read_to_string(&path).expect("Expected to read file.");
+        let result = diagnostics::parse_with_diagnostics(&text, &diagnostics::DiagnosticOptions::default());
+        let result = ParseResult {
+            comments: Default::default(),
+            value: result.value,
+            tokens: Vec::new(),
+            metrics: None,
+            encoding: None,
+        };
+        assert_eq!(verify_ranges(&result, &text), Ok(()), "{}", path.display());
+    }
+}
+
+#[test]
+fn test_update_file() {
+    use jsonc_parser::edits::update_file;
+    use jsonc_parser::value::JsonValue;
+
+    let path = std::env::temp_dir().join(format!("jsonc-parser-update-file-{}.jsonc", std::process::id()));
+    fs::copy("./tests/fixtures/tsconfig.jsonc", &path).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    }
+    update_file(&path, |doc| {
+        doc.set(&"compilerOptions.strict".parse()?, &JsonValue::Boolean(true))?;
+        doc.set(&"compilerOptions.noEmit".parse()?, &JsonValue::Boolean(true))?;
+        doc.remove(&"exclude[2]".parse()?)
+    }).expect("Expected no error.");
+    let expected_text = fs::read_to_string("./tests/fixtures/tsconfig.expected.jsonc").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), expected_text);
+
+    // the file is left as it is when a change conflicts with a previous change
+    let err = update_file(&path, |doc| {
+        doc.remove(&"exclude[1]".parse()?)?;
+        doc.set(&"exclude[1]".parse()?, &JsonValue::Null)
+    }).err().unwrap();
+    assert_eq!(err.to_string(), format!("{}:10:5: The change overlaps a previous change.", path.to_string_lossy()));
+    assert_eq!(fs::read_to_string(&path).unwrap(), expected_text);
+
+    // the permissions are kept and the temporary files are gone
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+    }
+    let temp_prefix = format!(".{}.", path.file_name().unwrap().to_string_lossy());
+    let mut temp_dir_entries = std::env::temp_dir().read_dir().unwrap().flatten();
+    assert!(temp_dir_entries.all(|entry| !entry.file_name().to_string_lossy().starts_with(&temp_prefix)));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_invalid_corpus() {
+    // each document is parsed tolerantly and the errors and recovered tree are compared with
+    // the snapshot in the .txt file next to it, so changes to recovery show up in review
+    let mut paths = Path::new("./tests/corpus/invalid").read_dir().expect("read dir failed").flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|ext| ext == "jsonc").unwrap_or(false))
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(paths.len() >= 30);
+
+    for path in paths {
+        let text = fs::read_to_string(&path).expect("Expected to read file.").replace("\r\n", "\n");
+        let result = diagnostics::parse_with_diagnostics(&text, &diagnostics::DiagnosticOptions::default());
+        assert!(!result.errors.is_empty(), "Expected an error in {}", path.display());
+
+        let mut result_text = String::from("errors:\n");
+        for error in result.errors.iter() {
+            result_text.push_str(&format!("  {}..{} {:?}: {}\n", error.range.start, error.range.end, error.kind.as_ref().unwrap(), error.message));
+        }
+        result_text.push_str("value:\n");
+        match &result.value {
+            Some(value) => write_corpus_value(&mut result_text, None, value, 1),
+            None => result_text.push_str("  none\n"),
+        }
+
+        let expected_path = path.with_extension("txt");
+        // run with UPDATE_SNAPSHOTS=1 to regenerate the snapshots after an intended change
+        if std::env::var("UPDATE_SNAPSHOTS").map(|value| value == "1").unwrap_or(false) {
+            fs::write(&expected_path, &result_text).unwrap();
+        }
+        let expected_text = fs::read_to_string(&expected_path).expect("Expected to read expected file.").replace("\r\n", "\n");
+        assert_eq!(result_text, expected_text, "{}", path.display());
+    }
+}
+
+#[test]
+fn test_validate_fast_matches_parser() {
+    let mut paths = get_json_file_paths_in_dir(Path::new("./tests/specs"));
+    paths.extend(Path::new("./tests/corpus/invalid").read_dir().expect("read dir failed").flatten().map(|entry| entry.path()).filter(|path| path.extension().map(|ext| ext == "jsonc").unwrap_or(false)));
+    let mut texts = paths.iter().map(|path| fs::read_to_string(path).expect("Expected to read file.")).collect::<Vec<_>>();
+    texts.push(String::from("// a\n{ /* b */ \"a\": [1, 2,], \"b\": { \"c\": null, }, \"d\": true, } // c\n"));
+    texts.push(String::from("[1 2] [3]"));
+    texts.push(String::from("\"a\" \"b\""));
+    texts.push(String::from("{ \"a\": 1 \"b\": 2 }"));
+    texts.push(String::from("[01, 0x1F, .5, 'a'] /*"));
+
+    let all_options = [
+        ParseOptions::default(),
+        ParseOptions::default().with_require_commas(true).with_source_name("file.json"),
+        ParseOptions::default().with_require_value(true),
+        ParseOptions::default().with_root_kind(RootKind::Object),
+        ParseOptions::default().with_root_kind(RootKind::Array),
+        ParseOptions::default().with_scanner_options(ScannerOptions::default().with_comments(false).with_strict_numbers(true)),
+        ParseOptions::default().with_scanner_options(ScannerOptions::default().with_single_quotes(true).with_number_dialect(numbers::NumberDialect::default().with_hexadecimal(true))),
+    ];
+
+    for text in texts.iter() {
+        // every prefix of the text, to cover the errors for incomplete text
+        let char_count = text.chars().count();
+        for len in 0..=char_count {
+            let text = text.chars().take(len).collect::<String>();
+            for options in all_options.iter() {
+                let expected = parse_text_with_options(&text, options).map(|_| ());
+                assert_eq!(validate_fast(&text, options), expected, "{:?} {:?}", text, options);
+            }
+        }
+    }
+}
+
+/// Writes a line per value in the form `<name>: <kind> <start>..<end>`.
+fn write_corpus_value(text: &mut String, name: Option<&str>, value: &Value, depth: usize) {
+    text.push_str(&"  ".repeat(depth));
+    if let Some(name) = name {
+        text.push_str(&format!("\"{}\": ", name));
+    }
+    let range = value.range();
+    let description = match value {
+        Value::StringLit(lit) => format!("string \"{}\"", lit.value.as_ref()),
+        Value::NumberLit(lit) => format!("number {}", lit.value.as_ref()),
+        Value::BooleanLit(lit) => format!("boolean {}", lit.value),
+        Value::NullKeyword(_) => String::from("null"),
+        Value::Object(_) => String::from("object"),
+        Value::Array(_) => String::from("array"),
+    };
+    text.push_str(&format!("{} {}..{}\n", description, range.start, range.end));
+    match value {
+        Value::Object(obj) => {
+            for prop in obj.properties.iter() {
+                write_corpus_value(text, Some(prop.name.value.as_ref()), &prop.value, depth + 1);
+            }
+        },
+        Value::Array(arr) => {
+            for element in arr.elements.iter() {
+                write_corpus_value(text, None, element, depth + 1);
+            }
+        },
+        _ => {},
+    }
+}
+
 fn get_json_file_paths_in_dir(path: &Path) -> Vec<PathBuf> {
     return read_dir_recursively(path);
 
     fn read_dir_recursively(dir_path: &Path) -> Vec<PathBuf> {
         let mut result = Vec::new();
 
-        for entry in dir_path.read_dir().expect("read dir failed") {
-            if let Ok(entry) = entry {
-                let entry_path = entry.path();
-                if entry_path.is_file() {
-                    if let Some(ext) = entry_path.extension() {
-                        if ext == "json" {
-                            result.push(entry_path);
-                        }
+        for entry in dir_path.read_dir().expect("read dir failed").flatten() {
+            let entry_path = entry.path();
+            if entry_path.is_file() {
+                if let Some(ext) = entry_path.extension() {
+                    if ext == "json" {
+                        result.push(entry_path);
                     }
-                } else {
-                    result.extend(read_dir_recursively(&entry_path));
                 }
+            } else {
+                result.extend(read_dir_recursively(&entry_path));
             }
         }
 
@@ -45,6 +335,134 @@ fn get_json_file_paths_in_dir(path: &Path) -> Vec<PathBuf> {
     }
 }
 
+mod prelude_workflow {
+    // only uses the prelude, like a downstream crate would
+    use jsonc_parser::prelude::*;
+
+    #[test]
+    fn test_read_modify_write_with_prelude() {
+        let text = "// settings\n{ \"port\": 80, \"hosts\": [\"a\"] }\n";
+        let parse_result: ParseResult = parse_text_with_options(text, &ParseOptions::default()).unwrap();
+        let root_range = parse_result.value.as_ref().unwrap().range().clone();
+
+        let mut value: JsonValue = parse_to_value(text).unwrap().unwrap();
+        if let JsonValue::Object(obj) = &mut value {
+            obj.insert(String::from("port"), JsonValue::Number(String::from("8080")));
+            if let Some(JsonValue::Array(hosts)) = obj.get_mut("hosts") {
+                hosts.push(JsonValue::String(String::from("b")));
+            }
+        }
+
+        let options = SerializeOptions::default().with_style(SerializeStyle::OneLine);
+        let edit = TextEdit {
+            start: root_range.start,
+            end: root_range.end,
+            new_text: serialize_json_value(&value, &options),
+        };
+        let new_text = apply_text_edits(text, &[edit]).unwrap();
+        assert_eq!(new_text, "// settings\n{\"port\": 8080, \"hosts\": [\"a\", \"b\"]}\n");
+        assert_eq!(parse_text(&new_text).unwrap().value.unwrap().into_value(), value);
+
+        let err: ParseError = parse_to_value("{").err().unwrap();
+        assert_eq!(err.line, 0);
+    }
+}
+
+mod round_trip {
+    //! Property tests that serialize generated values and check they parse to the same value.
+
+    use jsonc_parser::parse_text;
+    use jsonc_parser::prelude::*;
+    use jsonc_parser::serializer::serialize_value;
+    use proptest::prelude::*;
+    use proptest::test_runner::{Config, RngAlgorithm, TestError, TestRng, TestRunner};
+
+    const CASE_COUNT: u32 = 500;
+
+    fn json_value() -> impl Strategy<Value = JsonValue> {
+        let leaf = prop_oneof![
+            Just(JsonValue::Null),
+            any::<bool>().prop_map(JsonValue::Boolean),
+            number().prop_map(JsonValue::Number),
+            string().prop_map(JsonValue::String),
+        ];
+        // up to 4 levels deep with at most 4 elements or properties in each container
+        leaf.prop_recursive(4, 64, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..=4).prop_map(|elements| JsonValue::Array(elements.into())),
+                prop::collection::vec((string(), inner), 0..=4).prop_map(to_object),
+            ]
+        })
+    }
+
+    fn number() -> impl Strategy<Value = String> {
+        let boundaries = ["0", "-0", "1", "-1", "9007199254740993", "-9223372036854775808", "18446744073709551616", "1e308", "5e-324", "1.7976931348623157e+308"];
+        prop_oneof![
+            1 => prop::sample::select(boundaries.to_vec()).prop_map(String::from),
+            2 => r"-?(0|[1-9][0-9]{0,3})(\.[0-9]{1,4})?([eE][+-]?[0-9]{1,4})?",
+        ]
+    }
+
+    fn string() -> impl Strategy<Value = String> {
+        let parts = ["a", "Z", " ", "\"", "\\", "/", "//", "/*", "*/", "\n", "\r", "\t", "\u{0}", "\u{1f}", "\u{7f}", "\u{e9}", "\u{2028}", "\u{feff}", "\u{FFFF}", "\u{1F600}", "'", "\\u0041"];
+        prop::collection::vec(prop::sample::select(parts.to_vec()), 0..6).prop_map(|parts| parts.concat())
+    }
+
+    fn to_object(entries: Vec<(String, JsonValue)>) -> JsonValue {
+        let mut obj = JsonObject::new();
+        for (key, value) in entries {
+            obj.insert(key, value);
+        }
+        JsonValue::Object(obj)
+    }
+
+    proptest! {
+        #![proptest_config(Config::with_cases(CASE_COUNT))]
+
+        #[test]
+        fn test_round_trips_compact_text(value in json_value()) {
+            prop_assert_eq!(parse_to_value(&value.to_string()).ok().flatten(), Some(value.clone()));
+        }
+
+        #[test]
+        fn test_round_trips_pretty_and_one_line_text(value in json_value()) {
+            for style in [SerializeStyle::Pretty, SerializeStyle::OneLine].iter() {
+                let options = SerializeOptions::default().with_style(*style).with_indent_width(3);
+                prop_assert_eq!(parse_to_value(&serialize_json_value(&value, &options)).ok().flatten(), Some(value.clone()));
+            }
+        }
+
+        #[test]
+        fn test_minifies_formatted_text(value in json_value()) {
+            let options = SerializeOptions::default().with_style(SerializeStyle::Pretty);
+            // add comments between the tokens of the formatted text, which minifying removes
+            let formatted_text = format!("/* a */\n{}\n// b", serialize_json_value(&value, &options).replace(",\n", ", // c\n"));
+            let parse_result = parse_text(&formatted_text);
+            prop_assert!(parse_result.is_ok(), "{:?}", parse_result.err());
+            let minified_text = serialize_value(parse_result.unwrap().value.as_ref().unwrap());
+            prop_assert_eq!(parse_to_value(&minified_text).ok().flatten(), Some(value.clone()));
+        }
+    }
+
+    #[test]
+    fn test_shrinks_to_small_counterexamples() {
+        // a property that fails for any value with a string containing a quote
+        let mut runner = TestRunner::new_with_rng(Config::with_cases(CASE_COUNT), TestRng::deterministic_rng(RngAlgorithm::default()));
+        let result = runner.run(&json_value(), |value| {
+            prop_assert!(!value.to_string().contains("\\\""));
+            Ok(())
+        });
+        match result {
+            Err(TestError::Fail(_, value)) => {
+                // containers aren't replaced with their children, but the other items and characters are removed
+                let text = value.to_string();
+                assert!(text.len() <= 16 && text.contains(r#""\"""#), "{}", text);
+            },
+            other => panic!("Expected the property to fail: {:?}", other),
+        }
+    }
+}
+
 // todo: move elsewhere and improve
 
 fn parse_result_to_test_str(parse_result: &ParseResult) -> String {
@@ -57,12 +475,12 @@ fn parse_result_to_test_str(parse_result: &ParseResult) -> String {
     text.push_str("  \"comments\": [");
     let collection_count = parse_result.comments.len();
     let mut comments = parse_result.comments.iter().collect::<Vec<_>>();
-    comments.sort_by(|a, b|a.0.cmp(&b.0));
+    comments.sort_by(|a, b|a.0.cmp(b.0));
     for (i, comment_collection) in comments.into_iter().enumerate() {
         text.push_str("\n    ");
         text.push_str(&comments_to_test_str(comment_collection).replace("\n", "\n    "));
         if i + 1 < collection_count {
-            text.push_str(",");
+            text.push(',');
         }
     }
     text.push_str("\n  ]\n");
@@ -88,7 +506,7 @@ fn range_to_test_str(range: &Range) -> String {
     text.push_str(&format!("  \"end\": {},\n", range.end));
     text.push_str(&format!("  \"startLine\": {},\n", range.start_line));
     text.push_str(&format!("  \"endLine\": {}\n", range.end_line));
-    text.push_str("}");
+    text.push('}');
     text
 }
 
@@ -110,7 +528,7 @@ fn lit_to_test_str(lit_type: &str, value: &str, range: &Range) -> String {
     text.push_str(&format!("  \"type\": \"{}\",\n", lit_type));
     text.push_str(&format!("  {},\n", range_to_test_str(range).replace("\n", "\n  ")));
     text.push_str(&format!("  \"value\": \"{}\"\n", value));
-    text.push_str("}");
+    text.push('}');
     text
 }
 
@@ -125,11 +543,11 @@ fn object_to_test_str(obj: &Object) -> String {
         text.push_str("\n    ");
         text.push_str(&object_prop_to_test_str(prop).replace("\n", "\n    "));
         if i + 1 < prop_count {
-            text.push_str(",");
+            text.push(',');
         }
     }
     text.push_str("\n  ]\n");
-    text.push_str("}");
+    text.push('}');
     text
 }
 
@@ -140,7 +558,7 @@ fn object_prop_to_test_str(obj_prop: &ObjectProp) -> String {
     text.push_str(&format!("  {},\n", range_to_test_str(&obj_prop.range).replace("\n", "\n  ")));
     text.push_str(&format!("  \"name\": {},\n", string_lit_to_test_str(&obj_prop.name).replace("\n", "\n  ")));
     text.push_str(&format!("  \"value\": {}\n", value_to_test_str(&obj_prop.value).replace("\n", "\n  ")));
-    text.push_str("}");
+    text.push('}');
     text
 }
 
@@ -155,11 +573,11 @@ fn array_to_test_str(arr: &Array) -> String {
         text.push_str("\n    ");
         text.push_str(&value_to_test_str(element).replace("\n", "\n    "));
         if i + 1 < elements_count {
-            text.push_str(",");
+            text.push(',');
         }
     }
     text.push_str("\n  ]\n");
-    text.push_str("}");
+    text.push('}');
     text
 }
 
@@ -168,7 +586,7 @@ fn null_keyword_to_test_str(null_keyword: &NullKeyword) -> String {
     text.push_str("{\n");
     text.push_str("  \"type\": \"null\",\n");
     text.push_str(&format!("  {}\n", range_to_test_str(&null_keyword.range).replace("\n", "\n  ")));
-    text.push_str("}");
+    text.push('}');
     text
 }
 
@@ -182,11 +600,11 @@ fn comments_to_test_str(comments: (&usize, &Rc<Vec<Comment>>)) -> String {
         text.push_str("\n    ");
         text.push_str(&comment_to_test_str(comment).replace("\n", "\n    "));
         if i + 1 < comments_count {
-            text.push_str(",");
+            text.push(',');
         }
     }
     text.push_str("\n  ]\n");
-    text.push_str("}");
+    text.push('}');
     text
 }
 
@@ -198,9 +616,9 @@ fn comment_to_test_str(comment: &Comment) -> String {
 }
 
 fn comment_line_to_test_str(line: &CommentLine) -> String {
-    lit_to_test_str("line", &line.text.as_ref(), &line.range)
+    lit_to_test_str("line", line.text.as_ref(), &line.range)
 }
 
 fn comment_block_to_test_str(block: &CommentBlock) -> String {
-    lit_to_test_str("block", &block.text.as_ref(), &block.range)
+    lit_to_test_str("block", block.text.as_ref(), &block.range)
 }